@@ -0,0 +1,92 @@
+//! Criterion suite over every curve backend; `bench-report` turns its results
+//! into a comparison table.
+
+use criterion::{criterion_group, criterion_main, Criterion, SamplingMode};
+use crypto_test::{
+    algebra::{CommutativeOp, DiscreteRoot, Field, Ring},
+    base_traits::{FromRandom, Natural, RW},
+    curves::{p256, secp256k1, toy},
+    ecc::gen_keys,
+    mod_field::ModField,
+    points_group::{Point, PointCfg},
+    rsa,
+    uint::U256,
+};
+use rand::SeedableRng;
+
+/// Benchmarks one backend: `I` is the field's integer type, `IP` the scalars'.
+/// `slow` backends get the fewest samples criterion allows for the scalar
+/// multiplication based operations.
+fn backend<I: Natural + RW + FromRandom<()>, IP: Natural + FromRandom<()>>(
+    c: &mut Criterion,
+    name: &str,
+    cfg: &PointCfg<ModField<I>>,
+    slow: bool,
+) {
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+    let (x, y) = (
+        ModField::random(&mut rng, &cfg.cf),
+        ModField::random(&mut rng, &cfg.cf),
+    );
+    let p = Point::random(&mut rng, cfg);
+    let q = Point::random(&mut rng, cfg);
+    let k = IP::random(&mut rng, &());
+    let (_, pb) = gen_keys::<_, IP, Point<ModField<I>>>(&mut rng, cfg);
+
+    c.benchmark_group("field_mul")
+        .bench_function(name, |b| b.iter(|| Ring::mul(x, y, &cfg.cf)));
+    c.benchmark_group("field_inv")
+        .bench_function(name, |b| b.iter(|| x.reciprocal(&cfg.cf)));
+    let square = x.sqr(&cfg.cf);
+    c.benchmark_group("field_sqrt")
+        .bench_function(name, |b| b.iter(|| square.sqrt(&cfg.cf)));
+    c.benchmark_group("field_random")
+        .bench_function(name, |b| b.iter(|| ModField::random(&mut rng, &cfg.cf)));
+    c.benchmark_group("point_add")
+        .bench_function(name, |b| b.iter(|| Point::op(p, q, cfg)));
+
+    let mut heavy = |op: &str, f: &mut dyn FnMut()| {
+        let mut group = c.benchmark_group(op);
+        if slow {
+            group.sample_size(10).sampling_mode(SamplingMode::Flat);
+        }
+        group.bench_function(name, |b| b.iter(&mut *f));
+    };
+    heavy("scalar_mul", &mut || {
+        Point::exp(cfg.g, k, cfg);
+    });
+    heavy("keygen", &mut || {
+        gen_keys::<_, IP, Point<ModField<I>>>(&mut rng, cfg);
+    });
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+    heavy("encrypt", &mut || {
+        pb.encrypt::<IP>(p, &mut rng, cfg);
+    });
+}
+
+/// Textbook RSA with two `bits`-bit primes, for comparison with the curves.
+fn rsa_backend<I: Natural + FromRandom<()>>(c: &mut Criterion, name: &str, bits: u32) {
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+    let (pr, pb) = rsa::gen_keys::<I, _>(&mut rng, bits);
+    let msg = I::random(&mut rng, &()) % pb.n;
+    let ciphertext = pb.encrypt(msg);
+    let mut group = c.benchmark_group("keygen");
+    group.sample_size(10).sampling_mode(SamplingMode::Flat);
+    group.bench_function(name, |b| b.iter(|| rsa::gen_keys::<I, _>(&mut rng, bits)));
+    drop(group);
+    c.benchmark_group("encrypt")
+        .bench_function(name, |b| b.iter(|| pb.encrypt(msg)));
+    c.benchmark_group("decrypt")
+        .bench_function(name, |b| b.iter(|| pr.decrypt(ciphertext)));
+}
+
+fn backends(c: &mut Criterion) {
+    backend::<u64, u128>(c, "toy", &toy(), false);
+    backend::<U256, U256>(c, "secp256k1", &secp256k1(), true);
+    backend::<U256, U256>(c, "p256", &p256(), true);
+    rsa_backend::<u128>(c, "rsa-64", 32);
+    rsa_backend::<U256>(c, "rsa-256", 128);
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+//! Derives for newtypes around an existing field element, so a wrapper like
+//! `struct Fp(ModField<u64>)` gets the plumbing of `crypto_test::algebra`
+//! without writing every impl by hand:
+//!
+//! ```ignore
+//! #[derive(Clone, Copy, PartialEq, Eq, Configurable, CommutativeOps)]
+//! struct Fp(ModField<u64>);
+//! impl Ring for Fp {}
+//! impl Field for Fp {}
+//! ```
+//!
+//! The wrapper has to have exactly one field. Every operation delegates to
+//! it, with the same config.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
+
+/// The wrapped type, how to get at it and how to wrap it again.
+struct Newtype {
+    inner: Type,
+    get: TokenStream2,
+    wrap: Box<dyn Fn(TokenStream2) -> TokenStream2>,
+}
+
+fn newtype(input: &DeriveInput) -> syn::Result<Newtype> {
+    let error = || syn::Error::new_spanned(input, "expected a struct with exactly one field");
+    let Data::Struct(data) = &input.data else {
+        return Err(error());
+    };
+    match &data.fields {
+        Fields::Unnamed(f) if f.unnamed.len() == 1 => Ok(Newtype {
+            inner: f.unnamed[0].ty.clone(),
+            get: quote!(0),
+            wrap: Box::new(|v| quote!(Self(#v))),
+        }),
+        Fields::Named(f) if f.named.len() == 1 => {
+            let name = f.named[0].ident.clone().unwrap();
+            Ok(Newtype {
+                inner: f.named[0].ty.clone(),
+                get: quote!(#name),
+                wrap: Box::new(move |v| quote!(Self { #name: #v })),
+            })
+        }
+        _ => Err(error()),
+    }
+}
+
+/// `type Cfg` of the wrapped type.
+#[proc_macro_derive(Configurable)]
+pub fn derive_configurable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let Newtype { inner, .. } = match newtype(&input) {
+        Ok(n) => n,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::crypto_test::algebra::Configurable for #name #ty_generics #where_clause {
+            type Cfg = <#inner as ::crypto_test::algebra::Configurable>::Cfg;
+        }
+    }
+    .into()
+}
+
+/// Addition and multiplication with their identities and inverses, the marker
+/// traits on top and the constant-time swap, everything `Ring` and `Field`
+/// need but the marker impls themselves.
+#[proc_macro_derive(CommutativeOps)]
+pub fn derive_commutative_ops(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let Newtype { inner, get, wrap } = match newtype(&input) {
+        Ok(n) => n,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let alg = quote!(::crypto_test::algebra);
+    let this = quote!(#name #ty_generics);
+
+    let mut res = TokenStream2::new();
+    for op in [quote!(#alg::ops::Add), quote!(#alg::ops::Mul)] {
+        let op_result = wrap(quote!(<#inner as #alg::CommutativeOp<#op>>::op(a.#get, b.#get, c)));
+        let identity = wrap(quote!(<#inner as #alg::Identity<#op>>::identity(c)));
+        res.extend(quote! {
+            impl #impl_generics #alg::CommutativeOp<#op> for #this #where_clause {
+                fn op(a: Self, b: Self, c: &Self::Cfg) -> Self {
+                    #op_result
+                }
+            }
+            impl #impl_generics #alg::Identity<#op> for #this #where_clause {
+                fn identity(c: &Self::Cfg) -> Self {
+                    #identity
+                }
+            }
+            impl #impl_generics #alg::CommutativeMonoid<#op> for #this #where_clause {}
+        });
+    }
+    let neg = wrap(quote!(<#inner as #alg::Inverse<#alg::ops::Add>>::inv(self.#get, c)));
+    let reciprocal = wrap(quote!(v));
+    res.extend(quote! {
+        impl #impl_generics #alg::Inverse<#alg::ops::Add> for #this #where_clause {
+            fn inv(self, c: &Self::Cfg) -> Self {
+                #neg
+            }
+        }
+        impl #impl_generics #alg::InverseNonZero<#alg::ops::Mul> for #this #where_clause {
+            fn inv(self, c: &Self::Cfg) -> Option<Self> {
+                <#inner as #alg::InverseNonZero<#alg::ops::Mul>>::inv(self.#get, c).map(|v| #reciprocal)
+            }
+        }
+        impl #impl_generics #alg::AbelianGroup<#alg::ops::Add> for #this #where_clause {}
+        impl #impl_generics #alg::ConditionalSwap for #this #where_clause {
+            fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+                <#inner as #alg::ConditionalSwap>::conditional_swap(&mut a.#get, &mut b.#get, swap)
+            }
+        }
+    });
+    res.into()
+}
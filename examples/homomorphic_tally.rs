@@ -0,0 +1,61 @@
+//! An encrypted yes/no vote: each ballot is ElGamal of `v·G` for `v ∈ {0, 1}`,
+//! the ciphertexts are added up without decrypting any single one, and only
+//! the sum is decrypted. The tally is the discrete logarithm of `t·G`, found
+//! by trying every count up to the number of voters.
+//!
+//! Projective points are used since a "no" vote encrypts the point at
+//! infinity, which affine points can't hold.
+//!
+//! `cargo run --example homomorphic_tally -- 1 0 1 1 0`
+
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
+use crypto_test::{
+    algebra::{CommutativeMonoid, CommutativeOp, Identity},
+    curves::{secp256k1, secp256k1_order},
+    ecc::{PrivateKey, PublicKey},
+    mod_field::ModField,
+    projective::ProjectivePoint,
+    uint::U256,
+};
+
+type P = ProjectivePoint<ModField<U256>>;
+
+fn main() {
+    let votes = std::env::args()
+        .skip(1)
+        .map(|v| match v.as_str() {
+            "0" => Ok(0u64),
+            "1" => Ok(1),
+            _ => Err(v),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|v| {
+            eprintln!("votes are 0 or 1, got {:?}", v);
+            std::process::exit(2)
+        });
+    let (cfg, n) = (secp256k1(), secp256k1_order());
+    let mut rng = rand::thread_rng();
+    let authority = PrivateKey::random(&mut rng, &n);
+    let public: PublicKey<P> = authority.public_key(&cfg);
+    let g = P::from_affine(cfg.g, &cfg);
+
+    let ballots = votes
+        .iter()
+        .map(|&v| public.encrypt::<U256>(CommutativeMonoid::exp(g, v, &cfg), &mut rng, &cfg))
+        .collect::<Vec<_>>();
+    let identity = (P::identity(&cfg), P::identity(&cfg));
+    let sum = ballots.iter().fold(identity, |(a1, a2), &(c1, c2)| {
+        (P::op(a1, c1, &cfg), P::op(a2, c2, &cfg))
+    });
+
+    let tally_point = authority.decrypt(sum, &cfg);
+    let tally = (0..=votes.len() as u64)
+        .find(|&t| P::same_point(CommutativeMonoid::exp(g, t, &cfg), tally_point, &cfg))
+        .expect("the sum isn't a count of votes");
+    println!("{} of {} voted yes", tally, votes.len());
+    if tally != votes.iter().sum::<u64>() {
+        std::process::exit(1)
+    }
+}
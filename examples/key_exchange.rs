@@ -0,0 +1,53 @@
+//! Diffie–Hellman between two parties that only see each other's public key
+//! as base64, the way it would travel over a wire.
+//!
+//! `cargo run --example key_exchange -- [toy|secp256k1]`
+
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
+use crypto_test::{
+    base_traits::{FromRandom, Natural, RW},
+    curves::{secp256k1, secp256k1_order, toy, toy_order},
+    ecc::{PrivateKey, PublicKey},
+    mod_field::{ModField, ModFieldCfg},
+    points_group::{Point, PointCfg, ValidationPolicy},
+};
+
+fn exchange<I: Natural + RW, S: Natural + RW + FromRandom<()>>(
+    cfg: &PointCfg<ModField<I>>,
+    n: &ModFieldCfg<S>,
+) {
+    let mut rng = rand::thread_rng();
+    let mut party = || {
+        let pr = PrivateKey::random(&mut rng, n);
+        let pb: PublicKey<Point<ModField<I>>> = pr.public_key(cfg);
+        (pr, pb.base64())
+    };
+    let (alice, alice_wire) = party();
+    let (bob, bob_wire) = party();
+    println!("alice sends {}", alice_wire);
+    println!("bob sends   {}", bob_wire);
+
+    // each side checks the key it received before using it
+    let policy = ValidationPolicy::OnCurveAndSubgroup(n.rem);
+    let shared = |pr: PrivateKey<S>, wire: &str| {
+        let peer = PublicKey::from_base64_validated(wire, policy, cfg).expect("bad peer key");
+        pr.diffie_hellman(peer, policy, cfg)
+            .expect("no shared secret")
+    };
+    let (a, b) = (shared(alice, &bob_wire), shared(bob, &alice_wire));
+    assert!(a == b, "the parties disagree");
+    println!("shared secret {}", a);
+}
+
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        None | Some("secp256k1") => exchange(&secp256k1(), &secp256k1_order()),
+        Some("toy") => exchange(&toy(), &toy_order()),
+        Some(other) => {
+            eprintln!("unknown curve {}, expected toy or secp256k1", other);
+            std::process::exit(2)
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Shamir's secret sharing over the scalars of secp256k1: a secret of up to
+//! 31 bytes becomes the constant term of a random polynomial of degree
+//! `threshold - 1`, each share is a point on it, and any `threshold` shares
+//! give the secret back by Lagrange interpolation at zero.
+//!
+//! `cargo run --example secret_sharing -- <secret> <threshold> <shares>`
+
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
+use crypto_test::{
+    algebra::{Field, Ring},
+    base_traits::{Endianness, FromRandom, RW},
+    curves::secp256k1_order,
+    mod_field::{ModField, ModFieldCfg},
+    uint::U256,
+};
+
+type Scalar = ModField<U256>;
+
+fn split(
+    secret: Scalar,
+    threshold: usize,
+    shares: usize,
+    n: &ModFieldCfg<U256>,
+) -> Vec<(Scalar, Scalar)> {
+    let mut rng = rand::thread_rng();
+    let mut coefficients = vec![secret];
+    coefficients.extend((1..threshold).map(|_| Scalar::random(&mut rng, n)));
+    (1..=shares as u64)
+        .map(|i| {
+            let x = Scalar::new(U256::from(i), n);
+            // Horner, highest coefficient first
+            let y = coefficients.iter().rev().fold(Scalar::zero(n), |acc, &c| {
+                Scalar::add(Scalar::mul(acc, x, n), c, n)
+            });
+            (x, y)
+        })
+        .collect()
+}
+
+/// `Σ yᵢ · Π xⱼ / (xⱼ - xᵢ)`, the polynomial through the shares at zero.
+fn combine(shares: &[(Scalar, Scalar)], n: &ModFieldCfg<U256>) -> Scalar {
+    let terms = shares.iter().map(|&(xi, yi)| {
+        let basis = shares
+            .iter()
+            .filter(|&&(xj, _)| xj != xi)
+            .fold(Scalar::one(n), |acc, &(xj, _)| {
+                Scalar::mul(acc, Scalar::div(xj, Scalar::sub(xj, xi, n), n), n)
+            });
+        Scalar::mul(yi, basis, n)
+    });
+    terms.fold(Scalar::zero(n), |acc, t| Scalar::add(acc, t, n))
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let [secret, threshold, shares] = &args[..] else {
+        eprintln!("usage: secret_sharing <secret> <threshold> <shares>");
+        std::process::exit(2)
+    };
+    let (threshold, shares) = match (threshold.parse::<usize>(), shares.parse::<usize>()) {
+        (Ok(t), Ok(s)) if 1 <= t && t <= s => (t, s),
+        _ => {
+            eprintln!("expected 1 ≤ threshold ≤ shares");
+            std::process::exit(2)
+        }
+    };
+    if secret.len() > 31 {
+        eprintln!("the secret has to fit into 31 bytes");
+        std::process::exit(2)
+    }
+
+    let n = secp256k1_order();
+    // below 2^248, so below n and without loss
+    let mut padded = vec![0; 32 - secret.len()];
+    padded.extend(secret.as_bytes());
+    let value = U256::from_bytes_endian(&mut padded.as_slice(), Endianness::Big);
+    let all = split(Scalar::new(value, &n), threshold, shares, &n);
+    for (x, y) in &all {
+        println!("share {}: {}", x, y);
+    }
+
+    for subset in [&all[..threshold], &all[shares - threshold..]] {
+        let recovered = combine(subset, &n).nat().to_vec_endian(Endianness::Big);
+        let text = String::from_utf8_lossy(&recovered[32 - secret.len()..]).into_owned();
+        println!("{} shares give {:?}", subset.len(), text);
+        if text != *secret {
+            std::process::exit(1)
+        }
+    }
+    if threshold > 1 {
+        let short = combine(&all[..threshold - 1], &n);
+        println!("{} shares give {} instead", threshold - 1, short);
+    }
+}
@@ -0,0 +1,47 @@
+//! Schnorr signatures over secp256k1: signs the message given on the command
+//! line, prints the signature, and verifies it against the message and
+//! against a tampered copy.
+//!
+//! `cargo run --example signed_message -- "message to sign"`
+
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+
+use crypto_test::{
+    base_traits::RW,
+    curves::{secp256k1, secp256k1_order},
+    ecc::{PrivateKey, PublicKey},
+    mod_field::ModField,
+    points_group::Point,
+    schnorr::{verify, Signature, SigningSession},
+    uint::U256,
+};
+
+type P = Point<ModField<U256>>;
+
+fn main() {
+    let Some(msg) = std::env::args().nth(1) else {
+        eprintln!("usage: signed_message <message>");
+        std::process::exit(2)
+    };
+    let (cfg, n) = (secp256k1(), secp256k1_order());
+    let key = PrivateKey::random(&mut rand::thread_rng(), &n);
+    let public: PublicKey<P> = key.public_key(&cfg);
+
+    let sig: Signature<P, U256> = SigningSession::new(key, msg.as_bytes())
+        .commit(&cfg, &n)
+        .finalize(&cfg, &n);
+    println!("public key {}", public);
+    println!("signature  {}", sig.to_base64());
+
+    // what a verifier holding only the encodings does
+    let sig = Signature::<P, U256>::try_from_base64(&sig.to_base64()).expect("bad signature");
+    let valid = verify(public, msg.as_bytes(), sig, &cfg, &n);
+    println!("verifies: {}", valid);
+    let tampered = format!("{}!", msg);
+    let forged = verify(public, tampered.as_bytes(), sig, &cfg, &n);
+    println!("verifies for {:?}: {}", tampered, forged);
+    if !valid || forged {
+        std::process::exit(1)
+    }
+}
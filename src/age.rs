@@ -0,0 +1,280 @@
+//! age-style recipients and identities ([age](https://age-encryption.org/v1)).
+//! An age file is encrypted under a random 16-byte file key, and every
+//! recipient gets a stanza wrapping that key. This module provides the `ecc`
+//! stanza type, plus recipient (`age1ecc1...`) and identity
+//! (`AGE-PLUGIN-ECC-1...`) strings following age's plugin naming, so an
+//! `age-plugin-ecc` binary only has to speak the plugin protocol around it.
+//!
+//! Wrapping is ECIES: an ephemeral `E = t·G`, the shared point `t·Pub`, and
+//! HKDF-SHA256 derived keys to mask the file key and authenticate the result.
+
+use base64::prelude::*;
+use rand::Rng;
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field, InitialPoint},
+    base_traits::{FromRandom, Natural, Parity, RW},
+    bitcoin::{bech32_decode, bech32_encode, from_words, to_words},
+    ecc::{PrivateKey, PublicKey},
+    mac::{hkdf, hmac},
+    mod_field::ModFieldCfg,
+    points_group::{Point, PointCfg, ValidationPolicy},
+};
+
+pub const STANZA_TYPE: &str = "ecc";
+const RECIPIENT_HRP: &str = "age1ecc";
+const IDENTITY_HRP: &str = "age-plugin-ecc-";
+const LABEL: &[u8] = b"age-encryption.org/v1/ecc";
+
+pub type FileKey = [u8; 16];
+
+/// A recipient stanza: `-> tag args...` followed by the base64 body wrapped at
+/// 64 columns, the last line always shorter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stanza {
+    pub tag: String,
+    pub args: Vec<String>,
+    pub body: Vec<u8>,
+}
+
+impl Stanza {
+    pub fn encode(&self) -> String {
+        let mut res = format!("-> {}", self.tag);
+        for arg in &self.args {
+            res.push(' ');
+            res.push_str(arg);
+        }
+        res.push('\n');
+        let body = BASE64_STANDARD_NO_PAD.encode(&self.body);
+        for line in body.as_bytes().chunks(64) {
+            res.push_str(std::str::from_utf8(line).unwrap());
+            res.push('\n');
+        }
+        // a full last line is followed by an empty one
+        if body.len().is_multiple_of(64) {
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Parses one stanza and returns the rest of the input.
+    pub fn parse(s: &str) -> Option<(Self, &str)> {
+        let (header, mut rest) = s.strip_prefix("-> ")?.split_once('\n')?;
+        let mut words = header.split(' ');
+        let tag = words.next().filter(|t| !t.is_empty())?.to_string();
+        let args = words.map(str::to_string).collect::<Vec<_>>();
+        if args.iter().any(|a| a.is_empty()) {
+            return None;
+        }
+        let mut body = String::new();
+        loop {
+            let (line, tail) = rest.split_once('\n')?;
+            rest = tail;
+            if line.len() > 64 {
+                return None;
+            }
+            body.push_str(line);
+            if line.len() < 64 {
+                break;
+            }
+        }
+        let body = BASE64_STANDARD_NO_PAD.decode(body).ok()?;
+        Some((Self { tag, args, body }, rest))
+    }
+}
+
+impl<F: Field + RW + Parity> PublicKey<Point<F>> {
+    pub fn age_recipient(self) -> String {
+        bech32_encode(RECIPIENT_HRP, to_words(&self.point().to_sec1_compressed()))
+    }
+}
+
+impl<F: Field + RW + Parity + DiscreteRoot<algebra::ops::Mul>> PublicKey<Point<F>> {
+    pub fn from_age_recipient(s: &str, cfg: &PointCfg<F>) -> Option<Self> {
+        let (hrp, words) = bech32_decode(s)?;
+        if hrp != RECIPIENT_HRP {
+            return None;
+        }
+        Point::from_sec1_compressed(&from_words(&words)?, cfg)
+            .filter(|p| !p.is_infinity())
+            .map(PublicKey::from_point)
+    }
+
+    /// Wraps `file_key` for this recipient under a fresh ephemeral key.
+    pub fn wrap_file_key<I: Natural + FromRandom<()>>(
+        self,
+        file_key: &FileKey,
+        rng: &mut impl Rng,
+        cfg: &PointCfg<F>,
+    ) -> Stanza {
+        let t = I::random(rng, &());
+        let ephemeral = Point::mul_ct(cfg.g(), t, cfg);
+        let shared = self.point().mul_ct(t, cfg);
+        let (mask, mac) = wrap_keys(ephemeral, self.point(), shared);
+        let mut body = file_key.to_vec();
+        body.iter_mut().zip(mask).for_each(|(b, m)| *b ^= m);
+        body.extend(tag(&mac, &body));
+        Stanza {
+            tag: STANZA_TYPE.to_string(),
+            args: vec![BASE64_STANDARD_NO_PAD.encode(ephemeral.to_sec1_compressed())],
+            body,
+        }
+    }
+}
+
+impl<I: Natural + RW> PrivateKey<I> {
+    pub fn age_identity(self) -> String {
+        bech32_encode(IDENTITY_HRP, to_words(&self.to_vec())).to_ascii_uppercase()
+    }
+
+    pub fn from_age_identity(s: &str) -> Option<Self> {
+        let (hrp, words) = bech32_decode(s)?;
+        let bytes = from_words(&words)?;
+        (hrp == IDENTITY_HRP && bytes.len() == I::LEN).then(|| Self::from_bytes(&mut &bytes[..]))
+    }
+
+    /// The file key from the first `ecc` stanza addressed to this key, or
+    /// `None` if there is none. Ephemeral keys outside the subgroup of order
+    /// `n` are skipped, so a stanza can't probe the key modulo small orders.
+    pub fn unwrap_file_key<F: Field + RW + Parity + DiscreteRoot<algebra::ops::Mul>>(
+        self,
+        stanzas: &[Stanza],
+        cfg: &PointCfg<F>,
+        n: &ModFieldCfg<I>,
+    ) -> Option<FileKey> {
+        let public = Point::mul_ct(cfg.g(), self.scalar(), cfg);
+        stanzas
+            .iter()
+            .filter(|s| s.tag == STANZA_TYPE && s.args.len() == 1 && s.body.len() == 32)
+            .find_map(|s| {
+                let ephemeral = BASE64_STANDARD_NO_PAD.decode(&s.args[0]).ok()?;
+                let ephemeral = Point::from_sec1_compressed(&ephemeral, cfg)?
+                    .validate(ValidationPolicy::OnCurveAndSubgroup(n.rem), cfg)
+                    .ok()?;
+                let shared = ephemeral.mul_ct(self.scalar(), cfg);
+                let (mask, mac) = wrap_keys(ephemeral, public, shared);
+                let (masked, expected) = s.body.split_at(16);
+                if tag(&mac, masked)[..] != *expected {
+                    return None;
+                }
+                let mut key = FileKey::default();
+                key.iter_mut()
+                    .zip(masked.iter().zip(mask))
+                    .for_each(|(k, (b, m))| *k = b ^ m);
+                Some(key)
+            })
+    }
+}
+
+/// Mask and MAC keys from the ephemeral, recipient and shared points, by
+/// HKDF salted with the two public points like age's X25519 stanza.
+fn wrap_keys<F: Field + RW + Parity>(
+    ephemeral: Point<F>,
+    recipient: Point<F>,
+    shared: Point<F>,
+) -> ([u8; 32], [u8; 32]) {
+    let salt = [
+        ephemeral.to_sec1_compressed(),
+        recipient.to_sec1_compressed(),
+    ]
+    .concat();
+    hkdf(&salt, &shared.to_sec1_compressed(), LABEL)
+}
+
+/// HMAC-SHA256 truncated to 16 bytes.
+fn tag(mac: &[u8; 32], data: &[u8]) -> [u8; 16] {
+    hmac(mac, &[data])[..16].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        curves::{toy, toy_order},
+        ecc::{gen_keys, PrivateKey, PublicKey},
+        mod_field::ModField,
+        points_group::Point,
+    };
+
+    use super::Stanza;
+
+    type P = Point<ModField<u64>>;
+
+    #[test]
+    fn wrap_unwrap() {
+        let (cfg, n) = (toy(), toy_order());
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, P>(&mut gen, &cfg);
+        let (other, _) = gen_keys::<_, u128, P>(&mut gen, &cfg);
+        let file_key = *b"0123456789abcdef";
+        let stanza = pb.wrap_file_key::<u128>(&file_key, &mut gen, &cfg);
+        assert_eq!(stanza.tag, "ecc");
+        assert_eq!(
+            pr.unwrap_file_key(std::slice::from_ref(&stanza), &cfg, &n),
+            Some(file_key)
+        );
+        assert_eq!(
+            other.unwrap_file_key(std::slice::from_ref(&stanza), &cfg, &n),
+            None
+        );
+
+        let mut tampered = stanza.clone();
+        tampered.body[0] ^= 1;
+        assert_eq!(
+            pr.unwrap_file_key(std::slice::from_ref(&tampered), &cfg, &n),
+            None
+        );
+        assert_eq!(
+            pr.unwrap_file_key(&[tampered, stanza], &cfg, &n),
+            Some(file_key)
+        );
+    }
+
+    #[test]
+    fn key_strings() {
+        let cfg = toy();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, P>(&mut gen, &cfg);
+        let recipient = pb.age_recipient();
+        assert!(recipient.starts_with("age1ecc1"));
+        assert_eq!(PublicKey::from_age_recipient(&recipient, &cfg), Some(pb));
+        let identity = pr.age_identity();
+        assert!(identity.starts_with("AGE-PLUGIN-ECC-1"));
+        assert_eq!(PrivateKey::from_age_identity(&identity), Some(pr));
+        assert_eq!(PrivateKey::<u128>::from_age_identity(&recipient), None);
+        let mut broken = recipient.clone();
+        broken.pop();
+        broken.push(if recipient.ends_with('q') { 'p' } else { 'q' });
+        assert_eq!(PublicKey::from_age_recipient(&broken, &cfg), None);
+    }
+
+    #[test]
+    fn stanza_format() {
+        for len in [0, 16, 32, 47, 48, 49, 100] {
+            let stanza = Stanza {
+                tag: "ecc".to_string(),
+                args: vec!["arg".to_string()],
+                body: (0..len).map(|i| i as u8).collect(),
+            };
+            let encoded = stanza.encode();
+            let lines = encoded.lines().collect::<Vec<_>>();
+            assert_eq!(lines[0], "-> ecc arg");
+            assert!(lines.last().unwrap().len() < 64);
+            assert!(lines[1..].iter().rev().skip(1).all(|l| l.len() == 64));
+            let input = encoded + "tail";
+            let (parsed, rest) = Stanza::parse(&input).unwrap();
+            assert_eq!(parsed, stanza);
+            assert_eq!(rest, "tail");
+        }
+        // age spec example: 32 bytes encode to exactly 43 characters
+        let stanza = Stanza {
+            tag: "X25519".to_string(),
+            args: vec![],
+            body: vec![0; 32],
+        };
+        assert_eq!(stanza.encode().lines().nth(1).unwrap().len(), 43);
+        assert_eq!(Stanza::parse("-> \n\n"), None);
+        assert_eq!(Stanza::parse("no arrow\n"), None);
+    }
+}
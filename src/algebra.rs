@@ -1,5 +1,11 @@
+use rand::Rng;
+
 use crate::base_traits::Natural;
 
+pub use elliptic_curves_derive::{CommutativeOps, Configurable};
+
+pub mod laws;
+
 pub trait Configurable: Sized + Copy {
     type Cfg;
 }
@@ -22,6 +28,31 @@ pub trait CommutativeOp<Op>: Configurable {
             }
         }
     }
+
+    /// [`CommutativeOp::exp`] by reference and without recursion, right to
+    /// left. Panics for 0 the same way.
+    fn exp_ref<I: Natural>(&self, n: I, cfg: &Self::Cfg) -> Self {
+        if n == I::zero() {
+            panic!("Identity element for power 0 is not defined, use Monoid::exp");
+        }
+        let (mut acc, mut base, mut k) = (None, *self, n);
+        loop {
+            if k % I::two() == I::one() {
+                acc = Some(acc.map_or(base, |acc| CommutativeOp::op(acc, base, cfg)));
+            }
+            k = k / I::two();
+            if k == I::zero() {
+                return acc.unwrap();
+            }
+            base = CommutativeOp::op(base, base, cfg);
+        }
+    }
+
+    /// For public exponents: the work depends on `n`, so its timing gives `n`
+    /// away.
+    fn exp_vartime<I: Natural>(&self, n: I, cfg: &Self::Cfg) -> Self {
+        self.exp_ref(n, cfg)
+    }
 }
 
 pub trait Identity<Op>: Configurable {
@@ -44,6 +75,41 @@ pub trait CommutativeMonoid<Op>: CommutativeOp<Op> + Identity<Op> {
             CommutativeOp::exp(self, n, cfg)
         }
     }
+
+    /// For secret exponents: a Montgomery ladder over every bit of `I`, each
+    /// step one `op` of the two registers and one squaring, with the
+    /// registers exchanged by [`ConditionalSwap`] instead of picked by a
+    /// branch. Neither the sequence of operations nor the control flow
+    /// depends on `n`; `op` itself still has to be constant-time for the
+    /// whole to be.
+    fn exp_ct<I: Natural>(&self, n: I, cfg: &Self::Cfg) -> Self
+    where
+        Self: ConditionalSwap,
+    {
+        let (mut bits, mut k, mut width) = (vec![], n, <I as Natural>::max());
+        while width != I::zero() {
+            bits.push(k % I::two() == I::one());
+            k = k / I::two();
+            width = width / I::two();
+        }
+        // r1 = r0 · self throughout; r0 is self^n once every bit is in
+        let (mut r0, mut r1) = (Identity::identity(cfg), *self);
+        let mut swapped = false;
+        for &bit in bits.iter().rev() {
+            Self::conditional_swap(&mut r0, &mut r1, swapped ^ bit);
+            swapped = bit;
+            r1 = CommutativeOp::op(r0, r1, cfg);
+            r0 = CommutativeOp::op(r0, r0, cfg);
+        }
+        Self::conditional_swap(&mut r0, &mut r1, swapped);
+        r0
+    }
+}
+
+/// Exchanging two values or not without a branch on whether, for
+/// [`CommutativeMonoid::exp_ct`] to hide the bits of its exponent.
+pub trait ConditionalSwap: Sized {
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool);
 }
 
 pub trait AbelianGroup<Op>: CommutativeMonoid<Op> + Inverse<Op> {}
@@ -61,10 +127,11 @@ pub mod ops {
     pub struct Mul;
 }
 
+/// A commutative ring with one: everything a field has except guaranteed
+/// inverses. `ModField` with a composite modulus is one, and that's where RSA
+/// and factoring live.
 #[allow(dead_code)]
-pub trait Field:
-    Sized + AbelianGroup<ops::Add> + CommutativeMonoid<ops::Mul> + InverseNonZero<ops::Mul> + Eq
-{
+pub trait Ring: Sized + AbelianGroup<ops::Add> + CommutativeMonoid<ops::Mul> + Eq {
     fn add(a: Self, b: Self, cfg: &Self::Cfg) -> Self {
         CommutativeOp::<ops::Add>::op(a, b, cfg)
     }
@@ -74,9 +141,6 @@ pub trait Field:
     fn mul(a: Self, b: Self, cfg: &Self::Cfg) -> Self {
         CommutativeOp::<ops::Mul>::op(a, b, cfg)
     }
-    fn div(a: Self, b: Self, cfg: &Self::Cfg) -> Self {
-        CommutativeOp::<ops::Mul>::op(a, InverseNonZero::<ops::Mul>::inv(b, cfg).unwrap(), cfg)
-    }
     fn zero(cfg: &Self::Cfg) -> Self {
         Identity::<ops::Add>::identity(cfg)
     }
@@ -97,9 +161,6 @@ pub trait Field:
     fn pow<N: Natural>(self, n: N, cfg: &Self::Cfg) -> Self {
         CommutativeMonoid::<ops::Mul>::exp(self, n, cfg)
     }
-    fn reciprocal(self, cfg: &Self::Cfg) -> Option<Self> {
-        InverseNonZero::inv(self, cfg)
-    }
     fn neg(self, cfg: &Self::Cfg) -> Self {
         Inverse::inv(self, cfg)
     }
@@ -109,6 +170,63 @@ pub trait Field:
     fn cube(self, cfg: &Self::Cfg) -> Self {
         Self::mul(Self::sqr(self, cfg), self, cfg)
     }
+    /// `Σ aᵢ · bᵢ`. Implementations may accumulate unreduced and reduce once.
+    fn sum_of_products(pairs: &[(Self, Self)], cfg: &Self::Cfg) -> Self {
+        pairs.iter().fold(Self::zero(cfg), |acc, &(a, b)| {
+            Self::add(acc, Self::mul(a, b, cfg), cfg)
+        })
+    }
+    /// Shorthand for [`Self::sum_of_products`] in formulas.
+    fn sop(pairs: &[(Self, Self)], cfg: &Self::Cfg) -> Self {
+        Self::sum_of_products(pairs, cfg)
+    }
+}
+
+#[allow(dead_code)]
+/// Also [`ConditionalSwap`], so that points over any field can go through
+/// [`CommutativeMonoid::exp_ct`].
+pub trait Field: Ring + InverseNonZero<ops::Mul> + ConditionalSwap {
+    fn div(a: Self, b: Self, cfg: &Self::Cfg) -> Self {
+        CommutativeOp::<ops::Mul>::op(a, InverseNonZero::<ops::Mul>::inv(b, cfg).unwrap(), cfg)
+    }
+    fn reciprocal(self, cfg: &Self::Cfg) -> Option<Self> {
+        InverseNonZero::inv(self, cfg)
+    }
+    /// Whether this is the one representation of its value. Decoders that
+    /// don't reduce can produce others, which every operation accepts but
+    /// which compare unequal and encode differently.
+    fn is_canonical(self, _cfg: &Self::Cfg) -> bool {
+        true
+    }
+}
+
+/// A group of prime order `n`, written additively, for protocols that need
+/// no more than a generator, random elements and scalar multiplication.
+/// `Params` describes the group including its order, which the element's
+/// `Cfg` alone may not know.
+pub trait PrimeGroup:
+    CommutativeMonoid<ops::Add> + ConditionalSwap + Inverse<ops::Add> + Eq
+{
+    type Scalar: Natural;
+    type Params;
+
+    fn cfg(p: &Self::Params) -> &Self::Cfg;
+    fn generator(p: &Self::Params) -> Self;
+    fn order(p: &Self::Params) -> Self::Scalar;
+    /// A uniformly random element other than the identity.
+    fn random(rng: &mut impl Rng, p: &Self::Params) -> Self;
+
+    fn add(a: Self, b: Self, p: &Self::Params) -> Self {
+        CommutativeOp::op(a, b, Self::cfg(p))
+    }
+    fn neg(self, p: &Self::Params) -> Self {
+        Inverse::inv(self, Self::cfg(p))
+    }
+    /// `k · self`, the identity for a multiple of the order. `k` is usually
+    /// secret, so this is [`CommutativeMonoid::exp_ct`].
+    fn mul(self, k: Self::Scalar, p: &Self::Params) -> Self {
+        self.exp_ct(k, Self::cfg(p))
+    }
 }
 
 #[cfg(test)]
@@ -145,6 +263,20 @@ mod tests {
         assert_eq!(CommutativeOp::exp(q, 1u64, &()).val, 7);
     }
     #[test]
+    fn exp_ref() {
+        let q = Q { val: 7 };
+        for n in 1..40u64 {
+            assert_eq!(q.exp_ref(n, &()).val, 7 * n as u32);
+            assert_eq!(q.exp_vartime(n, &()).val, 7 * n as u32);
+        }
+        assert_eq!(q.exp_ref(u8::MAX, &()).val, 7 * 255);
+    }
+    #[test]
+    #[should_panic]
+    fn exp_ref_zero() {
+        Q { val: 7 }.exp_ref(0u64, &());
+    }
+    #[test]
     #[should_panic]
     fn exp4() {
         let q = Q { val: 7 };
@@ -161,4 +293,35 @@ mod tests {
         let q = Q { val: 7 };
         assert_eq!(CommutativeMonoid::exp(q, 0u64, &()).val, 1234);
     }
+
+    #[test]
+    fn derived_newtype() {
+        use crate::{
+            algebra::{CommutativeOps, Field, Ring},
+            mod_field::{ModField, ModFieldCfg},
+        };
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Configurable, CommutativeOps)]
+        struct Fp(ModField<u64>);
+        impl Ring for Fp {}
+        impl Field for Fp {}
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Configurable, CommutativeOps)]
+        struct Named<I: crate::base_traits::Natural> {
+            inner: ModField<I>,
+        }
+        impl<I: crate::base_traits::Natural> Ring for Named<I> {}
+
+        let c = ModFieldCfg { rem: 19u64 };
+        let f = |v| Fp(ModField::new(v, &c));
+        assert_eq!(Fp::add(f(7), f(13), &c), f(1));
+        assert_eq!(Fp::div(f(11), f(5), &c), f(6));
+        assert_eq!(f(11).neg(&c), f(8));
+        assert_eq!(f(0).reciprocal(&c), None);
+        assert_eq!(Fp::one(&c).pow(5u8, &c), f(1));
+        let n = Named {
+            inner: ModField::new(7u64, &c),
+        };
+        assert_eq!(n.sqr(&c).inner, ModField::new(11, &c));
+    }
 }
@@ -0,0 +1,95 @@
+//! Property checks of the algebraic laws, for the tests of any implementation
+//! of the traits in [`crate::algebra`]: given a source of random elements and
+//! a config, each function checks its laws on `rounds` random triples and
+//! panics naming the first one that fails. A new backend gets its coverage
+//! from a single call, e.g. `laws::field(|| F::random(&mut rng, &c), &c, 50)`.
+//!
+//! The checks only compare with `==`, so types with several representations
+//! of one element need a canonical form first.
+
+use std::fmt::Debug;
+
+use super::{ops, AbelianGroup, CommutativeMonoid, CommutativeOp, Field, Ring};
+
+/// Associativity and commutativity of `Op`. For types like affine points,
+/// which have no identity and whose operation may panic on inverses.
+pub fn commutative_op<Op, T>(mut sample: impl FnMut() -> T, c: &T::Cfg, rounds: usize)
+where
+    T: CommutativeOp<Op> + PartialEq + Debug,
+{
+    let op = |a, b| <T as CommutativeOp<Op>>::op(a, b, c);
+    for _ in 0..rounds {
+        let (a, b, d) = (sample(), sample(), sample());
+        assert_eq!(op(a, b), op(b, a), "commutativity of {:?} and {:?}", a, b);
+        assert_eq!(
+            op(op(a, b), d),
+            op(a, op(b, d)),
+            "associativity of {:?}, {:?} and {:?}",
+            a,
+            b,
+            d
+        );
+    }
+}
+
+/// [`commutative_op`] and the identity.
+pub fn monoid<Op, T>(mut sample: impl FnMut() -> T, c: &T::Cfg, rounds: usize)
+where
+    T: CommutativeMonoid<Op> + PartialEq + Debug,
+{
+    commutative_op::<Op, T>(&mut sample, c, rounds);
+    let e = <T as super::Identity<Op>>::identity(c);
+    for _ in 0..rounds {
+        let a = sample();
+        assert_eq!(CommutativeOp::<Op>::op(a, e, c), a, "identity with {:?}", a);
+    }
+}
+
+/// [`monoid`] and inverses.
+pub fn abelian_group<Op, T>(mut sample: impl FnMut() -> T, c: &T::Cfg, rounds: usize)
+where
+    T: AbelianGroup<Op> + PartialEq + Debug,
+{
+    monoid::<Op, T>(&mut sample, c, rounds);
+    let e = <T as super::Identity<Op>>::identity(c);
+    for _ in 0..rounds {
+        let a = sample();
+        let inv = <T as super::Inverse<Op>>::inv(a, c);
+        assert_eq!(CommutativeOp::<Op>::op(a, inv, c), e, "inverse of {:?}", a);
+    }
+}
+
+/// The additive group, the multiplicative monoid and distributivity.
+pub fn ring<T: Ring + Debug>(mut sample: impl FnMut() -> T, c: &T::Cfg, rounds: usize) {
+    abelian_group::<ops::Add, T>(&mut sample, c, rounds);
+    monoid::<ops::Mul, T>(&mut sample, c, rounds);
+    for _ in 0..rounds {
+        let (a, b, d) = (sample(), sample(), sample());
+        assert_eq!(
+            T::mul(a, T::add(b, d, c), c),
+            T::add(T::mul(a, b, c), T::mul(a, d, c), c),
+            "distributivity of {:?} over {:?} and {:?}",
+            a,
+            b,
+            d
+        );
+        assert_eq!(T::add(T::sub(a, b, c), b, c), a, "{:?} - {:?}", a, b);
+        assert_eq!(T::mul(a, T::zero(c), c), T::zero(c), "{:?} · 0", a);
+    }
+}
+
+/// [`ring`] and reciprocals of everything but zero.
+pub fn field<T: Field + Debug>(mut sample: impl FnMut() -> T, c: &T::Cfg, rounds: usize) {
+    ring(&mut sample, c, rounds);
+    assert_eq!(T::zero(c).reciprocal(c), None, "0 has a reciprocal");
+    for _ in 0..rounds {
+        let a = sample();
+        if a == T::zero(c) {
+            continue;
+        }
+        let inv = a
+            .reciprocal(c)
+            .unwrap_or_else(|| panic!("{:?} has no reciprocal", a));
+        assert_eq!(T::mul(a, inv, c), T::one(c), "reciprocal of {:?}", a);
+    }
+}
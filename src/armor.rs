@@ -0,0 +1,136 @@
+//! ASCII armor in the style of OpenPGP (RFC 4880, section 6.2): base64 wrapped
+//! at 64 columns between BEGIN/END lines, with a CRC-24 checksum line, so keys
+//! and ciphertexts survive being pasted into email. Parsing ignores
+//! surrounding text, indentation, blank lines and `Key: value` headers.
+
+use crate::{
+    ct_codec::{base64_decode, base64_encode},
+    error::Error,
+};
+
+pub const MESSAGE: &str = "ECC MESSAGE";
+pub const PUBLIC_KEY: &str = "ECC PUBLIC KEY";
+pub const PRIVATE_KEY: &str = "ECC PRIVATE KEY";
+pub const IDENTITY: &str = "ECC IDENTITY";
+
+const LINE: usize = 64;
+
+/// OpenPGP's CRC-24.
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc = 0xB704CEu32;
+    for &b in data {
+        crc ^= (b as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864CFB;
+            }
+        }
+    }
+    crc & 0xFFFFFF
+}
+
+pub fn armor(label: &str, data: &[u8]) -> String {
+    let mut res = format!("-----BEGIN {}-----\n\n", label);
+    // private keys are armored too
+    let body = base64_encode(data);
+    for line in body.as_bytes().chunks(LINE) {
+        res.push_str(std::str::from_utf8(line).unwrap());
+        res.push('\n');
+    }
+    res.push('=');
+    res.push_str(&base64_encode(&crc24(data).to_be_bytes()[1..]));
+    res.push('\n');
+    res.push_str(&format!("-----END {}-----\n", label));
+    res
+}
+
+/// The label and data of the first armored block in `text`. The checksum line
+/// is optional, as in OpenPGP, but has to match if present.
+pub fn dearmor(text: &str) -> Result<(String, Vec<u8>), Error> {
+    let mut lines = text.lines().map(str::trim);
+    let label = lines
+        .find_map(|l| l.strip_prefix("-----BEGIN ")?.strip_suffix("-----"))
+        .ok_or(Error::Armor)?;
+    let end = format!("-----END {}-----", label);
+    let mut body = String::new();
+    let mut checksum = None;
+    for line in lines {
+        if line == end {
+            let data = base64_decode(&body)?;
+            return match checksum {
+                Some(sum) if sum != crc24(&data) => Err(Error::ArmorChecksum),
+                _ => Ok((label.to_string(), data)),
+            };
+        }
+        if let Some(sum) = line.strip_prefix('=').filter(|s| s.len() == 4) {
+            let sum = base64_decode(sum).map_err(|_| Error::ArmorChecksum)?;
+            checksum = Some(u32::from_be_bytes([0, sum[0], sum[1], sum[2]]));
+        } else if !line.contains(": ") {
+            body.extend(line.split_whitespace());
+        }
+    }
+    Err(Error::Armor)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+
+    use super::{armor, crc24, dearmor, MESSAGE, PUBLIC_KEY};
+
+    #[test]
+    fn crc() {
+        // CRC-24/OPENPGP check value
+        assert_eq!(crc24(b"123456789"), 0x21CF02);
+    }
+
+    #[test]
+    fn round_trip() {
+        for len in [0, 1, 47, 48, 49, 200] {
+            let data = (0..len).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+            let armored = armor(MESSAGE, &data);
+            assert!(armored.starts_with("-----BEGIN ECC MESSAGE-----\n"));
+            assert!(armored.ends_with("-----END ECC MESSAGE-----\n"));
+            assert!(armored.lines().all(|l| l.len() <= 64));
+            assert_eq!(dearmor(&armored), Ok((MESSAGE.to_string(), data)));
+        }
+    }
+
+    #[test]
+    fn tolerant_parsing() {
+        let data = b"some key material, long enough to wrap over two lines at least".repeat(2);
+        let armored = armor(PUBLIC_KEY, &data);
+        // quoted in an email: indented, CRLF, headers, text around
+        let mangled = format!(
+            "Hi,\r\nhere is my key:\r\n\r\n{}\r\nBye\r\n",
+            armored
+                .lines()
+                .map(|l| format!("   {} ", l))
+                .collect::<Vec<_>>()
+                .join("\r\n")
+                .replacen("-----\r\n", "-----\r\n Comment: test key\r\n", 1)
+        );
+        assert_eq!(
+            dearmor(&mangled),
+            Ok((PUBLIC_KEY.to_string(), data.clone()))
+        );
+
+        let no_checksum = armored
+            .lines()
+            .filter(|l| !l.starts_with('='))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(dearmor(&no_checksum), Ok((PUBLIC_KEY.to_string(), data)));
+    }
+
+    #[test]
+    fn errors() {
+        let armored = armor(MESSAGE, b"hello!");
+        let flipped = armored.replacen("aGVsbG8h", "aGVsbG8i", 1);
+        assert_eq!(dearmor(&flipped), Err(Error::ArmorChecksum));
+        let truncated = armored.replace("-----END ECC MESSAGE-----", "");
+        assert_eq!(dearmor(&truncated), Err(Error::Armor));
+        assert_eq!(dearmor("aGVsbG8="), Err(Error::Armor));
+    }
+}
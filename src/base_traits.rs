@@ -4,10 +4,9 @@ use std::{
 };
 
 use base64::prelude::*;
-use primitive_types::U256;
 use rand::Rng;
 
-use crate::algebra::Configurable;
+use crate::{algebra::Configurable, error::Error};
 
 pub trait Natural:
     Sized
@@ -26,6 +25,63 @@ pub trait Natural:
         Self::one() + Self::one()
     }
     fn max() -> Self;
+
+    /// The wrapped sum and whether it wrapped.
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+    /// The wrapped difference and whether it borrowed.
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+    /// `a` if `choice`, else `b`, without branching on `choice`.
+    fn ct_select(a: Self, b: Self, choice: bool) -> Self;
+
+    /// The lowest `n ≤ 32` bits.
+    fn low_bits(self, n: u32) -> u32 {
+        let mut v = self;
+        let mut out = 0;
+        for i in 0..n {
+            if v % Self::two() == Self::one() {
+                out |= 1 << i;
+            }
+            v = v / Self::two();
+        }
+        out
+    }
+
+    /// `⌊self / 2^n⌋`
+    fn drop_bits(self, n: u32) -> Self {
+        (0..n).fold(self, |v, _| v / Self::two())
+    }
+
+    /// `self + rhs mod m` for `self, rhs < m`, without overflowing.
+    fn add_mod(self, rhs: Self, m: Self) -> Self {
+        if self >= m - rhs {
+            self - (m - rhs)
+        } else {
+            self + rhs
+        }
+    }
+
+    /// `self · rhs mod m` for `self, rhs < m`. By default doubles and adds,
+    /// reducing as it goes so nothing overflows; types with a double-width
+    /// product should do better.
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        let (mut acc, mut base, mut k) = (Self::zero(), self, rhs);
+        while k != Self::zero() {
+            if k % Self::two() == Self::one() {
+                acc = acc.add_mod(base, m);
+            }
+            base = base.add_mod(base, m);
+            k = k / Self::two();
+        }
+        acc
+    }
+
+    /// `Σ aᵢ · bᵢ mod m` for factors below `m`. Types with a double-width
+    /// product can add the products unreduced and reduce once at the end.
+    fn sum_of_products_mod(pairs: impl IntoIterator<Item = (Self, Self)>, m: Self) -> Self {
+        pairs
+            .into_iter()
+            .fold(Self::zero(), |acc, (a, b)| acc.add_mod(a.mul_mod(b, m), m))
+    }
 }
 
 pub trait FromRandom<C> {
@@ -44,12 +100,76 @@ impl<T> FromRandom<T> for u128 {
     }
 }
 
+/// Byte order of integers. [`RW`] itself is little-endian, SEC1, DER and
+/// most standards are big-endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
 pub trait RW: Sized {
     const LEN: usize;
 
     fn to_bytes(self, w: &mut impl Write) -> usize;
     fn from_bytes(r: &mut impl Read) -> Self;
 
+    /// [`RW::to_bytes`] with every integer in the given byte order. The
+    /// default reverses the whole encoding, which is only right for a single
+    /// integer; types made of several order each part on its own.
+    fn to_bytes_endian(self, e: Endianness, w: &mut impl Write) -> usize {
+        match e {
+            Endianness::Little => self.to_bytes(w),
+            Endianness::Big => {
+                let mut buf = self.to_vec();
+                buf.reverse();
+                w.write(&buf).unwrap()
+            }
+        }
+    }
+
+    fn from_bytes_endian(r: &mut impl Read, e: Endianness) -> Self {
+        match e {
+            Endianness::Little => Self::from_bytes(r),
+            Endianness::Big => {
+                let mut buf = vec![0; Self::LEN];
+                r.read_exact(&mut buf).unwrap();
+                buf.reverse();
+                Self::from_bytes(&mut buf.as_slice())
+            }
+        }
+    }
+
+    fn to_vec_endian(self, e: Endianness) -> Vec<u8> {
+        let mut buf = vec![];
+        self.to_bytes_endian(e, &mut buf);
+        buf
+    }
+
+    /// Serializes into a stack buffer instead of a growable writer.
+    fn to_array(self) -> [u8; Self::LEN]
+    where
+        [(); Self::LEN]:,
+    {
+        let mut buf = [0u8; Self::LEN];
+        let len = self.to_bytes(&mut buf.as_mut_slice());
+        debug_assert_eq!(len, Self::LEN);
+        buf
+    }
+
+    fn to_vec(self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.to_bytes(&mut buf);
+        buf
+    }
+
+    fn from_array(bytes: [u8; Self::LEN]) -> Self
+    where
+        [(); Self::LEN]:,
+    {
+        Self::from_bytes(&mut bytes.as_slice())
+    }
+
     fn to_base64(self) -> String {
         let mut buf = vec![];
         let len = self.to_bytes(&mut buf);
@@ -61,6 +181,24 @@ pub trait RW: Sized {
         let mut cur = Cursor::new(&decoded);
         Self::from_bytes(&mut cur)
     }
+
+    /// Like `from_base64`, but rejects invalid base64 and inputs of the wrong length.
+    fn try_from_base64(base64: &str) -> Result<Self, Error> {
+        let decoded = BASE64_STANDARD.decode(base64).map_err(|_| Error::Base64)?;
+        if decoded.len() != Self::LEN {
+            return Err(Error::Length {
+                expected: Self::LEN,
+                actual: decoded.len(),
+            });
+        }
+        let mut cur = Cursor::new(&decoded);
+        Ok(Self::from_bytes(&mut cur))
+    }
+}
+
+/// Parity of the canonical integer representation, as used by SEC1 point compression.
+pub trait Parity: Copy {
+    fn is_odd(self) -> bool;
 }
 
 pub trait Capacitor: Configurable {
@@ -78,9 +216,9 @@ macro_rules! impl_stuff {
             }
 
             fn from_bytes(r: &mut impl Read) -> Self {
-                let mut buf = vec![0u8; size_of::<Self>()];
+                let mut buf = [0u8; size_of::<Self>()];
                 r.read_exact(&mut buf).unwrap();
-                Self::from_le_bytes(buf.try_into().unwrap())
+                Self::from_le_bytes(buf)
             }
         }
 
@@ -96,6 +234,27 @@ macro_rules! impl_stuff {
             fn max() -> Self {
                 $ty::MAX
             }
+
+            fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+                $ty::overflowing_add(self, rhs)
+            }
+
+            fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+                $ty::overflowing_sub(self, rhs)
+            }
+
+            fn ct_select(a: Self, b: Self, choice: bool) -> Self {
+                let mask = std::hint::black_box(choice as $ty).wrapping_neg();
+                b ^ ((a ^ b) & mask)
+            }
+
+            fn low_bits(self, n: u32) -> u32 {
+                (self as u128 & ((1u128 << n) - 1)) as u32
+            }
+
+            fn drop_bits(self, n: u32) -> Self {
+                self.checked_shr(n).unwrap_or(0)
+            }
         }
     };
 }
@@ -104,47 +263,104 @@ impl_stuff!(u64);
 impl_stuff!(u128);
 impl_stuff!(u8);
 
-impl Natural for U256 {
-    fn zero() -> Self {
-        U256::zero()
+impl<A: RW, B: RW> RW for (A, B) {
+    const LEN: usize = A::LEN + B::LEN;
+
+    fn to_bytes(self, w: &mut impl Write) -> usize {
+        self.0.to_bytes(w) + self.1.to_bytes(w)
+    }
+
+    fn from_bytes(r: &mut impl Read) -> Self {
+        (A::from_bytes(r), B::from_bytes(r))
     }
 
-    fn one() -> Self {
-        U256::one()
+    fn to_bytes_endian(self, e: Endianness, w: &mut impl Write) -> usize {
+        self.0.to_bytes_endian(e, w) + self.1.to_bytes_endian(e, w)
     }
 
-    fn max() -> Self {
-        U256::MAX
+    fn from_bytes_endian(r: &mut impl Read, e: Endianness) -> Self {
+        (A::from_bytes_endian(r, e), B::from_bytes_endian(r, e))
     }
 }
 
-impl RW for U256 {
-    const LEN: usize = size_of::<U256>();
+/// Writes `items` as a little-endian `u32` count, then the items one after
+/// another. Read them back with [`slice_from_bytes`].
+pub fn slice_to_bytes<T: RW + Copy>(items: &[T], w: &mut impl Write) -> usize {
+    slice_to_bytes_endian(items, Endianness::Little, w)
+}
 
-    fn to_bytes(self, w: &mut impl Write) -> usize {
-        w.write(&self.to_little_endian()).unwrap()
-    }
+/// [`slice_to_bytes`] with the count and the items in the given byte order.
+pub fn slice_to_bytes_endian<T: RW + Copy>(
+    items: &[T],
+    e: Endianness,
+    w: &mut impl Write,
+) -> usize {
+    let count = u32::try_from(items.len()).expect("too many items for a u32 count");
+    let count = match e {
+        Endianness::Little => count.to_le_bytes(),
+        Endianness::Big => count.to_be_bytes(),
+    };
+    w.write(&count).unwrap() + items.iter().map(|t| t.to_bytes_endian(e, w)).sum::<usize>()
+}
 
-    fn from_bytes(r: &mut impl Read) -> Self {
-        let mut buf = vec![0u8; size_of::<Self>()];
-        r.read_exact(&mut buf).unwrap();
-        Self::from_little_endian(&buf)
+/// Reads what [`slice_to_bytes`] wrote from the front of `r`, leaving the
+/// rest. The items have to be of a fixed length, so this isn't an `RW` impl:
+/// the encoding has no fixed `LEN`. [`Error::Length`] if `r` is shorter than
+/// the count says, with the lengths counted from the count.
+pub fn slice_from_bytes<T: RW>(r: &mut &[u8]) -> Result<Vec<T>, Error> {
+    slice_from_bytes_endian(r, Endianness::Little)
+}
+
+/// [`slice_from_bytes`] with the count and the items in the given byte order.
+pub fn slice_from_bytes_endian<T: RW>(r: &mut &[u8], e: Endianness) -> Result<Vec<T>, Error> {
+    let (&count, rest) = r.split_first_chunk().ok_or(Error::Length {
+        expected: size_of::<u32>(),
+        actual: r.len(),
+    })?;
+    let count = match e {
+        Endianness::Little => u32::from_le_bytes(count),
+        Endianness::Big => u32::from_be_bytes(count),
+    };
+    let len = (count as usize).saturating_mul(T::LEN);
+    if rest.len() < len {
+        return Err(Error::Length {
+            expected: len.saturating_add(size_of::<u32>()),
+            actual: r.len(),
+        });
     }
+    let (mut items, rest) = rest.split_at(len);
+    *r = rest;
+    Ok((0..count)
+        .map(|_| T::from_bytes_endian(&mut items, e))
+        .collect())
 }
 
-impl FromRandom<()> for U256 {
-    fn random(rng: &mut impl Rng, cfg: &()) -> Self {
-        let l1: U256 = u128::random(rng, cfg).into();
-        let l2: U256 = u128::random(rng, cfg).into();
-        (l1 << 128) + l2
+/// Like [`slice_from_bytes`] on decoded base64, but rejects invalid base64 and
+/// bytes after the items.
+pub fn try_slice_from_base64<T: RW>(base64: &str) -> Result<Vec<T>, Error> {
+    let decoded = BASE64_STANDARD.decode(base64).map_err(|_| Error::Base64)?;
+    let mut rest = decoded.as_slice();
+    let items = slice_from_bytes(&mut rest)?;
+    if !rest.is_empty() {
+        return Err(Error::Length {
+            expected: decoded.len() - rest.len(),
+            actual: decoded.len(),
+        });
     }
+    Ok(items)
 }
 
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
-    use super::RW;
+    use base64::prelude::*;
+
+    use super::{
+        slice_from_bytes, slice_from_bytes_endian, slice_to_bytes, slice_to_bytes_endian,
+        try_slice_from_base64, Endianness, RW,
+    };
+    use crate::{error::Error, uint::U256};
 
     #[test]
     fn data_persistance() {
@@ -155,4 +371,82 @@ mod tests {
         let c = u128::from_bytes(&mut cur);
         assert_eq!(n, c);
     }
+
+    #[test]
+    fn array_matches_writer() {
+        let n = U256::from(0x0123_4567_89AB_CDEFu64) << 100;
+        let mut buf = vec![];
+        n.to_bytes(&mut buf);
+        assert_eq!(n.to_array()[..], buf[..]);
+        assert_eq!(U256::from_array(n.to_array()), n);
+        assert_eq!(u64::from_array(7u64.to_array()), 7);
+    }
+
+    #[test]
+    fn endianness() {
+        let n = 0x0102_0304u64;
+        assert_eq!(n.to_vec_endian(Endianness::Big), [0, 0, 0, 0, 1, 2, 3, 4]);
+        assert_eq!(n.to_vec_endian(Endianness::Little), n.to_vec());
+        let big = U256::from(0x0102u64) << 200;
+        assert_eq!(big.to_vec_endian(Endianness::Big), big.to_big_endian());
+        assert_eq!(
+            U256::from_bytes_endian(&mut big.to_big_endian().as_slice(), Endianness::Big),
+            big
+        );
+
+        // each integer is reversed on its own, not the whole encoding
+        let items = vec![(1u8, 2u64)];
+        let mut encoded = vec![];
+        slice_to_bytes_endian(&items, Endianness::Big, &mut encoded);
+        assert_eq!(encoded, [0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(
+            slice_from_bytes_endian::<(u8, u64)>(&mut encoded.as_slice(), Endianness::Big),
+            Ok(items)
+        );
+    }
+
+    #[test]
+    fn collections() {
+        let pairs = vec![(1u8, 2u64), (3, 4)];
+        let mut buf = vec![];
+        assert_eq!(slice_to_bytes(&pairs, &mut buf), 4 + 2 * 9);
+        assert_eq!(buf[..4], [2, 0, 0, 0]);
+        // what follows the items is left for the caller
+        buf.push(0xAA);
+        let mut rest = buf.as_slice();
+        assert_eq!(slice_from_bytes::<(u8, u64)>(&mut rest), Ok(pairs));
+        assert_eq!(rest, [0xAA]);
+        buf.pop();
+
+        let base64 = BASE64_STANDARD.encode(&buf);
+        assert_eq!(
+            try_slice_from_base64::<(u8, u64)>(&base64).unwrap().len(),
+            2
+        );
+        let short = BASE64_STANDARD.encode(&buf[..buf.len() - 1]);
+        assert_eq!(
+            try_slice_from_base64::<(u8, u64)>(&short),
+            Err(Error::Length {
+                expected: 22,
+                actual: 21
+            })
+        );
+        assert_eq!(
+            try_slice_from_base64::<u8>(""),
+            Err(Error::Length {
+                expected: 4,
+                actual: 0
+            })
+        );
+        let long = BASE64_STANDARD.encode([&buf[..], &[0]].concat());
+        assert_eq!(
+            try_slice_from_base64::<(u8, u64)>(&long),
+            Err(Error::Length {
+                expected: 22,
+                actual: 23
+            })
+        );
+        // a count far beyond the input fails before reading anything
+        assert!(slice_from_bytes::<U256>(&mut &[0xFF, 0xFF, 0xFF, 0xFF, 1][..]).is_err());
+    }
 }
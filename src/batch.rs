@@ -0,0 +1,160 @@
+//! Batched field arithmetic: `N` independent operations at once, for inner
+//! loops where many unrelated multiplications happen side by side. Lanes are
+//! plain arrays, so the compiler is free to vectorize them; with the `simd`
+//! feature `ModField<u64>` over moduli below 2^32 uses `std::simd` explicitly.
+
+use std::array;
+
+use crate::{algebra::Field, mod_field::ModField, uint::U256};
+
+pub trait BatchField: Field {
+    fn add_batch<const N: usize>(a: [Self; N], b: [Self; N], cfg: &Self::Cfg) -> [Self; N] {
+        array::from_fn(|i| Self::add(a[i], b[i], cfg))
+    }
+
+    fn sub_batch<const N: usize>(a: [Self; N], b: [Self; N], cfg: &Self::Cfg) -> [Self; N] {
+        array::from_fn(|i| Self::sub(a[i], b[i], cfg))
+    }
+
+    fn mul_batch<const N: usize>(a: [Self; N], b: [Self; N], cfg: &Self::Cfg) -> [Self; N] {
+        array::from_fn(|i| Self::mul(a[i], b[i], cfg))
+    }
+
+    fn sqr_batch<const N: usize>(a: [Self; N], cfg: &Self::Cfg) -> [Self; N] {
+        Self::mul_batch(a, a, cfg)
+    }
+}
+
+/// Inverts all elements with a single field inversion (Montgomery's trick):
+/// invert the product of all, then peel off one factor at a time. `None` if any
+/// element is zero.
+pub fn batch_inverse<F: Field>(xs: &[F], cfg: &F::Cfg) -> Option<Vec<F>> {
+    // prefix[i] = xs[0] · ... · xs[i - 1]
+    let mut prefix = Vec::with_capacity(xs.len());
+    let mut acc = F::one(cfg);
+    for &x in xs {
+        prefix.push(acc);
+        acc = F::mul(acc, x, cfg);
+    }
+    // acc^-1 = (xs[0] · ... · xs[i])^-1 going backwards
+    let mut acc = acc.reciprocal(cfg)?;
+    let mut out = vec![F::zero(cfg); xs.len()];
+    for i in (0..xs.len()).rev() {
+        out[i] = F::mul(acc, prefix[i], cfg);
+        acc = F::mul(acc, xs[i], cfg);
+    }
+    Some(out)
+}
+
+impl BatchField for ModField<u8> {}
+impl BatchField for ModField<u128> {}
+impl BatchField for ModField<U256> {}
+
+impl BatchField for ModField<u64> {
+    fn mul_batch<const N: usize>(a: [Self; N], b: [Self; N], cfg: &Self::Cfg) -> [Self; N] {
+        #[cfg(feature = "simd")]
+        if cfg.rem <= 1 << 32 {
+            return simd::mul_small(a, b, cfg);
+        }
+        // one widening multiplication per lane instead of double-and-add
+        array::from_fn(|i| {
+            let prod = a[i].nat() as u128 * b[i].nat() as u128;
+            ModField::new((prod % cfg.rem as u128) as u64, cfg)
+        })
+    }
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use std::simd::Simd;
+
+    use crate::mod_field::{ModField, ModFieldCfg};
+
+    const LANES: usize = 4;
+
+    /// Products of values below 2^32 fit into a u64 lane, so the whole
+    /// multiplication and reduction stays in vector registers.
+    pub fn mul_small<const N: usize>(
+        a: [ModField<u64>; N],
+        b: [ModField<u64>; N],
+        cfg: &ModFieldCfg<u64>,
+    ) -> [ModField<u64>; N] {
+        let rem = Simd::<u64, LANES>::splat(cfg.rem);
+        let mut out = a;
+        for (i, chunk) in out.chunks_mut(LANES).enumerate() {
+            let lanes =
+                |v: &[ModField<u64>; N]| Simd::<u64, LANES>::from_array(array_lanes(v, i * LANES));
+            let prod = (lanes(&a) * lanes(&b)) % rem;
+            for (x, p) in chunk.iter_mut().zip(prod.to_array()) {
+                *x = ModField::new(p, cfg);
+            }
+        }
+        out
+    }
+
+    /// Lanes `start..start + LANES`, zero-padded past the end.
+    fn array_lanes<const N: usize>(v: &[ModField<u64>; N], start: usize) -> [u64; LANES] {
+        std::array::from_fn(|j| v.get(start + j).map_or(0, |x| x.nat()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{Field, Ring},
+        base_traits::FromRandom,
+        mod_field::{ModField, ModFieldCfg},
+    };
+
+    use super::{batch_inverse, BatchField};
+
+    fn check<const N: usize>(cfg: &ModFieldCfg<u64>) {
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for _ in 0..50 {
+            let a: [ModField<u64>; N] = std::array::from_fn(|_| ModField::random(&mut gen, cfg));
+            let b: [ModField<u64>; N] = std::array::from_fn(|_| ModField::random(&mut gen, cfg));
+            let mul = ModField::mul_batch(a, b, cfg);
+            let add = ModField::add_batch(a, b, cfg);
+            let sub = ModField::sub_batch(a, b, cfg);
+            let sqr = ModField::sqr_batch(a, cfg);
+            for i in 0..N {
+                assert_eq!(mul[i], Ring::mul(a[i], b[i], cfg));
+                assert_eq!(add[i], Ring::add(a[i], b[i], cfg));
+                assert_eq!(sub[i], Ring::sub(a[i], b[i], cfg));
+                assert_eq!(sqr[i], a[i].sqr(cfg));
+            }
+        }
+    }
+
+    #[test]
+    fn inverse() {
+        let cfg = ModFieldCfg {
+            rem: 0x0014_4C3B_27FFu64,
+        };
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let xs = (0..20)
+            .map(|_| ModField::random_nonzero(&mut gen, &cfg))
+            .collect::<Vec<_>>();
+        let invs = batch_inverse(&xs, &cfg).unwrap();
+        for (x, inv) in xs.iter().zip(&invs) {
+            assert_eq!(Some(*inv), x.reciprocal(&cfg));
+        }
+        assert_eq!(batch_inverse::<ModField<u64>>(&[], &cfg), Some(vec![]));
+        let with_zero = [xs[0], ModField::zero(&cfg), xs[1]];
+        assert_eq!(batch_inverse(&with_zero, &cfg), None);
+    }
+
+    #[test]
+    fn matches_scalar_ops() {
+        // above and below the 2^32 bound of the simd path
+        for rem in [0x0014_4C3B_27FF, 4294967291, 65521] {
+            let cfg = ModFieldCfg { rem };
+            check::<1>(&cfg);
+            check::<4>(&cfg);
+            check::<7>(&cfg);
+            check::<8>(&cfg);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Runs the criterion suite (`benches/ecc.rs`) and prints ns/op of every
+//! operation per curve backend side by side. Arguments are passed on to
+//! `cargo bench`, e.g. `cargo run --release --bin bench-report -- --features qr`;
+//! `--no-run` only prints the results of the last run, `--json` prints them as
+//! `{"op": {"backend": ns, ...}, ...}`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const OPS: [&str; 8] = [
+    "field_mul",
+    "field_inv",
+    "field_sqrt",
+    "field_random",
+    "point_add",
+    "scalar_mul",
+    "keygen",
+    "encrypt",
+];
+
+fn criterion_dir() -> PathBuf {
+    let target = env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+    Path::new(&target).join("criterion")
+}
+
+/// Mean ns/iteration from criterion's `estimates.json`, without pulling in a
+/// JSON parser for a single number.
+fn mean_ns(estimates: &str) -> Option<f64> {
+    let mean = &estimates[estimates.find("\"mean\"")?..];
+    let value = &mean[mean.find("\"point_estimate\":")? + "\"point_estimate\":".len()..];
+    let end = value.find([',', '}'])?;
+    value[..end].trim().parse().ok()
+}
+
+fn format_ns(ns: f64) -> String {
+    match ns {
+        ns if ns < 1e3 => format!("{:.1} ns", ns),
+        ns if ns < 1e6 => format!("{:.1} µs", ns / 1e3),
+        ns if ns < 1e9 => format!("{:.1} ms", ns / 1e6),
+        ns => format!("{:.2} s", ns / 1e9),
+    }
+}
+
+fn to_json(rows: &[(&str, Vec<(String, f64)>)]) -> String {
+    let ops = rows
+        .iter()
+        .map(|(op, row)| {
+            let cells = row
+                .iter()
+                .map(|(backend, ns)| format!(r#""{}": {}"#, backend, ns))
+                .collect::<Vec<_>>();
+            format!(r#""{}": {{{}}}"#, op, cells.join(", "))
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", ops.join(", "))
+}
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let no_run = args.iter().any(|a| a == "--no-run");
+    let json = args.iter().any(|a| a == "--json");
+    args.retain(|a| a != "--no-run" && a != "--json");
+    if !no_run {
+        let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+            .args(["bench", "--bench", "ecc"])
+            .args(&args)
+            .status()
+            .expect("failed to run cargo bench");
+        assert!(status.success(), "cargo bench failed");
+    }
+
+    let dir = criterion_dir();
+    let mut backends = vec![];
+    let mut rows = vec![];
+    for op in OPS {
+        let mut row = vec![];
+        for entry in fs::read_dir(dir.join(op)).into_iter().flatten().flatten() {
+            let backend = entry.file_name().to_string_lossy().into_owned();
+            let Ok(json) = fs::read_to_string(entry.path().join("new/estimates.json")) else {
+                continue;
+            };
+            if let Some(ns) = mean_ns(&json) {
+                if !backends.contains(&backend) {
+                    backends.push(backend.clone());
+                }
+                row.push((backend, ns));
+            }
+        }
+        rows.push((op, row));
+    }
+    if backends.is_empty() {
+        eprintln!("no criterion results in {}", dir.display());
+        std::process::exit(1);
+    }
+    backends.sort();
+    if json {
+        println!("{}", to_json(&rows));
+        return;
+    }
+
+    let width = backends.iter().map(|b| b.len()).max().unwrap().max(10);
+    print!("{:<12}", "op");
+    for backend in &backends {
+        print!(" {:>width$}", backend);
+    }
+    println!();
+    for (op, row) in rows {
+        print!("{:<12}", op);
+        for backend in &backends {
+            let cell = row
+                .iter()
+                .find(|(b, _)| b == backend)
+                .map_or("-".to_string(), |&(_, ns)| format_ns(ns));
+            print!(" {:>width$}", cell);
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_ns, mean_ns, to_json};
+
+    #[test]
+    fn parse_estimates() {
+        let json = r#"{"mean":{"confidence_interval":{"confidence_level":0.95,"lower_bound":10.5,"upper_bound":12.0},"point_estimate":11.25,"standard_error":0.3},"median":{"point_estimate":9.0}}"#;
+        assert_eq!(mean_ns(json), Some(11.25));
+        assert_eq!(mean_ns("{}"), None);
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(format_ns(12.34), "12.3 ns");
+        assert_eq!(format_ns(45_600.0), "45.6 µs");
+        assert_eq!(format_ns(9.1e9), "9.10 s");
+    }
+
+    #[test]
+    fn json() {
+        let rows = [
+            (
+                "field_mul",
+                vec![("toy".to_string(), 3.5), ("secp256k1".to_string(), 40.0)],
+            ),
+            ("keygen", vec![]),
+        ];
+        assert_eq!(
+            to_json(&rows),
+            r#"{"field_mul": {"toy": 3.5, "secp256k1": 40}, "keygen": {}}"#
+        );
+    }
+}
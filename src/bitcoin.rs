@@ -0,0 +1,358 @@
+use std::io::Cursor;
+
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::Field,
+    base_traits::{Endianness, Parity, RW},
+    ecc::{PrivateKey, PublicKey},
+    points_group::Point,
+};
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    fn wif_prefix(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x80,
+            Network::Testnet => 0xEF,
+        }
+    }
+
+    fn p2pkh_prefix(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet => 0x6F,
+        }
+    }
+
+    fn hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet => "tb",
+        }
+    }
+
+    fn from_wif_prefix(prefix: u8) -> Option<Self> {
+        [Network::Mainnet, Network::Testnet]
+            .into_iter()
+            .find(|n| n.wif_prefix() == prefix)
+    }
+}
+
+fn sha256d(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(bytes)).into()
+}
+
+fn hash160(bytes: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(bytes)).into()
+}
+
+pub fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    // base58 digits, least significant first
+    let mut digits: Vec<u8> = vec![];
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut res = "1".repeat(zeros);
+    res.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&d| BASE58_ALPHABET[d as usize] as char),
+    );
+    res
+}
+
+pub fn base58_decode(s: &str) -> Option<Vec<u8>> {
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    // bytes, least significant first
+    let mut bytes: Vec<u8> = vec![];
+    for c in s.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for b in bytes.iter_mut() {
+            carry += (*b as u32) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut res = vec![0u8; zeros];
+    res.extend(bytes.iter().rev());
+    Some(res)
+}
+
+pub fn base58check_encode(payload: &[u8]) -> String {
+    let mut buf = payload.to_vec();
+    buf.extend_from_slice(&sha256d(payload)[..4]);
+    base58_encode(&buf)
+}
+
+pub fn base58check_decode(s: &str) -> Option<Vec<u8>> {
+    let mut buf = base58_decode(s)?;
+    if buf.len() < 4 {
+        return None;
+    }
+    let checksum = buf.split_off(buf.len() - 4);
+    if sha256d(&buf)[..4] != checksum[..] {
+        return None;
+    }
+    Some(buf)
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut chk = 1u32;
+    for &v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+/// Regroups 8-bit bytes into 5-bit bech32 words, zero-padding the tail.
+pub(crate) fn to_words(bytes: &[u8]) -> Vec<u8> {
+    let mut res = vec![];
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &b in bytes {
+        acc = (acc << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            res.push(((acc >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        res.push(((acc << (5 - bits)) & 31) as u8);
+    }
+    res
+}
+
+/// Regroups 5-bit words back into bytes; `None` if the padding isn't zero.
+pub(crate) fn from_words(words: &[u8]) -> Option<Vec<u8>> {
+    let mut res = vec![];
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &w in words {
+        acc = (acc << 5) | w as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((acc >> bits) as u8);
+        }
+    }
+    (bits < 5 && acc & ((1 << bits) - 1) == 0).then_some(res)
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut values: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|c| c & 31));
+    values
+}
+
+/// BIP-173 bech32 (not bech32m) of 5-bit words, lowercase.
+pub(crate) fn bech32_encode(hrp: &str, mut data: Vec<u8>) -> String {
+    let mut values = hrp_expand(hrp);
+    values.extend(&data);
+    values.extend([0u8; 6]);
+    let polymod = bech32_polymod(&values) ^ 1;
+    data.extend((0..6).map(|i| ((polymod >> (5 * (5 - i))) & 31) as u8));
+
+    let mut res = format!("{}1", hrp);
+    res.extend(data.iter().map(|&d| BECH32_CHARSET[d as usize] as char));
+    res
+}
+
+/// Splits bech32 into the lowercase human-readable part and the 5-bit words,
+/// checking the checksum. Either case is accepted, mixed case is not.
+pub(crate) fn bech32_decode(s: &str) -> Option<(String, Vec<u8>)> {
+    if s.chars().any(|c| c.is_ascii_lowercase()) && s.chars().any(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let s = s.to_ascii_lowercase();
+    let (hrp, data) = s.rsplit_once('1')?;
+    if hrp.is_empty() || data.len() < 6 {
+        return None;
+    }
+    let words = data
+        .bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|&d| d == c).map(|i| i as u8))
+        .collect::<Option<Vec<_>>>()?;
+    let mut values = hrp_expand(hrp);
+    values.extend(&words);
+    if bech32_polymod(&values) != 1 {
+        return None;
+    }
+    Some((hrp.to_string(), words[..words.len() - 6].to_vec()))
+}
+
+/// BIP-173 segwit v0 address (bech32, not bech32m).
+fn segwit_v0_address(hrp: &str, program: &[u8]) -> String {
+    let mut data = vec![0u8];
+    data.extend(to_words(program));
+    bech32_encode(hrp, data)
+}
+
+impl<I: RW> PrivateKey<I> {
+    /// Exports the key as a compressed-pubkey WIF string.
+    pub fn wif(self, net: Network) -> String {
+        assert!(I::LEN <= 32);
+        let mut key = vec![0; 32 - I::LEN];
+        self.to_bytes_endian(Endianness::Big, &mut key);
+
+        let mut payload = vec![net.wif_prefix()];
+        payload.extend(key);
+        payload.push(0x01);
+        base58check_encode(&payload)
+    }
+
+    /// Imports a compressed-pubkey WIF string. Returns `None` on a bad checksum,
+    /// an unknown prefix, or a key which does not fit into `I`.
+    pub fn from_wif(wif: &str) -> Option<(Self, Network)> {
+        let payload = base58check_decode(wif)?;
+        if payload.len() != 34 || payload[33] != 0x01 {
+            return None;
+        }
+        let net = Network::from_wif_prefix(payload[0])?;
+        let key = &payload[1..33];
+        if I::LEN > 32 || key[..32 - I::LEN].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let mut cur = Cursor::new(&key[32 - I::LEN..]);
+        Some((Self::from_bytes_endian(&mut cur, Endianness::Big), net))
+    }
+}
+
+impl<F: Field + RW + Parity> PublicKey<Point<F>> {
+    pub fn p2pkh_address(self, net: Network) -> String {
+        let mut payload = vec![net.p2pkh_prefix()];
+        payload.extend(hash160(&self.point().to_sec1_compressed()));
+        base58check_encode(&payload)
+    }
+
+    pub fn p2wpkh_address(self, net: Network) -> String {
+        segwit_v0_address(net.hrp(), &hash160(&self.point().to_sec1_compressed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        base_traits::RW,
+        curves::secp256k1,
+        ecc::{PrivateKey, PublicKey},
+        mod_field::ModField,
+        points_group::Point,
+        uint::U256,
+    };
+
+    use super::{base58_decode, base58_encode, base58check_decode, Network};
+
+    // private key 1, i.e. the public key is G
+    const WIF_ONE: &str = "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn";
+
+    fn one() -> PrivateKey<U256> {
+        let mut key = [0u8; 32];
+        key[0] = 1;
+        PrivateKey::from_bytes(&mut key.as_slice())
+    }
+
+    #[test]
+    fn base58_back_forth() {
+        for bytes in [
+            &[][..],
+            &[0, 0, 1, 2, 3],
+            &[255; 40],
+            &[0, 0x3c, 0x17, 0x6e],
+        ] {
+            assert_eq!(base58_decode(&base58_encode(bytes)).unwrap(), bytes);
+        }
+        assert_eq!(base58_encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+    }
+
+    #[test]
+    fn base58check_rejects_typo() {
+        assert!(base58check_decode(WIF_ONE).is_some());
+        assert!(base58check_decode(&WIF_ONE.replace("W", "V")).is_none());
+        assert!(base58_decode("0OIl").is_none());
+    }
+
+    #[test]
+    fn wif() {
+        assert_eq!(one().wif(Network::Mainnet), WIF_ONE);
+        assert_eq!(
+            one().wif(Network::Testnet),
+            "cMahea7zqjxrtgAbB7LSGbcQUr1uX1ojuat9jZodMN87JcbXMTcA"
+        );
+        assert_eq!(
+            PrivateKey::from_wif(WIF_ONE),
+            Some((one(), Network::Mainnet))
+        );
+        let testnet = one().wif(Network::Testnet);
+        assert_eq!(
+            PrivateKey::from_wif(&testnet),
+            Some((one(), Network::Testnet))
+        );
+    }
+
+    #[test]
+    fn wif_small_key() {
+        let (pr, _) = PrivateKey::<u64>::from_wif(WIF_ONE).unwrap();
+        assert_eq!(pr.wif(Network::Mainnet), WIF_ONE);
+        let big = PrivateKey::<U256>::from_bytes(&mut [0xFFu8; 32].as_slice());
+        assert_eq!(
+            PrivateKey::<u64>::from_wif(&big.wif(Network::Mainnet)),
+            None
+        );
+    }
+
+    #[test]
+    fn addresses() {
+        let pb = PublicKey::<Point<ModField<U256>>>::from_base64(&secp256k1().g.to_base64());
+        assert_eq!(
+            pb.point().to_sec1_compressed()[..4],
+            [0x02, 0x79, 0xBE, 0x66]
+        );
+        assert_eq!(
+            pb.p2pkh_address(Network::Mainnet),
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"
+        );
+        assert_eq!(
+            pb.p2pkh_address(Network::Testnet),
+            "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r"
+        );
+        assert_eq!(
+            pb.p2wpkh_address(Network::Mainnet),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+}
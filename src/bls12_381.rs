@@ -0,0 +1,204 @@
+//! BLS12-381: the prime field tower `Fp2 = Fp[u]/(u² + 1)`,
+//! `Fp6 = Fp2[v]/(v³ - ξ)` with `ξ = u + 1`, `Fp12 = Fp6[w]/(w² - v)`, the
+//! groups G1 ⊂ E(Fp) and G2 ⊂ E'(Fp2) on `y² = x³ + 4` and its sextic twist
+//! `y² = x³ + 4ξ`, and the optimal ate pairing.
+//!
+//! The pairing maps G2 into E(Fp12) and runs the generic Miller loop there,
+//! with a plain exponentiation for the hard part of the final exponentiation.
+//! That keeps it short and obviously right, not fast: see [`pairing`].
+
+use crate::{
+    algebra::{Field, Ring},
+    extension::{Ext2, Ext3, NonResidue},
+    mod_field::{ModField, ModFieldCfg},
+    pairing::miller_loop,
+    points_group::{Point, PointCfg},
+    uint::Uint,
+};
+
+pub type Fp = ModField<Uint<6>>;
+pub type Fp2 = Ext2<Fp, MinusOne>;
+pub type Fp6 = Ext3<Fp2, Xi>;
+pub type Fp12 = Ext2<Fp6, V>;
+pub type G1 = Point<Fp>;
+pub type G2 = Point<Fp2>;
+
+/// `u² = -1`.
+pub struct MinusOne;
+/// `v³ = ξ = u + 1`.
+pub struct Xi;
+/// `w² = v`.
+pub struct V;
+
+impl NonResidue<Fp> for MinusOne {
+    fn mul_by(x: Fp, cfg: &ModFieldCfg<Uint<6>>) -> Fp {
+        x.neg(cfg)
+    }
+}
+
+impl NonResidue<Fp2> for Xi {
+    fn mul_by(x: Fp2, cfg: &ModFieldCfg<Uint<6>>) -> Fp2 {
+        // (c0 + c1 u)(1 + u) = (c0 - c1) + (c0 + c1) u
+        Fp2::new(Fp::sub(x.c0, x.c1, cfg), Fp::add(x.c0, x.c1, cfg))
+    }
+}
+
+impl NonResidue<Fp6> for V {
+    fn mul_by(x: Fp6, cfg: &ModFieldCfg<Uint<6>>) -> Fp6 {
+        Fp6::new(Xi::mul_by(x.c2, cfg), x.c0, x.c1)
+    }
+}
+
+/// `|x|` for the curve parameter `x = -0xd201000000010000`.
+const X_ABS: u64 = 0xd201_0000_0001_0000;
+
+fn hex(s: &str) -> Uint<6> {
+    Uint::from_be_hex(s).unwrap()
+}
+
+pub fn fp() -> ModFieldCfg<Uint<6>> {
+    ModFieldCfg {
+        rem: hex("1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab"),
+    }
+}
+
+/// Order of G1 and G2.
+pub fn order() -> ModFieldCfg<Uint<6>> {
+    ModFieldCfg {
+        rem: hex("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"),
+    }
+}
+
+pub fn g1() -> PointCfg<Fp> {
+    let cf = fp();
+    let f = |s| ModField::new(hex(s), &cf);
+    PointCfg {
+        g: Point::new_unsafe(
+            f("17f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"),
+            f("08b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1"),
+        ),
+        a: Fp::zero(&cf),
+        b: ModField::new(Uint::from(4u64), &cf),
+        cf,
+    }
+}
+
+pub fn g2() -> PointCfg<Fp2> {
+    let cf = fp();
+    let f = |s| ModField::new(hex(s), &cf);
+    let four = ModField::new(Uint::from(4u64), &cf);
+    PointCfg {
+        g: Point::new_unsafe(
+            Fp2::new(
+                f("024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8"),
+                f("13e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e"),
+            ),
+            Fp2::new(
+                f("0ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801"),
+                f("0606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be"),
+            ),
+        ),
+        a: Fp2::zero(&cf),
+        b: Fp2::new(four, four),
+        cf,
+    }
+}
+
+fn embed(x: Fp2, cf: &ModFieldCfg<Uint<6>>) -> Fp12 {
+    Fp12::from_base(Fp6::from_base(x, cf), cf)
+}
+
+/// E(Fp12), where both groups live.
+fn e12() -> PointCfg<Fp12> {
+    let cf = fp();
+    let g = g1();
+    PointCfg {
+        g: Point::new_unsafe(
+            embed(Fp2::from_base(g.g.x(), &cf), &cf),
+            embed(Fp2::from_base(g.g.y(), &cf), &cf),
+        ),
+        a: Fp12::zero(&cf),
+        b: embed(Fp2::from_base(g.b, &cf), &cf),
+        cf,
+    }
+}
+
+/// The untwisting `(x, y) ↦ (x / w², y / w³)` from E' to E, as `w⁶ = ξ`.
+fn untwist(q: G2, c: &PointCfg<Fp12>) -> Point<Fp12> {
+    let cf = &c.cf;
+    let w = Fp12::new(Fp6::zero(cf), Fp6::one(cf));
+    let w2 = w.sqr(cf);
+    let w3 = Fp12::mul(w2, w, cf);
+    Point::new(
+        Fp12::div(embed(q.x(), cf), w2, cf),
+        Fp12::div(embed(q.y(), cf), w3, cf),
+        c,
+    )
+}
+
+/// `f^((p¹² - 1) / r)`: `f^(p⁶ - 1)` is a conjugate over an inverse, and
+/// `(p⁶ + 1) / r` is left to a plain square-and-multiply. That exponent has
+/// some 2300 bits, too many for the recursive `pow`.
+fn final_exponentiation(f: Fp12, cf: &ModFieldCfg<Uint<6>>) -> Fp12 {
+    let f = Fp12::div(f.conjugate(cf), f, cf);
+    let p = Uint::<36>::from_little_endian(&cf.rem.to_little_endian());
+    let r = Uint::<36>::from_little_endian(&order().rem.to_little_endian());
+    let p6 = p * p * p * p * p * p;
+    let e = (p6 + Uint::from(1u64)) / r;
+    (0..e.bits() as usize).rev().fold(Fp12::one(cf), |acc, i| {
+        let acc = acc.sqr(cf);
+        if (e >> i).low_u64() & 1 == 1 {
+            Fp12::mul(acc, f, cf)
+        } else {
+            acc
+        }
+    })
+}
+
+/// The optimal ate pairing `e(p, q) = f_{x,q}(p)^((p¹² - 1) / r)`. Takes
+/// seconds in release builds, most of it in the final exponentiation.
+pub fn pairing(p: G1, q: G2) -> Fp12 {
+    let c = e12();
+    let cf = &c.cf;
+    let p = Point::new(
+        embed(Fp2::from_base(p.x(), cf), cf),
+        embed(Fp2::from_base(p.y(), cf), cf),
+        &c,
+    );
+    let (f, _) = miller_loop(untwist(q, &c), X_ABS, p, &c).expect("p isn't in G1");
+    // x is negative, and f_{-|x|} is 1 / f_{|x|} up to factors the final
+    // exponentiation kills
+    final_exponentiation(f, cf).reciprocal(cf).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        algebra::{CommutativeOp, Ring},
+        points_group::Point,
+    };
+
+    use super::{fp, g1, g2, order, pairing, Fp12};
+
+    #[test]
+    fn generators() {
+        let (c1, c2) = (g1(), g2());
+        Point::new(c1.g.x(), c1.g.y(), &c1);
+        Point::new(c2.g.x(), c2.g.y(), &c2);
+        assert!(c1.g.in_subgroup(order().rem, &c1));
+    }
+
+    #[test]
+    #[ignore = "minutes without --release"]
+    fn bilinear() {
+        let (c1, c2) = (g1(), g2());
+        let (p, q) = (c1.g, c2.g);
+        let e = pairing(p, q);
+        let cf = fp();
+        assert_ne!(e, Fp12::one(&cf));
+        assert_eq!(e.pow(order().rem, &cf), Fp12::one(&cf));
+        let (p2, q3) = (Point::exp(p, 2u64, &c1), Point::exp(q, 3u64, &c2));
+        assert_eq!(pairing(p2, q3), e.pow(6u64, &cf));
+        assert!(q.in_subgroup(order().rem, &c2));
+    }
+}
@@ -0,0 +1,174 @@
+//! Many encryptions or verifications in one call, on owned inputs so the call
+//! can move to another thread as a whole. With the `tokio` feature the
+//! `_async` variants do exactly that through `spawn_blocking`, which keeps
+//! the scalar multiplications off the executor's worker threads.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{self, CommutativeOp, DiscreteRoot, Field, InitialPoint},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    context::CurveContext,
+    ecc::PublicKey,
+    error::Error,
+    mod_field::ModFieldCfg,
+    points_group::Point,
+    schnorr::{self, Signature},
+};
+
+/// A message signed by a key.
+pub type SignedMessage<P, I> = (PublicKey<P>, Vec<u8>, Signature<P, I>);
+
+/// [`CurveContext::encrypt_file`] of every message to the same key.
+pub fn encrypt_many<F, I>(
+    key: PublicKey<Point<F>>,
+    messages: Vec<Vec<u8>>,
+    mut rng: impl Rng,
+    ctx: &CurveContext<F, I>,
+) -> Result<Vec<Vec<u8>>, Error>
+where
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural + RW,
+    [(); Point::<F>::LEN]:,
+    [(); F::LEN]:,
+{
+    messages
+        .iter()
+        .map(|m| ctx.encrypt_file(key, m, &mut rng))
+        .collect()
+}
+
+/// [`schnorr::verify`] of every signature, in order.
+pub fn verify_many<I, P>(
+    items: Vec<SignedMessage<P, I>>,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> Vec<bool>
+where
+    I: Natural + RW,
+    P: CommutativeOp<algebra::ops::Add> + RW + Eq,
+    P::Cfg: InitialPoint<P>,
+{
+    items
+        .into_iter()
+        .map(|(key, msg, sig)| schnorr::verify(key, &msg, sig, cfg, n))
+        .collect()
+}
+
+/// Runs `f` on tokio's blocking pool, passing a panic on to the caller.
+#[cfg(feature = "tokio")]
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(v) => v,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// [`encrypt_many`] on the blocking pool.
+#[cfg(feature = "tokio")]
+pub async fn encrypt_many_async<F, I, R>(
+    key: PublicKey<Point<F>>,
+    messages: Vec<Vec<u8>>,
+    rng: R,
+    ctx: std::sync::Arc<CurveContext<F, I>>,
+) -> Result<Vec<Vec<u8>>, Error>
+where
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity + Send + Sync + 'static,
+    F::Cfg: Send + Sync,
+    I: FromRandom<()> + Natural + RW + Send + Sync + 'static,
+    R: Rng + Send + 'static,
+    [(); Point::<F>::LEN]:,
+    [(); F::LEN]:,
+{
+    blocking(move || encrypt_many(key, messages, rng, &ctx)).await
+}
+
+/// [`verify_many`] on the blocking pool.
+#[cfg(feature = "tokio")]
+pub async fn verify_many_async<I, P>(
+    items: Vec<SignedMessage<P, I>>,
+    cfg: P::Cfg,
+    n: ModFieldCfg<I>,
+) -> Vec<bool>
+where
+    I: Natural + RW + Send + 'static,
+    P: CommutativeOp<algebra::ops::Add> + RW + Eq + Send + 'static,
+    P::Cfg: InitialPoint<P> + Send + 'static,
+{
+    blocking(move || verify_many(items, &cfg, &n)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        context::CurveContext,
+        curves::{toy, toy_order},
+        ecc::PrivateKey,
+        encoding_utils::decrypt_file,
+        mod_field::ModField,
+        points_group::Point,
+        schnorr::SigningSession,
+    };
+
+    use super::{encrypt_many, verify_many, SignedMessage};
+
+    type P = Point<ModField<u64>>;
+
+    fn messages() -> Vec<Vec<u8>> {
+        vec![b"".to_vec(), b"first".to_vec(), vec![7; 40]]
+    }
+
+    fn signed(count: usize) -> (Vec<SignedMessage<P, u128>>, Vec<bool>) {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let key = PrivateKey::random(&mut rng, &n);
+        let items = (0..count)
+            .map(|i| {
+                let msg = vec![i as u8; i];
+                let sig = SigningSession::new(key, &msg)
+                    .commit(&cfg, &n)
+                    .finalize(&cfg, &n);
+                // every other one is checked against another message
+                let claimed = if i % 2 == 0 { msg } else { vec![0xFF] };
+                (key.public_key(&cfg), claimed, sig)
+            })
+            .collect();
+        (items, (0..count).map(|i| i % 2 == 0).collect())
+    }
+
+    #[test]
+    fn encrypt_and_verify() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (cfg, ctx) = (toy(), CurveContext::new(toy(), toy_order()));
+        let (pr, pb) = ctx.gen_keys(&mut rng);
+        let files = encrypt_many(pb, messages(), rng, &ctx).unwrap();
+        let decrypted = files.iter().map(|f| decrypt_file(pr, f, &cfg).unwrap());
+        assert!(decrypted.eq(messages()));
+
+        let (items, expected) = signed(6);
+        assert_eq!(verify_many(items, &toy(), &toy_order()), expected);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn on_the_blocking_pool() {
+        use super::{encrypt_many_async, verify_many_async};
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let ctx = CurveContext::shared(toy(), toy_order());
+        let (pr, pb) = ctx.gen_keys(&mut rng);
+        let files = runtime
+            .block_on(encrypt_many_async(pb, messages(), rng, ctx))
+            .unwrap();
+        assert_eq!(decrypt_file(pr, &files[1], &toy()).unwrap(), b"first");
+
+        let (items, expected) = signed(4);
+        let verified = runtime.block_on(verify_many_async(items, toy(), toy_order()));
+        assert_eq!(verified, expected);
+    }
+}
@@ -0,0 +1,205 @@
+/// Minimal CBOR (RFC 8949) data model: just what COSE needs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+}
+
+/// Arrays, maps and tags nested deeper than this are rejected, so that
+/// decoding can't run out of stack on hostile input. COSE needs 3.
+const MAX_DEPTH: usize = 32;
+
+fn write_head(major: u8, n: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend((n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend((n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend(n.to_be_bytes());
+    }
+}
+
+impl Value {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Int(i) if *i >= 0 => write_head(0, *i as u64, out),
+            Value::Int(i) => write_head(1, (-1 - *i) as u64, out),
+            Value::Bytes(b) => {
+                write_head(2, b.len() as u64, out);
+                out.extend(b);
+            }
+            Value::Text(t) => {
+                write_head(3, t.len() as u64, out);
+                out.extend(t.as_bytes());
+            }
+            Value::Array(items) => {
+                write_head(4, items.len() as u64, out);
+                items.iter().for_each(|v| v.write(out));
+            }
+            Value::Map(entries) => {
+                write_head(5, entries.len() as u64, out);
+                for (k, v) in entries {
+                    k.write(out);
+                    v.write(out);
+                }
+            }
+            Value::Tag(tag, v) => {
+                write_head(6, *tag, out);
+                v.write(out);
+            }
+        }
+    }
+
+    /// Decodes exactly one item; trailing bytes, indefinite lengths,
+    /// unsupported major types and nesting beyond 32 levels are rejected.
+    pub fn decode(bytes: &[u8]) -> Option<Value> {
+        let mut pos = 0;
+        let v = Self::read(bytes, &mut pos, 0)?;
+        (pos == bytes.len()).then_some(v)
+    }
+
+    fn read(bytes: &[u8], pos: &mut usize, depth: usize) -> Option<Value> {
+        let first = *bytes.get(*pos)?;
+        *pos += 1;
+        let (major, info) = (first >> 5, first & 31);
+        let n = match info {
+            0..24 => info as u64,
+            24..=27 => {
+                let len = 1 << (info - 24);
+                let raw = bytes.get(*pos..*pos + len)?;
+                *pos += len;
+                raw.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64)
+            }
+            _ => return None,
+        };
+        let mut take = |len: u64| -> Option<Vec<u8>> {
+            let end = pos.checked_add(usize::try_from(len).ok()?)?;
+            let raw = bytes.get(*pos..end)?.to_vec();
+            *pos = end;
+            Some(raw)
+        };
+        let nested = depth + 1;
+        if major >= 4 && nested > MAX_DEPTH {
+            return None;
+        }
+        match major {
+            0 => Some(Value::Int(i64::try_from(n).ok()?)),
+            1 => Some(Value::Int(-1 - i64::try_from(n).ok()?)),
+            2 => Some(Value::Bytes(take(n)?)),
+            3 => Some(Value::Text(String::from_utf8(take(n)?).ok()?)),
+            4 => {
+                let mut items = vec![];
+                for _ in 0..n {
+                    items.push(Self::read(bytes, pos, nested)?);
+                }
+                Some(Value::Array(items))
+            }
+            5 => {
+                let mut entries = vec![];
+                for _ in 0..n {
+                    let k = Self::read(bytes, pos, nested)?;
+                    let v = Self::read(bytes, pos, nested)?;
+                    entries.push((k, v));
+                }
+                Some(Value::Map(entries))
+            }
+            6 => Some(Value::Tag(n, Box::new(Self::read(bytes, pos, nested)?))),
+            _ => None,
+        }
+    }
+
+    /// Looks up an integer key in a map.
+    pub fn get(&self, key: i64) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(k, _)| *k == Value::Int(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    // RFC 8949, appendix A
+    #[test]
+    fn known_encodings() {
+        assert_eq!(Value::Int(10).encode(), [0x0a]);
+        assert_eq!(Value::Int(25).encode(), [0x18, 0x19]);
+        assert_eq!(Value::Int(1000).encode(), [0x19, 0x03, 0xe8]);
+        assert_eq!(Value::Int(-1000).encode(), [0x39, 0x03, 0xe7]);
+        assert_eq!(Value::Bytes(vec![1, 2, 3, 4]).encode(), [0x44, 1, 2, 3, 4]);
+        assert_eq!(Value::Text("IETF".into()).encode(), b"\x64IETF");
+        assert_eq!(
+            Value::Array(vec![Value::Int(1), Value::Array(vec![Value::Int(2)])]).encode(),
+            [0x82, 0x01, 0x81, 0x02]
+        );
+    }
+
+    #[test]
+    fn back_forth() {
+        let v = Value::Tag(
+            18,
+            Box::new(Value::Map(vec![
+                (Value::Int(-3), Value::Bytes(vec![7; 300])),
+                (Value::Text("k".into()), Value::Int(i64::MIN)),
+            ])),
+        );
+        assert_eq!(Value::decode(&v.encode()), Some(v));
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert_eq!(Value::decode(&[0x0a, 0x00]), None);
+        assert_eq!(Value::decode(&[0x44, 1, 2]), None);
+        assert_eq!(Value::decode(&[0x5f]), None);
+        assert_eq!(Value::decode(&[0x1b, 0xff, 0, 0, 0, 0, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize| [vec![0x81; depth], vec![0x00]].concat();
+        assert!(Value::decode(&nested(32)).is_some());
+        assert_eq!(Value::decode(&nested(33)), None);
+        // would overflow the stack if every level recursed
+        assert_eq!(Value::decode(&nested(1_000_000)), None);
+        let tags = [vec![0xd2; 33], vec![0x00]].concat();
+        assert_eq!(Value::decode(&tags), None);
+    }
+}
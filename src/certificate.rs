@@ -0,0 +1,271 @@
+//! Toy certificates: a subject's name and public key, the issuer's name, a
+//! validity period and whether the subject may issue certificates itself,
+//! signed by the issuer with a Schnorr signature. Enough for a PKI of a root,
+//! intermediates and leaves, without any of X.509's encoding or extensions.
+//! What's signed is:
+//!
+//! ```text
+//! label | subject length (1) | subject | issuer length (1) | issuer
+//!     | not before (8, LE) | not after (8, LE) | CA (1) | public key (P::LEN)
+//! ```
+
+use crate::{
+    algebra::{self, CommutativeOp, InitialPoint},
+    base_traits::{Natural, RW},
+    ecc::PublicKey,
+    error::Error,
+    mod_field::ModFieldCfg,
+    schnorr::{self, Signature},
+    signer::Signer,
+};
+
+const LABEL: &[u8] = b"elliptic-curves/certificate/v1";
+
+/// What the issuer signs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateBody<P> {
+    pub subject: String,
+    pub public: PublicKey<P>,
+    pub issuer: String,
+    /// Unix seconds, both ends included.
+    pub not_before: u64,
+    pub not_after: u64,
+    /// Whether the subject's key may sign certificates.
+    pub ca: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate<P, I> {
+    pub body: CertificateBody<P>,
+    pub signature: Signature<P, I>,
+}
+
+impl<P: RW + Copy> CertificateBody<P> {
+    /// [`Error::TooLarge`] for a name longer than its length byte can say.
+    fn to_signed_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = LABEL.to_vec();
+        for name in [&self.subject, &self.issuer] {
+            let len = u8::try_from(name.len()).map_err(|_| Error::TooLarge {
+                limit: u8::MAX as usize,
+            })?;
+            bytes.push(len);
+            bytes.extend(name.as_bytes());
+        }
+        bytes.extend(self.not_before.to_le_bytes());
+        bytes.extend(self.not_after.to_le_bytes());
+        bytes.push(self.ca as u8);
+        bytes.extend(self.public.point().to_vec());
+        Ok(bytes)
+    }
+
+    /// [`Error::NotYetValid`] or [`Error::Expired`] unless `time` is within
+    /// the validity period.
+    pub fn validity_at(&self, time: u64) -> Result<(), Error> {
+        if time < self.not_before {
+            return Err(Error::NotYetValid {
+                from: self.not_before,
+            });
+        }
+        if time > self.not_after {
+            return Err(Error::Expired { at: self.not_after });
+        }
+        Ok(())
+    }
+
+    /// Signs the body with the issuer's key. Nothing checks that the key is
+    /// the one of a certificate for `self.issuer`; that's up to verification.
+    pub fn issue<I>(
+        self,
+        issuer: &impl Signer<P, I>,
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Certificate<P, I>, Error>
+    where
+        P: CommutativeOp<algebra::ops::Add>,
+    {
+        let signature = issuer.sign(&self.to_signed_bytes()?, cfg, n)?;
+        Ok(Certificate {
+            body: self,
+            signature,
+        })
+    }
+}
+
+impl<P, I> Certificate<P, I>
+where
+    P: CommutativeOp<algebra::ops::Add> + RW + Copy + Eq,
+    P::Cfg: InitialPoint<P>,
+    I: Natural + RW,
+{
+    /// The signature under `issuer` and the validity at `time`; names and
+    /// the CA flag are left to [`verify_chain`].
+    pub fn verify(
+        &self,
+        issuer: PublicKey<P>,
+        time: u64,
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<(), Error> {
+        let bytes = self.body.to_signed_bytes()?;
+        if !schnorr::verify(issuer, &bytes, self.signature, cfg, n) {
+            return Err(Error::Signature);
+        }
+        self.body.validity_at(time)
+    }
+}
+
+/// Validates `chain`, the leaf first and every further certificate the
+/// issuer of the one before, up to a certificate issued by one of `roots`
+/// or one of `roots` itself. Roots are trusted as they are, but have to be
+/// valid at `time` like the rest, and every issuer has to be a CA.
+pub fn verify_chain<P, I>(
+    chain: &[Certificate<P, I>],
+    roots: &[Certificate<P, I>],
+    time: u64,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> Result<(), Error>
+where
+    P: CommutativeOp<algebra::ops::Add> + RW + Copy + Eq,
+    P::Cfg: InitialPoint<P>,
+    I: Natural + RW,
+{
+    let last = chain.last().ok_or(Error::Length {
+        expected: 1,
+        actual: 0,
+    })?;
+    for (cert, issuer) in chain.iter().zip(&chain[1..]) {
+        verify_issued(cert, issuer, time, cfg, n)?;
+    }
+    if roots.contains(last) {
+        return last.body.validity_at(time);
+    }
+    let mut candidates = roots
+        .iter()
+        .filter(|root| root.body.subject == last.body.issuer)
+        .map(|root| verify_issued(last, root, time, cfg, n))
+        .peekable();
+    let first_error = match candidates.peek() {
+        None => return Err(Error::Untrusted(last.body.issuer.clone())),
+        Some(result) => result.clone(),
+    };
+    // one that works, or what was wrong with the first
+    candidates.find(Result::is_ok).unwrap_or(first_error)
+}
+
+fn verify_issued<P, I>(
+    cert: &Certificate<P, I>,
+    issuer: &Certificate<P, I>,
+    time: u64,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> Result<(), Error>
+where
+    P: CommutativeOp<algebra::ops::Add> + RW + Copy + Eq,
+    P::Cfg: InitialPoint<P>,
+    I: Natural + RW,
+{
+    if cert.body.issuer != issuer.body.subject {
+        return Err(Error::Untrusted(cert.body.issuer.clone()));
+    }
+    if !issuer.body.ca {
+        return Err(Error::NotCa(issuer.body.subject.clone()));
+    }
+    issuer.body.validity_at(time)?;
+    cert.verify(issuer.body.public, time, cfg, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+
+    use rand::SeedableRng;
+
+    use crate::{
+        curves::{toy, toy_order},
+        ecc::{PrivateKey, PublicKey},
+        error::Error,
+        mod_field::ModField,
+        points_group::Point,
+    };
+
+    use super::{verify_chain, Certificate, CertificateBody};
+
+    type P = Point<ModField<u64>>;
+
+    fn body(subject: &str, key: PrivateKey<u128>, issuer: &str, ca: bool) -> CertificateBody<P> {
+        CertificateBody {
+            subject: subject.to_string(),
+            public: key.public_key(&toy()),
+            issuer: issuer.to_string(),
+            not_before: 100,
+            not_after: 200,
+            ca,
+        }
+    }
+
+    #[test]
+    fn chains() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([6u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let mut key = || PrivateKey::<u128>::random(&mut rng, &n);
+        let (root_key, ca_key, leaf_key, other_key) = (key(), key(), key(), key());
+        let issue = |body: CertificateBody<P>, key: PrivateKey<u128>| -> Certificate<P, u128> {
+            body.issue(&key, &cfg, &n).unwrap()
+        };
+        let root = issue(body("root", root_key, "root", true), root_key);
+        let ca = issue(body("ca", ca_key, "root", true), root_key);
+        let leaf = issue(body("alice", leaf_key, "ca", false), ca_key);
+        let roots = [root.clone()];
+        let verify =
+            |chain: &[Certificate<P, u128>], time| verify_chain(chain, &roots, time, &cfg, &n);
+
+        assert_eq!(verify(&[leaf.clone(), ca.clone()], 150), Ok(()));
+        assert_eq!(
+            verify(&[leaf.clone(), ca.clone(), root.clone()], 150),
+            Ok(())
+        );
+        assert_eq!(verify(slice::from_ref(&ca), 150), Ok(()));
+        assert_eq!(verify(slice::from_ref(&root), 150), Ok(()));
+        assert_eq!(
+            verify(&[leaf.clone(), ca.clone()], 201),
+            Err(Error::Expired { at: 200 })
+        );
+        assert_eq!(
+            verify(slice::from_ref(&leaf), 150),
+            Err(Error::Untrusted("ca".to_string()))
+        );
+        assert_eq!(
+            verify(&[ca.clone(), leaf.clone()], 150),
+            Err(Error::Untrusted("root".to_string()))
+        );
+
+        // a leaf can't issue, and signatures have to be the issuer's
+        let mallory = issue(body("mallory", other_key, "alice", false), leaf_key);
+        assert_eq!(
+            verify(&[mallory, leaf.clone(), ca.clone()], 150),
+            Err(Error::NotCa("alice".to_string()))
+        );
+        let forged = issue(body("bob", other_key, "ca", false), other_key);
+        assert_eq!(verify(&[forged, ca.clone()], 150), Err(Error::Signature));
+        let mut altered = leaf.clone();
+        altered.body.not_after = 1000;
+        assert_eq!(verify(&[altered, ca.clone()], 150), Err(Error::Signature));
+        let mut swapped = leaf.clone();
+        swapped.body.public = PublicKey::from_point(other_key.public_key::<P>(&cfg).point());
+        assert_eq!(verify(&[swapped, ca.clone()], 150), Err(Error::Signature));
+
+        // a name too long to sign is an error, also in a received certificate
+        let long = "x".repeat(256);
+        assert_eq!(
+            body(&long, other_key, "ca", false).issue(&ca_key, &cfg, &n),
+            Err(Error::TooLarge { limit: 255 })
+        );
+        let mut renamed = leaf;
+        renamed.body.subject = long;
+        assert_eq!(
+            verify(&[renamed, ca], 150),
+            Err(Error::TooLarge { limit: 255 })
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Committing encryption: next to the ciphertext the sender publishes a hash
+//! commitment to the plaintext, whose opening travels inside the ciphertext.
+//! The recipient checks the two agree when decrypting, and can later prove
+//! what was encrypted by revealing the [`Opening`] instead of the key, e.g.
+//! a sealed bid after an auction closes or a ballot in a disputed count.
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    ecc::{Ciphertext, PublicKey},
+    encoding_utils::{encrypt_bytes, try_points_to_bytes, DEFAULT_KAPPA},
+    error::Error,
+    points_group::{Point, PointCfg},
+    signer::Decrypter,
+};
+
+const LABEL: &[u8] = b"elliptic-curves/commitment/v1";
+
+/// SHA-256 of a label, a random nonce and the plaintext: binding, and hiding
+/// as long as the nonce stays secret.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Commitment(pub [u8; 32]);
+
+/// The plaintext of a [`Commitment`] and the nonce that opens it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub nonce: [u8; 32],
+    pub message: Vec<u8>,
+}
+
+impl Opening {
+    pub fn commitment(&self) -> Commitment {
+        let digest = Sha256::new()
+            .chain_update(LABEL)
+            .chain_update(self.nonce)
+            .chain_update(&self.message)
+            .finalize();
+        Commitment(digest.into())
+    }
+}
+
+impl Commitment {
+    pub fn verify(&self, opening: &Opening) -> bool {
+        opening.commitment() == *self
+    }
+}
+
+/// Encrypts the nonce followed by `msg`, and commits to both.
+pub fn encrypt_committing<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    msg: &[u8],
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+) -> Result<(Ciphertext<Point<F>>, Commitment), Error> {
+    let opening = Opening {
+        nonce: rng.gen(),
+        message: msg.to_vec(),
+    };
+    let plaintext = [&opening.nonce[..], msg].concat();
+    let ciphertext = encrypt_bytes::<F, I>(key, &plaintext, rng, cfg)?;
+    Ok((ciphertext, opening.commitment()))
+}
+
+/// Inverse of [`encrypt_committing`]: the opening, or [`Error::Commitment`]
+/// if the plaintext isn't the one committed to.
+pub fn decrypt_committing<F: RW + Field>(
+    key: impl Decrypter<Point<F>>,
+    ciphertext: &Ciphertext<Point<F>>,
+    commitment: &Commitment,
+    cfg: &PointCfg<F>,
+) -> Result<Opening, Error>
+where
+    [(); F::LEN]:,
+{
+    let decrypted = ciphertext
+        .chunks
+        .iter()
+        .enumerate()
+        .map(|(i, &pair)| key.decrypt(pair, cfg).map_err(|e| e.in_chunk(i)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let plaintext = try_points_to_bytes(decrypted.into_iter(), DEFAULT_KAPPA)?;
+    let (nonce, message) = plaintext
+        .split_first_chunk::<32>()
+        .ok_or(Error::Commitment)?;
+    let opening = Opening {
+        nonce: *nonce,
+        message: message.to_vec(),
+    };
+    if !commitment.verify(&opening) {
+        return Err(Error::Commitment);
+    }
+    Ok(opening)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{curves::toy, ecc::gen_keys, error::Error, points_group::Point};
+
+    use super::{decrypt_committing, encrypt_committing};
+
+    #[test]
+    fn commit_encrypt_open() {
+        let cfg = toy();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([4u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, Point<_>>(&mut rng, &cfg);
+        let bid = b"bid: 120";
+        let (ct, commitment) = encrypt_committing::<_, u64>(pb, bid, &mut rng, &cfg).unwrap();
+        let opening = decrypt_committing(pr, &ct, &commitment, &cfg).unwrap();
+        assert_eq!(opening.message, bid);
+        // anyone can check the opening against the published commitment
+        assert!(commitment.verify(&opening));
+        let mut lie = opening.clone();
+        lie.message = b"bid: 100".to_vec();
+        assert!(!commitment.verify(&lie));
+
+        // a ciphertext of another plaintext doesn't match
+        let (other, _) = encrypt_committing::<_, u64>(pb, bid, &mut rng, &cfg).unwrap();
+        assert_eq!(
+            decrypt_committing(pr, &other, &commitment, &cfg),
+            Err(Error::Commitment)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! The CLI's config file, so the same flags don't have to be repeated on every
+//! invocation. It's a flat TOML document of string values; like the JSON in
+//! `kat`, the subset is small enough not to need a TOML crate.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+pub const TEMPLATE: &str = r#"# Defaults for the ecc CLI; flags given on the command line win.
+
+# curve of the `vectors` subcommand: "toy", "secp256k1" or "all"
+curve = "all"
+
+# output of `genkey` and `encrypt`: "base64" or "armor"
+format = "base64"
+
+# keys used when `encrypt`/`decrypt` get none, base64 or ASCII-armored
+# public_key = "/path/to/public.key"
+# private_key = "/path/to/private.key"
+"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Base64,
+    Armor,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    pub curve: Option<String>,
+    pub format: Option<Format>,
+    pub public_key: Option<PathBuf>,
+    pub private_key: Option<PathBuf>,
+}
+
+/// `$ECC_CONFIG`, else `ecc/config.toml` in `$XDG_CONFIG_HOME` or `~/.config`.
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("ECC_CONFIG") {
+        return Some(path.into());
+    }
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(dir.join("ecc").join("config.toml"))
+}
+
+/// The config at [`path`]; a missing file is an empty config.
+pub fn load() -> Result<Config, String> {
+    let Some(path) = path() else {
+        return Ok(Config::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Writes [`TEMPLATE`] to [`path`] unless a config exists and `force` is off.
+pub fn init(force: bool) -> Result<PathBuf, String> {
+    let path = path().ok_or("no config location, set ECC_CONFIG or HOME")?;
+    if path.exists() && !force {
+        return Err(format!(
+            "{} exists, pass --force to overwrite",
+            path.display()
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, TEMPLATE).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+pub fn parse(text: &str) -> Result<Config, String> {
+    let mut config = Config::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| format!("line {}: {}", i + 1, msg);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| err("expected key = \"value\""))?;
+        let value = string(value.trim()).ok_or_else(|| err("expected a quoted string"))?;
+        match key.trim() {
+            "curve" if ["toy", "secp256k1", "all"].contains(&value.as_str()) => {
+                config.curve = Some(value)
+            }
+            "curve" => return Err(err("curve must be \"toy\", \"secp256k1\" or \"all\"")),
+            "format" => {
+                config.format = Some(match value.as_str() {
+                    "base64" => Format::Base64,
+                    "armor" => Format::Armor,
+                    _ => return Err(err("format must be \"base64\" or \"armor\"")),
+                })
+            }
+            "public_key" => config.public_key = Some(value.into()),
+            "private_key" => config.private_key = Some(value.into()),
+            key => return Err(err(&format!("unknown key {:?}", key))),
+        }
+    }
+    Ok(config)
+}
+
+/// A basic TOML string, optionally followed by a comment.
+fn string(s: &str) -> Option<String> {
+    let mut chars = s.strip_prefix('"')?.chars();
+    let mut res = String::new();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => res.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                't' => '\t',
+                _ => return None,
+            }),
+            c => res.push(c),
+        }
+    }
+    let rest = chars.as_str().trim_start();
+    (rest.is_empty() || rest.starts_with('#')).then_some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{parse, Config, Format, TEMPLATE};
+
+    #[test]
+    fn template_parses() {
+        assert_eq!(
+            parse(TEMPLATE),
+            Ok(Config {
+                curve: Some("all".to_string()),
+                format: Some(Format::Base64),
+                public_key: None,
+                private_key: None,
+            })
+        );
+    }
+
+    #[test]
+    fn values() {
+        let config = parse(
+            "format = \"armor\"  # paste into email\n  private_key=\"C:\\\\keys\\\\my \\\"key\\\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.format, Some(Format::Armor));
+        assert_eq!(
+            config.private_key,
+            Some(PathBuf::from("C:\\keys\\my \"key\""))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("\n\nformat = \"pem\""),
+            Err("line 3: format must be \"base64\" or \"armor\"".to_string())
+        );
+        assert_eq!(
+            parse("colour = \"red\""),
+            Err("line 1: unknown key \"colour\"".to_string())
+        );
+        assert!(parse("curve = toy").is_err());
+        assert!(parse("curve = \"toy\" trailing").is_err());
+        assert!(parse("[section]").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! A curve with its precomputations. Multiples of `G` come from a window
+//! table built on first use and kept for the life of the context, so a
+//! context made once at startup (and put in an `Arc` for other threads)
+//! pays for the table once instead of on every call. The field itself has
+//! nothing to cache: `ModField` works on plain residues.
+
+use std::sync::{Arc, OnceLock};
+
+use rand::Rng;
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    ecc::{PrivateKey, PublicKey, WindowTable},
+    encoding_utils::{decrypt_file, encrypt_file_with},
+    error::Error,
+    mod_field::ModFieldCfg,
+    points_group::{Point, PointCfg},
+};
+
+/// A curve, the order of its generator and the lazily built table of the
+/// generator's multiples, for `I`-sized scalars.
+pub struct CurveContext<F: Field, I> {
+    cfg: PointCfg<F>,
+    order: ModFieldCfg<I>,
+    generator: OnceLock<WindowTable<Point<F>>>,
+}
+
+impl<F: Field, I: Natural> CurveContext<F, I> {
+    pub fn new(cfg: PointCfg<F>, order: ModFieldCfg<I>) -> Self {
+        Self {
+            cfg,
+            order,
+            generator: OnceLock::new(),
+        }
+    }
+
+    /// [`Self::new`], ready to hand out to threads.
+    pub fn shared(cfg: PointCfg<F>, order: ModFieldCfg<I>) -> Arc<Self> {
+        Arc::new(Self::new(cfg, order))
+    }
+
+    pub fn cfg(&self) -> &PointCfg<F> {
+        &self.cfg
+    }
+
+    pub fn order(&self) -> &ModFieldCfg<I> {
+        &self.order
+    }
+
+    fn generator(&self) -> &WindowTable<Point<F>> {
+        self.generator
+            .get_or_init(|| WindowTable::new::<I>(self.cfg.g, &self.cfg))
+    }
+
+    /// `k · G` for `k ≠ 0`, from the table.
+    pub fn mul_g(&self, k: I) -> Point<F> {
+        self.generator().mul(k, &self.cfg)
+    }
+
+    pub fn public_key(&self, key: PrivateKey<I>) -> PublicKey<Point<F>> {
+        PublicKey::from_point(self.mul_g(key.scalar()))
+    }
+
+    /// A uniformly random key pair, see [`PrivateKey::random`].
+    pub fn gen_keys(&self, rng: &mut impl Rng) -> (PrivateKey<I>, PublicKey<Point<F>>)
+    where
+        I: FromRandom<()>,
+    {
+        let pr = PrivateKey::random(rng, &self.order);
+        (pr, self.public_key(pr))
+    }
+}
+
+impl<F, I> CurveContext<F, I>
+where
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural + RW,
+    [(); Point::<F>::LEN]:,
+    [(); F::LEN]:,
+{
+    /// [`encrypt_file`](crate::encoding_utils::encrypt_file) with the table.
+    pub fn encrypt_file(
+        &self,
+        key: PublicKey<Point<F>>,
+        data: &[u8],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<u8>, Error> {
+        encrypt_file_with::<F, I>(key, data, rng, Some(self.generator()), &self.cfg)
+    }
+
+    /// The inverse of [`Self::encrypt_file`].
+    pub fn decrypt_file(&self, key: PrivateKey<I>, file: &[u8]) -> Result<Vec<u8>, Error> {
+        decrypt_file(key, file, &self.cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use rand::SeedableRng;
+
+    use crate::{
+        curves::{toy, toy_order},
+        ecc::PrivateKey,
+        encoding_utils::{decrypt_file, encrypt_file},
+    };
+
+    use super::CurveContext;
+
+    #[test]
+    fn matches_plain_config() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([3u8; 32]);
+        let ctx = CurveContext::new(toy(), toy_order());
+        for _ in 0..20 {
+            let (pr, pb) = ctx.gen_keys(&mut rng);
+            assert_eq!(pb, pr.public_key(&toy()));
+        }
+
+        let (pr, pb) = ctx.gen_keys(&mut rng);
+        let data = vec![0x5A; 100];
+        let seeded = || rand_chacha::ChaCha8Rng::from_seed([4u8; 32]);
+        let file = ctx.encrypt_file(pb, &data, &mut seeded()).unwrap();
+        assert_eq!(
+            file,
+            encrypt_file::<_, u128>(pb, &data, &mut seeded(), &toy()).unwrap()
+        );
+        assert_eq!(decrypt_file(pr, &file, &toy()).unwrap(), data);
+        assert_eq!(ctx.decrypt_file(pr, &file).unwrap(), data);
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let ctx = CurveContext::shared(toy(), toy_order());
+        let keys: Vec<_> = (1..=4u128)
+            .map(|k| {
+                let ctx = Arc::clone(&ctx);
+                thread::spawn(move || {
+                    ctx.public_key(PrivateKey::from_scalar(k, &toy_order()).unwrap())
+                })
+            })
+            .map(|h| h.join().unwrap())
+            .collect();
+        for (k, pb) in (1..=4u128).zip(keys) {
+            assert_eq!(
+                pb,
+                PrivateKey::from_scalar(k, &toy_order())
+                    .unwrap()
+                    .public_key(&toy())
+            );
+        }
+    }
+}
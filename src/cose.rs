@@ -0,0 +1,269 @@
+use std::io::Cursor;
+
+use crate::{
+    algebra::Field,
+    base_traits::{Endianness, Natural, RW},
+    cbor::Value,
+    ecc::{PrivateKey, PublicKey},
+    points_group::{Point, PointCfg, ValidationPolicy},
+};
+
+// RFC 9052/9053 labels
+const KTY: i64 = 1;
+const KTY_EC2: i64 = 2;
+const CRV: i64 = -1;
+const X: i64 = -2;
+const Y: i64 = -3;
+const D: i64 = -4;
+const HEADER_ALG: i64 = 1;
+const COSE_SIGN1_TAG: u64 = 18;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoseCurve {
+    P256,
+    P384,
+    P521,
+    Secp256k1,
+}
+
+impl CoseCurve {
+    fn id(self) -> i64 {
+        match self {
+            CoseCurve::P256 => 1,
+            CoseCurve::P384 => 2,
+            CoseCurve::P521 => 3,
+            CoseCurve::Secp256k1 => 8,
+        }
+    }
+
+    fn from_id(id: i64) -> Option<Self> {
+        [
+            CoseCurve::P256,
+            CoseCurve::P384,
+            CoseCurve::P521,
+            CoseCurve::Secp256k1,
+        ]
+        .into_iter()
+        .find(|c| c.id() == id)
+    }
+
+    /// ECDSA algorithm id for this curve (ES256, ES384, ES512, ES256K).
+    pub fn alg(self) -> i64 {
+        match self {
+            CoseCurve::P256 => -7,
+            CoseCurve::P384 => -35,
+            CoseCurve::P521 => -36,
+            CoseCurve::Secp256k1 => -47,
+        }
+    }
+}
+
+fn be_bytes<T: RW>(v: T) -> Vec<u8> {
+    v.to_vec_endian(Endianness::Big)
+}
+
+fn from_be_bytes<T: RW>(bytes: &[u8]) -> Option<T> {
+    if bytes.len() != T::LEN {
+        return None;
+    }
+    Some(T::from_bytes_endian(
+        &mut Cursor::new(bytes),
+        Endianness::Big,
+    ))
+}
+
+/// Parses an EC2 COSE_Key map, returning it along with the curve.
+fn ec2_key(bytes: &[u8]) -> Option<(Value, CoseCurve)> {
+    let key = Value::decode(bytes)?;
+    if key.get(KTY)?.as_int()? != KTY_EC2 {
+        return None;
+    }
+    let crv = CoseCurve::from_id(key.get(CRV)?.as_int()?)?;
+    Some((key, crv))
+}
+
+impl<F: Field + RW> PublicKey<Point<F>> {
+    /// COSE_Key (EC2) with big-endian x and y coordinates.
+    pub fn cose_key(self, crv: CoseCurve) -> Vec<u8> {
+        let p = self.point();
+        Value::Map(vec![
+            (Value::Int(KTY), Value::Int(KTY_EC2)),
+            (Value::Int(CRV), Value::Int(crv.id())),
+            (Value::Int(X), Value::Bytes(be_bytes(p.x()))),
+            (Value::Int(Y), Value::Bytes(be_bytes(p.y()))),
+        ])
+        .encode()
+    }
+
+    /// The key if it's one for `crv`, whose parameters `cp` are, and its
+    /// point passes the checks of `policy`.
+    pub fn from_cose_key<I: Natural>(
+        bytes: &[u8],
+        crv: CoseCurve,
+        policy: ValidationPolicy<I>,
+        cp: &PointCfg<F>,
+    ) -> Option<Self> {
+        let (key, found) = ec2_key(bytes)?;
+        if found != crv {
+            return None;
+        }
+        let x = from_be_bytes(key.get(X)?.as_bytes()?)?;
+        let y = from_be_bytes(key.get(Y)?.as_bytes()?)?;
+        let point = Point::new_unsafe(x, y).validate(policy, cp).ok()?;
+        Some(Self::from_point(point))
+    }
+}
+
+impl<I: RW> PrivateKey<I> {
+    /// COSE_Key (EC2) carrying only the private scalar `d`.
+    pub fn cose_key(self, crv: CoseCurve) -> Vec<u8> {
+        Value::Map(vec![
+            (Value::Int(KTY), Value::Int(KTY_EC2)),
+            (Value::Int(CRV), Value::Int(crv.id())),
+            (Value::Int(D), Value::Bytes(be_bytes(self))),
+        ])
+        .encode()
+    }
+
+    pub fn from_cose_key(bytes: &[u8]) -> Option<(Self, CoseCurve)> {
+        let (key, crv) = ec2_key(bytes)?;
+        Some((from_be_bytes(key.get(D)?.as_bytes()?)?, crv))
+    }
+}
+
+/// COSE_Sign1 envelope. The signature (`r || s`, big-endian) is produced
+/// externally over [`CoseSign1::sig_structure`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoseSign1 {
+    pub alg: i64,
+    pub payload: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    fn protected(&self) -> Vec<u8> {
+        Value::Map(vec![(Value::Int(HEADER_ALG), Value::Int(self.alg))]).encode()
+    }
+
+    /// The `Sig_structure` (RFC 9052, section 4.4) to be signed and verified.
+    pub fn sig_structure(&self, external_aad: &[u8]) -> Vec<u8> {
+        Value::Array(vec![
+            Value::Text("Signature1".into()),
+            Value::Bytes(self.protected()),
+            Value::Bytes(external_aad.to_vec()),
+            Value::Bytes(self.payload.clone()),
+        ])
+        .encode()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(self.protected()),
+                Value::Map(vec![]),
+                Value::Bytes(self.payload.clone()),
+                Value::Bytes(self.signature.clone()),
+            ])),
+        )
+        .encode()
+    }
+
+    /// Accepts both the tagged and the untagged form.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let v = match Value::decode(bytes)? {
+            Value::Tag(COSE_SIGN1_TAG, v) => *v,
+            v => v,
+        };
+        let Value::Array(items) = v else {
+            return None;
+        };
+        let [protected, Value::Map(_), Value::Bytes(payload), Value::Bytes(signature)] =
+            <[Value; 4]>::try_from(items).ok()?
+        else {
+            return None;
+        };
+        let alg = Value::decode(protected.as_bytes()?)?
+            .get(HEADER_ALG)?
+            .as_int()?;
+        Some(Self {
+            alg,
+            payload,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algebra::CommutativeOp,
+        base_traits::RW,
+        curves::secp256k1,
+        ecc::{PrivateKey, PublicKey},
+        points_group::{Point, ValidationPolicy},
+        uint::U256,
+    };
+
+    use super::{CoseCurve, CoseSign1};
+
+    #[test]
+    fn public_key() {
+        let pb = PublicKey::from_point(secp256k1().g);
+        let encoded = pb.cose_key(CoseCurve::Secp256k1);
+        // {1: 2, -1: 8, -2: h'79BE...', ...}
+        assert_eq!(
+            encoded[..8],
+            [0xa4, 0x01, 0x02, 0x20, 0x08, 0x21, 0x58, 0x20]
+        );
+        assert_eq!(encoded[8..10], [0x79, 0xBE]);
+        let cfg = secp256k1();
+        let policy = ValidationPolicy::<U256>::OnCurve;
+        let decode = |bytes: &[u8], crv| PublicKey::from_cose_key(bytes, crv, policy, &cfg);
+        assert_eq!(decode(&encoded, CoseCurve::Secp256k1), Some(pb));
+        assert_eq!(decode(&encoded, CoseCurve::P256), None);
+        // y of another point
+        let other = PublicKey::from_point(Point::op(cfg.g, cfg.g, &cfg));
+        let mut off_curve = encoded.clone();
+        let tail = other.cose_key(CoseCurve::Secp256k1);
+        let n = off_curve.len();
+        off_curve[n - 32..].copy_from_slice(&tail[n - 32..]);
+        assert_eq!(decode(&off_curve, CoseCurve::Secp256k1), None);
+    }
+
+    #[test]
+    fn private_key() {
+        let pr = PrivateKey::<u128>::from_bytes(&mut [0xA5u8; 16].as_slice());
+        let encoded = pr.cose_key(CoseCurve::P256);
+        assert_eq!(
+            PrivateKey::<u128>::from_cose_key(&encoded),
+            Some((pr, CoseCurve::P256))
+        );
+        // wrong scalar width
+        assert_eq!(PrivateKey::<u64>::from_cose_key(&encoded), None);
+    }
+
+    #[test]
+    fn sign1() {
+        let msg = CoseSign1 {
+            alg: CoseCurve::Secp256k1.alg(),
+            payload: b"hello".to_vec(),
+            signature: vec![0x11; 64],
+        };
+        let encoded = msg.encode();
+        assert_eq!(encoded[..3], [0xd2, 0x84, 0x44]);
+        assert_eq!(CoseSign1::decode(&encoded), Some(msg.clone()));
+        assert_eq!(CoseSign1::decode(&encoded[1..]), Some(msg.clone()));
+        assert_eq!(
+            msg.sig_structure(&[]),
+            [
+                &[0x84, 0x6a][..],
+                b"Signature1",
+                &[0x44, 0xa1, 0x01, 0x38, 0x2e, 0x40, 0x45],
+                b"hello"
+            ]
+            .concat()
+        );
+    }
+}
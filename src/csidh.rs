@@ -0,0 +1,246 @@
+//! A toy CSIDH (Castryck, Lange, Martindale, Panny, Renes): the class group of
+//! `Z[√-p]` acts on the supersingular Montgomery curves `y² = x³ + A·x² + x`
+//! over `F_p`, and since that action commutes, it gives a Diffie–Hellman. An
+//! element of the group is a vector of exponents, one per small odd prime
+//! `ℓ | p + 1`; applying it walks `e` steps of `ℓ`-isogenies for each, the
+//! direction given by the sign.
+//!
+//! Educational only: the parameters are tiny, the walk isn't constant time,
+//! and keys are validated by counting points. The point is to show the
+//! isogeny walk, not to be post-quantum secure.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{Field, Ring},
+    base_traits::FromRandom,
+    mod_field::{ModField, ModFieldCfg},
+};
+
+type Fp = ModField<u64>;
+
+/// `p = 4·ℓ₁·…·ℓₙ - 1` with the `ℓᵢ`, and the range `[-bound, bound]` private
+/// exponents are drawn from.
+pub struct CsidhParams {
+    pub primes: Vec<u64>,
+    pub cf: ModFieldCfg<u64>,
+    pub bound: i8,
+}
+
+impl CsidhParams {
+    /// `p = 4·3·5·7 - 1 = 419`, the example of the CSIDH paper.
+    pub fn toy() -> Self {
+        Self {
+            primes: vec![3, 5, 7],
+            cf: ModFieldCfg { rem: 419 },
+            bound: 2,
+        }
+    }
+}
+
+/// Exponents of the class group element, one per prime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKey(pub Vec<i8>);
+
+/// The Montgomery coefficient `A` of the curve the base curve `A = 0` is
+/// walked to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub Fp);
+
+/// `(X : Z)`, the x-coordinate of a point in projective form.
+#[derive(Clone, Copy)]
+struct Xz {
+    x: Fp,
+    z: Fp,
+}
+
+fn xdbl(p: Xz, a24: Fp, cf: &ModFieldCfg<u64>) -> Xz {
+    let s = Fp::add(p.x, p.z, cf).sqr(cf);
+    let d = Fp::sub(p.x, p.z, cf).sqr(cf);
+    // t = 4·X·Z
+    let t = Fp::sub(s, d, cf);
+    Xz {
+        x: Fp::mul(s, d, cf),
+        z: Fp::mul(t, Fp::sop(&[(a24, t), (d, Fp::one(cf))], cf), cf),
+    }
+}
+
+/// `x(p + q)` from `x(p)`, `x(q)` and the affine `x(p - q)`.
+fn xadd(p: Xz, q: Xz, diff: Fp, cf: &ModFieldCfg<u64>) -> Xz {
+    let u = Fp::mul(Fp::sub(p.x, p.z, cf), Fp::add(q.x, q.z, cf), cf);
+    let v = Fp::mul(Fp::add(p.x, p.z, cf), Fp::sub(q.x, q.z, cf), cf);
+    Xz {
+        x: Fp::add(u, v, cf).sqr(cf),
+        z: Fp::mul(diff, Fp::sub(u, v, cf).sqr(cf), cf),
+    }
+}
+
+/// `x(k·p)` by the Montgomery ladder, for `k ≥ 1` and `x(p) ≠ 0`.
+fn ladder(x: Fp, k: u64, a24: Fp, cf: &ModFieldCfg<u64>) -> Xz {
+    let p = Xz { x, z: Fp::one(cf) };
+    let (mut r0, mut r1) = (p, xdbl(p, a24, cf));
+    for i in (0..63 - k.leading_zeros()).rev() {
+        if k >> i & 1 == 1 {
+            (r0, r1) = (xadd(r0, r1, x, cf), xdbl(r1, a24, cf));
+        } else {
+            (r0, r1) = (xdbl(r0, a24, cf), xadd(r0, r1, x, cf));
+        }
+    }
+    r0
+}
+
+/// `x³ + A·x² + x`.
+fn rhs(a: Fp, x: Fp, cf: &ModFieldCfg<u64>) -> Fp {
+    let one = Fp::one(cf);
+    Fp::mul(x, Fp::sop(&[(x, x), (a, x), (one, one)], cf), cf)
+}
+
+fn is_square(v: Fp, cf: &ModFieldCfg<u64>) -> bool {
+    v.pow((cf.rem - 1) / 2, cf) == Fp::one(cf)
+}
+
+/// The curve `A'` of the `ℓ`-isogeny with kernel generated by the point with
+/// x-coordinate `x` of odd order `ℓ`, by Vélu's formulas in the Montgomery
+/// form of Costello and Hisil: with `xᵢ = x(i·P)` for `i ≤ (ℓ - 1) / 2`,
+/// `A' = (∏ xᵢ)² · (A - 6·Σ (xᵢ - 1/xᵢ))`.
+fn isogenous(a: Fp, x: Fp, l: u64, a24: Fp, cf: &ModFieldCfg<u64>) -> Fp {
+    let (mut pi, mut sigma) = (Fp::one(cf), Fp::zero(cf));
+    for i in 1..=l / 2 {
+        let q = ladder(x, i, a24, cf);
+        let xi = Fp::div(q.x, q.z, cf);
+        pi = Fp::mul(pi, xi, cf);
+        sigma = Fp::add(sigma, Fp::sub(xi, xi.reciprocal(cf).unwrap(), cf), cf);
+    }
+    let six = Fp::add(Fp::three(cf), Fp::three(cf), cf);
+    Fp::mul(pi.sqr(cf), Fp::sub(a, Fp::mul(six, sigma, cf), cf), cf)
+}
+
+/// Applies the class group element `key` to the curve `a`. Each step samples
+/// a fresh point: one on the curve moves in the positive direction, one on
+/// its quadratic twist in the negative one, and `(p + 1) / ℓ` times it is the
+/// kernel unless that's the point at infinity.
+pub fn act(a: Fp, key: &PrivateKey, params: &CsidhParams, rng: &mut impl Rng) -> Fp {
+    let cf = &params.cf;
+    let mut e = key.0.clone();
+    let mut a = a;
+    while e.iter().any(|&e| e != 0) {
+        let x = Fp::random(rng, cf);
+        let y2 = rhs(a, x, cf);
+        if y2 == Fp::zero(cf) {
+            continue;
+        }
+        let sign = if is_square(y2, cf) { 1 } else { -1 };
+        let a24 = Fp::div(Fp::add(a, Fp::two(cf), cf), Fp::four(cf), cf);
+        for (i, &l) in params.primes.iter().enumerate() {
+            if e[i].signum() != sign {
+                continue;
+            }
+            let q = ladder(x, (cf.rem + 1) / l, a24, cf);
+            if q.z != Fp::zero(cf) {
+                a = isogenous(a, Fp::div(q.x, q.z, cf), l, a24, cf);
+                e[i] -= sign;
+                break;
+            }
+        }
+    }
+    a
+}
+
+/// Whether `y² = x³ + A·x² + x` has the `p + 1` points of a supersingular
+/// curve over `F_p`, `p > 3`. Counts them one x at a time, so toy sizes only.
+pub fn is_supersingular(a: Fp, cf: &ModFieldCfg<u64>) -> bool {
+    let four = Fp::four(cf);
+    if a.sqr(cf) == four {
+        return false;
+    }
+    // the point at infinity, and for each x: 2 points, 1 or none
+    let count = (0..cf.rem).fold(1, |n, x| {
+        let y2 = rhs(a, ModField::new(x, cf), cf);
+        n + if y2 == Fp::zero(cf) {
+            1
+        } else if is_square(y2, cf) {
+            2
+        } else {
+            0
+        }
+    });
+    count == cf.rem + 1
+}
+
+pub fn gen_keys(params: &CsidhParams, rng: &mut impl Rng) -> (PrivateKey, PublicKey) {
+    let key = PrivateKey(
+        params
+            .primes
+            .iter()
+            .map(|_| rng.gen_range(-params.bound..=params.bound))
+            .collect(),
+    );
+    let public = act(Fp::zero(&params.cf), &key, params, rng);
+    (key, PublicKey(public))
+}
+
+/// The shared curve, or `None` if `theirs` isn't a supersingular curve and
+/// so not reachable by any key.
+pub fn shared_secret(
+    ours: &PrivateKey,
+    theirs: PublicKey,
+    params: &CsidhParams,
+    rng: &mut impl Rng,
+) -> Option<Fp> {
+    is_supersingular(theirs.0, &params.cf).then(|| act(theirs.0, ours, params, rng))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{algebra::Ring, mod_field::ModField};
+
+    use super::{
+        act, gen_keys, is_supersingular, shared_secret, CsidhParams, PrivateKey, PublicKey,
+    };
+
+    #[test]
+    fn key_exchange() {
+        let params = CsidhParams::toy();
+        let cf = &params.cf;
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        assert!(is_supersingular(ModField::zero(cf), cf));
+        assert!(!is_supersingular(ModField::new(1, cf), cf));
+        for _ in 0..5 {
+            let (alice, alice_pub) = gen_keys(&params, &mut rng);
+            let (bob, bob_pub) = gen_keys(&params, &mut rng);
+            assert!(is_supersingular(alice_pub.0, cf));
+            let s1 = shared_secret(&alice, bob_pub, &params, &mut rng);
+            let s2 = shared_secret(&bob, alice_pub, &params, &mut rng);
+            assert!(s1.is_some());
+            assert_eq!(s1, s2);
+        }
+        assert_eq!(
+            shared_secret(
+                &PrivateKey(vec![1, 0, 0]),
+                PublicKey(ModField::new(1, cf)),
+                &params,
+                &mut rng
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn walks_back() {
+        let params = CsidhParams::toy();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let zero = ModField::zero(&params.cf);
+        for key in [vec![1, 0, 0], vec![0, -1, 0], vec![2, -1, 1]] {
+            let there = act(zero, &PrivateKey(key.clone()), &params, &mut rng);
+            assert_ne!(there, zero);
+            let back = PrivateKey(key.iter().map(|e| -e).collect());
+            assert_eq!(act(there, &back, &params, &mut rng), zero);
+        }
+        assert_eq!(
+            act(zero, &PrivateKey(vec![0, 0, 0]), &params, &mut rng),
+            zero
+        );
+    }
+}
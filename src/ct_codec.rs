@@ -0,0 +1,187 @@
+//! Constant-time base64 (standard alphabet, padded) and hex codecs for secret
+//! material. The usual decoders index a table with every input character, so
+//! cache timing can leak the key being imported; here characters are mapped
+//! with branch-free arithmetic and errors are collected in a mask that is only
+//! looked at once the whole input is processed. Only the length and the
+//! position of padding, both public for a key, affect timing.
+
+use crate::error::Error;
+
+/// All ones if `lo <= c <= hi`, else zero.
+fn in_range(c: i16, lo: u8, hi: u8) -> i16 {
+    ((lo as i16 - 1 - c) & (c - hi as i16 - 1)) >> 15
+}
+
+fn encode6(x: u8) -> u8 {
+    let x = x as i16;
+    let mut c = x + b'A' as i16;
+    c += ((25 - x) >> 8) & 6; // 'a' - 26 - 'A'
+    c -= ((51 - x) >> 8) & 75; // 'a' + 26 - '0'
+    c -= ((61 - x) >> 8) & 15; // '0' + 10 - '+'
+    c += ((62 - x) >> 8) & 3; // '/' - '+' - 1
+    c as u8
+}
+
+/// The 6-bit value of a base64 character and a mask that's all ones if it is
+/// one.
+fn decode6(c: u8) -> (u8, u8) {
+    let c = c as i16;
+    let mut res = 0;
+    let mut valid = 0;
+    for (mask, value) in [
+        (in_range(c, b'A', b'Z'), c - b'A' as i16),
+        (in_range(c, b'a', b'z'), c - b'a' as i16 + 26),
+        (in_range(c, b'0', b'9'), c - b'0' as i16 + 52),
+        (in_range(c, b'+', b'+'), 62),
+        (in_range(c, b'/', b'/'), 63),
+    ] {
+        res |= mask & value;
+        valid |= mask;
+    }
+    (res as u8, valid as u8)
+}
+
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut res = Vec::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let sextets = [
+            b[0] >> 2,
+            (b[0] & 0x03) << 4 | b[1] >> 4,
+            (b[1] & 0x0F) << 2 | b[2] >> 6,
+            b[2] & 0x3F,
+        ];
+        for (i, &s) in sextets.iter().enumerate() {
+            res.push(if i <= chunk.len() { encode6(s) } else { b'=' });
+        }
+    }
+    String::from_utf8(res).unwrap()
+}
+
+/// Decodes canonical base64: padded, and with the unused bits of the last
+/// group zero, so every byte string has exactly one encoding.
+pub fn base64_decode(s: &str) -> Result<Vec<u8>, Error> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(4) {
+        return Err(Error::Base64);
+    }
+    let padding = s.iter().rev().take(2).take_while(|&&c| c == b'=').count();
+    let mut res = Vec::with_capacity(s.len() / 4 * 3);
+    let mut bad = 0u8;
+    for (i, group) in s.chunks(4).enumerate() {
+        let last = i + 1 == s.len() / 4;
+        let mut bits = 0u32;
+        for (j, &c) in group.iter().enumerate() {
+            let (value, valid) = if last && j >= 4 - padding {
+                (0, 0xFF)
+            } else {
+                decode6(c)
+            };
+            bits = bits << 6 | value as u32;
+            bad |= !valid;
+        }
+        let bytes = &bits.to_be_bytes()[1..];
+        if last {
+            res.extend(&bytes[..3 - padding]);
+            bad |= bytes[3 - padding..].iter().fold(0, |acc, b| acc | b);
+        } else {
+            res.extend(bytes);
+        }
+    }
+    if bad != 0 {
+        return Err(Error::Base64);
+    }
+    Ok(res)
+}
+
+/// Lowercase hex.
+pub fn hex_encode(data: &[u8]) -> String {
+    let nibble = |n: u8| {
+        let n = n as i16;
+        (n + b'0' as i16 + (((9 - n) >> 8) & 39)) as u8
+    };
+    let res = data
+        .iter()
+        .flat_map(|&b| [nibble(b >> 4), nibble(b & 0x0F)])
+        .collect();
+    String::from_utf8(res).unwrap()
+}
+
+/// Decodes hex of either case.
+pub fn hex_decode(s: &str) -> Result<Vec<u8>, Error> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(2) {
+        return Err(Error::Hex);
+    }
+    let mut bad = 0;
+    let mut nibble = |c: u8| {
+        let c = c as i16;
+        let digit = in_range(c, b'0', b'9');
+        let lower = in_range(c, b'a', b'f');
+        let upper = in_range(c, b'A', b'F');
+        bad |= !(digit | lower | upper);
+        ((digit & (c - b'0' as i16))
+            | (lower & (c - b'a' as i16 + 10))
+            | (upper & (c - b'A' as i16 + 10))) as u8
+    };
+    let res = s
+        .chunks(2)
+        .map(|pair| nibble(pair[0]) << 4 | nibble(pair[1]))
+        .collect();
+    if bad != 0 {
+        return Err(Error::Hex);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::prelude::*;
+
+    use crate::error::Error;
+
+    use super::{base64_decode, base64_encode, decode6, encode6, hex_decode, hex_encode};
+
+    #[test]
+    fn alphabet() {
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        for (i, &c) in alphabet.iter().enumerate() {
+            assert_eq!(encode6(i as u8), c);
+            assert_eq!(decode6(c), (i as u8, 0xFF));
+        }
+        for c in (0..=255).filter(|c| !alphabet.contains(c)) {
+            assert_eq!(decode6(c).1, 0);
+        }
+    }
+
+    #[test]
+    fn matches_base64_crate() {
+        for len in 0..40 {
+            let data = (0..len).map(|i| (i * 37 + 11) as u8).collect::<Vec<_>>();
+            let encoded = base64_encode(&data);
+            assert_eq!(encoded, BASE64_STANDARD.encode(&data));
+            assert_eq!(base64_decode(&encoded), Ok(data));
+        }
+        for bad in ["A", "AB=C", "A===", "AB", "ABC*", "QR==", "QUJ=", "Zm9v\n"] {
+            assert_eq!(base64_decode(bad), Err(Error::Base64), "{}", bad);
+            assert!(BASE64_STANDARD.decode(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn hex() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let encoded = hex_encode(&data);
+        assert!(encoded.starts_with("000102"));
+        assert!(encoded.ends_with("fdfeff"));
+        assert_eq!(hex_decode(&encoded), Ok(data.clone()));
+        assert_eq!(hex_decode(&encoded.to_uppercase()), Ok(data));
+        assert_eq!(hex_decode("abc"), Err(Error::Hex));
+        assert_eq!(hex_decode("0g"), Err(Error::Hex));
+        assert_eq!(hex_decode(":0"), Err(Error::Hex));
+    }
+}
@@ -0,0 +1,401 @@
+//! Custom curves as documents, so one can be passed around instead of
+//! compiled in: a flat TOML table or JSON object of big-endian hex strings
+//! `p`, `a`, `b`, `gx`, `gy`, optionally `order` and `cofactor`. Like the
+//! CLI's config file, the subset is small enough to parse by hand.
+//!
+//! ```toml
+//! p = "0x144c3b27ff"
+//! a = "0x64"
+//! b = "0x01"
+//! gx = "0x09c4"
+//! gy = "0x01e849"
+//! ```
+
+use std::{fs, path::Path};
+
+use rand::Rng;
+
+use crate::{
+    algebra::{PrimeGroup, Ring},
+    base_traits::{Endianness, FromRandom, Natural, RW},
+    ct_codec, kat,
+    mod_field::{ModField, ModFieldCfg},
+    points_group::{scalar_mul_ladder, Point, PointCfg},
+};
+
+/// A curve over `ModField<I>` with what's known about its group.
+pub struct Curve<I: Natural> {
+    pub cfg: PointCfg<ModField<I>>,
+    /// Order of the generator.
+    pub order: Option<I>,
+    pub cofactor: Option<I>,
+}
+
+const REQUIRED: [&str; 5] = ["p", "a", "b", "gx", "gy"];
+
+/// `0x`-prefixed or bare big-endian hex of any length whose value fits `I`.
+fn from_hex<I: Natural + RW>(s: &str) -> Option<I> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let padded = if s.len() % 2 == 1 {
+        format!("0{}", s)
+    } else {
+        s.to_string()
+    };
+    let bytes = ct_codec::hex_decode(&padded).ok()?;
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let bytes = &bytes[zeros..];
+    if bytes.len() > I::LEN {
+        return None;
+    }
+    let mut be = vec![0; I::LEN - bytes.len()];
+    be.extend(bytes);
+    Some(I::from_bytes_endian(&mut be.as_slice(), Endianness::Big))
+}
+
+/// A quoted string without escapes, optionally followed by a comment.
+fn quoted(s: &str) -> Option<String> {
+    let (value, rest) = s.strip_prefix('"')?.split_once('"')?;
+    let rest = rest.trim_start();
+    (rest.is_empty() || rest.starts_with('#')).then(|| value.to_string())
+}
+
+impl<I: Natural + RW> Curve<I> {
+    fn fields(&self) -> Vec<(&'static str, I)> {
+        let c = &self.cfg;
+        let mut res = vec![
+            ("p", c.cf.rem),
+            ("a", c.a.nat()),
+            ("b", c.b.nat()),
+            ("gx", c.g.x().nat()),
+            ("gy", c.g.y().nat()),
+        ];
+        res.extend(self.order.map(|n| ("order", n)));
+        res.extend(self.cofactor.map(|h| ("cofactor", h)));
+        res
+    }
+
+    pub fn to_toml(&self) -> String {
+        self.fields()
+            .into_iter()
+            .map(|(k, v)| format!("{} = \"0x{}\"\n", k, kat::hex(v)))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let fields = self
+            .fields()
+            .into_iter()
+            .map(|(k, v)| format!("  \"{}\": \"0x{}\"", k, kat::hex(v)))
+            .collect::<Vec<_>>();
+        format!("{{\n{}\n}}\n", fields.join(",\n"))
+    }
+
+    fn from_fields(fields: Vec<(String, String)>) -> Result<Self, String> {
+        let mut values: [Option<I>; 7] = [None; 7];
+        for (key, value) in fields {
+            let i = REQUIRED
+                .iter()
+                .chain(&["order", "cofactor"])
+                .position(|&k| k == key)
+                .ok_or_else(|| format!("unknown key {:?}", key))?;
+            if values[i].is_some() {
+                return Err(format!("duplicate key {:?}", key));
+            }
+            values[i] = Some(from_hex(&value).ok_or_else(|| format!("{}: not a hex number", key))?);
+        }
+        let mut required = [I::zero(); 5];
+        for (i, name) in REQUIRED.iter().enumerate() {
+            required[i] = values[i].ok_or_else(|| format!("missing key {:?}", name))?;
+        }
+        let [p, a, b, gx, gy] = required;
+        Self::new(p, a, b, (gx, gy), values[5], values[6])
+    }
+
+    /// `y² = x³ + a·x + b` over `F_p` with generator `g`, checking that `g`
+    /// is on it and, if given, has the stated order.
+    pub fn new(
+        p: I,
+        a: I,
+        b: I,
+        (gx, gy): (I, I),
+        order: Option<I>,
+        cofactor: Option<I>,
+    ) -> Result<Self, String> {
+        if p <= I::two() {
+            return Err("p: not an odd prime".to_string());
+        }
+        if [a, b, gx, gy].iter().any(|&v| v >= p) {
+            return Err("coordinates and coefficients must be below p".to_string());
+        }
+        let cf = ModFieldCfg { rem: p };
+        let (gx, gy) = (ModField::new(gx, &cf), ModField::new(gy, &cf));
+        let cfg = PointCfg {
+            g: Point::new_unsafe(gx, gy),
+            a: ModField::new(a, &cf),
+            b: ModField::new(b, &cf),
+            cf,
+        };
+        let one = ModField::one(&cf);
+        let rhs = ModField::sop(&[(gx.sqr(&cf), gx), (cfg.a, gx), (cfg.b, one)], &cf);
+        if gy.sqr(&cf) != rhs {
+            return Err("the generator isn't on the curve".to_string());
+        }
+        if let Some(n) = order {
+            if !cfg.g.in_subgroup(n, &cfg) {
+                return Err("the generator doesn't have the given order".to_string());
+            }
+        }
+        Ok(Self {
+            cfg,
+            order,
+            cofactor,
+        })
+    }
+
+    pub fn parse_toml(text: &str) -> Result<Self, String> {
+        let mut fields = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: &str| format!("line {}: {}", i + 1, msg);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err("expected key = \"value\""))?;
+            let value = quoted(value.trim()).ok_or_else(|| err("expected a quoted string"))?;
+            fields.push((key.trim().to_string(), value));
+        }
+        Self::from_fields(fields)
+    }
+
+    pub fn parse_json(text: &str) -> Result<Self, String> {
+        let body = text
+            .trim()
+            .strip_prefix('{')
+            .and_then(|t| t.strip_suffix('}'))
+            .ok_or("expected a JSON object")?;
+        let mut fields = vec![];
+        for member in body.split(',').filter(|m| !m.trim().is_empty()) {
+            let field = member
+                .split_once(':')
+                .and_then(|(k, v)| Some((quoted(k.trim())?, quoted(v.trim())?)));
+            fields
+                .push(field.ok_or_else(|| {
+                    format!("expected \"key\": \"value\", got {}", member.trim())
+                })?);
+        }
+        Self::from_fields(fields)
+    }
+
+    /// Reads a `.json` file as JSON and anything else as TOML.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let res = if path.extension().is_some_and(|e| e == "json") {
+            Self::parse_json(&text)
+        } else {
+            Self::parse_toml(&text)
+        };
+        res.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// A [`Curve`] whose generator has a known prime order, the parameters of
+/// the [`PrimeGroup`] it generates.
+pub struct PrimeCurve<I: Natural> {
+    cfg: PointCfg<ModField<I>>,
+    order: I,
+}
+
+impl<I: Natural> Curve<I> {
+    /// The subgroup generated by `g`; an error if the order isn't known.
+    pub fn prime_group(self) -> Result<PrimeCurve<I>, String> {
+        let order = self
+            .order
+            .ok_or("a prime group needs the order of the generator")?;
+        Ok(PrimeCurve {
+            cfg: self.cfg,
+            order,
+        })
+    }
+}
+
+impl<I: Natural + RW + FromRandom<()>> PrimeGroup for Point<ModField<I>> {
+    type Scalar = I;
+    type Params = PrimeCurve<I>;
+
+    fn cfg(p: &PrimeCurve<I>) -> &PointCfg<ModField<I>> {
+        &p.cfg
+    }
+
+    fn generator(p: &PrimeCurve<I>) -> Self {
+        p.cfg.g
+    }
+
+    fn order(p: &PrimeCurve<I>) -> I {
+        p.order
+    }
+
+    fn random(rng: &mut impl Rng, p: &PrimeCurve<I>) -> Self {
+        let n = Self::order(p);
+        let k = I::random(rng, &()) % (n - I::one()) + I::one();
+        PrimeGroup::mul(Self::generator(p), k, p)
+    }
+
+    /// Over the complete projective formulas, [`scalar_mul_ladder`].
+    fn mul(self, k: I, p: &PrimeCurve<I>) -> Self {
+        scalar_mul_ladder(self, k, &p.cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use rand::{Rng, SeedableRng};
+
+    use crate::{
+        algebra::PrimeGroup,
+        base_traits::{FromRandom, Natural},
+        curves::{secp256k1, secp256k1_order, toy, toy_order},
+        uint::U256,
+    };
+
+    use super::Curve;
+
+    fn toy_curve() -> Curve<u64> {
+        Curve {
+            cfg: toy(),
+            order: Some(toy_order().rem as u64),
+            cofactor: None,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let curve = toy_curve();
+        let toml = curve.to_toml();
+        assert!(toml.starts_with("p = \"0x000000144c3b27ff\"\n"));
+        for parsed in [
+            Curve::<u64>::parse_toml(&toml).unwrap(),
+            Curve::<u64>::parse_json(&curve.to_json()).unwrap(),
+        ] {
+            assert_eq!(parsed.fields(), curve.fields());
+        }
+        let secp = Curve {
+            cfg: secp256k1(),
+            order: Some(secp256k1_order().rem),
+            cofactor: Some(U256::from(1u64)),
+        };
+        let parsed = Curve::<U256>::parse_json(&secp.to_json()).unwrap();
+        assert_eq!(parsed.fields(), secp.fields());
+        assert_eq!(parsed.cfg.g, secp.cfg.g);
+    }
+
+    #[test]
+    fn module_example() {
+        let curve = Curve::<u64>::parse_toml(
+            "p = \"0x144c3b27ff\"\na = \"0x64\"\nb = \"0x01\"\ngx = \"0x09c4\"\ngy = \"0x01e849\"",
+        )
+        .unwrap();
+        assert_eq!(curve.cfg.g, toy().g);
+        assert_eq!(curve.order, None);
+    }
+
+    #[test]
+    fn errors() {
+        let toml = toy_curve().to_toml();
+        let err = |text: &str| Curve::<u64>::parse_toml(text).err().unwrap();
+        assert_eq!(err(&toml.replace("gy", "gz")), "unknown key \"gz\"");
+        assert_eq!(err(&toml.replace("gy = ", "# gy = ")), "missing key \"gy\"");
+        assert_eq!(err(&format!("{}a = \"0x01\"", toml)), "duplicate key \"a\"");
+        assert_eq!(
+            err(&toml.replace("b = \"0x0000000000000001\"", "b = \"0x02\"")),
+            "the generator isn't on the curve"
+        );
+        let wrong_order = Curve {
+            order: Some(toy_order().rem as u64 - 1),
+            ..toy_curve()
+        };
+        assert_eq!(
+            err(&wrong_order.to_toml()),
+            "the generator doesn't have the given order"
+        );
+        assert_eq!(
+            err(&toml.replace("a = \"0x0000000000000064", "a = \"0x000000144c3b27ff")),
+            "coordinates and coefficients must be below p"
+        );
+        assert_eq!(err("p = \"0x1ffffffffffffffff\""), "p: not a hex number");
+        assert_eq!(err("p = 7"), "line 1: expected a quoted string");
+        assert!(Curve::<u64>::parse_json("p = \"0x07\"").is_err());
+    }
+
+    #[test]
+    fn load() {
+        let dir = env::temp_dir().join(format!("curve-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let curve = toy_curve();
+        let (toml, json) = (dir.join("toy.toml"), dir.join("toy.json"));
+        fs::write(&toml, curve.to_toml()).unwrap();
+        fs::write(&json, curve.to_json()).unwrap();
+        assert_eq!(Curve::<u64>::load(&toml).unwrap().fields(), curve.fields());
+        assert_eq!(Curve::<u64>::load(&json).unwrap().fields(), curve.fields());
+        fs::write(&json, curve.to_toml()).unwrap();
+        assert!(Curve::<u64>::load(&json)
+            .err()
+            .unwrap()
+            .starts_with(&json.display().to_string()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Schnorr's identification protocol, written against any prime group.
+    fn identify<G, R>(rng: &mut R, p: &G::Params) -> bool
+    where
+        G: PrimeGroup,
+        G::Scalar: FromRandom<()>,
+        R: Rng,
+    {
+        let n = G::order(p);
+        let scalar =
+            |rng: &mut R| G::Scalar::random(rng, &()) % (n - G::Scalar::one()) + G::Scalar::one();
+        let (x, r, e) = (scalar(rng), scalar(rng), scalar(rng));
+        let (public, commitment) = (G::generator(p).mul(x, p), G::generator(p).mul(r, p));
+        // s = r + e·x mod n, without overflowing
+        let s = r.add_mod(e.mul_mod(x, n), n);
+        G::generator(p).mul(s, p) == G::add(commitment, public.mul(e, p), p)
+    }
+
+    #[test]
+    fn prime_group() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let toy = Curve {
+            cfg: toy(),
+            order: Some(toy_order().rem as u64),
+            cofactor: None,
+        }
+        .prime_group()
+        .unwrap();
+        let secp = Curve {
+            cfg: secp256k1(),
+            order: Some(secp256k1_order().rem),
+            cofactor: None,
+        }
+        .prime_group()
+        .unwrap();
+        for _ in 0..4 {
+            assert!(identify::<super::Point<_>, _>(&mut rng, &toy));
+        }
+        assert!(identify::<super::Point<_>, _>(&mut rng, &secp));
+        let p: super::Point<_> = PrimeGroup::random(&mut rng, &toy);
+        assert!(p.in_subgroup(toy_order().rem, &toy.cfg));
+        assert_eq!(p.mul(toy_order().rem as u64 + 1, &toy), p);
+        assert!(p.mul(toy_order().rem as u64, &toy).is_infinity());
+        // no order, no group
+        let unknown = Curve {
+            cfg: crate::curves::toy(),
+            order: None,
+            cofactor: None,
+        };
+        assert!(unknown.prime_group().is_err());
+    }
+}
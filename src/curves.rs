@@ -0,0 +1,199 @@
+//! Curve parameters used by the CLI, the benchmarks and the tests.
+
+use crate::{
+    algebra::{Field, Ring},
+    mod_field::{ModField, ModFieldCfg},
+    models::{Montgomery, TwistedEdwards},
+    points_group::{Point, PointCfg},
+    uint::U256,
+};
+
+// https://en.bitcoin.it/wiki/Secp256k1
+pub fn secp256k1() -> PointCfg<ModField<U256>> {
+    let cfg_field = ModFieldCfg {
+        rem: U256::from_big_endian(&[
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
+            0xFF, 0xFF, 0xFC, 0x2F,
+        ]),
+    };
+    let gx = U256::from_big_endian(&[
+        0x79, 0xBE, 0x66, 0x7E, 0xF9, 0xDC, 0xBB, 0xAC, 0x55, 0xA0, 0x62, 0x95, 0xCE, 0x87, 0x0B,
+        0x07, 0x02, 0x9B, 0xFC, 0xDB, 0x2D, 0xCE, 0x28, 0xD9, 0x59, 0xF2, 0x81, 0x5B, 0x16, 0xF8,
+        0x17, 0x98,
+    ]);
+    let gy = U256::from_big_endian(&[
+        0x48, 0x3A, 0xDA, 0x77, 0x26, 0xA3, 0xC4, 0x65, 0x5D, 0xA4, 0xFB, 0xFC, 0x0E, 0x11, 0x08,
+        0xA8, 0xFD, 0x17, 0xB4, 0x48, 0xA6, 0x85, 0x54, 0x19, 0x9C, 0x47, 0xD0, 0x8F, 0xFB, 0x10,
+        0xD4, 0xB8,
+    ]);
+    PointCfg {
+        g: Point::new_unsafe(ModField::new(gx, &cfg_field), ModField::new(gy, &cfg_field)),
+        a: ModField::new(U256::ZERO, &cfg_field),
+        b: ModField::new(U256::from(7u64), &cfg_field),
+        cf: cfg_field,
+    }
+}
+
+/// Order of the secp256k1 generator, the modulus of its scalars.
+pub fn secp256k1_order() -> ModFieldCfg<U256> {
+    ModFieldCfg {
+        rem: U256::from_be_hex("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
+            .unwrap(),
+    }
+}
+
+// https://neuromancer.sk/std/nist/P-256
+pub fn p256() -> PointCfg<ModField<U256>> {
+    let u256 = |hex| U256::from_be_hex(hex).unwrap();
+    let cfg_field = ModFieldCfg {
+        rem: u256("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"),
+    };
+    PointCfg {
+        g: Point::new_unsafe(
+            ModField::new(
+                u256("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"),
+                &cfg_field,
+            ),
+            ModField::new(
+                u256("4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+                &cfg_field,
+            ),
+        ),
+        a: ModField::new(cfg_field.rem - U256::from(3u64), &cfg_field),
+        b: ModField::new(
+            u256("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"),
+            &cfg_field,
+        ),
+        cf: cfg_field,
+    }
+}
+
+/// Order of the P-256 generator.
+pub fn p256_order() -> ModFieldCfg<U256> {
+    ModFieldCfg {
+        rem: U256::from_be_hex("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551")
+            .unwrap(),
+    }
+}
+
+/// `2²⁵⁵ - 19`.
+fn p25519() -> ModFieldCfg<U256> {
+    ModFieldCfg {
+        rem: U256::from_be_hex("7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed")
+            .unwrap(),
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc7748#section-4.1
+pub fn curve25519() -> Montgomery<ModField<U256>> {
+    let cf = p25519();
+    Montgomery {
+        a: ModField::new(U256::from(486662u64), &cf),
+        b: ModField::one(&cf),
+        cf,
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc8032#section-5.1
+pub fn ed25519() -> TwistedEdwards<ModField<U256>> {
+    let cf = p25519();
+    let f = |v: u64| ModField::new(U256::from(v), &cf);
+    TwistedEdwards {
+        a: f(1).neg(&cf),
+        d: ModField::div(f(121665), f(121666), &cf).neg(&cf),
+        cf,
+    }
+}
+
+/// The base field of Pallas, which is the scalar field of Vesta.
+pub fn pallas_base() -> ModFieldCfg<U256> {
+    ModFieldCfg {
+        rem: U256::from_be_hex("40000000000000000000000000000000224698fc094cf91b992d30ed00000001")
+            .unwrap(),
+    }
+}
+
+/// The base field of Vesta, which is the scalar field of Pallas.
+pub fn vesta_base() -> ModFieldCfg<U256> {
+    ModFieldCfg {
+        rem: U256::from_be_hex("40000000000000000000000000000000224698fc0994a8dd8c46eb2100000001")
+            .unwrap(),
+    }
+}
+
+/// `y² = x³ + 5` with generator `(-1, 2)` over the given field: Pallas over
+/// [`pallas_base`], Vesta over [`vesta_base`].
+fn pasta(cfg_field: ModFieldCfg<U256>) -> PointCfg<ModField<U256>> {
+    PointCfg {
+        g: Point::new_unsafe(
+            ModField::new(cfg_field.rem - U256::from(1u64), &cfg_field),
+            ModField::new(U256::from(2u64), &cfg_field),
+        ),
+        a: ModField::new(U256::ZERO, &cfg_field),
+        b: ModField::new(U256::from(5u64), &cfg_field),
+        cf: cfg_field,
+    }
+}
+
+// https://github.com/zcash/pasta
+// Each curve has as many points as the other's base field has elements, so
+// the scalars of one are the coordinates of the other. Both fields are
+// 1 mod 2^32, where `ModField::sqrt` can't take roots yet, so points are only
+// reached through the generator, not decompressed.
+pub fn pallas() -> PointCfg<ModField<U256>> {
+    pasta(pallas_base())
+}
+
+/// Order of the Pallas generator.
+pub fn pallas_order() -> ModFieldCfg<U256> {
+    vesta_base()
+}
+
+pub fn vesta() -> PointCfg<ModField<U256>> {
+    pasta(vesta_base())
+}
+
+/// Order of the Vesta generator.
+pub fn vesta_order() -> ModFieldCfg<U256> {
+    pallas_base()
+}
+
+/// The small curve used throughout the tests; cheap enough for quick vectors.
+pub fn toy() -> PointCfg<ModField<u64>> {
+    let cfg_field = ModFieldCfg {
+        rem: 0x0014_4C3B_27FFu64,
+    };
+    PointCfg {
+        g: Point::new_unsafe(
+            ModField::new(2500, &cfg_field),
+            ModField::new(125001, &cfg_field),
+        ),
+        a: ModField::new(100, &cfg_field),
+        b: ModField::new(1, &cfg_field),
+        cf: cfg_field,
+    }
+}
+
+/// Order of the toy generator. It is 1277 · 2383 · 3581, nowhere near prime,
+/// so discrete logs on this curve are easy; it only makes tests fast.
+pub fn toy_order() -> ModFieldCfg<u128> {
+    ModFieldCfg { rem: 10897308871 }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::points_group::Point;
+
+    use super::{pallas, pallas_order, vesta, vesta_order};
+
+    #[test]
+    fn pasta_cycle() {
+        for (c, n) in [(pallas(), pallas_order()), (vesta(), vesta_order())] {
+            Point::new(c.g.x(), c.g.y(), &c);
+            assert!(c.g.in_subgroup(n.rem, &c));
+        }
+        assert_eq!(pallas().cf.rem, vesta_order().rem);
+        assert_eq!(vesta().cf.rem, pallas_order().rem);
+    }
+}
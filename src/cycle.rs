@@ -0,0 +1,115 @@
+//! Cycles of curves: two curves over prime fields where the group order of
+//! each is the base field of the other, so a statement about one curve's
+//! points is arithmetic in the other's scalars. That's what recursive proofs
+//! are built on. Values are plain `ModField`s on both sides; what changes is
+//! which configuration they're reduced by.
+
+use crate::{
+    base_traits::Natural,
+    curves::{pallas, vesta},
+    mod_field::{ModField, ModFieldCfg},
+    points_group::PointCfg,
+    uint::U256,
+};
+
+pub trait CurveCycle {
+    type I: Natural;
+
+    fn first() -> PointCfg<ModField<Self::I>>;
+    fn second() -> PointCfg<ModField<Self::I>>;
+
+    /// The scalars of the first curve: the second one's base field.
+    fn first_scalars() -> ModFieldCfg<Self::I> {
+        Self::second().cf
+    }
+
+    /// The scalars of the second curve: the first one's base field.
+    fn second_scalars() -> ModFieldCfg<Self::I> {
+        Self::first().cf
+    }
+
+    /// A scalar of the first curve as a coordinate of the second.
+    fn first_scalar_to_second_base(s: ModField<Self::I>) -> ModField<Self::I> {
+        ModField::new(s.nat(), &Self::second().cf)
+    }
+
+    /// A scalar of the second curve as a coordinate of the first.
+    fn second_scalar_to_first_base(s: ModField<Self::I>) -> ModField<Self::I> {
+        ModField::new(s.nat(), &Self::first().cf)
+    }
+
+    /// A coordinate of the first curve as one of its own scalars, which is
+    /// the non-native direction: `None` if it doesn't fit below the order.
+    fn first_base_to_scalar(x: ModField<Self::I>) -> Option<ModField<Self::I>> {
+        let n = Self::first_scalars();
+        (x.nat() < n.rem).then(|| ModField::new(x.nat(), &n))
+    }
+
+    /// Like [`Self::first_base_to_scalar`], for the second curve.
+    fn second_base_to_scalar(x: ModField<Self::I>) -> Option<ModField<Self::I>> {
+        let n = Self::second_scalars();
+        (x.nat() < n.rem).then(|| ModField::new(x.nat(), &n))
+    }
+}
+
+/// Pallas first, then Vesta.
+pub struct Pasta;
+
+impl CurveCycle for Pasta {
+    type I = U256;
+
+    fn first() -> PointCfg<ModField<Self::I>> {
+        pallas()
+    }
+
+    fn second() -> PointCfg<ModField<Self::I>> {
+        vesta()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeOp, Ring},
+        curves::{pallas_order, vesta_order},
+        mod_field::ModField,
+        points_group::Point,
+        uint::U256,
+    };
+
+    use super::{CurveCycle, Pasta};
+
+    #[test]
+    fn pasta() {
+        let (pallas, vesta) = (Pasta::first(), Pasta::second());
+        assert_eq!(Pasta::first_scalars().rem, pallas_order().rem);
+        assert_eq!(Pasta::second_scalars().rem, vesta_order().rem);
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let s = ModField::random_nonzero(&mut rng, &Pasta::first_scalars());
+        let x = Pasta::first_scalar_to_second_base(s);
+        assert_eq!(Pasta::second_scalar_to_first_base(x), s);
+        // the scalar acts on Pallas the same either way
+        let t = ModField::sub(s, ModField::one(&pallas_order()), &pallas_order());
+        assert_eq!(
+            Point::exp(pallas.g, s.nat(), &pallas),
+            Point::op(Point::exp(pallas.g, t.nat(), &pallas), pallas.g, &pallas)
+        );
+        // Pallas' field is the smaller one, so its coordinates always fit
+        // among its scalars, while Vesta's may not
+        let x = pallas.g.x();
+        assert_eq!(
+            Pasta::first_base_to_scalar(x).map(ModField::nat),
+            Some(x.nat())
+        );
+        let big = vesta.g.x();
+        assert!(big.nat() >= vesta_order().rem);
+        assert_eq!(Pasta::second_base_to_scalar(big), None);
+        let small = ModField::new(U256::from(7u64), &vesta.cf);
+        assert_eq!(
+            Pasta::second_base_to_scalar(small).map(ModField::nat),
+            Some(U256::from(7u64))
+        );
+    }
+}
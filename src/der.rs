@@ -0,0 +1,247 @@
+//! Explicit domain parameters as the DER `ECParameters` of SEC1 (section
+//! C.2) and RFC 3279, for curves over prime fields, so a custom curve can be
+//! handed to OpenSSL and friends:
+//!
+//! ```text
+//! ECParameters ::= SEQUENCE {
+//!     version   INTEGER { ecpVer1(1) },
+//!     fieldID   SEQUENCE { prime-field OBJECT IDENTIFIER, p INTEGER },
+//!     curve     SEQUENCE { a OCTET STRING, b OCTET STRING, seed BIT STRING OPTIONAL },
+//!     base      OCTET STRING,  -- uncompressed point
+//!     order     INTEGER,
+//!     cofactor  INTEGER OPTIONAL }
+//! ```
+//!
+//! Only the DER subset this needs is implemented. The seed is skipped on
+//! import, and compressed base points aren't supported.
+
+use crate::{
+    base_traits::{Endianness, Natural, RW},
+    curve_file::Curve,
+};
+
+pub(crate) const SEQUENCE: u8 = 0x30;
+pub(crate) const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+pub(crate) const BIT_STRING: u8 = 0x03;
+pub(crate) const OBJECT_IDENTIFIER: u8 = 0x06;
+
+/// `1.2.840.10045.1.1`, `prime-field`.
+const PRIME_FIELD: [u8; 7] = [0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x01, 0x01];
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let len = content.len();
+    let mut res = vec![tag];
+    match len {
+        0..=0x7F => res.push(len as u8),
+        0x80..0x100 => res.extend([0x81, len as u8]),
+        _ => res.extend([0x82, (len >> 8) as u8, len as u8]),
+    }
+    res.extend(content);
+    res
+}
+
+/// Big-endian bytes without leading zeros.
+fn be_bytes<I: RW>(v: I) -> Vec<u8> {
+    let mut bytes = v.to_vec_endian(Endianness::Big);
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    bytes.split_off(zeros)
+}
+
+/// Big-endian bytes padded to `len`.
+fn fixed_bytes<I: RW>(v: I, len: usize) -> Vec<u8> {
+    let bytes = be_bytes(v);
+    [vec![0; len - bytes.len()], bytes].concat()
+}
+
+fn integer<I: RW>(v: I) -> Vec<u8> {
+    let mut bytes = be_bytes(v);
+    if bytes.first().is_none_or(|&b| b & 0x80 != 0) {
+        bytes.insert(0, 0);
+    }
+    tlv(INTEGER, &bytes)
+}
+
+/// The content of the element with the given tag at the start of `input`,
+/// and what's after it.
+pub(crate) fn read(input: &[u8], tag: u8) -> Result<(&[u8], &[u8]), String> {
+    let err = || format!("expected a DER element with tag {:#04x}", tag);
+    let (&t, rest) = input.split_first().ok_or_else(err)?;
+    if t != tag {
+        return Err(err());
+    }
+    let (&first, rest) = rest.split_first().ok_or_else(err)?;
+    let (len, rest) = match first {
+        0..=0x7F => (first as usize, rest),
+        0x81 if rest.first().is_some_and(|&l| l >= 0x80) => (rest[0] as usize, &rest[1..]),
+        0x82 if rest.len() >= 2 && rest[0] != 0 => {
+            (((rest[0] as usize) << 8) | rest[1] as usize, &rest[2..])
+        }
+        _ => return Err("unsupported or non-minimal DER length".to_string()),
+    };
+    if rest.len() < len {
+        return Err("truncated DER element".to_string());
+    }
+    Ok(rest.split_at(len))
+}
+
+/// Big-endian bytes of at most `I::LEN` significant bytes as `I`.
+fn natural<I: Natural + RW>(bytes: &[u8]) -> Result<I, String> {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let bytes = &bytes[zeros..];
+    if bytes.len() > I::LEN {
+        return Err("integer too large".to_string());
+    }
+    let mut be = vec![0; I::LEN - bytes.len()];
+    be.extend(bytes);
+    Ok(I::from_bytes_endian(&mut be.as_slice(), Endianness::Big))
+}
+
+pub(crate) fn read_integer<I: Natural + RW>(input: &[u8]) -> Result<(I, &[u8]), String> {
+    let (content, rest) = read(input, INTEGER)?;
+    match content {
+        [] => Err("empty INTEGER".to_string()),
+        [b, ..] if b & 0x80 != 0 => Err("negative INTEGER".to_string()),
+        [0, b, ..] if b & 0x80 == 0 => Err("non-minimal INTEGER".to_string()),
+        _ => Ok((natural(content)?, rest)),
+    }
+}
+
+impl<I: Natural + RW> Curve<I> {
+    /// `None` without a known order, which `ECParameters` requires.
+    pub fn to_der(&self) -> Option<Vec<u8>> {
+        let c = &self.cfg;
+        let len = be_bytes(c.cf.rem).len();
+        let field_id = [tlv(OBJECT_IDENTIFIER, &PRIME_FIELD), integer(c.cf.rem)].concat();
+        let curve = [
+            tlv(OCTET_STRING, &fixed_bytes(c.a.nat(), len)),
+            tlv(OCTET_STRING, &fixed_bytes(c.b.nat(), len)),
+        ]
+        .concat();
+        let base = [
+            vec![0x04],
+            fixed_bytes(c.g.x().nat(), len),
+            fixed_bytes(c.g.y().nat(), len),
+        ]
+        .concat();
+        let mut params = [
+            integer(1u8),
+            tlv(SEQUENCE, &field_id),
+            tlv(SEQUENCE, &curve),
+            tlv(OCTET_STRING, &base),
+            integer(self.order?),
+        ]
+        .concat();
+        if let Some(h) = self.cofactor {
+            params.extend(integer(h));
+        }
+        Some(tlv(SEQUENCE, &params))
+    }
+
+    /// Reads `ECParameters` with the checks of [`Curve::new`].
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let (params, rest) = read(der, SEQUENCE)?;
+        if !rest.is_empty() {
+            return Err("trailing data after ECParameters".to_string());
+        }
+        let (version, params) = read_integer::<u8>(params)?;
+        if version != 1 {
+            return Err(format!("unsupported version {}", version));
+        }
+
+        let (field_id, params) = read(params, SEQUENCE)?;
+        let (oid, field_id) = read(field_id, OBJECT_IDENTIFIER)?;
+        if oid != PRIME_FIELD {
+            return Err("only prime fields are supported".to_string());
+        }
+        let (p, _) = read_integer::<I>(field_id)?;
+
+        let (curve, params) = read(params, SEQUENCE)?;
+        let (a, curve) = read(curve, OCTET_STRING)?;
+        let (b, curve) = read(curve, OCTET_STRING)?;
+        if !curve.is_empty() {
+            read(curve, BIT_STRING)?;
+        }
+
+        let (base, params) = read(params, OCTET_STRING)?;
+        let coords = match base.split_first() {
+            Some((0x04, xy)) if xy.len() % 2 == 0 => xy,
+            _ => return Err("the base point isn't uncompressed".to_string()),
+        };
+        let (gx, gy) = coords.split_at(coords.len() / 2);
+
+        let (order, params) = read_integer::<I>(params)?;
+        let cofactor = if params.is_empty() {
+            None
+        } else {
+            Some(read_integer::<I>(params)?.0)
+        };
+        Self::new(
+            p,
+            natural(a)?,
+            natural(b)?,
+            (natural(gx)?, natural(gy)?),
+            Some(order),
+            cofactor,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ct_codec::hex_decode,
+        curve_file::Curve,
+        curves::{secp256k1, secp256k1_order, toy, toy_order},
+        uint::U256,
+    };
+
+    // openssl ecparam -name secp256k1 -param_enc explicit -outform DER
+    const SECP256K1: &str = concat!(
+        "3081e0020101302c06072a8648ce3d0101022100ffffffffffffffffffffffffffffffff",
+        "fffffffffffffffffffffffefffffc2f3044042000000000000000000000000000000000",
+        "000000000000000000000000000000000420000000000000000000000000000000000000",
+        "000000000000000000000000000704410479be667ef9dcbbac55a06295ce870b07029bfc",
+        "db2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554",
+        "199c47d08ffb10d4b8022100fffffffffffffffffffffffffffffffebaaedce6af48a03b",
+        "bfd25e8cd0364141020101",
+    );
+
+    #[test]
+    fn secp256k1_matches_openssl() {
+        let curve = Curve {
+            cfg: secp256k1(),
+            order: Some(secp256k1_order().rem),
+            cofactor: Some(U256::from(1u64)),
+        };
+        let der = hex_decode(SECP256K1).unwrap();
+        assert_eq!(curve.to_der().unwrap(), der);
+        let parsed = Curve::<U256>::from_der(&der).unwrap();
+        assert_eq!(parsed.cfg.g, curve.cfg.g);
+        assert_eq!(parsed.cfg.a, curve.cfg.a);
+        assert_eq!(parsed.order, curve.order);
+        assert_eq!(parsed.cofactor, curve.cofactor);
+    }
+
+    #[test]
+    fn toy_round_trip() {
+        let mut curve = Curve {
+            cfg: toy(),
+            order: None,
+            cofactor: None,
+        };
+        assert_eq!(curve.to_der(), None);
+        curve.order = Some(toy_order().rem as u64);
+        let der = curve.to_der().unwrap();
+        let parsed = Curve::<u64>::from_der(&der).unwrap();
+        assert_eq!(parsed.cfg.g, curve.cfg.g);
+        assert_eq!((parsed.order, parsed.cofactor), (curve.order, None));
+        assert!(Curve::<u64>::from_der(&der[..der.len() - 1]).is_err());
+        assert!(Curve::<u64>::from_der(&[der.clone(), vec![0]].concat()).is_err());
+        // a secp256k1 prime doesn't fit into u64
+        assert_eq!(
+            Curve::<u64>::from_der(&hex_decode(SECP256K1).unwrap()).err(),
+            Some("integer too large".to_string())
+        );
+    }
+}
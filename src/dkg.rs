@@ -0,0 +1,788 @@
+//! Distributed key generation among `parties` parties, any `threshold` of
+//! which can later act with the key while fewer learn nothing about it.
+//!
+//! Every party deals a Feldman VSS of a random secret: it picks a polynomial
+//! `f` of degree `threshold - 1`, broadcasts the commitments `a_k·G` to its
+//! coefficients, [`Participant::dealing`], and sends party `j` the share
+//! `f(j)` privately, [`Participant::share_for`]. Each share is checked
+//! against its dealer's commitments, [`verify_share`], and a party's share
+//! of the joint key is the sum of what it received, [`Participant::finish`].
+//! The joint key is the sum of the dealt secrets, which nobody knows, and
+//! its public key the sum of the constant commitments, which everybody does.
+//!
+//! Parties are numbered from 1; the share of party `j` is the joint
+//! polynomial at `j`, so 0 would be the key itself. A share that doesn't
+//! match fails with [`Error::Dealing`] naming its dealer, for the others to
+//! hear the complaint and exclude it. This is the plain Joint-Feldman
+//! protocol: a dealer who speaks last can bias the public key, which doesn't
+//! help it against discrete-log based signatures but rules out uses that
+//! need the key uniform.
+//!
+//! A long-lived key changes its shares, not itself: a proactive refresh,
+//! [`KeyShare::refresh`], adds a fresh sharing of 0 to every share, and a
+//! [`Resharing`] hands the key to another threshold or set of parties.
+//! Both go through dealings checked the same way.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{self, CommutativeMonoid, CommutativeOp, ConditionalSwap, InitialPoint, Ring},
+    base_traits::{FromRandom, Natural},
+    ecc::PublicKey,
+    error::Error,
+    mod_field::{ModField, ModFieldCfg},
+};
+
+/// What a dealer broadcasts: commitments to the coefficients of its
+/// polynomial, the constant one first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dealing<P> {
+    pub dealer: u32,
+    pub commitments: Vec<P>,
+}
+
+/// The dealer's polynomial at the recipient's index, for the recipient
+/// alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Share<I> {
+    pub dealer: u32,
+    pub recipient: u32,
+    pub value: I,
+}
+
+/// One party's side of a run, holding the polynomial it deals.
+pub struct Participant<I: Natural> {
+    index: u32,
+    parties: u32,
+    coefficients: Vec<ModField<I>>,
+}
+
+/// A party's share of the joint key, with what everybody knows about the
+/// others'.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyShare<P, I> {
+    index: u32,
+    parties: u32,
+    secret: I,
+    /// The sums of the dealers' commitments, committing to the joint
+    /// polynomial.
+    commitments: Vec<P>,
+}
+
+/// `i` as a scalar mod `n`.
+fn index_scalar<I: Natural>(i: u32, n: &ModFieldCfg<I>) -> ModField<I> {
+    (0..32).rev().fold(ModField::zero(n), |acc, bit| {
+        let acc = ModField::add(acc, acc, n);
+        if i >> bit & 1 == 1 {
+            ModField::add(acc, ModField::one(n), n)
+        } else {
+            acc
+        }
+    })
+}
+
+/// `Σ C_k·i^k`, the committed polynomial at `i` times `G`; `None` if every
+/// term is zero.
+fn evaluate_commitments<P: CommutativeOp<algebra::ops::Add> + Copy, I: Natural>(
+    commitments: &[P],
+    i: u32,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> Option<P> {
+    let x = index_scalar(i, n);
+    let mut power = ModField::one(n);
+    let mut sum: Option<P> = None;
+    for &c in commitments {
+        // exp can't take 0
+        if power != ModField::zero(n) {
+            let term = P::exp(c, power.nat(), cfg);
+            sum = Some(sum.map_or(term, |s| P::op(s, term, cfg)));
+        }
+        power = ModField::mul(power, x, n);
+    }
+    sum
+}
+
+/// Whether `share` is the polynomial committed to in `dealing` at the
+/// recipient's index.
+pub fn verify_share<P, I>(
+    dealing: &Dealing<P>,
+    share: &Share<I>,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> bool
+where
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + Eq,
+    P::Cfg: InitialPoint<P>,
+    I: Natural,
+{
+    if share.dealer != dealing.dealer || share.value >= n.rem {
+        return false;
+    }
+    let lhs = (share.value != I::zero()).then(|| cfg.g().exp_ct(share.value, cfg));
+    lhs == evaluate_commitments(&dealing.commitments, share.recipient, cfg, n)
+}
+
+/// The Lagrange coefficient of party `i` for interpolating at 0 from the
+/// shares of `indices`: `Π j / (j - i)` over the other `j`. The key is the
+/// sum of these times the shares. [`Error::Participant`] if an index is 0
+/// or appears twice, or, modulo a composite order, a difference has no
+/// inverse.
+pub fn lagrange_coefficient<I: Natural>(
+    indices: &[u32],
+    i: u32,
+    n: &ModFieldCfg<I>,
+) -> Result<ModField<I>, Error> {
+    if let Some((_, &j)) = indices
+        .iter()
+        .enumerate()
+        .find(|&(k, j)| indices[..k].contains(j))
+    {
+        return Err(Error::Participant(j));
+    }
+    let x = index_scalar(i, n);
+    if x == ModField::zero(n) || !indices.contains(&i) {
+        return Err(Error::Participant(i));
+    }
+    let mut num = ModField::one(n);
+    let mut den = ModField::one(n);
+    for &j in indices.iter().filter(|&&j| j != i) {
+        let y = index_scalar(j, n);
+        num = ModField::mul(num, y, n);
+        den = ModField::mul(den, ModField::sub(y, x, n), n);
+    }
+    // a zero j makes the numerator zero
+    if num == ModField::zero(n) {
+        return Err(Error::Participant(0));
+    }
+    let den = den.inv_or_factor(n).map_err(|_| Error::Participant(i))?;
+    Ok(ModField::mul(num, den, n))
+}
+
+impl<I: Natural + FromRandom<()>> Participant<I> {
+    /// Party `index` of `parties`, with a fresh polynomial of degree
+    /// `threshold - 1`. [`Error::Threshold`] unless
+    /// `1 ≤ threshold ≤ parties`, [`Error::Participant`] unless
+    /// `1 ≤ index ≤ parties`.
+    pub fn new(
+        index: u32,
+        threshold: usize,
+        parties: u32,
+        rng: &mut impl Rng,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Self, Error> {
+        if threshold == 0 || threshold > parties as usize {
+            return Err(Error::Threshold { threshold, parties });
+        }
+        if index == 0 || index > parties {
+            return Err(Error::Participant(index));
+        }
+        // nonzero, so every commitment is a point
+        let coefficients = (0..threshold)
+            .map(|_| ModField::random_nonzero(rng, n))
+            .collect();
+        Ok(Self {
+            index,
+            parties,
+            coefficients,
+        })
+    }
+}
+
+impl<I: Natural> Participant<I> {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn dealing<P>(&self, cfg: &P::Cfg) -> Dealing<P>
+    where
+        P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap,
+        P::Cfg: InitialPoint<P>,
+    {
+        Dealing {
+            dealer: self.index,
+            commitments: self
+                .coefficients
+                .iter()
+                .map(|a| cfg.g().exp_ct(a.nat(), cfg))
+                .collect(),
+        }
+    }
+
+    /// The share for party `recipient`, this one included.
+    pub fn share_for(&self, recipient: u32, n: &ModFieldCfg<I>) -> Share<I> {
+        let x = index_scalar(recipient, n);
+        // Horner's rule, from the top coefficient
+        let value = self
+            .coefficients
+            .iter()
+            .rev()
+            .fold(ModField::zero(n), |acc, &a| {
+                ModField::add(ModField::mul(acc, x, n), a, n)
+            });
+        Share {
+            dealer: self.index,
+            recipient,
+            value: value.nat(),
+        }
+    }
+
+    /// Ends the run, given every party's dealing and the shares sent to
+    /// this one, its own included. A dealing missing or of the wrong
+    /// degree, or a share missing or not matching its dealing, fails with
+    /// [`Error::Dealing`] of the dealer; a dealer or recipient that isn't
+    /// a party with [`Error::Participant`].
+    pub fn finish<P>(
+        self,
+        dealings: &[Dealing<P>],
+        shares: &[Share<I>],
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<KeyShare<P, I>, Error>
+    where
+        P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + Eq,
+        P::Cfg: InitialPoint<P>,
+    {
+        let threshold = self.coefficients.len();
+        let dealers: Vec<u32> = (1..=self.parties).collect();
+        let received = collect(&dealers, self.index, dealings, shares, |d, s| {
+            d.commitments.len() == threshold && verify_share(d, s, cfg, n)
+        })?;
+        Ok(KeyShare {
+            index: self.index,
+            parties: self.parties,
+            secret: sum_shares(ModField::zero(n), &received, n),
+            commitments: sum_commitments(&received, cfg),
+        })
+    }
+}
+
+/// A dealer's dealing and its share for this party.
+type Received<'a, P, I> = (&'a Dealing<P>, &'a Share<I>);
+
+/// The one dealing and the one share from each of `dealers`, each pair
+/// passing `check`. What comes from anyone else, or goes to anyone but
+/// `recipient`, fails with [`Error::Participant`].
+fn collect<'a, P, I>(
+    dealers: &[u32],
+    recipient: u32,
+    dealings: &'a [Dealing<P>],
+    shares: &'a [Share<I>],
+    check: impl Fn(&Dealing<P>, &Share<I>) -> bool,
+) -> Result<Vec<Received<'a, P, I>>, Error> {
+    if let Some(d) = dealings.iter().find(|d| !dealers.contains(&d.dealer)) {
+        return Err(Error::Participant(d.dealer));
+    }
+    if let Some(s) = shares
+        .iter()
+        .find(|s| !dealers.contains(&s.dealer) || s.recipient != recipient)
+    {
+        let stranger = if dealers.contains(&s.dealer) {
+            s.recipient
+        } else {
+            s.dealer
+        };
+        return Err(Error::Participant(stranger));
+    }
+    let mut received = vec![];
+    for &dealer in dealers {
+        let mut of_dealer = dealings.iter().filter(|d| d.dealer == dealer);
+        let dealing = match (of_dealer.next(), of_dealer.next()) {
+            (Some(d), None) => d,
+            _ => return Err(Error::Dealing(dealer)),
+        };
+        let mut of_dealer = shares.iter().filter(|s| s.dealer == dealer);
+        match (of_dealer.next(), of_dealer.next()) {
+            (Some(s), None) if check(dealing, s) => received.push((dealing, s)),
+            _ => return Err(Error::Dealing(dealer)),
+        }
+    }
+    Ok(received)
+}
+
+fn sum_shares<P, I: Natural>(
+    init: ModField<I>,
+    received: &[Received<P, I>],
+    n: &ModFieldCfg<I>,
+) -> I {
+    received
+        .iter()
+        .fold(init, |acc, (_, s)| {
+            ModField::add(acc, ModField::new(s.value, n), n)
+        })
+        .nat()
+}
+
+/// The coefficient-wise sum of the dealings' commitments, all of the same
+/// length and at least one of them.
+fn sum_commitments<P: CommutativeOp<algebra::ops::Add> + Copy, I>(
+    received: &[Received<P, I>],
+    cfg: &P::Cfg,
+) -> Vec<P> {
+    let (first, rest) = received.split_first().expect("at least one dealer");
+    rest.iter()
+        .fold(first.0.commitments.clone(), |acc, (d, _)| {
+            add_commitments(acc, &d.commitments, cfg)
+        })
+}
+
+fn add_commitments<P: CommutativeOp<algebra::ops::Add> + Copy>(
+    a: Vec<P>,
+    b: &[P],
+    cfg: &P::Cfg,
+) -> Vec<P> {
+    a.into_iter()
+        .zip(b)
+        .map(|(a, &b)| P::op(a, b, cfg))
+        .collect()
+}
+
+impl<P: CommutativeOp<algebra::ops::Add> + Copy, I: Natural> KeyShare<P, I> {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// How many parties it takes to use the key.
+    pub fn threshold(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// How many parties hold a share.
+    pub fn parties(&self) -> u32 {
+        self.parties
+    }
+
+    /// The joint public key, the same for every party.
+    pub fn public_key(&self) -> PublicKey<P> {
+        PublicKey::from_point(self.commitments[0])
+    }
+
+    /// The commitments to the joint polynomial, public like the key. A
+    /// [`Resharing`] starts from them.
+    pub fn commitments(&self) -> &[P] {
+        &self.commitments
+    }
+
+    /// `x_j·G` for the share `x_j` of party `j`, to check what it
+    /// contributes when the key is used. `None` in the negligible case of a
+    /// zero share.
+    pub fn verification_share(&self, j: u32, cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Option<P> {
+        evaluate_commitments(&self.commitments, j, cfg, n)
+    }
+}
+
+/// Proactive refresh: every party deals a sharing of 0 and adds what it
+/// receives to its share. The key and the threshold stay, but the shares
+/// are new, so whatever an attacker collected of the old ones doesn't
+/// combine with the new ones. A refresh dealing commits to the
+/// coefficients from the linear one on; the constant one is 0 by
+/// construction.
+impl<P, I> KeyShare<P, I>
+where
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + Eq,
+    P::Cfg: InitialPoint<P>,
+    I: Natural + FromRandom<()>,
+{
+    /// This party's dealing of 0, and its share for every party.
+    pub fn refresh_dealing(
+        &self,
+        rng: &mut impl Rng,
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> (Dealing<P>, Vec<Share<I>>) {
+        let mut coefficients = vec![ModField::zero(n)];
+        coefficients.extend((1..self.threshold()).map(|_| ModField::random_nonzero(rng, n)));
+        let dealer = Participant {
+            index: self.index,
+            parties: self.parties,
+            coefficients,
+        };
+        let commitments = dealer.coefficients[1..]
+            .iter()
+            .map(|a| cfg.g().exp_ct(a.nat(), cfg))
+            .collect();
+        let dealing = Dealing {
+            dealer: self.index,
+            commitments,
+        };
+        let shares = (1..=self.parties).map(|j| dealer.share_for(j, n)).collect();
+        (dealing, shares)
+    }
+
+    /// Adds every party's sharing of 0 to this share, checked as in
+    /// [`Participant::finish`].
+    pub fn refresh(
+        self,
+        dealings: &[Dealing<P>],
+        shares: &[Share<I>],
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Self, Error> {
+        let degree = self.threshold() - 1;
+        let dealers: Vec<u32> = (1..=self.parties).collect();
+        let received = collect(&dealers, self.index, dealings, shares, |d, s| {
+            // the polynomial is x times the committed one
+            let lhs = (s.value != I::zero()).then(|| cfg.g().exp_ct(s.value, cfg));
+            let x = index_scalar(s.recipient, n);
+            let rhs = evaluate_commitments(&d.commitments, s.recipient, cfg, n)
+                .filter(|_| x != ModField::zero(n))
+                .map(|p| CommutativeOp::exp(p, x.nat(), cfg));
+            d.commitments.len() == degree && s.value < n.rem && lhs == rhs
+        })?;
+        let secret = sum_shares(ModField::new(self.secret, n), &received, n);
+        let mut commitments = self.commitments;
+        for (d, _) in received {
+            let linear = add_commitments(commitments[1..].to_vec(), &d.commitments, cfg);
+            commitments.truncate(1);
+            commitments.extend(linear);
+        }
+        Ok(Self {
+            secret,
+            commitments,
+            ..self
+        })
+    }
+}
+
+/// What old and new parties agree on to move a key to another threshold
+/// or set of parties: every dealer, at least the old threshold of the old
+/// parties, deals its share weighted by its Lagrange coefficient, and the
+/// shares of those dealings add up to a sharing of the same key. The old
+/// shares are useless afterwards, with an old threshold of them as much as
+/// with fewer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resharing<P> {
+    old: Vec<P>,
+    dealers: Vec<u32>,
+    threshold: usize,
+    parties: u32,
+}
+
+impl<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + Eq> Resharing<P>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    /// From the old [`KeyShare::commitments`] to a `threshold` out of
+    /// `parties`, dealt by the old parties `dealers`. [`Error::Threshold`]
+    /// for fewer dealers than the old threshold or a new threshold not
+    /// between 1 and `parties`.
+    pub fn new(
+        old: Vec<P>,
+        dealers: Vec<u32>,
+        threshold: usize,
+        parties: u32,
+    ) -> Result<Self, Error> {
+        if dealers.len() < old.len() {
+            return Err(Error::Threshold {
+                threshold: old.len(),
+                parties: dealers.len() as u32,
+            });
+        }
+        if threshold == 0 || threshold > parties as usize {
+            return Err(Error::Threshold { threshold, parties });
+        }
+        Ok(Self {
+            old,
+            dealers,
+            threshold,
+            parties,
+        })
+    }
+
+    /// The dealing of the old party holding `key`, and its share for every
+    /// new party. [`Error::Participant`] if it isn't among the dealers or
+    /// the dealers don't interpolate.
+    pub fn deal<I: Natural + FromRandom<()>>(
+        &self,
+        key: &KeyShare<P, I>,
+        rng: &mut impl Rng,
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<(Dealing<P>, Vec<Share<I>>), Error> {
+        let weight = lagrange_coefficient(&self.dealers, key.index, n)?;
+        let constant = ModField::mul(weight, ModField::new(key.secret, n), n);
+        if constant == ModField::zero(n) {
+            // only a zero share gets here, which a sum of random ones
+            // practically never is
+            return Err(Error::Participant(key.index));
+        }
+        let mut coefficients = vec![constant];
+        coefficients.extend((1..self.threshold).map(|_| ModField::random_nonzero(rng, n)));
+        let dealer = Participant {
+            index: key.index,
+            parties: self.parties,
+            coefficients,
+        };
+        let shares = (1..=self.parties).map(|j| dealer.share_for(j, n)).collect();
+        Ok((dealer.dealing(cfg), shares))
+    }
+
+    /// The new share of party `index`, from the dealings of every dealer
+    /// and their shares for it. A dealing also fails with
+    /// [`Error::Dealing`] if it doesn't deal its dealer's weighted old
+    /// share, which the old commitments give away in the exponent.
+    pub fn finish<I: Natural>(
+        &self,
+        index: u32,
+        dealings: &[Dealing<P>],
+        shares: &[Share<I>],
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<KeyShare<P, I>, Error> {
+        if index == 0 || index > self.parties {
+            return Err(Error::Participant(index));
+        }
+        let received = collect(&self.dealers, index, dealings, shares, |d, s| {
+            let Ok(weight) = lagrange_coefficient(&self.dealers, d.dealer, n) else {
+                return false;
+            };
+            let dealt = evaluate_commitments(&self.old, d.dealer, cfg, n)
+                .map(|p| CommutativeOp::exp(p, weight.nat(), cfg));
+            d.commitments.len() == self.threshold
+                && dealt == Some(d.commitments[0])
+                && verify_share(d, s, cfg, n)
+        })?;
+        Ok(KeyShare {
+            index,
+            parties: self.parties,
+            secret: sum_shares(ModField::zero(n), &received, n),
+            commitments: sum_commitments(&received, cfg),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeOp, Ring},
+        curves::{toy, toy_order},
+        error::Error,
+        mod_field::ModField,
+        points_group::Point,
+    };
+
+    use super::{lagrange_coefficient, verify_share, KeyShare, Participant, Resharing};
+
+    type P = Point<ModField<u64>>;
+
+    fn run(threshold: usize, parties: u32, rng: &mut impl rand::Rng) -> Vec<KeyShare<P, u128>> {
+        let (cfg, n) = (toy(), toy_order());
+        let participants: Vec<_> = (1..=parties)
+            .map(|i| Participant::<u128>::new(i, threshold, parties, rng, &n).unwrap())
+            .collect();
+        let dealings: Vec<_> = participants.iter().map(|p| p.dealing(&cfg)).collect();
+        let shares: Vec<Vec<_>> = (1..=parties)
+            .map(|j| participants.iter().map(|p| p.share_for(j, &n)).collect())
+            .collect();
+        participants
+            .into_iter()
+            .zip(&shares)
+            .map(|(p, shares)| p.finish(&dealings, shares, &cfg, &n).unwrap())
+            .collect()
+    }
+
+    /// The secret the shares of `keys` interpolate to.
+    fn interpolate(keys: &[&KeyShare<P, u128>]) -> u128 {
+        let n = toy_order();
+        let indices: Vec<u32> = keys.iter().map(|k| k.index).collect();
+        keys.iter()
+            .fold(ModField::zero(&n), |acc, k| {
+                let l = lagrange_coefficient(&indices, k.index, &n).unwrap();
+                ModField::add(acc, ModField::mul(l, ModField::new(k.secret, &n), &n), &n)
+            })
+            .nat()
+    }
+
+    #[test]
+    fn three_parties_two_needed() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([12u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let participants: Vec<_> = (1..=3)
+            .map(|i| Participant::<u128>::new(i, 2, 3, &mut rng, &n).unwrap())
+            .collect();
+        let dealings: Vec<_> = participants.iter().map(|p| p.dealing::<P>(&cfg)).collect();
+        let shares_for =
+            |j| -> Vec<_> { participants.iter().map(|p| p.share_for(j, &n)).collect() };
+        let all_shares: Vec<_> = (1..=3).map(shares_for).collect();
+
+        // a share off by one is blamed on its dealer
+        let mut tampered = all_shares[0].clone();
+        tampered[1].value = (tampered[1].value + 1) % n.rem;
+        assert!(!verify_share(&dealings[1], &tampered[1], &cfg, &n));
+        let first = || Participant {
+            index: 1,
+            parties: 3,
+            coefficients: participants[0].coefficients.clone(),
+        };
+        assert_eq!(
+            first().finish(&dealings, &tampered, &cfg, &n),
+            Err(Error::Dealing(2))
+        );
+        assert_eq!(
+            first().finish(&dealings[..2], &all_shares[0], &cfg, &n),
+            Err(Error::Dealing(3))
+        );
+
+        let keys: Vec<KeyShare<P, u128>> = participants
+            .into_iter()
+            .zip(&all_shares)
+            .map(|(p, shares)| p.finish(&dealings, shares, &cfg, &n).unwrap())
+            .collect();
+        let public = keys[0].public_key();
+        for key in &keys {
+            assert_eq!(key.public_key(), public);
+            assert_eq!(key.threshold(), 2);
+            let own = CommutativeOp::exp(cfg.g, key.secret, &cfg);
+            for other in &keys {
+                assert_eq!(other.verification_share(key.index(), &cfg, &n), Some(own));
+            }
+        }
+
+        // any two interpolate to the key, which is the sum of the dealt secrets
+        for pair in [[1, 2], [1, 3], [2, 3]] {
+            let x = pair.iter().fold(ModField::zero(&n), |acc, &i| {
+                let l = lagrange_coefficient(&pair, i, &n).unwrap();
+                let share = ModField::new(keys[i as usize - 1].secret, &n);
+                ModField::add(acc, ModField::mul(l, share, &n), &n)
+            });
+            assert_eq!(CommutativeOp::exp(cfg.g, x.nat(), &cfg), public.point());
+        }
+    }
+
+    #[test]
+    fn parameters() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([13u8; 32]);
+        let n = toy_order();
+        let mut new = |i, t, parties| Participant::<u128>::new(i, t, parties, &mut rng, &n).err();
+        assert_eq!(
+            new(1, 0, 3),
+            Some(Error::Threshold {
+                threshold: 0,
+                parties: 3
+            })
+        );
+        assert_eq!(
+            new(1, 4, 3),
+            Some(Error::Threshold {
+                threshold: 4,
+                parties: 3
+            })
+        );
+        assert_eq!(new(0, 2, 3), Some(Error::Participant(0)));
+        assert_eq!(new(4, 2, 3), Some(Error::Participant(4)));
+        assert_eq!(new(3, 3, 3), None);
+        assert_eq!(
+            lagrange_coefficient(&[1, 2, 2], 1, &n),
+            Err(Error::Participant(2))
+        );
+        assert_eq!(
+            lagrange_coefficient(&[1, 2], 3, &n),
+            Err(Error::Participant(3))
+        );
+    }
+
+    #[test]
+    fn refresh() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([14u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let old = run(2, 3, &mut rng);
+        let key = interpolate(&[&old[0], &old[1]]);
+        let (dealings, shares): (Vec<_>, Vec<_>) = old
+            .iter()
+            .map(|k| k.refresh_dealing(&mut rng, &cfg, &n))
+            .unzip();
+        let shares_for = |j: u32| -> Vec<_> { shares.iter().map(|s| s[j as usize - 1]).collect() };
+
+        let mut tampered = shares_for(1);
+        tampered[2].value = (tampered[2].value + 1) % n.rem;
+        assert_eq!(
+            old[0].clone().refresh(&dealings, &tampered, &cfg, &n),
+            Err(Error::Dealing(3))
+        );
+
+        let new: Vec<_> = old
+            .iter()
+            .map(|k| {
+                let shares = shares_for(k.index());
+                k.clone().refresh(&dealings, &shares, &cfg, &n).unwrap()
+            })
+            .collect();
+        for (before, after) in old.iter().zip(&new) {
+            assert_eq!(after.public_key(), before.public_key());
+            assert_eq!(after.threshold(), 2);
+            assert_ne!(after.secret, before.secret);
+            let own = CommutativeOp::exp(cfg.g, after.secret, &cfg);
+            assert_eq!(
+                new[0].verification_share(after.index(), &cfg, &n),
+                Some(own)
+            );
+        }
+        assert_eq!(interpolate(&[&new[0], &new[2]]), key);
+        assert_eq!(interpolate(&[&new[1], &new[2]]), key);
+        // old and new shares don't mix
+        assert_ne!(interpolate(&[&old[0], &new[1]]), key);
+    }
+
+    #[test]
+    fn reshare() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([15u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let old = run(2, 3, &mut rng);
+        let public = old[0].public_key();
+        let commitments = old[0].commitments().to_vec();
+        assert_eq!(
+            Resharing::new(commitments.clone(), vec![2], 3, 4),
+            Err(Error::Threshold {
+                threshold: 2,
+                parties: 1
+            })
+        );
+
+        // two out of three to three out of four, dealt by 1 and 3
+        let resharing = Resharing::new(commitments.clone(), vec![1, 3], 3, 4).unwrap();
+        assert_eq!(
+            resharing.deal(&old[1], &mut rng, &cfg, &n),
+            Err(Error::Participant(2))
+        );
+        let (mut dealings, shares): (Vec<_>, Vec<_>) = [&old[0], &old[2]]
+            .into_iter()
+            .map(|k| resharing.deal(k, &mut rng, &cfg, &n).unwrap())
+            .unzip();
+        let shares_for = |j: u32| -> Vec<_> { shares.iter().map(|s| s[j as usize - 1]).collect() };
+        let new: Vec<KeyShare<P, u128>> = (1..=4)
+            .map(|j| {
+                resharing
+                    .finish(j, &dealings, &shares_for(j), &cfg, &n)
+                    .unwrap()
+            })
+            .collect();
+        for key in &new {
+            assert_eq!(key.public_key(), public);
+            assert_eq!((key.threshold(), key.parties()), (3, 4));
+            let own = CommutativeOp::exp(cfg.g, key.secret, &cfg);
+            assert_eq!(new[0].verification_share(key.index(), &cfg, &n), Some(own));
+        }
+        let key = interpolate(&[&old[0], &old[1]]);
+        assert_eq!(interpolate(&[&new[0], &new[1], &new[2]]), key);
+        assert_eq!(interpolate(&[&new[0], &new[2], &new[3]]), key);
+        assert_ne!(interpolate(&[&new[0], &new[1]]), key);
+
+        // a dealer that deals anything but its weighted share is caught,
+        // even with shares that match the dealing
+        let other = Resharing::new(commitments, vec![1, 2], 3, 4).unwrap();
+        let (forged, forged_shares) = other.deal(&old[0], &mut rng, &cfg, &n).unwrap();
+        dealings[0] = forged;
+        let mut shares = shares_for(1);
+        shares[0] = forged_shares[0];
+        assert_eq!(
+            resharing.finish(1, &dealings, &shares, &cfg, &n),
+            Err(Error::Dealing(1))
+        );
+        assert_eq!(
+            resharing.finish(5, &dealings, &shares, &cfg, &n),
+            Err(Error::Participant(5))
+        );
+    }
+}
@@ -1,8 +1,16 @@
+use std::{fmt::Display, marker::PhantomData, str::FromStr};
+
 use rand::Rng;
 
 use crate::{
-    algebra::{self, CommutativeOp, InitialPoint, Inverse},
-    base_traits::{FromRandom, Natural, RW},
+    algebra::{
+        self, AbelianGroup, CommutativeMonoid, ConditionalSwap, DiscreteRoot, Field, InitialPoint,
+    },
+    base_traits::{FromRandom, Natural, Parity, RW},
+    ct_codec,
+    error::Error,
+    mod_field::{ModField, ModFieldCfg},
+    points_group::{Point, PointCfg, ValidationPolicy},
 };
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -11,7 +19,18 @@ pub struct PrivateKey<I>(I);
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PublicKey<P>(P);
 
-pub fn gen_keys<R: Rng, I: FromRandom<()> + Natural, P: CommutativeOp<algebra::ops::Add>>(
+/// An encrypted message: one `(C1, C2)` pair per embedded chunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ciphertext<P> {
+    pub chunks: Vec<(P, P)>,
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub fn gen_keys<
+    R: Rng,
+    I: FromRandom<()> + Natural,
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap,
+>(
     r: &mut R,
     cfg: &P::Cfg,
 ) -> (PrivateKey<I>, PublicKey<P>)
@@ -19,28 +38,38 @@ where
     P::Cfg: InitialPoint<P>,
 {
     let pri = I::random(r, &());
-    let pub_ = P::exp(cfg.g(), pri, cfg);
+    let pub_ = cfg.g().exp_ct(pri, cfg);
     (PrivateKey(pri), PublicKey(pub_))
 }
 
-impl<P: CommutativeOp<algebra::ops::Add> + RW> PublicKey<P>
+impl<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap> PublicKey<P>
 where
     <P as algebra::Configurable>::Cfg: InitialPoint<P>,
 {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn encrypt<I: Natural + FromRandom<()>>(
         self,
         msg: P,
         rng: &mut impl Rng,
         cfg: &P::Cfg,
     ) -> (P, P) {
-        let t = I::random(rng, &());
+        self.encrypt_with_nonce(msg, I::random(rng, &()), cfg)
+    }
+
+    /// Deterministic encryption with a caller-chosen ephemeral scalar `t`, for
+    /// reproducible test vectors. Using the same nonce twice reveals the
+    /// difference of the two messages, so anything but tests should go through
+    /// [`PublicKey::encrypt`].
+    pub fn encrypt_with_nonce<I: Natural>(self, msg: P, t: I, cfg: &P::Cfg) -> (P, P) {
         // C1 = t * G
-        let c1 = P::exp(InitialPoint::g(cfg), t, cfg);
+        let c1 = InitialPoint::g(cfg).exp_ct(t, cfg);
         // C2 = t * Pub + msg
-        let c2 = P::op(P::exp(self.0, t, cfg), msg, cfg);
+        let c2 = P::op(self.0.exp_ct(t, cfg), msg, cfg);
         (c1, c2)
     }
+}
 
+impl<P: RW> PublicKey<P> {
     pub fn base64(self) -> String {
         self.0.to_base64()
     }
@@ -50,8 +79,135 @@ where
     }
 }
 
+impl<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap> PublicKey<P> {
+    /// Builds the window table for `I`-sized nonces. Costs about as much as
+    /// three scalar multiplications and pays off from there on.
+    pub fn precompute<I: Natural>(self, cfg: &P::Cfg) -> PreparedPublicKey<P, I> {
+        PreparedPublicKey {
+            key: self,
+            table: WindowTable::new::<I>(self.0, cfg),
+            nonce: PhantomData,
+        }
+    }
+}
+
+/// Bits per window of a [`WindowTable`].
+const WINDOW: u32 = 4;
+
+/// `d · 2^(WINDOW·i) · P` for every window `i` of a scalar and every digit
+/// `d ≠ 0`, so multiplying `P` takes an addition per window and no
+/// doublings.
+#[derive(Clone, Debug)]
+pub(crate) struct WindowTable<P>(Vec<Vec<P>>);
+
+impl<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap> WindowTable<P> {
+    pub(crate) fn new<I: Natural>(p: P, cfg: &P::Cfg) -> Self {
+        let radix = (0..WINDOW).fold(I::one(), |r, _| r * I::two());
+        let mut rows = vec![];
+        let mut base = p;
+        let mut rest = <I as Natural>::max();
+        while rest != I::zero() {
+            let mut row = vec![base];
+            for _ in 2..1 << WINDOW {
+                row.push(P::op(*row.last().unwrap(), base, cfg));
+            }
+            rest = rest / radix;
+            if rest != I::zero() {
+                // 2^WINDOW · base, the next window's base
+                base = P::op(*row.last().unwrap(), base, cfg);
+            }
+            rows.push(row);
+        }
+        Self(rows)
+    }
+
+    /// `k · P` for a secret `k`: every entry of a row is read and the
+    /// digit's one picked by [`ConditionalSwap`], so neither the memory
+    /// accesses nor the number of additions depend on `k`.
+    pub(crate) fn mul<I: Natural>(&self, mut k: I, cfg: &P::Cfg) -> P {
+        let mut acc = P::identity(cfg);
+        for row in &self.0 {
+            let mut digit = 0;
+            for bit in 0..WINDOW {
+                digit |= usize::from(k % I::two() == I::one()) << bit;
+                k = k / I::two();
+            }
+            // the identity for digit 0
+            let mut e = P::identity(cfg);
+            for (d, &entry) in (1..).zip(row) {
+                let mut entry = entry;
+                P::conditional_swap(&mut e, &mut entry, d == digit);
+            }
+            acc = P::op(acc, e, cfg);
+        }
+        acc
+    }
+}
+
+/// A public key with a precomputed table of its multiples, for encrypting many
+/// chunks to the same recipient. `I` is the type of the nonces.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey<P, I> {
+    key: PublicKey<P>,
+    table: WindowTable<P>,
+    nonce: PhantomData<I>,
+}
+
+impl<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap, I: Natural> PreparedPublicKey<P, I>
+where
+    <P as algebra::Configurable>::Cfg: InitialPoint<P>,
+{
+    pub fn key(&self) -> PublicKey<P> {
+        self.key
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn encrypt(&self, msg: P, rng: &mut impl Rng, cfg: &P::Cfg) -> (P, P)
+    where
+        I: FromRandom<()>,
+    {
+        self.encrypt_with_nonce(msg, I::random(rng, &()), cfg)
+    }
+
+    /// See [`PublicKey::encrypt_with_nonce`].
+    pub fn encrypt_with_nonce(&self, msg: P, t: I, cfg: &P::Cfg) -> (P, P) {
+        let c1 = InitialPoint::g(cfg).exp_ct(t, cfg);
+        let c2 = P::op(self.table.mul(t, cfg), msg, cfg);
+        (c1, c2)
+    }
+}
+
+impl<F: Field + RW> PublicKey<Point<F>> {
+    /// Like `from_str`, but with the point checked according to `policy`.
+    pub fn from_base64_validated<I: Natural>(
+        base64: &str,
+        policy: ValidationPolicy<I>,
+        c: &PointCfg<F>,
+    ) -> Result<Self, Error> {
+        let bytes = ct_codec::base64_decode(base64)?;
+        Point::from_bytes_validated(&bytes, policy, c).map(Self)
+    }
+}
+
+impl<P> PublicKey<P> {
+    pub fn from_point(p: P) -> Self {
+        Self(p)
+    }
+
+    pub fn point(self) -> P {
+        self.0
+    }
+}
+
+impl<I: Copy> PrivateKey<I> {
+    pub(crate) fn scalar(self) -> I {
+        self.0
+    }
+}
+
 impl<I: Natural + RW> PrivateKey<I> {
-    pub fn decrypt<P: CommutativeOp<algebra::ops::Add> + Inverse<algebra::ops::Add>>(
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    pub fn decrypt<P: AbelianGroup<algebra::ops::Add> + ConditionalSwap>(
         self,
         (c1, c2): (P, P),
         cfg: &P::Cfg,
@@ -60,15 +216,186 @@ impl<I: Natural + RW> PrivateKey<I> {
         // = t * Pub + msg - priv * t * G
         // = t * priv * G + msg - priv * t * G
         // = msg
-        P::op(c2, P::inv(P::exp(c1, self.0, cfg), cfg), cfg)
+        P::op(c2, P::inv(c1.exp_ct(self.0, cfg), cfg), cfg)
     }
 
+    /// Encoded in constant time, as are the other private key codecs.
     pub fn base64(self) -> String {
-        self.0.to_base64()
+        ct_codec::base64_encode(&self.0.to_vec())
     }
 
     pub fn from_base64(base64: &str) -> Self {
-        Self(I::from_base64(base64))
+        let bytes = ct_codec::base64_decode(base64).unwrap();
+        Self(I::from_bytes(&mut &bytes[..]))
+    }
+
+    /// Little-endian, like [`PrivateKey::base64`].
+    pub fn hex(self) -> String {
+        ct_codec::hex_encode(&self.0.to_vec())
+    }
+
+    /// The key `k mod n` for the group order `n`; zero is rejected.
+    pub fn from_scalar(k: I, n: &ModFieldCfg<I>) -> Result<Self, Error> {
+        match k % n.rem {
+            k if k == I::zero() => Err(Error::ScalarRange),
+            k => Ok(Self(k)),
+        }
+    }
+
+    /// Decodes `I::LEN` little-endian bytes, accepting only `1..n` so that
+    /// every key has exactly one encoding.
+    pub fn from_bytes_checked(bytes: &[u8], n: &ModFieldCfg<I>) -> Result<Self, Error> {
+        if bytes.len() != I::LEN {
+            return Err(Error::Length {
+                expected: I::LEN,
+                actual: bytes.len(),
+            });
+        }
+        let k = I::from_bytes(&mut &bytes[..]);
+        if k == I::zero() || k >= n.rem {
+            return Err(Error::ScalarRange);
+        }
+        Ok(Self(k))
+    }
+
+    /// [`PrivateKey::from_bytes_checked`] of base64.
+    pub fn from_base64_checked(base64: &str, n: &ModFieldCfg<I>) -> Result<Self, Error> {
+        Self::from_bytes_checked(&ct_codec::base64_decode(base64)?, n)
+    }
+
+    /// [`PrivateKey::from_bytes_checked`] of hex.
+    pub fn from_hex_checked(hex: &str, n: &ModFieldCfg<I>) -> Result<Self, Error> {
+        Self::from_bytes_checked(&ct_codec::hex_decode(hex)?, n)
+    }
+}
+
+impl<I: Natural> PrivateKey<I> {
+    /// Diffie–Hellman: `self · Q` for the peer's key `Q`, after checking `Q`
+    /// according to `policy`. A peer key of small order gives the point at
+    /// infinity, which is an error rather than a shared secret.
+    pub fn diffie_hellman<F: Field>(
+        self,
+        peer: PublicKey<Point<F>>,
+        policy: ValidationPolicy<I>,
+        c: &PointCfg<F>,
+    ) -> Result<Point<F>, Error> {
+        let q = peer.0.validate(policy, c)?;
+        let shared = q.mul_ct(self.0, c);
+        match shared.is_infinity() {
+            true => Err(Error::Infinity),
+            false => Ok(shared),
+        }
+    }
+
+    /// `self · G`, the key [`gen_keys`] would have paired with this one.
+    pub fn public_key<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap>(
+        self,
+        cfg: &P::Cfg,
+    ) -> PublicKey<P>
+    where
+        P::Cfg: InitialPoint<P>,
+    {
+        PublicKey(cfg.g().exp_ct(self.0, cfg))
+    }
+}
+
+impl<I: Natural + FromRandom<()>> PrivateKey<I> {
+    /// A uniformly random key in `1..n`. [`gen_keys`] takes any value of `I`,
+    /// which is only right when the order fills the type.
+    pub fn random(rng: &mut impl Rng, n: &ModFieldCfg<I>) -> Self {
+        Self(ModField::random_nonzero(rng, n).nat())
+    }
+}
+
+/// A quick battery over a curve, cheap enough for application startup, to
+/// catch a misconfigured custom curve before it's used: a singular curve, `G`
+/// off the curve or not of the given order, square roots that don't square
+/// back, and an encryption that doesn't decrypt. [`Error::SelfTest`] names
+/// the first failed check.
+pub fn self_test<F, I, R>(cfg: &PointCfg<F>, order: Option<I>, rng: &mut R) -> Result<(), Error>
+where
+    F: Field + RW + Parity + DiscreteRoot<algebra::ops::Mul> + FromRandom<F::Cfg>,
+    I: Natural + RW + FromRandom<()>,
+    R: Rng,
+{
+    let cf = &cfg.cf;
+    let discriminant = F::add(
+        F::mul(F::four(cf), cfg.a.cube(cf), cf),
+        F::mul(F::three(cf).cube(cf), cfg.b.sqr(cf), cf),
+        cf,
+    );
+    if discriminant == F::zero(cf) {
+        return Err(Error::SelfTest("the curve is singular, 4a³ + 27b² = 0"));
+    }
+    if !cfg.g.is_on(cfg) {
+        return Err(Error::SelfTest("G isn't on the curve"));
+    }
+    if let Some(n) = order {
+        if n == I::zero() || !cfg.g.in_subgroup(n, cfg) {
+            return Err(Error::SelfTest("n·G isn't the point at infinity"));
+        }
+    }
+    for _ in 0..4 {
+        let x = F::random(rng, cf);
+        let root = x.sqr(cf).sqrt(cf);
+        if root != Some(x) && root != Some(x.neg(cf)) {
+            return Err(Error::SelfTest("the square root of x² is neither x nor -x"));
+        }
+    }
+    let msg = Point::random(rng, cfg);
+    let (pr, pb) = gen_keys::<_, I, Point<F>>(rng, cfg);
+    if pr.decrypt(pb.encrypt::<I>(msg, rng, cfg), cfg) != msg {
+        return Err(Error::SelfTest(
+            "a message doesn't survive encryption and decryption",
+        ));
+    }
+    Ok(())
+}
+
+impl<I: RW> RW for PrivateKey<I> {
+    fn to_bytes(self, w: &mut impl std::io::Write) -> usize {
+        self.0.to_bytes(w)
+    }
+
+    fn from_bytes(r: &mut impl std::io::Read) -> Self {
+        Self(I::from_bytes(r))
+    }
+
+    const LEN: usize = I::LEN;
+}
+
+impl<P: RW + Copy> Display for PublicKey<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.to_base64())
+    }
+}
+
+impl<P: RW> FromStr for PublicKey<P> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        P::try_from_base64(s).map(Self)
+    }
+}
+
+impl<I: RW + Copy> Display for PrivateKey<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&ct_codec::base64_encode(&self.0.to_vec()))
+    }
+}
+
+impl<I: RW> FromStr for PrivateKey<I> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let bytes = ct_codec::base64_decode(s)?;
+        if bytes.len() != I::LEN {
+            return Err(Error::Length {
+                expected: I::LEN,
+                actual: bytes.len(),
+            });
+        }
+        Ok(Self(I::from_bytes(&mut &bytes[..])))
     }
 }
 
@@ -77,12 +404,16 @@ mod tests {
     use rand::SeedableRng;
 
     use crate::{
-        ecc::{gen_keys, PublicKey},
+        algebra::CommutativeOp,
+        curves::{secp256k1, secp256k1_order, toy, toy_order},
+        ecc::{gen_keys, self_test, PublicKey},
         mod_field::{ModField, ModFieldCfg},
-        points_group::{Point, PointCfg},
+        points_group::{Point, PointCfg, ValidationPolicy},
     };
 
     use super::PrivateKey;
+    use crate::error::Error;
+
     fn cfg() -> PointCfg<ModField<u64>> {
         let cfg_field = ModFieldCfg {
             rem: 0x0014_4C3B_27FFu64,
@@ -113,6 +444,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fixed_nonce() {
+        let cfg_group = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let msg = Point::random(&mut gen, &cfg_group);
+        let encrypted = pb.encrypt_with_nonce(msg, 12345u128, &cfg_group);
+        assert_eq!(encrypted, pb.encrypt_with_nonce(msg, 12345u128, &cfg_group));
+        assert_ne!(encrypted, pb.encrypt_with_nonce(msg, 12346u128, &cfg_group));
+        assert_eq!(encrypted.0, Point::exp(cfg_group.g, 12345u128, &cfg_group));
+        assert_eq!(pr.decrypt(encrypted, &cfg_group), msg);
+    }
+
+    #[test]
+    fn prepared_key() {
+        let cfg_group = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let prepared = pb.precompute::<u128>(&cfg_group);
+        assert_eq!(prepared.key(), pb);
+        for t in [1u128, 2, 15, 16, 17, 0xF0F0, u128::MAX, 12345] {
+            let msg = Point::random(&mut gen, &cfg_group);
+            let encrypted = prepared.encrypt_with_nonce(msg, t, &cfg_group);
+            assert_eq!(encrypted, pb.encrypt_with_nonce(msg, t, &cfg_group));
+            assert_eq!(pr.decrypt(encrypted, &cfg_group), msg);
+        }
+    }
+
     #[test]
     fn key_persistance() {
         let cfg_group = cfg();
@@ -125,4 +484,140 @@ mod tests {
             assert_eq!(pb, pb_new);
         }
     }
+
+    #[test]
+    fn display_from_str() {
+        let cfg_group = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, Point<ModField<u64>>>(&mut gen, &cfg_group);
+        assert_eq!(pr.to_string(), pr.base64());
+        assert_eq!(pb.to_string(), pb.base64());
+        assert_eq!(pr.to_string().parse(), Ok(pr));
+        assert_eq!(pb.to_string().parse(), Ok(pb));
+        assert_eq!(pb.point().to_string().parse(), Ok(pb.point()));
+    }
+
+    #[test]
+    fn from_str_errors() {
+        assert_eq!(
+            "AAAA".parse::<PrivateKey<u128>>(),
+            Err(Error::Length {
+                expected: 16,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            "not base64!".parse::<PrivateKey<u128>>(),
+            Err(Error::Base64)
+        );
+    }
+
+    #[test]
+    fn range_checked_import() {
+        let n = ModFieldCfg { rem: 1000u128 };
+        assert_eq!(PrivateKey::from_scalar(1234, &n), Ok(PrivateKey(234)));
+        assert_eq!(PrivateKey::from_scalar(0, &n), Err(Error::ScalarRange));
+        assert_eq!(PrivateKey::from_scalar(3000, &n), Err(Error::ScalarRange));
+
+        let check = |k: u128| PrivateKey::from_bytes_checked(&k.to_le_bytes(), &n);
+        assert_eq!(check(999), Ok(PrivateKey(999)));
+        assert_eq!(check(1), Ok(PrivateKey(1)));
+        assert_eq!(check(0), Err(Error::ScalarRange));
+        assert_eq!(check(1000), Err(Error::ScalarRange));
+        assert_eq!(check(u128::MAX), Err(Error::ScalarRange));
+        assert_eq!(
+            PrivateKey::from_bytes_checked(&[1; 8], &n),
+            Err(Error::Length {
+                expected: 16,
+                actual: 8
+            })
+        );
+        let pr = PrivateKey(17u128);
+        assert_eq!(PrivateKey::from_base64_checked(&pr.base64(), &n), Ok(pr));
+        assert_eq!(
+            PrivateKey::<u128>::from_base64_checked("not base64!", &n),
+            Err(Error::Base64)
+        );
+        assert_eq!(pr.hex(), "11000000000000000000000000000000");
+        assert_eq!(PrivateKey::from_hex_checked(&pr.hex(), &n), Ok(pr));
+        assert_eq!(
+            PrivateKey::<u128>::from_hex_checked("11", &n),
+            Err(Error::Length {
+                expected: 16,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            PrivateKey::<u128>::from_hex_checked("zz", &n),
+            Err(Error::Hex)
+        );
+    }
+
+    #[test]
+    fn diffie_hellman() {
+        let c = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (alice, alice_pub) = gen_keys::<_, u128, Point<_>>(&mut gen, &c);
+        let (bob, bob_pub) = gen_keys::<_, u128, Point<_>>(&mut gen, &c);
+        let policy = ValidationPolicy::OnCurve;
+        let shared = alice.diffie_hellman(bob_pub, policy, &c).unwrap();
+        assert_eq!(bob.diffie_hellman(alice_pub, policy, &c), Ok(shared));
+
+        let off_curve = Point::new_unsafe(bob_pub.point().x(), alice_pub.point().y());
+        let encoded = PublicKey::from_point(off_curve).base64();
+        assert_eq!(
+            PublicKey::from_base64_validated(&encoded, policy, &c),
+            Err(Error::Point)
+        );
+        let unchecked =
+            PublicKey::from_base64_validated(&encoded, ValidationPolicy::<u128>::None, &c);
+        assert_eq!(unchecked, Ok(PublicKey::from_point(off_curve)));
+        assert_eq!(
+            alice.diffie_hellman(unchecked.unwrap(), policy, &c),
+            Err(Error::Point)
+        );
+    }
+
+    #[test]
+    fn self_tests() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        assert_eq!(self_test(&toy(), Some(toy_order().rem), &mut rng), Ok(()));
+        assert_eq!(self_test::<_, u128, _>(&cfg(), None, &mut rng), Ok(()));
+        let order = Some(secp256k1_order().rem);
+        assert_eq!(self_test(&secp256k1(), order, &mut rng), Ok(()));
+
+        let wrong_order = Some(toy_order().rem + 1);
+        assert_eq!(
+            self_test(&toy(), wrong_order, &mut rng),
+            Err(Error::SelfTest("n·G isn't the point at infinity"))
+        );
+        let off_curve = PointCfg {
+            b: ModField::new(2, &cfg().cf),
+            ..cfg()
+        };
+        assert!(self_test::<_, u128, _>(&off_curve, None, &mut rng).is_err());
+        let cf = ModFieldCfg { rem: 23u64 };
+        let singular = PointCfg {
+            g: Point::new_unsafe(ModField::new(1, &cf), ModField::new(0, &cf)),
+            a: ModField::new(20, &cf),
+            b: ModField::new(2, &cf),
+            cf,
+        };
+        assert_eq!(
+            self_test::<_, u64, _>(&singular, None, &mut rng),
+            Err(Error::SelfTest("the curve is singular, 4a³ + 27b² = 0"))
+        );
+    }
+
+    #[test]
+    fn random_keys() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let n = toy_order();
+        for _ in 0..20 {
+            let pr = PrivateKey::random(&mut rng, &n);
+            assert!(pr.0 != 0 && pr.0 < n.rem);
+            let pb: PublicKey<Point<_>> = pr.public_key(&toy());
+            assert_eq!(pb.point(), Point::exp(toy().g, pr.0, &toy()));
+        }
+    }
 }
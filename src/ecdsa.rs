@@ -0,0 +1,139 @@
+//! ECDSA (SEC1, section 4.1) on the 256-bit curves over a 32-byte digest the
+//! caller computed, for the formats that carry it: X.509 and OpenPGP. The
+//! nonce is hedged: hashed from the key, the digest and bytes of `rng`, so
+//! neither a broken generator nor a fault in the hash alone leaks the key.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{Field, Ring},
+    base_traits::{Endianness, Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    mod_field::{ModField, ModFieldCfg},
+    points_group::{Point, PointCfg},
+    secret_scalar::{ScalarModulus, SecretScalar},
+    uint::U256,
+};
+
+type P = Point<ModField<U256>>;
+
+const NONCE_TAG: &[u8] = b"ecdsa/nonce";
+
+/// `e`: a 256-bit digest is already as long as the order, so there's
+/// nothing to truncate, only to reduce.
+fn digest_scalar(digest: &[u8; 32], n: &ModFieldCfg<U256>) -> ModField<U256> {
+    ModField::new(
+        U256::from_bytes_endian(&mut &digest[..], Endianness::Big),
+        n,
+    )
+}
+
+/// `(r, s)` with `r` the x coordinate of `k·G` mod n and `s = k⁻¹(e + r·d)`.
+/// `k` is derived like [`SigningSession::commit`] does, with 32 bytes of
+/// `rng` mixed in: equal digests still get unrelated nonces, and a
+/// generator that repeats itself only makes the signature deterministic.
+///
+/// [`SigningSession::commit`]: crate::schnorr::SigningSession::commit
+pub fn sign_digest(
+    key: PrivateKey<U256>,
+    digest: &[u8; 32],
+    rng: &mut impl Rng,
+    cfg: &PointCfg<ModField<U256>>,
+    n: &ModFieldCfg<U256>,
+) -> (U256, U256) {
+    let m = ScalarModulus::new(n);
+    let secret = |v: U256| SecretScalar::from_natural(v, &m);
+    let e = secret(digest_scalar(digest, n).nat());
+    let d = secret(key.scalar());
+    let x = key.scalar().to_vec_endian(Endianness::Big);
+    let mut fresh = [0u8; 32];
+    rng.fill_bytes(&mut fresh);
+    let mut counter = 0u8;
+    loop {
+        // every part has a fixed length, so plain concatenation is unambiguous
+        let k = SecretScalar::from_hash(
+            NONCE_TAG,
+            &[&x, &digest[..], &fresh, &[counter]].concat(),
+            &m,
+        )
+        .to_natural();
+        counter += 1;
+        if k == U256::zero() {
+            continue;
+        }
+        let r = cfg.g.mul_ct(k, cfg).x().to_scalar(n);
+        if r == ModField::zero(n) {
+            continue;
+        }
+        // the inversion branches, so it only sees k blinded by a random b:
+        // k⁻¹ = (k·b)⁻¹·b
+        let b = secret(ModField::random_nonzero(rng, n).nat());
+        let kb = ModField::new(secret(k).mul(&b, &m).to_natural(), n);
+        let k_inv = secret(kb.reciprocal(n).unwrap().nat()).mul(&b, &m);
+        let s = k_inv.mul(&e.add(&secret(r.nat()).mul(&d, &m), &m), &m);
+        if !s.is_zero() {
+            return (r.nat(), s.to_natural());
+        }
+    }
+}
+
+/// With `w = s⁻¹`, the x coordinate of `(e·w)·G + (r·w)·Q` has to be `r`
+/// mod n.
+pub fn verify_digest(
+    key: PublicKey<P>,
+    digest: &[u8; 32],
+    (r, s): (U256, U256),
+    cfg: &PointCfg<ModField<U256>>,
+    n: &ModFieldCfg<U256>,
+) -> bool {
+    if [r, s].iter().any(|&v| v == U256::zero() || v >= n.rem) {
+        return false;
+    }
+    let e = digest_scalar(digest, n);
+    let Some(w) = ModField::new(s, n).reciprocal(n) else {
+        return false;
+    };
+    let u1 = ModField::mul(e, w, n).nat();
+    let u2 = ModField::mul(ModField::new(r, n), w, n).nat();
+    // over a prime field no inversion fails, and any infinity is a bad signature
+    let sum = cfg.g.try_mul(u1, cfg).and_then(|a| {
+        let b = key.point().try_mul(u2, cfg)?;
+        Point::try_add(a, b, cfg)
+    });
+    matches!(sum, Ok(Some(p)) if p.x().to_scalar(n).nat() == r)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use sha2::{Digest, Sha256};
+
+    use crate::{
+        curves::{p256, p256_order},
+        ecc::PrivateKey,
+    };
+
+    use super::{sign_digest, verify_digest};
+
+    #[test]
+    #[ignore = "slow without optimizations"]
+    fn sign_verify() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([9u8; 32]);
+        let (cfg, n) = (p256(), p256_order());
+        let key = PrivateKey::random(&mut rng, &n);
+        let public = key.public_key(&cfg);
+        let digest: [u8; 32] = Sha256::digest(b"message").into();
+        let (r, s) = sign_digest(key, &digest, &mut rng, &cfg, &n);
+        assert!(verify_digest(public, &digest, (r, s), &cfg, &n));
+        let other: [u8; 32] = Sha256::digest(b"other").into();
+        assert!(!verify_digest(public, &other, (r, s), &cfg, &n));
+        assert!(!verify_digest(public, &digest, (s, r), &cfg, &n));
+        assert!(!verify_digest(public, &digest, (r, n.rem), &cfg, &n));
+
+        // a generator stuck on one output repeats signatures, not nonces
+        let stuck = || rand_chacha::ChaCha8Rng::from_seed([0u8; 32]);
+        let same = sign_digest(key, &digest, &mut stuck(), &cfg, &n);
+        assert_eq!(same, sign_digest(key, &digest, &mut stuck(), &cfg, &n));
+        assert_ne!(same.0, sign_digest(key, &other, &mut stuck(), &cfg, &n).0);
+    }
+}
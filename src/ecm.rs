@@ -0,0 +1,98 @@
+//! Lenstra's elliptic curve factorization. A curve over `Z/nZ` is a curve
+//! over `Z/pZ` for every prime `p | n` at once; if its order modulo some `p`
+//! is `bound`-smooth, `bound! · P` is the point at infinity there but not
+//! modulo the other factors, and computing it runs into a denominator that
+//! `p` divides. [`Point::try_mul`] reports exactly that gcd.
+
+use rand::Rng;
+
+use crate::{
+    algebra::Ring,
+    base_traits::{FromRandom, Natural},
+    mod_field::{ModField, ModFieldCfg},
+    points_group::{Point, PointCfg},
+};
+
+/// A proper factor of `n`, trying up to `curves` random curves with stage-one
+/// bound `bound`. `n` should be composite and not a prime power; for a prime
+/// it's just a waste of time.
+pub fn find_factor<I, R>(n: I, bound: u32, curves: usize, rng: &mut R) -> Option<I>
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    if n % I::two() == I::zero() && n != I::two() {
+        return Some(I::two());
+    }
+    let cf = ModFieldCfg { rem: n };
+    for _ in 0..curves {
+        // pick the point first and make the curve go through it
+        let (x, y, a) = (
+            ModField::random(rng, &cf),
+            ModField::random(rng, &cf),
+            ModField::random(rng, &cf),
+        );
+        let minus_x = ModField::neg(x, &cf);
+        let b = ModField::sop(&[(y, y), (x.sqr(&cf), minus_x), (a, minus_x)], &cf);
+        let g = Point::new_unsafe(x, y);
+        let c = PointCfg { g, a, b, cf };
+        let (mut p, mut j) = (g, I::one());
+        for _ in 1..bound {
+            j = j + I::one();
+            match p.try_mul(j, &c) {
+                Ok(Some(q)) => p = q,
+                // infinity modulo every factor at once, try another curve
+                Ok(None) => break,
+                Err(d) if d != n => return Some(d),
+                Err(_) => break,
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        mod_field::{ModField, ModFieldCfg},
+        points_group::{Point, PointCfg},
+    };
+
+    use super::find_factor;
+
+    #[test]
+    fn try_add_reports_factor() {
+        // y² = x³ + 1 over Z/35Z; (7, 8) is (2, 3) of order 6 modulo 5 and
+        // (0, 1) of order 3 modulo 7
+        let cf = ModFieldCfg { rem: 35u64 };
+        let f = |v| ModField::new(v, &cf);
+        let (a, b) = (f(0), f(1));
+        let g = Point::new_unsafe(f(7), f(8));
+        let c = PointCfg {
+            g: Point::new(g.x(), g.y(), &PointCfg { g, a, b, cf }),
+            a,
+            b,
+            cf,
+        };
+        let double = Point::try_add(Some(g), Some(g), &c).unwrap().unwrap();
+        assert_eq!(Point::try_mul(g, 2u64, &c), Ok(Some(double)));
+        assert_eq!(Point::try_add(None, Some(g), &c), Ok(Some(g)));
+        assert_eq!(
+            Point::try_add(Some(g), Some(Point::new(f(7), f(27), &c)), &c),
+            Ok(None)
+        );
+        assert_eq!(Point::try_mul(g, 3u64, &c), Err(7));
+    }
+
+    #[test]
+    fn factors() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for (p, q) in [(1_000_003u64, 999_983), (65_537, 2_147_483_647), (101, 103)] {
+            let d = find_factor(p * q, 200, 200, &mut rng).unwrap();
+            assert!(d == p || d == q, "{} * {}: {}", p, q, d);
+        }
+        assert_eq!(find_factor(2u64 * 999_983, 10, 1, &mut rng), Some(2));
+    }
+}
@@ -1,77 +1,341 @@
-use std::io::Cursor;
+use std::{fmt::Display, io::Cursor, str::FromStr};
 
 use crate::{
-    algebra::{self, DiscreteRoot, Field},
-    base_traits::{Capacitor, FromRandom, Natural, RW},
-    ecc::{PrivateKey, PublicKey},
-    points_group::{Point, PointCfg},
+    algebra::{self, CommutativeOp, DiscreteRoot, Field, InitialPoint, Inverse},
+    base_traits::{slice_from_bytes, slice_to_bytes, Capacitor, FromRandom, Natural, Parity, RW},
+    ecc::{Ciphertext, PrivateKey, PublicKey, WindowTable},
+    error::Error,
+    mac::hmac,
+    points_group::{Point, PointCfg, ValidationPolicy, SEC1_INFINITY},
+    signer::Decrypter,
 };
 use base64::prelude::*;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
 
-fn bytes_to_point<F: Field + RW + DiscreteRoot<algebra::ops::Mul>, I: Natural + Sized>(
-    bytes: &[u8],
+/// Default Koblitz parameter: a byte worth of candidates per chunk, so embedding
+/// fails with probability of roughly 2^-256.
+pub const DEFAULT_KAPPA: u32 = 256;
+
+/// Messages of at least this many chunks are encrypted with a precomputed
+/// table of the recipient's key.
+const PRECOMPUTE_MIN_CHUNKS: usize = 4;
+
+/// Number of bytes the Koblitz counter takes, i.e. bytes needed for `κ - 1`.
+fn kappa_bytes(kappa: u32) -> usize {
+    (u32::BITS - (kappa - 1).leading_zeros()).div_ceil(8) as usize
+}
+
+/// `m·κ` over a little-endian byte string, in place.
+fn mul_le(m: &mut [u8], kappa: u32) {
+    let mut carry = 0u64;
+    for b in m.iter_mut() {
+        carry += *b as u64 * kappa as u64;
+        *b = carry as u8;
+        carry >>= 8;
+    }
+    debug_assert_eq!(carry, 0);
+}
+
+/// `⌊x / κ⌋` over a little-endian byte string, in place.
+fn div_le(x: &mut [u8], kappa: u32) {
+    let mut rem = 0u64;
+    for b in x.iter_mut().rev() {
+        let cur = (rem << 8) | *b as u64;
+        *b = (cur / kappa as u64) as u8;
+        rem = cur % kappa as u64;
+    }
+}
+
+/// Koblitz embedding: the slot, read as a little-endian integer `m`, is
+/// mapped to the first `x` among `m·κ, m·κ + 1, ..., m·κ + κ - 1` which lies
+/// on the curve.
+fn bytes_to_point<F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Parity>(
+    slot: &[u8],
+    kappa: u32,
     cfg: &PointCfg<F>,
-    cap: usize,
-) -> Point<F> {
-    assert!(bytes.len() < F::LEN);
-    let mut quintuple = vec![0u8; F::LEN];
-    quintuple[0..bytes.len()].copy_from_slice(bytes);
-    loop {
-        let mut cur = Cursor::new(&quintuple);
-        let x = F::from_bytes(&mut cur);
-        if let Some(point) = Point::from_x(x, cfg) {
-            return point;
+) -> Result<Point<F>, Error> {
+    let mut m = vec![0u8; F::LEN];
+    m[..slot.len()].copy_from_slice(slot);
+    mul_le(&mut m, kappa);
+    let mut x = F::from_bytes(&mut m.as_slice());
+    for _ in 0..kappa {
+        if let Some(point) = Point::from_x_with_parity(x, false, cfg) {
+            return Ok(point);
         }
-        quintuple[cap] += 1;
+        x = F::add(x, F::one(&cfg.cf), &cfg.cf);
     }
+    Err(Error::Embedding)
 }
 
-pub fn text_to_points<F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor, I: Natural>(
-    text: &str,
-    cfg: &PointCfg<F>,
-) -> Vec<Point<F>>
+/// Inverse of the Koblitz embedding: strips the counter by computing `⌊x / κ⌋`.
+fn point_to_slot<F: RW + Field>(point: Point<F>, kappa: u32) -> [u8; F::LEN]
 where
-    [(); F::LEN - 1]:,
+    [(); F::LEN]:,
 {
-    let bytes = text.as_bytes();
+    let mut m = point.x().to_array();
+    div_le(&mut m, kappa);
+    m
+}
 
-    let eff_length_incl_padding = F::capacity(&cfg.cf).min(F::LEN - 1) - 1;
-    assert!(eff_length_incl_padding > 1);
-    let iter_count = bytes.len() / eff_length_incl_padding;
-    let mut res = vec![];
-    for i in 0..iter_count {
-        let chunk = &bytes[i * eff_length_incl_padding..(i + 1) * eff_length_incl_padding];
-        res.push(bytes_to_point::<F, I>(chunk, cfg, eff_length_incl_padding));
-    }
-    if bytes.len() % eff_length_incl_padding != 0 {
-        let chunk = &bytes[bytes.len() / eff_length_incl_padding * eff_length_incl_padding..];
-        res.push(bytes_to_point::<F, I>(chunk, cfg, eff_length_incl_padding));
+/// Bytes of `m` a point holds: the field's capacity less the counter.
+fn slot_len<F: Field + Capacitor>(kappa: u32, cfg: &PointCfg<F>) -> Result<usize, Error> {
+    // m·κ + κ - 1 < 256^(slot + counter) <= p, and a slot needs room for a
+    // length byte and a byte of message
+    let counter = kappa_bytes(kappa);
+    let capacity = F::capacity(&cfg.cf);
+    if capacity <= counter + 1 {
+        return Err(Error::FieldTooSmall {
+            capacity,
+            required: counter + 2,
+        });
     }
+    Ok(capacity - counter)
+}
 
-    res
+/// How a message fills the slots of the points it's embedded in. Decoding
+/// with the scheme it was encoded with checks the padding, failing with
+/// [`Error::Plaintext`] in the chunk where it's wrong.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaddingScheme {
+    /// Every slot is a length byte, then up to the rest of the slot of the
+    /// message, then zeros. What [`bytes_to_points`] does.
+    #[default]
+    LengthByte,
+    /// PKCS#7 over the whole message: it's padded with `n` bytes of value
+    /// `n` to whole slots, which are all message, so an empty message still
+    /// takes a point. Slots are capped at 255 bytes for `n` to fit.
+    Pkcs7,
+    /// [`Self::LengthByte`] filled with random bytes instead of zeros, so
+    /// equal short chunks embed differently. Only the length can be checked.
+    Random,
 }
 
-pub fn points_to_text<F: RW + Field>(points: impl Iterator<Item = Point<F>>, cap: usize) -> String {
-    let mut bytes = vec![];
-    let mut buf = vec![];
-    for point in points {
-        buf.clear();
-        let b = point.x().to_bytes(&mut buf);
-        for v in 0..b.min(cap) {
-            if buf[v] == 0x00 {
-                break;
+impl PaddingScheme {
+    /// Bytes of the message per point.
+    pub fn capacity<F: Field + Capacitor>(
+        self,
+        kappa: u32,
+        cfg: &PointCfg<F>,
+    ) -> Result<usize, Error> {
+        let slot = slot_len(kappa, cfg)?;
+        Ok(match self {
+            PaddingScheme::Pkcs7 => slot.min(u8::MAX as usize),
+            PaddingScheme::LengthByte | PaddingScheme::Random => slot - 1,
+        })
+    }
+
+    /// The slots of `bytes`, `fill` filling what [`Self::Random`] leaves over.
+    fn pad(self, bytes: &[u8], capacity: usize, mut fill: impl FnMut(&mut [u8])) -> Vec<Vec<u8>> {
+        if self == PaddingScheme::Pkcs7 {
+            let n = capacity - bytes.len() % capacity;
+            let mut padded = bytes.to_vec();
+            padded.resize(bytes.len() + n, n as u8);
+            return padded.chunks(capacity).map(<[u8]>::to_vec).collect();
+        }
+        bytes
+            .chunks(capacity)
+            .map(|chunk| {
+                let mut slot = vec![0; capacity + 1];
+                slot[0] = chunk.len() as u8;
+                slot[1..=chunk.len()].copy_from_slice(chunk);
+                if self == PaddingScheme::Random {
+                    fill(&mut slot[chunk.len() + 1..]);
+                }
+                slot
+            })
+            .collect()
+    }
+
+    /// Inverse of [`Self::pad`], over the slots of the points.
+    fn unpad(
+        self,
+        slots: impl Iterator<Item = impl AsRef<[u8]>>,
+        capacity: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        let mut chunks = 0;
+        for (i, slot) in slots.enumerate() {
+            let slot = slot.as_ref();
+            chunks += 1;
+            let (message, rest) = match self {
+                PaddingScheme::Pkcs7 => slot.split_at_checked(capacity),
+                PaddingScheme::LengthByte | PaddingScheme::Random => {
+                    let len = slot[0] as usize;
+                    (len <= capacity)
+                        .then(|| slot[1..].split_at_checked(len))
+                        .flatten()
+                }
+            }
+            .ok_or(Error::Plaintext.in_chunk(i))?;
+            if self != PaddingScheme::Random && rest.iter().any(|&b| b != 0) {
+                return Err(Error::Plaintext.in_chunk(i));
+            }
+            bytes.extend_from_slice(message);
+        }
+        if self == PaddingScheme::Pkcs7 {
+            let last = Error::Plaintext.in_chunk(chunks.max(1) - 1);
+            let n = *bytes.last().ok_or(last.clone())? as usize;
+            let valid = (1..=capacity).contains(&n)
+                && bytes[bytes.len() - n..].iter().all(|&b| b as usize == n);
+            if !valid {
+                return Err(last);
             }
-            bytes.push(buf[v]);
+            bytes.truncate(bytes.len() - n);
         }
+        Ok(bytes)
     }
-    String::from_utf8(bytes).unwrap()
 }
 
-pub fn points_to_base64<F: RW + Field>(points: impl Iterator<Item = Point<F>>) -> String {
-    let mut v = vec![];
+fn embed<F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity>(
+    bytes: &[u8],
+    padding: PaddingScheme,
+    kappa: u32,
+    fill: impl FnMut(&mut [u8]),
+    cfg: &PointCfg<F>,
+) -> Result<Vec<Point<F>>, Error> {
+    let capacity = padding.capacity(kappa, cfg)?;
+    padding
+        .pad(bytes, capacity, fill)
+        .iter()
+        .enumerate()
+        .map(|(i, slot)| bytes_to_point(slot, kappa, cfg).map_err(|e| e.in_chunk(i)))
+        .collect()
+}
+
+pub fn bytes_to_points<F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity>(
+    bytes: &[u8],
+    kappa: u32,
+    cfg: &PointCfg<F>,
+) -> Result<Vec<Point<F>>, Error> {
+    embed(bytes, PaddingScheme::LengthByte, kappa, |_| {}, cfg)
+}
+
+/// [`bytes_to_points`] with the given padding; `rng` is only used by
+/// [`PaddingScheme::Random`].
+pub fn bytes_to_points_padded<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+>(
+    bytes: &[u8],
+    padding: PaddingScheme,
+    kappa: u32,
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+) -> Result<Vec<Point<F>>, Error> {
+    embed(bytes, padding, kappa, |rest| rng.fill(rest), cfg)
+}
+
+/// Inverse of [`bytes_to_points_padded`] with the same padding and `kappa`.
+pub fn points_to_bytes_padded<F: RW + Field + Capacitor>(
+    points: impl Iterator<Item = Point<F>>,
+    padding: PaddingScheme,
+    kappa: u32,
+    cfg: &PointCfg<F>,
+) -> Result<Vec<u8>, Error>
+where
+    [(); F::LEN]:,
+{
+    let capacity = padding.capacity(kappa, cfg)?;
+    padding.unpad(points.map(|p| point_to_slot(p, kappa)), capacity)
+}
+
+pub fn text_to_points<F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity>(
+    text: &str,
+    kappa: u32,
+    cfg: &PointCfg<F>,
+) -> Result<Vec<Point<F>>, Error> {
+    bytes_to_points(text.as_bytes(), kappa, cfg)
+}
+
+/// [`Error::Plaintext`] in the first chunk that isn't [`PaddingScheme::LengthByte`]
+/// padded. Without the field's capacity any length the slot holds is accepted.
+pub(crate) fn try_points_to_bytes<F: RW + Field>(
+    points: impl Iterator<Item = Point<F>>,
+    kappa: u32,
+) -> Result<Vec<u8>, Error>
+where
+    [(); F::LEN]:,
+{
+    PaddingScheme::LengthByte.unpad(points.map(|p| point_to_slot(p, kappa)), F::LEN - 1)
+}
+
+/// Inverse of [`bytes_to_points`]; panics on malformed padding, e.g. on points
+/// that weren't embedded with the same `kappa`.
+pub fn points_to_bytes<F: RW + Field>(points: impl Iterator<Item = Point<F>>, kappa: u32) -> Vec<u8>
+where
+    [(); F::LEN]:,
+{
+    try_points_to_bytes(points, kappa).expect("malformed chunk length")
+}
+
+/// [`points_to_bytes`] as text; chunks are cut at any byte, so only the
+/// whole message has to be UTF-8.
+pub fn points_to_text<F: RW + Field>(
+    points: impl Iterator<Item = Point<F>>,
+    kappa: u32,
+) -> Result<String, Error>
+where
+    [(); F::LEN]:,
+{
+    utf8(try_points_to_bytes(points, kappa)?)
+}
+
+/// Compression applied to the plaintext before it's embedded into points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+}
+
+/// First byte of a compressed payload. It never starts valid UTF-8, so plain
+/// messages need no header at all.
+const DEFLATE_HEADER: u8 = 0xFF;
+
+/// Magic and format version in front of an encrypted file.
+const FILE_MAGIC: &[u8; 5] = b"ECCF\x03";
+
+/// Plaintext bytes to embed: either the message itself or, when it pays off,
+/// the header followed by its DEFLATE stream.
+fn pack(msg: &str, compression: Compression) -> Vec<u8> {
+    if compression == Compression::Deflate {
+        let packed = miniz_oxide::deflate::compress_to_vec(msg.as_bytes(), 9);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(plain = msg.len(), packed = packed.len() + 1, "deflated");
+        if packed.len() + 1 < msg.len() {
+            return [&[DEFLATE_HEADER], packed.as_slice()].concat();
+        }
+    }
+    msg.as_bytes().to_vec()
+}
+
+/// Inverse of [`pack`], inflating at most `limit` bytes.
+fn unpack(bytes: Vec<u8>, limit: usize) -> Result<Vec<u8>, Error> {
+    match bytes.split_first() {
+        Some((&DEFLATE_HEADER, packed)) => {
+            miniz_oxide::inflate::decompress_to_vec_with_limit(packed, limit).map_err(|e| {
+                match e.status {
+                    miniz_oxide::inflate::TINFLStatus::HasMoreOutput => Error::TooLarge { limit },
+                    _ => Error::Plaintext,
+                }
+            })
+        }
+        _ => Ok(bytes),
+    }
+}
+
+/// Where bytes become text: [`Error::Utf8`] hands them back if they aren't.
+fn utf8(bytes: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(bytes).map_err(|e| Error::Utf8(e.into_bytes()))
+}
+
+pub fn points_to_base64<F: RW + Field>(points: impl Iterator<Item = Point<F>>) -> String
+where
+    [(); Point::<F>::LEN]:,
+{
+    let mut v = Vec::with_capacity(points.size_hint().0 * Point::<F>::LEN);
     for p in points {
-        p.to_bytes(&mut v);
+        v.extend_from_slice(&p.to_array());
     }
     BASE64_STANDARD.encode(&v)
 }
@@ -90,62 +354,573 @@ where
     res
 }
 
+impl<F: RW + Field> Ciphertext<Point<F>> {
+    pub fn base64(&self) -> String
+    where
+        [(); Point::<F>::LEN]:,
+    {
+        points_to_base64(self.chunks.iter().flat_map(|&(c1, c2)| [c1, c2]))
+    }
+
+    pub fn from_base64(base64: &str) -> Self
+    where
+        [(); Point::<F>::LEN]:,
+    {
+        let points = base64_to_points::<F>(base64);
+        assert!(points.len() % 2 == 0);
+        Self {
+            chunks: points
+                .into_iter()
+                .array_chunks::<2>()
+                .map(|[c1, c2]| (c1, c2))
+                .collect(),
+        }
+    }
+}
+
+impl<F: RW + Field + Parity> Ciphertext<Point<F>> {
+    /// Both points of every pair SEC1 compressed: x and the parity of y, about
+    /// half the size of [`Self::base64`]'s bytes. A point at infinity is the
+    /// single byte `0x00`, so the length isn't always a multiple of a pair.
+    pub fn to_compressed(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .flat_map(|&(c1, c2)| [c1.to_sec1_compressed(), c2.to_sec1_compressed()])
+            .flatten()
+            .collect()
+    }
+
+    pub fn compressed_base64(&self) -> String {
+        BASE64_STANDARD.encode(self.to_compressed())
+    }
+}
+
+impl<F: RW + Field + Parity + DiscreteRoot<algebra::ops::Mul>> Ciphertext<Point<F>> {
+    /// Inverse of [`Self::to_compressed`], recovering each y from its parity.
+    /// Each point's tag tells its length: one byte for the point at infinity,
+    /// a tag and x otherwise.
+    pub fn from_compressed(bytes: &[u8], cfg: &PointCfg<F>) -> Result<Self, Error> {
+        let point_len = F::LEN + 1;
+        let mut points = vec![];
+        let mut rest = bytes;
+        while let Some(&tag) = rest.first() {
+            let len = if tag == SEC1_INFINITY { 1 } else { point_len };
+            let (point, tail) = rest.split_at_checked(len).ok_or(Error::Length {
+                expected: bytes.len() - rest.len() + len,
+                actual: bytes.len(),
+            })?;
+            let i = points.len() / 2;
+            points.push(Point::from_sec1_compressed(point, cfg).ok_or(Error::Point.in_chunk(i))?);
+            rest = tail;
+        }
+        if !points.len().is_multiple_of(2) {
+            return Err(Error::Length {
+                expected: bytes.len() + point_len,
+                actual: bytes.len(),
+            });
+        }
+        Ok(Self {
+            chunks: points
+                .into_iter()
+                .array_chunks::<2>()
+                .map(|[c1, c2]| (c1, c2))
+                .collect(),
+        })
+    }
+
+    pub fn from_compressed_base64(s: &str, cfg: &PointCfg<F>) -> Result<Self, Error> {
+        Self::from_compressed(&BASE64_STANDARD.decode(s).map_err(|_| Error::Base64)?, cfg)
+    }
+}
+
+impl<F: RW + Field> Display for Ciphertext<Point<F>>
+where
+    [(); Point::<F>::LEN]:,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.base64())
+    }
+}
+
+impl<F: RW + Field> FromStr for Ciphertext<Point<F>> {
+    type Err = Error;
+
+    /// Without a curve to check the points against; input from outside
+    /// goes through [`Ciphertext::from_raw`] instead.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::from_raw_unchecked(&BASE64_STANDARD.decode(s).map_err(|_| Error::Base64)?)
+    }
+}
+
+impl<F: RW + Field> Ciphertext<Point<F>> {
+    fn from_raw_unchecked(bytes: &[u8]) -> Result<Self, Error> {
+        let pair_len = 2 * Point::<F>::LEN;
+        if !bytes.len().is_multiple_of(pair_len) {
+            return Err(Error::Length {
+                expected: bytes.len().next_multiple_of(pair_len),
+                actual: bytes.len(),
+            });
+        }
+        let mut r = bytes;
+        let chunks = (0..bytes.len() / pair_len)
+            .map(|_| (Point::from_bytes(&mut r), Point::from_bytes(&mut r)))
+            .collect();
+        Ok(Self { chunks })
+    }
+
+    /// The ciphertext from its points' bytes, i.e. decoded [`Self::base64`],
+    /// with every point checked according to `policy`. A point that fails
+    /// is an error of its chunk.
+    pub fn from_raw<I: Natural>(
+        bytes: &[u8],
+        policy: ValidationPolicy<I>,
+        cfg: &PointCfg<F>,
+    ) -> Result<Self, Error> {
+        Self::from_raw_unchecked(bytes)?.validated(policy, cfg)
+    }
+
+    /// Every point checked according to `policy`.
+    fn validated<I: Natural>(
+        self,
+        policy: ValidationPolicy<I>,
+        cfg: &PointCfg<F>,
+    ) -> Result<Self, Error> {
+        for (i, &(c1, c2)) in self.chunks.iter().enumerate() {
+            for p in [c1, c2] {
+                p.validate(policy, cfg).map_err(|e| e.in_chunk(i))?;
+            }
+        }
+        Ok(self)
+    }
+}
+
+pub(crate) fn encrypt_bytes<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    bytes: &[u8],
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+) -> Result<Ciphertext<Point<F>>, Error> {
+    encrypt_bytes_with::<F, I>(key, bytes, rng, None, cfg)
+}
+
+/// [`encrypt_bytes`] with the nonce multiples of `G` taken from `generator`
+/// if there is one, a table for `I`-sized scalars.
+fn encrypt_bytes_with<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    bytes: &[u8],
+    rng: &mut impl Rng,
+    generator: Option<&WindowTable<Point<F>>>,
+    cfg: &PointCfg<F>,
+) -> Result<Ciphertext<Point<F>>, Error> {
+    let points = bytes_to_points(bytes, DEFAULT_KAPPA, cfg)?;
+    Ok(encrypt_points_with::<F, I>(key, &points, rng, generator, cfg).0)
+}
+
+/// The ciphertext of embedded points, and every chunk's mask `t·Pub`.
+fn encrypt_points_with<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    points: &[Point<F>],
+    rng: &mut impl Rng,
+    generator: Option<&WindowTable<Point<F>>>,
+    cfg: &PointCfg<F>,
+) -> (Ciphertext<Point<F>>, Vec<Point<F>>) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(chunks = points.len(), "embedded");
+    let prepared =
+        (points.len() >= PRECOMPUTE_MIN_CHUNKS).then(|| WindowTable::new::<I>(key.point(), cfg));
+    let mul = |table: Option<&WindowTable<_>>, p, t| match table {
+        Some(table) => table.mul(t, cfg),
+        None => Point::mul_ct(p, t, cfg),
+    };
+    // what PublicKey::encrypt does, with either side possibly from a table
+    let (chunks, masks) = points
+        .iter()
+        .map(|&m| {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("encrypt").entered();
+            let t = I::random(rng, &());
+            let c1 = mul(generator, cfg.g(), t);
+            let mask = mul(prepared.as_ref(), key.point(), t);
+            ((c1, Point::op(mask, m, cfg)), mask)
+        })
+        .unzip();
+    (Ciphertext { chunks }, masks)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(len = msg.len()))
+)]
+pub fn encrypt_message<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    msg: &str,
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+) -> Result<Ciphertext<Point<F>>, Error> {
+    encrypt_bytes::<F, I>(key, msg.as_bytes(), rng, cfg)
+}
+
+/// [`encrypt_message`] with every chunk's nonce drawn from a ChaCha20 stream
+/// seeded by `seed`, so the same inputs give the same ciphertext. Meant for
+/// tests and cross-implementation vectors: a seed must never be reused for
+/// another message.
+pub fn encrypt_message_seeded<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    msg: &str,
+    seed: [u8; 32],
+    cfg: &PointCfg<F>,
+) -> Result<Ciphertext<Point<F>>, Error> {
+    encrypt_message::<F, I>(key, msg, &mut ChaCha20Rng::from_seed(seed), cfg)
+}
+
+/// Encrypts the message and base64-encodes the [compressed
+/// ciphertext](Ciphertext::to_compressed). With [`Compression::Deflate`] the
+/// message is compressed first unless that makes it longer; note that the
+/// ciphertext length then depends on the content, not only on the size.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(len = msg.len(), ?compression))
+)]
 pub fn encrypt_message_and_encode<
-    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor,
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
     I: FromRandom<()> + Natural,
 >(
     key: PublicKey<Point<F>>,
     msg: &str,
+    compression: Compression,
     rng: &mut impl Rng,
     cfg: &PointCfg<F>,
-) -> String
+) -> Result<String, Error> {
+    encrypt_bytes::<F, I>(key, &pack(msg, compression), rng, cfg).map(|c| c.compressed_base64())
+}
+
+/// Label of the key that binds associated data to a ciphertext.
+const AAD_LABEL: &[u8] = b"elliptic-curves/aad/v1";
+
+/// Bytes of the tag after a ciphertext with associated data.
+const TAG_LEN: usize = 16;
+
+/// HMAC-SHA256 over `aad` and the compressed ciphertext, truncated to
+/// [`TAG_LEN`], under a key hashed from the chunks' masks, which only the
+/// sender and the recipient know.
+fn aad_tag<F: Field + RW + Parity>(
+    masks: &[Point<F>],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> [u8; TAG_LEN] {
+    let mut key = Sha256::new().chain_update(AAD_LABEL);
+    for mask in masks {
+        key.update(mask.to_sec1_compressed());
+    }
+    let aad_len = (aad.len() as u64).to_le_bytes();
+    let mac = hmac(&key.finalize(), &[&aad_len, aad, ciphertext]);
+    mac[..TAG_LEN].try_into().unwrap()
+}
+
+/// [`encrypt_message_and_encode`] with associated data, e.g. a header or
+/// routing metadata: `aad` is neither encrypted nor included, but the tag
+/// after the ciphertext covers it, so decryption fails unless given the same.
+/// The message is [`PaddingScheme::Pkcs7`] padded, so that even an empty one
+/// has a mask to key the tag.
+pub fn encrypt_message_and_encode_with_aad<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    msg: &str,
+    aad: &[u8],
+    compression: Compression,
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+) -> Result<String, Error> {
+    let packed = pack(msg, compression);
+    let points = embed(&packed, PaddingScheme::Pkcs7, DEFAULT_KAPPA, |_| {}, cfg)?;
+    let (ciphertext, masks) = encrypt_points_with::<F, I>(key, &points, rng, None, cfg);
+    let mut bytes = ciphertext.to_compressed();
+    bytes.extend(aad_tag(&masks, aad, &bytes));
+    Ok(BASE64_STANDARD.encode(bytes))
+}
+
+/// Inverse of [`encrypt_message_and_encode_with_aad`]: [`Error::Tag`] if the
+/// ciphertext or `aad` differ from what was encrypted, or the key is wrong.
+/// The tag proves nothing about the sender, so inflation is bounded as in
+/// [`decrypt_message`].
+pub fn decode_message_and_decrypt_with_aad<
+    F: RW + Field + Capacitor + Parity + DiscreteRoot<algebra::ops::Mul>,
+>(
+    key: impl Decrypter<Point<F>>,
+    msg_base64: &str,
+    aad: &[u8],
+    cfg: &PointCfg<F>,
+) -> Result<String, Error>
 where
-    [(); F::LEN - 1]:,
+    [(); F::LEN]:,
 {
-    let points = text_to_points::<F, I>(msg, cfg);
-    let encrypted = points
+    let bytes = BASE64_STANDARD
+        .decode(msg_base64)
+        .map_err(|_| Error::Base64)?;
+    let (compressed, tag) = bytes
+        .split_at_checked(bytes.len().wrapping_sub(TAG_LEN))
+        .ok_or(Error::Length {
+            expected: TAG_LEN,
+            actual: bytes.len(),
+        })?;
+    let ciphertext = Ciphertext::from_compressed(compressed, cfg)?;
+    let masks = ciphertext
+        .chunks
         .iter()
-        .flat_map(|p| {
-            let (c1, c2) = key.encrypt::<I>(*p, rng, cfg);
-            [c1, c2]
-        })
-        .collect::<Vec<_>>();
-    points_to_base64(encrypted.into_iter())
+        .enumerate()
+        .map(|(i, &(c1, _))| key.diffie_hellman(c1, cfg).map_err(|e| e.in_chunk(i)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = aad_tag(&masks, aad, compressed);
+    if expected
+        .iter()
+        .zip(tag)
+        .fold(0, |acc, (a, b)| acc | (a ^ b))
+        != 0
+    {
+        return Err(Error::Tag);
+    }
+    let points = ciphertext
+        .chunks
+        .iter()
+        .zip(masks)
+        .map(|(&(_, c2), mask)| Point::op(c2, Point::inv(mask, cfg), cfg));
+    let packed = points_to_bytes_padded(points, PaddingScheme::Pkcs7, DEFAULT_KAPPA, cfg)?;
+    utf8(unpack(packed, DecodeLimits::default().max_plaintext)?)
 }
 
-pub fn decode_message_and_decrypt<IP: RW + Natural, F: RW + Field + Capacitor>(
+/// Encrypts arbitrary bytes, e.g. a file's contents, into the magic, the
+/// plaintext length as a little-endian `u64` and the ciphertext's pairs of
+/// points by [`slice_to_bytes`].
+pub fn encrypt_file<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    data: &[u8],
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+) -> Result<Vec<u8>, Error>
+where
+    [(); Point::<F>::LEN]:,
+{
+    encrypt_file_with::<F, I>(key, data, rng, None, cfg)
+}
+
+/// [`encrypt_file`] by [`encrypt_bytes_with`].
+pub(crate) fn encrypt_file_with<
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+>(
+    key: PublicKey<Point<F>>,
+    data: &[u8],
+    rng: &mut impl Rng,
+    generator: Option<&WindowTable<Point<F>>>,
+    cfg: &PointCfg<F>,
+) -> Result<Vec<u8>, Error>
+where
+    [(); Point::<F>::LEN]:,
+{
+    let ciphertext = encrypt_bytes_with::<F, I>(key, data, rng, generator, cfg)?;
+    let mut file = FILE_MAGIC.to_vec();
+    file.extend((data.len() as u64).to_le_bytes());
+    slice_to_bytes(&ciphertext.chunks, &mut file);
+    Ok(file)
+}
+
+/// Inverse of [`encrypt_file`].
+pub fn decrypt_file<F: RW + Field>(
+    key: impl Decrypter<Point<F>>,
+    file: &[u8],
+    cfg: &PointCfg<F>,
+) -> Result<Vec<u8>, Error>
+where
+    [(); F::LEN]:,
+{
+    let rest = file.strip_prefix(FILE_MAGIC).ok_or(Error::FileHeader)?;
+    let (len, mut rest) = rest.split_first_chunk::<8>().ok_or(Error::FileHeader)?;
+    let total = rest.len();
+    let chunks = slice_from_bytes(&mut rest)?;
+    if !rest.is_empty() {
+        return Err(Error::Length {
+            expected: total - rest.len(),
+            actual: total,
+        });
+    }
+    // the second point of a pair carries a message point, which needn't be
+    // in the subgroup
+    let ciphertext = Ciphertext { chunks }.validated(ValidationPolicy::<u8>::OnCurve, cfg)?;
+    let decrypted = ciphertext
+        .chunks
+        .iter()
+        .enumerate()
+        .map(|(i, &pair)| key.decrypt(pair, cfg).map_err(|e| e.in_chunk(i)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = try_points_to_bytes(decrypted.into_iter(), DEFAULT_KAPPA)?;
+    let expected = u64::from_le_bytes(*len) as usize;
+    if data.len() != expected {
+        return Err(Error::Length {
+            expected,
+            actual: data.len(),
+        });
+    }
+    Ok(data)
+}
+
+/// A compressed message inflates to at most [`DecodeLimits::default`]'s
+/// `max_plaintext`, since anyone with the public key can send a DEFLATE bomb;
+/// [`decode_message_and_decrypt_strict`] takes other limits.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(chunks = ciphertext.chunks.len()))
+)]
+pub fn decrypt_message<IP: RW + Natural, F: RW + Field>(
+    key: PrivateKey<IP>,
+    ciphertext: &Ciphertext<Point<F>>,
+    cfg: &PointCfg<F>,
+) -> Result<String, Error>
+where
+    [(); F::LEN]:,
+{
+    let decrypted = ciphertext.chunks.iter().map(|&pair| key.decrypt(pair, cfg));
+    utf8(unpack(
+        try_points_to_bytes(decrypted, DEFAULT_KAPPA)?,
+        DecodeLimits::default().max_plaintext,
+    )?)
+}
+
+/// Inverse of [`encrypt_message_and_encode`].
+pub fn decode_message_and_decrypt<
+    IP: RW + Natural,
+    F: RW + Field + Parity + DiscreteRoot<algebra::ops::Mul>,
+>(
     key: PrivateKey<IP>,
     msg_base64: &str,
     cfg: &PointCfg<F>,
-) -> String
+) -> Result<String, Error>
 where
     [(); F::LEN]:,
-    [(); Point::<F>::LEN]:,
 {
-    let points = base64_to_points::<F>(msg_base64);
-    assert!(points.len() % 2 == 0);
-    let decrypted = points
-        .iter()
-        .array_chunks::<2>()
-        .map(|[c1, c2]| key.decrypt((*c1, *c2), cfg))
-        .collect::<Vec<_>>();
-    points_to_text(decrypted.into_iter(), F::capacity(&cfg.cf) - 1)
+    let ciphertext = Ciphertext::from_compressed_base64(msg_base64, cfg)?;
+    decrypt_message(key, &ciphertext, cfg)
+}
+
+/// Bytes of the [compressed ciphertext](Ciphertext::to_compressed) of
+/// `plaintext_len` bytes: each chunk of the field's capacity less the length
+/// byte and the Koblitz counter becomes two points. Exact for uncompressed
+/// messages; with [`Compression::Deflate`] it's an upper bound. Base64 makes
+/// it `len.div_ceil(3) * 4`.
+pub fn estimate_ciphertext_len<F: Field + RW + Capacitor>(
+    plaintext_len: usize,
+    cfg: &PointCfg<F>,
+) -> Result<usize, Error> {
+    let chunks = plaintext_len.div_ceil(PaddingScheme::LengthByte.capacity(DEFAULT_KAPPA, cfg)?);
+    Ok(chunks * 2 * (F::LEN + 1))
+}
+
+/// The longest plaintext whose [`estimate_ciphertext_len`] is at most `len`.
+pub fn max_plaintext_for<F: Field + RW + Capacitor>(
+    len: usize,
+    cfg: &PointCfg<F>,
+) -> Result<usize, Error> {
+    Ok(len / (2 * (F::LEN + 1)) * PaddingScheme::LengthByte.capacity(DEFAULT_KAPPA, cfg)?)
+}
+
+/// Bounds for [`decode_message_and_decrypt_strict`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Ciphertext pairs, which also bounds the input's length.
+    pub max_chunks: usize,
+    /// Bytes of the decompressed message.
+    pub max_plaintext: usize,
+}
+
+impl Default for DecodeLimits {
+    /// 4096 chunks and 1 MiB, which is more than any chunk count fills.
+    fn default() -> Self {
+        Self {
+            max_chunks: 4096,
+            max_plaintext: 1 << 20,
+        }
+    }
+}
+
+/// [`decode_message_and_decrypt`] for input from the network: input longer
+/// than `limits` allow is rejected before anything is decoded, so nothing
+/// allocated depends on more than the limits, and every malformation is an
+/// error rather than a panic. Base64 has to be canonical and points have to
+/// be canonical compressed encodings, without trailing bytes.
+pub fn decode_message_and_decrypt_strict<
+    IP: RW + Natural,
+    F: RW + Field + Parity + DiscreteRoot<algebra::ops::Mul>,
+>(
+    key: PrivateKey<IP>,
+    msg_base64: &str,
+    limits: &DecodeLimits,
+    cfg: &PointCfg<F>,
+) -> Result<String, Error>
+where
+    [(); F::LEN]:,
+{
+    // saturating, so that usize::MAX chunks means no limit
+    let max_len = limits
+        .max_chunks
+        .saturating_mul(2 * (F::LEN + 1))
+        .div_ceil(3)
+        .saturating_mul(4);
+    if msg_base64.len() > max_len {
+        return Err(Error::TooLarge { limit: max_len });
+    }
+    let ciphertext = Ciphertext::from_compressed_base64(msg_base64, cfg)?;
+    let decrypted = ciphertext.chunks.iter().map(|&pair| key.decrypt(pair, cfg));
+    let bytes = try_points_to_bytes(decrypted, DEFAULT_KAPPA)?;
+    if bytes.len() > limits.max_plaintext {
+        return Err(Error::TooLarge {
+            limit: limits.max_plaintext,
+        });
+    }
+    utf8(unpack(bytes, limits.max_plaintext)?)
 }
 
 #[cfg(test)]
 mod tests {
 
+    use base64::prelude::*;
     use rand::SeedableRng;
 
     use crate::{
-        base_traits::Capacitor,
-        ecc::gen_keys,
+        algebra::Ring,
+        base_traits::Natural,
+        ecc::{gen_keys, Ciphertext},
+        error::Error,
         mod_field::{ModField, ModFieldCfg},
         points_group::{Point, PointCfg},
-        points_to_text, text_to_points,
     };
 
-    use super::{decode_message_and_decrypt, encrypt_message_and_encode};
+    use super::{
+        bytes_to_points, bytes_to_points_padded, decode_message_and_decrypt,
+        decode_message_and_decrypt_strict, decode_message_and_decrypt_with_aad, decrypt_file,
+        decrypt_message, div_le, encrypt_file, encrypt_message, encrypt_message_and_encode,
+        encrypt_message_and_encode_with_aad, encrypt_message_seeded, estimate_ciphertext_len,
+        kappa_bytes, max_plaintext_for, mul_le, points_to_bytes, points_to_bytes_padded,
+        points_to_text, text_to_points, Compression, DecodeLimits, PaddingScheme, DEFAULT_KAPPA,
+    };
 
     fn config() -> PointCfg<ModField<u64>> {
         let cfg_field = ModFieldCfg {
@@ -174,15 +949,195 @@ mod tests {
     fn text2points2text() {
         let cfg_group = config();
         for text in TEXTS {
-            let points = text_to_points::<_, u64>(text, &cfg_group);
-            let text2 = points_to_text(
-                points.iter().copied(),
-                ModField::<u64>::capacity(&cfg_group.cf) - 1,
+            for kappa in [24, 100, DEFAULT_KAPPA, 1000] {
+                let points = text_to_points(text, kappa, &cfg_group).unwrap();
+                let text2 = points_to_text(points.iter().copied(), kappa);
+                assert_eq!(text2.as_deref(), Ok(text));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let cfg_group = config();
+        // a lone continuation byte, and a character split between chunks
+        for data in [&b"ok \x80"[..], "ééééééééé".as_bytes()] {
+            let points = bytes_to_points(data, DEFAULT_KAPPA, &cfg_group).unwrap();
+            let text = points_to_text(points.into_iter(), DEFAULT_KAPPA);
+            match std::str::from_utf8(data) {
+                Ok(s) => assert_eq!(text.as_deref(), Ok(s)),
+                Err(_) => assert_eq!(text, Err(Error::Utf8(data.to_vec()))),
+            }
+        }
+
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let ct = Ciphertext {
+            chunks: bytes_to_points(b"\xFE\xFE", DEFAULT_KAPPA, &cfg_group)
+                .unwrap()
+                .into_iter()
+                .map(|p| pb.encrypt::<u64>(p, &mut gen, &cfg_group))
+                .collect(),
+        };
+        assert_eq!(
+            decrypt_message(pr, &ct, &cfg_group),
+            Err(Error::Utf8(vec![0xFE, 0xFE]))
+        );
+    }
+
+    #[test]
+    fn padding_schemes() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let schemes = [
+            PaddingScheme::LengthByte,
+            PaddingScheme::Pkcs7,
+            PaddingScheme::Random,
+        ];
+        let data: Vec<u8> = (1..=40).collect();
+        for padding in schemes {
+            let capacity = padding.capacity(DEFAULT_KAPPA, &cfg_group).unwrap();
+            for len in 0..data.len() {
+                let points = bytes_to_points_padded(
+                    &data[..len],
+                    padding,
+                    DEFAULT_KAPPA,
+                    &mut gen,
+                    &cfg_group,
+                )
+                .unwrap();
+                let expected = match padding {
+                    PaddingScheme::Pkcs7 => len / capacity + 1,
+                    _ => len.div_ceil(capacity),
+                };
+                assert_eq!(points.len(), expected);
+                let bytes =
+                    points_to_bytes_padded(points.into_iter(), padding, DEFAULT_KAPPA, &cfg_group);
+                assert_eq!(bytes.as_deref(), Ok(&data[..len]));
+            }
+        }
+        assert_eq!(
+            bytes_to_points_padded(
+                &data,
+                PaddingScheme::LengthByte,
+                DEFAULT_KAPPA,
+                &mut gen,
+                &cfg_group
+            ),
+            bytes_to_points(&data, DEFAULT_KAPPA, &cfg_group)
+        );
+
+        // random filling differs between equal chunks, and isn't zero padding
+        let random = |gen: &mut rand_chacha::ChaCha8Rng| {
+            bytes_to_points_padded(b"a", PaddingScheme::Random, DEFAULT_KAPPA, gen, &cfg_group)
+                .unwrap()
+        };
+        let (a, b) = (random(&mut gen), random(&mut gen));
+        assert_ne!(a, b);
+        let checked = points_to_bytes_padded(
+            a.into_iter(),
+            PaddingScheme::LengthByte,
+            DEFAULT_KAPPA,
+            &cfg_group,
+        );
+        assert_eq!(checked, Err(Error::Plaintext.in_chunk(0)));
+
+        // zero filling isn't a PKCS#7 pad
+        let points = bytes_to_points(b"a", DEFAULT_KAPPA, &cfg_group).unwrap();
+        let checked = points_to_bytes_padded(
+            points.into_iter(),
+            PaddingScheme::Pkcs7,
+            DEFAULT_KAPPA,
+            &cfg_group,
+        );
+        assert_eq!(checked, Err(Error::Plaintext.in_chunk(0)));
+        assert_eq!(
+            points_to_bytes_padded(
+                std::iter::empty(),
+                PaddingScheme::Pkcs7,
+                DEFAULT_KAPPA,
+                &cfg_group
+            ),
+            Err(Error::Plaintext.in_chunk(0))
+        );
+    }
+
+    #[test]
+    fn zero_bytes_survive() {
+        let cfg_group = config();
+        for data in [&b"\0"[..], b"a\0\0b", b"trailing\0\0\0", &[0; 10]] {
+            let points = bytes_to_points(data, DEFAULT_KAPPA, &cfg_group).unwrap();
+            assert_eq!(points_to_bytes(points.into_iter(), DEFAULT_KAPPA), data);
+        }
+    }
+
+    #[test]
+    fn koblitz_arithmetic() {
+        assert_eq!(kappa_bytes(2), 1);
+        assert_eq!(kappa_bytes(256), 1);
+        assert_eq!(kappa_bytes(257), 2);
+        let mut m = [0xFF, 0x12, 0x00, 0x00];
+        mul_le(&mut m, 1000);
+        assert_eq!(u32::from_le_bytes(m), 0x12FF * 1000);
+        // m·κ + κ - 1 still floors back to m
+        m = (0x12FF * 1000 + 999u32).to_le_bytes();
+        div_le(&mut m, 1000);
+        assert_eq!(m, [0xFF, 0x12, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn koblitz_failure_is_an_error() {
+        // with a single candidate, about half of the chunks can't be embedded
+        let cfg_group = config();
+        let failures = (1..=255u8)
+            .map(|b| String::from_utf8(vec![b'a', b % 127 + 1]).unwrap())
+            .filter(|t| text_to_points(t, 1, &cfg_group) == Err(Error::Embedding.in_chunk(0)))
+            .count();
+        assert!(failures > 50);
+    }
+
+    fn small_config<I: Natural>(p: I) -> PointCfg<ModField<I>> {
+        let cfg_field = ModFieldCfg { rem: p };
+        PointCfg {
+            g: Point::new_unsafe(ModField::zero(&cfg_field), ModField::zero(&cfg_field)),
+            a: ModField::new(I::one(), &cfg_field),
+            b: ModField::new(I::two(), &cfg_field),
+            cf: cfg_field,
+        }
+    }
+
+    #[test]
+    fn single_byte_chunks() {
+        // 2^24 <= p < 2^32: a length byte, one byte of message and one of counter
+        let cfg_group = small_config(4294967291u64);
+        for text in TEXTS {
+            let points = text_to_points(text, DEFAULT_KAPPA, &cfg_group).unwrap();
+            assert_eq!(points.len(), text.len());
+            assert_eq!(
+                points_to_text(points.into_iter(), DEFAULT_KAPPA).as_deref(),
+                Ok(text)
             );
-            assert_eq!(text, text2);
         }
     }
 
+    #[test]
+    fn field_too_small() {
+        assert_eq!(
+            text_to_points("Hi", DEFAULT_KAPPA, &small_config(251u8)),
+            Err(Error::FieldTooSmall {
+                capacity: 0,
+                required: 3
+            })
+        );
+        assert_eq!(
+            text_to_points("Hi", 1000, &small_config(4294967291u64)),
+            Err(Error::FieldTooSmall {
+                capacity: 3,
+                required: 4
+            })
+        );
+    }
+
     #[test]
     fn encrypt_encode_decode_decrypt() {
         let cfg_group = config();
@@ -190,10 +1145,395 @@ mod tests {
         let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
         for text in TEXTS {
             for _ in 0..10 {
-                let secret = encrypt_message_and_encode::<_, u64>(pb, text, &mut gen, &cfg_group);
+                let secret = encrypt_message_and_encode::<_, u64>(
+                    pb,
+                    text,
+                    Compression::None,
+                    &mut gen,
+                    &cfg_group,
+                )
+                .unwrap();
                 let decoded = decode_message_and_decrypt(pr, &secret, &cfg_group);
-                assert_eq!(text, decoded);
+                assert_eq!(decoded, Ok(text.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn ciphertext_len() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (_, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        for text in TEXTS {
+            let secret = encrypt_message_and_encode::<_, u64>(
+                pb,
+                text,
+                Compression::None,
+                &mut gen,
+                &cfg_group,
+            )
+            .unwrap();
+            let len = estimate_ciphertext_len(text.len(), &cfg_group).unwrap();
+            assert_eq!(secret.len(), len.div_ceil(3) * 4);
+            assert!(max_plaintext_for(len, &cfg_group).unwrap() >= text.len());
+        }
+        for len in 0..100 {
+            let max = max_plaintext_for(len, &cfg_group).unwrap();
+            assert!(estimate_ciphertext_len(max, &cfg_group).unwrap() <= len);
+            assert!(estimate_ciphertext_len(max + 1, &cfg_group).unwrap() > len);
+        }
+        assert!(estimate_ciphertext_len(1, &small_config(251u8)).is_err());
+    }
+
+    #[test]
+    fn associated_data() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let (other, _) = gen_keys::<_, u128, Point<_>>(&mut gen, &cfg_group);
+        let aad = b"to: bob, id: 7";
+        for text in ["", TEXTS[0], TEXTS[3]] {
+            let secret = encrypt_message_and_encode_with_aad::<_, u64>(
+                pb,
+                text,
+                aad,
+                Compression::Deflate,
+                &mut gen,
+                &cfg_group,
+            )
+            .unwrap();
+            let decrypt = |secret: &str, aad: &[u8], key| {
+                decode_message_and_decrypt_with_aad(key, secret, aad, &cfg_group)
+            };
+            assert_eq!(decrypt(&secret, aad, pr), Ok(text.to_string()));
+            assert_eq!(decrypt(&secret, b"to: eve, id: 7", pr), Err(Error::Tag));
+            assert_eq!(decrypt(&secret, aad, other), Err(Error::Tag));
+
+            let mut bytes = BASE64_STANDARD.decode(&secret).unwrap();
+            *bytes.last_mut().unwrap() ^= 1;
+            let forged = BASE64_STANDARD.encode(&bytes);
+            assert_eq!(decrypt(&forged, aad, pr), Err(Error::Tag));
+            // the ciphertext of another message under the same tag
+            let other_text = encrypt_message_and_encode_with_aad::<_, u64>(
+                pb,
+                text,
+                aad,
+                Compression::Deflate,
+                &mut gen,
+                &cfg_group,
+            )
+            .unwrap();
+            let mut spliced = BASE64_STANDARD.decode(&other_text).unwrap();
+            let n = spliced.len();
+            spliced[n - 16..].copy_from_slice(&bytes[n - 16..]);
+            *spliced.last_mut().unwrap() ^= 1;
+            let spliced = BASE64_STANDARD.encode(&spliced);
+            assert_eq!(decrypt(&spliced, aad, pr), Err(Error::Tag));
+        }
+        // the sender can tag a bomb as well as anything else
+        let bomb = encrypt_message_and_encode_with_aad::<_, u64>(
+            pb,
+            &"0".repeat((1 << 20) + 1),
+            aad,
+            Compression::Deflate,
+            &mut gen,
+            &cfg_group,
+        )
+        .unwrap();
+        assert_eq!(
+            decode_message_and_decrypt_with_aad(pr, &bomb, aad, &cfg_group),
+            Err(Error::TooLarge { limit: 1 << 20 })
+        );
+        assert_eq!(
+            decode_message_and_decrypt_with_aad(pr, "AAAA", aad, &cfg_group),
+            Err(Error::Length {
+                expected: 16,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let mut enc = |text: &str, compression| {
+            encrypt_message_and_encode::<_, u64>(pb, text, compression, &mut gen, &cfg_group)
+                .unwrap()
+        };
+        for text in TEXTS {
+            let packed = enc(text, Compression::Deflate);
+            assert!(packed.len() <= enc(text, Compression::None).len());
+            assert_eq!(
+                decode_message_and_decrypt(pr, &packed, &cfg_group),
+                Ok(text.to_string())
+            );
+        }
+        let long = "all work and no play makes jack a dull boy ".repeat(20);
+        let packed = enc(&long, Compression::Deflate);
+        assert!(packed.len() * 5 < enc(&long, Compression::None).len());
+        assert_eq!(
+            decode_message_and_decrypt(pr, &packed, &cfg_group),
+            Ok(long)
+        );
+
+        // a kilobyte or so that would inflate past 1 MiB
+        let bomb = enc(&"0".repeat((1 << 20) + 1), Compression::Deflate);
+        assert_eq!(
+            decode_message_and_decrypt(pr, &bomb, &cfg_group),
+            Err(Error::TooLarge { limit: 1 << 20 })
+        );
+    }
+
+    #[test]
+    fn strict_decoding() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let (other, _) = gen_keys::<_, u128, Point<_>>(&mut gen, &cfg_group);
+        let mut enc = |text: &str, compression| {
+            encrypt_message_and_encode::<_, u64>(pb, text, compression, &mut gen, &cfg_group)
+                .unwrap()
+        };
+        let strict = |key, msg: &str, limits| {
+            decode_message_and_decrypt_strict(key, msg, &limits, &cfg_group)
+        };
+        let limits = DecodeLimits::default();
+        for text in TEXTS {
+            let secret = enc(text, Compression::Deflate);
+            assert_eq!(strict(pr, &secret, limits), Ok(text.to_string()));
+        }
+
+        let secret = enc("hello world", Compression::None);
+        let small = DecodeLimits {
+            max_chunks: 1,
+            ..limits
+        };
+        assert!(matches!(
+            strict(pr, &secret, small),
+            Err(Error::TooLarge { .. })
+        ));
+        let unlimited = DecodeLimits {
+            max_chunks: usize::MAX,
+            ..limits
+        };
+        assert_eq!(
+            strict(pr, &secret, unlimited),
+            Ok("hello world".to_string())
+        );
+        // the lenient decoder panics on most of these
+        let garbage = (0..50).map(|_| strict(other, &secret, limits));
+        assert!(garbage
+            .into_iter()
+            .all(|r| r != Ok("hello world".to_string())));
+        assert!(strict(pr, &format!("{}AA==", secret), limits).is_err());
+        assert_eq!(strict(pr, "A", limits), Err(Error::Base64));
+
+        let bomb = enc(&"0".repeat(3000), Compression::Deflate);
+        let tight = DecodeLimits {
+            max_plaintext: 1000,
+            ..limits
+        };
+        assert_eq!(
+            strict(pr, &bomb, tight),
+            Err(Error::TooLarge { limit: 1000 })
+        );
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let binary = (0..600).map(|i| (i % 7 * 40) as u8).collect::<Vec<_>>();
+        for data in [&b""[..], b"\0", b"text\n", &binary] {
+            let file = encrypt_file::<_, u64>(pb, data, &mut gen, &cfg_group).unwrap();
+            assert!(file.starts_with(b"ECCF\x03"));
+            assert_eq!(decrypt_file(pr, &file, &cfg_group).unwrap(), data);
+        }
+        let file = encrypt_file::<_, u64>(pb, b"data", &mut gen, &cfg_group).unwrap();
+        for old in [&b"ECCF\x01"[..], b"ECCF\x02"] {
+            assert_eq!(
+                decrypt_file(pr, &[old, &file[5..]].concat(), &cfg_group),
+                Err(Error::FileHeader)
+            );
+        }
+        let mut wrong_len = file.clone();
+        wrong_len[5] = 3;
+        assert_eq!(
+            decrypt_file(pr, &wrong_len, &cfg_group),
+            Err(Error::Length {
+                expected: 3,
+                actual: 4
+            })
+        );
+        assert!(decrypt_file(pr, &file[..file.len() - 1], &cfg_group).is_err());
+        assert_eq!(
+            decrypt_file(pr, &[&file[..], &[0]].concat(), &cfg_group),
+            Err(Error::Length {
+                expected: file.len() - 13,
+                actual: file.len() - 12
+            })
+        );
+        // a flipped bit of y takes the first point off the curve
+        let mut tampered = file.clone();
+        tampered[13 + 4 + 8] ^= 1;
+        assert_eq!(
+            decrypt_file(pr, &tampered, &cfg_group),
+            Err(Error::Point.in_chunk(0))
+        );
+    }
+
+    #[test]
+    fn seeded_encryption() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let ct = encrypt_message_seeded::<_, u64>(pb, TEXTS[2], [7; 32], &cfg_group).unwrap();
+        assert_eq!(
+            encrypt_message_seeded::<_, u64>(pb, TEXTS[2], [7; 32], &cfg_group),
+            Ok(ct.clone())
+        );
+        assert_ne!(
+            encrypt_message_seeded::<_, u64>(pb, TEXTS[2], [8; 32], &cfg_group),
+            Ok(ct.clone())
+        );
+        assert_eq!(
+            decrypt_message(pr, &ct, &cfg_group).as_deref(),
+            Ok(TEXTS[2])
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn traced_spans() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{field, span, subscriber, Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Spans(Mutex<Vec<String>>);
+
+        struct Line(String);
+
+        impl field::Visit for Line {
+            fn record_debug(&mut self, field: &field::Field, value: &dyn std::fmt::Debug) {
+                self.0 += &format!(" {}={:?}", field.name(), value);
+            }
+        }
+
+        impl Subscriber for Spans {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                let mut line = Line(span.metadata().name().to_string());
+                span.record(&mut line);
+                let mut spans = self.0.lock().unwrap();
+                spans.push(line.0);
+                span::Id::from_u64(spans.len() as u64)
             }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
         }
+
+        let spans = Arc::new(Spans::default());
+        let chunks = subscriber::with_default(spans.clone(), || {
+            let cfg_group = config();
+            let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+            let (pr, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+            let ct = encrypt_message::<_, u64>(pb, TEXTS[0], &mut gen, &cfg_group).unwrap();
+            decrypt_message(pr, &ct, &cfg_group).unwrap();
+            ct.chunks.len()
+        });
+        let spans = spans.0.lock().unwrap();
+        assert_eq!(spans[0], "gen_keys");
+        assert_eq!(spans[1], "encrypt_message len=12");
+        assert!(spans.contains(&format!("decrypt_message chunks={chunks}")));
+        assert_eq!(spans.iter().filter(|s| *s == "encrypt").count(), chunks);
+        assert_eq!(spans.iter().filter(|s| *s == "decrypt").count(), chunks);
+    }
+
+    #[test]
+    fn ciphertext_display_from_str() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (_, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let ct = encrypt_message::<_, u64>(pb, TEXTS[2], &mut gen, &cfg_group).unwrap();
+        assert_eq!(ct.to_string(), ct.base64());
+        assert_eq!(ct.to_string().parse(), Ok(ct));
+        assert_eq!(
+            "AAAA".parse::<Ciphertext<Point<ModField<u64>>>>(),
+            Err(Error::Length {
+                expected: 32,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn compressed_ciphertext() {
+        let cfg_group = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (_, pb) = gen_keys::<_, u128, _>(&mut gen, &cfg_group);
+        let ct = encrypt_message::<_, u64>(pb, TEXTS[2], &mut gen, &cfg_group).unwrap();
+        // 16 bytes per point down to 9: a tag and x
+        let compressed = ct.to_compressed();
+        assert_eq!(compressed.len(), ct.chunks.len() * 2 * 9);
+        assert_eq!(
+            Ciphertext::from_compressed(&compressed, &cfg_group),
+            Ok(ct.clone())
+        );
+        assert_eq!(
+            Ciphertext::from_compressed_base64(&ct.compressed_base64(), &cfg_group),
+            Ok(ct.clone())
+        );
+        assert_eq!(
+            Ciphertext::from_compressed(&compressed[..compressed.len() - 1], &cfg_group),
+            Err(Error::Length {
+                expected: compressed.len(),
+                actual: compressed.len() - 1
+            })
+        );
+        assert_eq!(
+            Ciphertext::from_compressed(&compressed[..compressed.len() - 9], &cfg_group),
+            Err(Error::Length {
+                expected: compressed.len(),
+                actual: compressed.len() - 9
+            })
+        );
+        // the point at infinity is a byte, and what follows still parses
+        let o = Point::infinity(&cfg_group);
+        let mut with_infinity = ct;
+        with_infinity.chunks[0].1 = o;
+        let bytes = with_infinity.to_compressed();
+        assert_eq!(bytes.len(), compressed.len() - 8);
+        assert_eq!(
+            Ciphertext::from_compressed(&bytes, &cfg_group),
+            Ok(with_infinity)
+        );
+        let mut bad_tag = compressed.clone();
+        bad_tag[0] = 0x04;
+        assert_eq!(
+            Ciphertext::from_compressed(&bad_tag, &cfg_group),
+            Err(Error::Point.in_chunk(0))
+        );
+        let mut bad_tag = compressed;
+        let last = bad_tag.len() - 9;
+        bad_tag[last] = 0x04;
+        let err = Ciphertext::from_compressed(&bad_tag, &cfg_group).unwrap_err();
+        let index = bad_tag.len() / 18 - 1;
+        assert_eq!(err, Error::Point.in_chunk(index));
+        assert_eq!(
+            err.to_string(),
+            format!("chunk {}: not a point on the curve", index)
+        );
+        assert_eq!(
+            std::error::Error::source(&err).map(|e| e.to_string()),
+            Some(Error::Point.to_string())
+        );
     }
 }
@@ -0,0 +1,120 @@
+//! The operating system's entropy source (via `getrandom`) behind the `rand`
+//! traits, checked once at startup with the two continuous health tests of
+//! NIST SP 800-90B, section 4.4, over 8-bit samples. A source stuck on a value
+//! or badly biased towards one fails them; a merely weak one won't, so this
+//! catches a broken source, not a bad one.
+
+use rand::{CryptoRng, RngCore};
+
+use crate::error::Error;
+
+/// Bytes drawn and tested by [`SystemEntropy::new`].
+const STARTUP_SAMPLES: usize = 1024;
+
+/// Repetition count test cutoff: `1 + ⌈40 / 8⌉` for a false positive rate of
+/// 2^-40 on a full-entropy byte source.
+const REPETITION_CUTOFF: usize = 6;
+
+/// Adaptive proportion test window and cutoff: the smallest count of the
+/// window's first value whose binomial tail probability is below 2^-40.
+const PROPORTION_WINDOW: usize = 512;
+const PROPORTION_CUTOFF: usize = 19;
+
+/// Randomness straight from the OS, only obtainable through [`Self::new`], i.e.
+/// after the source passed the health tests.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemEntropy(());
+
+impl SystemEntropy {
+    pub fn new() -> Result<Self, Error> {
+        let mut sample = [0u8; STARTUP_SAMPLES];
+        getrandom::getrandom(&mut sample).map_err(|_| Error::Entropy)?;
+        if repetition_count(&sample) && adaptive_proportion(&sample) {
+            Ok(Self(()))
+        } else {
+            Err(Error::Entropy)
+        }
+    }
+}
+
+/// Fails on a run of [`REPETITION_CUTOFF`] equal samples.
+fn repetition_count(samples: &[u8]) -> bool {
+    let mut run = 0;
+    for (i, &b) in samples.iter().enumerate() {
+        run = if i > 0 && samples[i - 1] == b {
+            run + 1
+        } else {
+            1
+        };
+        if run >= REPETITION_CUTOFF {
+            return false;
+        }
+    }
+    true
+}
+
+/// Fails if the first sample of a window shows up [`PROPORTION_CUTOFF`] times
+/// in it.
+fn adaptive_proportion(samples: &[u8]) -> bool {
+    samples.chunks(PROPORTION_WINDOW).all(|window| {
+        let first = window[0];
+        window.iter().filter(|&&b| b == first).count() < PROPORTION_CUTOFF
+    })
+}
+
+impl RngCore for SystemEntropy {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        getrandom::getrandom(dest).expect("system entropy source failed")
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        getrandom::getrandom(dest).map_err(rand::Error::from)
+    }
+}
+
+impl CryptoRng for SystemEntropy {}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, RngCore, SeedableRng};
+
+    use super::{adaptive_proportion, repetition_count, SystemEntropy};
+
+    #[test]
+    fn health_tests() {
+        let mut good = [0u8; 4096];
+        rand_chacha::ChaCha8Rng::from_seed([1u8; 32]).fill_bytes(&mut good);
+        assert!(repetition_count(&good));
+        assert!(adaptive_proportion(&good));
+
+        let mut stuck = good;
+        stuck[100..106].fill(0xAB);
+        assert!(!repetition_count(&stuck));
+        assert!(repetition_count(&stuck[..105]));
+
+        // a sixteenth of the bytes are zero, about 32 per window
+        let mut biased = good.map(|b| if b < 16 { 0 } else { b });
+        biased[0] = 0;
+        assert!(!adaptive_proportion(&biased));
+    }
+
+    #[test]
+    fn system_source() {
+        let mut rng = SystemEntropy::new().unwrap();
+        let a: [u8; 32] = rng.gen();
+        let b: [u8; 32] = rng.gen();
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The input is not valid base64.
+    Base64,
+    /// The input is not valid hex.
+    Hex,
+    /// Encoded bytes don't describe a point on the curve.
+    Point,
+    /// A point lies on the curve, but outside the prime-order subgroup.
+    Subgroup,
+    /// A scalar multiplication came out as the point at infinity.
+    Infinity,
+    /// A field element is encoded as a value not below the modulus.
+    NonCanonical,
+    /// The decoded input has the wrong number of bytes.
+    Length { expected: usize, actual: usize },
+    /// None of the Koblitz candidates for a message chunk lies on the curve.
+    Embedding,
+    /// The field can't hold a single message byte next to the Koblitz counter.
+    FieldTooSmall { capacity: usize, required: usize },
+    /// A private scalar is zero or not below the group order.
+    ScalarRange,
+    /// The input isn't `<key id>.<ciphertext>`.
+    Envelope,
+    /// No key with this id is in the key ring.
+    UnknownKey(u32),
+    /// The key ring has no key to encrypt to.
+    EmptyKeyRing,
+    /// No complete ASCII-armored block, or one with an unexpected label.
+    Armor,
+    /// The armor checksum doesn't match the data.
+    ArmorChecksum,
+    /// Not an encrypted file, or one of an unknown version.
+    FileHeader,
+    /// The system entropy source failed or didn't pass its health tests.
+    Entropy,
+    /// A malformed identity file, or one whose keys don't belong together.
+    Identity,
+    /// An identity file for another curve.
+    WrongCurve { expected: String, found: String },
+    /// Reading or writing a file failed; the message names the file.
+    Io(String),
+    /// A key usage observer refused the operation, for the reason given.
+    Denied(String),
+    /// The input, or what it decodes to, exceeds the limit.
+    TooLarge { limit: usize },
+    /// The decrypted data isn't a message: the wrong key, or a corrupted or
+    /// forged ciphertext.
+    Plaintext,
+    /// Decrypted bytes that were expected to be text aren't UTF-8.
+    Utf8(Vec<u8>),
+    /// The authentication tag doesn't match: the ciphertext or its
+    /// associated data were modified, or the key is wrong.
+    Tag,
+    /// A decrypted plaintext isn't the one its commitment is to.
+    Commitment,
+    /// A handshake message out of turn, or a key the pattern needs is
+    /// missing.
+    Handshake,
+    /// A key that is only valid from this time on, in Unix seconds.
+    NotYetValid { from: u64 },
+    /// A key that expired at this time, in Unix seconds.
+    Expired { at: u64 },
+    /// A signature doesn't verify under the key it should be from.
+    Signature,
+    /// No trusted certificate issued the one issued by this name, or the
+    /// certificate of this name isn't the issuer it should be.
+    Untrusted(String),
+    /// The certificate of this subject issued another, but isn't a CA.
+    NotCa(String),
+    /// The frame with this sequence number was seen before, or is too far
+    /// behind the newest.
+    Replay(u64),
+    /// `error` occurred in the chunk of a message with this index, counting
+    /// from 0.
+    Chunk { index: usize, error: Box<Error> },
+    /// A key shared among this many parties can't take this many of them to
+    /// use: the threshold has to be between 1 and the number of parties.
+    Threshold { threshold: usize, parties: u32 },
+    /// There's no party with this index among those of a key generation, or
+    /// it appears twice.
+    Participant(u32),
+    /// The dealing of the party with this index is missing or malformed, or
+    /// its share doesn't match it.
+    Dealing(u32),
+    /// A curve failed [`self_test`](crate::ecc::self_test) at this check.
+    SelfTest(&'static str),
+}
+
+impl Error {
+    /// This error, as one of the chunk with this index.
+    pub fn in_chunk(self, index: usize) -> Self {
+        Error::Chunk {
+            index,
+            error: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Base64 => write!(f, "invalid base64"),
+            Error::Hex => write!(f, "invalid hex"),
+            Error::Point => write!(f, "not a point on the curve"),
+            Error::Subgroup => write!(f, "point outside the prime-order subgroup"),
+            Error::Infinity => write!(f, "the result is the point at infinity"),
+            Error::NonCanonical => write!(f, "field element not below the modulus"),
+            Error::Length { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            Error::Embedding => write!(f, "message chunk can't be embedded into a point"),
+            Error::FieldTooSmall { capacity, required } => write!(
+                f,
+                "field holds {} bytes, at least {} are needed",
+                capacity, required
+            ),
+            Error::ScalarRange => write!(f, "scalar is zero or not below the group order"),
+            Error::Envelope => write!(f, "malformed envelope, expected <key id>.<ciphertext>"),
+            Error::UnknownKey(id) => write!(f, "no key with id {}", id),
+            Error::EmptyKeyRing => write!(f, "the key ring is empty"),
+            Error::Armor => write!(f, "malformed ASCII armor"),
+            Error::ArmorChecksum => write!(f, "ASCII armor checksum mismatch"),
+            Error::FileHeader => write!(f, "not an encrypted file, or an unsupported version"),
+            Error::Entropy => write!(f, "the system entropy source failed its health tests"),
+            Error::Identity => write!(f, "malformed identity file"),
+            Error::WrongCurve { expected, found } => {
+                write!(f, "identity is for the curve {}, not {}", found, expected)
+            }
+            Error::Io(msg) => write!(f, "{}", msg),
+            Error::Denied(reason) => write!(f, "key use denied: {}", reason),
+            Error::TooLarge { limit } => write!(f, "input exceeds the limit of {} bytes", limit),
+            Error::Plaintext => write!(f, "decrypted data is malformed, wrong key?"),
+            Error::Utf8(bytes) => write!(f, "the {} decrypted bytes aren't UTF-8", bytes.len()),
+            Error::Tag => write!(f, "authentication tag mismatch"),
+            Error::Commitment => write!(f, "plaintext doesn't match its commitment"),
+            Error::Handshake => write!(f, "handshake message out of turn, or a key is missing"),
+            Error::NotYetValid { from } => write!(f, "key not valid before {}", from),
+            Error::Expired { at } => write!(f, "key expired at {}", at),
+            Error::Signature => write!(f, "signature verification failed"),
+            Error::Untrusted(issuer) => write!(f, "no trusted issuer {:?}", issuer),
+            Error::NotCa(subject) => write!(f, "{:?} isn't allowed to issue certificates", subject),
+            Error::Replay(sequence) => {
+                write!(f, "frame {} replayed or outside the window", sequence)
+            }
+            Error::Chunk { index, error } => write!(f, "chunk {}: {}", index, error),
+            Error::Threshold { threshold, parties } => write!(
+                f,
+                "a threshold of {} isn't possible with {} parties",
+                threshold, parties
+            ),
+            Error::Participant(i) => write!(f, "no single party with index {}", i),
+            Error::Dealing(i) => write!(f, "the dealing of party {} doesn't check out", i),
+            Error::SelfTest(check) => write!(f, "curve self test failed: {}", check),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Chunk { error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
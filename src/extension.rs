@@ -0,0 +1,343 @@
+//! Quadratic and cubic extension fields `F[t] / (t² - n)` and `F[t] / (t³ - n)`
+//! for a non-residue `n` of `F`, which is what pairing-friendly towers are
+//! built from. They share the base field's configuration, so the whole tower
+//! runs on the prime field's `ModFieldCfg`.
+
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::algebra::{
+    self, AbelianGroup, CommutativeMonoid, CommutativeOp, ConditionalSwap, Configurable, Field,
+    Identity, Inverse, InverseNonZero, Ring,
+};
+
+/// Multiplication by the non-residue an extension is built with.
+pub trait NonResidue<F: Field> {
+    fn mul_by(x: F, cfg: &F::Cfg) -> F;
+}
+
+/// `c0 + c1·t` with `t² = n`.
+pub struct Ext2<F, N> {
+    pub c0: F,
+    pub c1: F,
+    n: PhantomData<N>,
+}
+
+/// `c0 + c1·t + c2·t²` with `t³ = n`.
+pub struct Ext3<F, N> {
+    pub c0: F,
+    pub c1: F,
+    pub c2: F,
+    n: PhantomData<N>,
+}
+
+impl<F, N> Ext2<F, N> {
+    pub fn new(c0: F, c1: F) -> Self {
+        Self {
+            c0,
+            c1,
+            n: PhantomData,
+        }
+    }
+}
+
+impl<F, N> Ext3<F, N> {
+    pub fn new(c0: F, c1: F, c2: F) -> Self {
+        Self {
+            c0,
+            c1,
+            c2,
+            n: PhantomData,
+        }
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Ext2<F, N> {
+    /// `c0 - c1·t`, the image under the non-trivial automorphism.
+    pub fn conjugate(self, cfg: &F::Cfg) -> Self {
+        Self::new(self.c0, self.c1.neg(cfg))
+    }
+
+    /// Embeds the base field.
+    pub fn from_base(c0: F, cfg: &F::Cfg) -> Self {
+        Self::new(c0, F::zero(cfg))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Ext3<F, N> {
+    pub fn from_base(c0: F, cfg: &F::Cfg) -> Self {
+        Self::new(c0, F::zero(cfg), F::zero(cfg))
+    }
+}
+
+// `derive` would put the bounds on `N` too, which is just a marker.
+impl<F: Copy, N> Clone for Ext2<F, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<F: Copy, N> Copy for Ext2<F, N> {}
+impl<F: PartialEq, N> PartialEq for Ext2<F, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.c0 == other.c0 && self.c1 == other.c1
+    }
+}
+impl<F: Eq, N> Eq for Ext2<F, N> {}
+impl<F: Debug, N> Debug for Ext2<F, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Ext2")
+            .field(&self.c0)
+            .field(&self.c1)
+            .finish()
+    }
+}
+
+impl<F: Copy, N> Clone for Ext3<F, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<F: Copy, N> Copy for Ext3<F, N> {}
+impl<F: PartialEq, N> PartialEq for Ext3<F, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.c0 == other.c0 && self.c1 == other.c1 && self.c2 == other.c2
+    }
+}
+impl<F: Eq, N> Eq for Ext3<F, N> {}
+impl<F: Debug, N> Debug for Ext3<F, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Ext3")
+            .field(&self.c0)
+            .field(&self.c1)
+            .field(&self.c2)
+            .finish()
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Configurable for Ext2<F, N> {
+    type Cfg = F::Cfg;
+}
+
+impl<F: Field, N: NonResidue<F>> CommutativeOp<algebra::ops::Add> for Ext2<F, N> {
+    fn op(a: Self, b: Self, c: &F::Cfg) -> Self {
+        Self::new(F::add(a.c0, b.c0, c), F::add(a.c1, b.c1, c))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Inverse<algebra::ops::Add> for Ext2<F, N> {
+    fn inv(self, c: &F::Cfg) -> Self {
+        Self::new(self.c0.neg(c), self.c1.neg(c))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Identity<algebra::ops::Add> for Ext2<F, N> {
+    fn identity(c: &F::Cfg) -> Self {
+        Self::new(F::zero(c), F::zero(c))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> CommutativeOp<algebra::ops::Mul> for Ext2<F, N> {
+    fn op(a: Self, b: Self, c: &F::Cfg) -> Self {
+        let c1 = F::sop(&[(a.c0, b.c1), (a.c1, b.c0)], c);
+        let c0 = F::add(
+            F::mul(a.c0, b.c0, c),
+            N::mul_by(F::mul(a.c1, b.c1, c), c),
+            c,
+        );
+        Self::new(c0, c1)
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Identity<algebra::ops::Mul> for Ext2<F, N> {
+    fn identity(c: &F::Cfg) -> Self {
+        Self::from_base(F::one(c), c)
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> CommutativeMonoid<algebra::ops::Add> for Ext2<F, N> {}
+impl<F: Field, N: NonResidue<F>> CommutativeMonoid<algebra::ops::Mul> for Ext2<F, N> {}
+impl<F: Field, N: NonResidue<F>> AbelianGroup<algebra::ops::Add> for Ext2<F, N> {}
+
+impl<F: Field, N: NonResidue<F>> InverseNonZero<algebra::ops::Mul> for Ext2<F, N> {
+    fn inv(self, c: &F::Cfg) -> Option<Self> {
+        // 1 / (c0 + c1 t) = (c0 - c1 t) / (c0² - n c1²)
+        let norm = F::sub(self.c0.sqr(c), N::mul_by(self.c1.sqr(c), c), c);
+        let norm_inv = norm.reciprocal(c)?;
+        let conj = self.conjugate(c);
+        Some(Self::new(
+            F::mul(conj.c0, norm_inv, c),
+            F::mul(conj.c1, norm_inv, c),
+        ))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Ring for Ext2<F, N> {}
+impl<F: Field, N: NonResidue<F>> ConditionalSwap for Ext2<F, N> {
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+        F::conditional_swap(&mut a.c0, &mut b.c0, swap);
+        F::conditional_swap(&mut a.c1, &mut b.c1, swap);
+    }
+}
+impl<F: Field, N: NonResidue<F>> Field for Ext2<F, N> {
+    fn is_canonical(self, c: &F::Cfg) -> bool {
+        self.c0.is_canonical(c) && self.c1.is_canonical(c)
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Configurable for Ext3<F, N> {
+    type Cfg = F::Cfg;
+}
+
+impl<F: Field, N: NonResidue<F>> CommutativeOp<algebra::ops::Add> for Ext3<F, N> {
+    fn op(a: Self, b: Self, c: &F::Cfg) -> Self {
+        Self::new(
+            F::add(a.c0, b.c0, c),
+            F::add(a.c1, b.c1, c),
+            F::add(a.c2, b.c2, c),
+        )
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Inverse<algebra::ops::Add> for Ext3<F, N> {
+    fn inv(self, c: &F::Cfg) -> Self {
+        Self::new(self.c0.neg(c), self.c1.neg(c), self.c2.neg(c))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Identity<algebra::ops::Add> for Ext3<F, N> {
+    fn identity(c: &F::Cfg) -> Self {
+        Self::from_base(F::zero(c), c)
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> CommutativeOp<algebra::ops::Mul> for Ext3<F, N> {
+    fn op(a: Self, b: Self, c: &F::Cfg) -> Self {
+        let n = |x| N::mul_by(x, c);
+        Self::new(
+            F::add(
+                F::mul(a.c0, b.c0, c),
+                n(F::sop(&[(a.c1, b.c2), (a.c2, b.c1)], c)),
+                c,
+            ),
+            F::add(
+                F::sop(&[(a.c0, b.c1), (a.c1, b.c0)], c),
+                n(F::mul(a.c2, b.c2, c)),
+                c,
+            ),
+            F::sop(&[(a.c0, b.c2), (a.c1, b.c1), (a.c2, b.c0)], c),
+        )
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Identity<algebra::ops::Mul> for Ext3<F, N> {
+    fn identity(c: &F::Cfg) -> Self {
+        Self::from_base(F::one(c), c)
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> CommutativeMonoid<algebra::ops::Add> for Ext3<F, N> {}
+impl<F: Field, N: NonResidue<F>> CommutativeMonoid<algebra::ops::Mul> for Ext3<F, N> {}
+impl<F: Field, N: NonResidue<F>> AbelianGroup<algebra::ops::Add> for Ext3<F, N> {}
+
+impl<F: Field, N: NonResidue<F>> InverseNonZero<algebra::ops::Mul> for Ext3<F, N> {
+    fn inv(self, c: &F::Cfg) -> Option<Self> {
+        let n = |x| N::mul_by(x, c);
+        let Self { c0, c1, c2, .. } = self;
+        let a = F::sub(c0.sqr(c), n(F::mul(c1, c2, c)), c);
+        let b = F::sub(n(c2.sqr(c)), F::mul(c0, c1, c), c);
+        let d = F::sub(c1.sqr(c), F::mul(c0, c2, c), c);
+        let norm = F::add(F::mul(c0, a, c), n(F::sop(&[(c2, b), (c1, d)], c)), c);
+        let norm_inv = norm.reciprocal(c)?;
+        Some(Self::new(
+            F::mul(a, norm_inv, c),
+            F::mul(b, norm_inv, c),
+            F::mul(d, norm_inv, c),
+        ))
+    }
+}
+
+impl<F: Field, N: NonResidue<F>> Ring for Ext3<F, N> {}
+impl<F: Field, N: NonResidue<F>> ConditionalSwap for Ext3<F, N> {
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+        F::conditional_swap(&mut a.c0, &mut b.c0, swap);
+        F::conditional_swap(&mut a.c1, &mut b.c1, swap);
+        F::conditional_swap(&mut a.c2, &mut b.c2, swap);
+    }
+}
+impl<F: Field, N: NonResidue<F>> Field for Ext3<F, N> {
+    fn is_canonical(self, c: &F::Cfg) -> bool {
+        self.c0.is_canonical(c) && self.c1.is_canonical(c) && self.c2.is_canonical(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use crate::{
+        algebra::{laws, Field, Ring},
+        mod_field::{ModField, ModFieldCfg},
+    };
+
+    use super::{Ext2, Ext3, NonResidue};
+
+    type Fp = ModField<u64>;
+
+    /// -1, a non-residue modulo p = 3 mod 4.
+    struct MinusOne;
+    impl NonResidue<Fp> for MinusOne {
+        fn mul_by(x: Fp, cfg: &ModFieldCfg<u64>) -> Fp {
+            x.neg(cfg)
+        }
+    }
+    type Fp2 = Ext2<Fp, MinusOne>;
+
+    /// 1 + i, which has no cube root in F_p² for p = 1039.
+    struct OnePlusI;
+    impl NonResidue<Fp2> for OnePlusI {
+        fn mul_by(x: Fp2, cfg: &ModFieldCfg<u64>) -> Fp2 {
+            Fp2::new(Ring::sub(x.c0, x.c1, cfg), Ring::add(x.c0, x.c1, cfg))
+        }
+    }
+    type Fp6 = Ext3<Fp2, OnePlusI>;
+
+    #[test]
+    fn field_axioms() {
+        let c = ModFieldCfg { rem: 1039u64 };
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let mut f = || ModField::new(rng.gen(), &c);
+        let mut fp2 = || Fp2::new(f(), f());
+        let mut elems = || Fp6::new(fp2(), fp2(), fp2());
+        for _ in 0..50 {
+            let (x, y, z) = (elems(), elems(), elems());
+            assert_eq!(Ring::mul(x, y, &c), Ring::mul(y, x, &c));
+            assert_eq!(
+                Ring::mul(x, Ring::add(y, z, &c), &c),
+                Ring::add(Ring::mul(x, y, &c), Ring::mul(x, z, &c), &c)
+            );
+            assert_eq!(
+                Ring::mul(Ring::mul(x, y, &c), z, &c),
+                Ring::mul(x, Ring::mul(y, z, &c), &c)
+            );
+            assert_eq!(Ring::mul(x, x.reciprocal(&c).unwrap(), &c), Fp6::one(&c));
+            assert_eq!(
+                Ring::mul(x.c0, x.c0.reciprocal(&c).unwrap(), &c),
+                Fp2::one(&c)
+            );
+        }
+        // i² = -1, and the multiplicative group of F_p² has order p² - 1
+        let i = Fp2::new(ModField::new(0, &c), ModField::new(1, &c));
+        assert_eq!(i.sqr(&c), Ring::neg(Fp2::one(&c), &c));
+        assert_eq!(fp2().pow(1039u64 * 1039 - 1, &c), Fp2::one(&c));
+        assert_eq!(Fp6::zero(&c).reciprocal(&c), None);
+    }
+
+    #[test]
+    fn laws() {
+        let c = ModFieldCfg { rem: 1039u64 };
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let mut f = || ModField::new(rng.gen(), &c);
+        let mut fp2 = || Fp2::new(f(), f());
+        laws::field(&mut fp2, &c, 50);
+        laws::field(|| Fp6::new(fp2(), fp2(), fp2()), &c, 50);
+    }
+}
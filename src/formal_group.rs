@@ -0,0 +1,219 @@
+//! The formal group of `y² = x³ + a·x + b`: near the point at infinity, in
+//! the parameter `z = -x/y`, addition is a power series `F(z1, z2)` with
+//! coefficients polynomial in `a` and `b` (Silverman, AEC IV.1). Everything is
+//! truncated at total degree `prec`, and all coefficients below it are exact:
+//! raising `prec` adds terms but never changes the ones already there.
+
+use crate::algebra::Ring;
+
+/// A power series in `z1`, `z2` truncated at total degree `prec`: `c[i][j]`
+/// is the coefficient of `z1^i z2^j`, for `i + j < prec`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Series2<F> {
+    prec: usize,
+    c: Vec<Vec<F>>,
+}
+
+impl<F: Ring> Series2<F> {
+    pub fn zero(prec: usize, cfg: &F::Cfg) -> Self {
+        Self {
+            prec,
+            c: (0..prec).map(|i| vec![F::zero(cfg); prec - i]).collect(),
+        }
+    }
+
+    fn monomial(i: usize, j: usize, coeff: F, prec: usize, cfg: &F::Cfg) -> Self {
+        let mut res = Self::zero(prec, cfg);
+        if i + j < prec {
+            res.c[i][j] = coeff;
+        }
+        res
+    }
+
+    pub fn prec(&self) -> usize {
+        self.prec
+    }
+
+    /// The coefficient of `z1^i z2^j`, which must be below the precision.
+    pub fn coeff(&self, i: usize, j: usize) -> F {
+        assert!(i + j < self.prec, "beyond the precision");
+        self.c[i][j]
+    }
+
+    fn zip(&self, other: &Self, f: impl Fn(F, F) -> F) -> Self {
+        let mut res = self.clone();
+        for (row, other) in res.c.iter_mut().zip(&other.c) {
+            for (x, &y) in row.iter_mut().zip(other) {
+                *x = f(*x, y);
+            }
+        }
+        res
+    }
+
+    pub fn add(&self, other: &Self, cfg: &F::Cfg) -> Self {
+        self.zip(other, |x, y| F::add(x, y, cfg))
+    }
+
+    pub fn sub(&self, other: &Self, cfg: &F::Cfg) -> Self {
+        self.zip(other, |x, y| F::sub(x, y, cfg))
+    }
+
+    pub fn scale(&self, k: F, cfg: &F::Cfg) -> Self {
+        self.zip(self, |x, _| F::mul(k, x, cfg))
+    }
+
+    pub fn mul(&self, other: &Self, cfg: &F::Cfg) -> Self {
+        let mut res = Self::zero(self.prec, cfg);
+        for (i1, row1) in self.c.iter().enumerate() {
+            for (j1, &x) in row1.iter().enumerate() {
+                if x == F::zero(cfg) {
+                    continue;
+                }
+                for (i2, row2) in other.c.iter().enumerate().take(self.prec - i1 - j1) {
+                    for (j2, &y) in row2.iter().enumerate().take(self.prec - i1 - j1 - i2) {
+                        let c = &mut res.c[i1 + i2][j1 + j2];
+                        *c = F::add(*c, F::mul(x, y, cfg), cfg);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// `1 / self` for a series with constant term one, as `Σ (1 - self)^k`.
+    fn inv_unit(&self, cfg: &F::Cfg) -> Self {
+        assert!(self.c[0][0] == F::one(cfg), "not a unit series");
+        let one = Self::monomial(0, 0, F::one(cfg), self.prec, cfg);
+        let u = one.sub(self, cfg);
+        let (mut res, mut power) = (one.clone(), one);
+        for _ in 1..self.prec {
+            power = power.mul(&u, cfg);
+            res = res.add(&power, cfg);
+        }
+        res
+    }
+
+    /// `F(z, -z)` as a series in `z`, which is zero for the group law.
+    pub fn on_antidiagonal(&self, cfg: &F::Cfg) -> Vec<F> {
+        let mut res = vec![F::zero(cfg); self.prec];
+        for (i, row) in self.c.iter().enumerate() {
+            for (j, &x) in row.iter().enumerate() {
+                let x = if j % 2 == 1 { F::neg(x, cfg) } else { x };
+                res[i + j] = F::add(res[i + j], x, cfg);
+            }
+        }
+        res
+    }
+}
+
+/// `w(z) = -1/y` as a series in `z`, the fixed point of
+/// `w = z³ + a·z·w² + b·w³`; each iteration fixes at least one more
+/// coefficient.
+pub fn w_series<F: Ring>(a: F, b: F, prec: usize, cfg: &F::Cfg) -> Vec<F> {
+    let mul = |p: &[F], q: &[F]| {
+        let mut res = vec![F::zero(cfg); prec];
+        for (i, &x) in p.iter().enumerate() {
+            for (j, &y) in q.iter().enumerate().take(prec - i) {
+                res[i + j] = F::add(res[i + j], F::mul(x, y, cfg), cfg);
+            }
+        }
+        res
+    };
+    let mut z = vec![F::zero(cfg); prec];
+    if prec > 1 {
+        z[1] = F::one(cfg);
+    }
+    let z3 = mul(&mul(&z, &z), &z);
+    let mut w = z3.clone();
+    for _ in 0..prec {
+        let w2 = mul(&w, &w);
+        let azw2 = mul(&z, &w2).into_iter().map(|x| F::mul(a, x, cfg));
+        let bw3 = mul(&w2, &w).into_iter().map(|x| F::mul(b, x, cfg));
+        w = z3
+            .iter()
+            .zip(azw2.zip(bw3))
+            .map(|(&t, (u, v))| F::add(t, F::add(u, v, cfg), cfg))
+            .collect();
+    }
+    w
+}
+
+/// The formal group law `F(z1, z2)`: with `λ`, `ν` the slope and intercept
+/// of the line through the two points in the `(z, w)` plane,
+/// `F = z1 + z2 + (2a·λ·ν + 3b·λ²·ν) / (1 + a·λ² + b·λ³)`.
+pub fn group_law<F: Ring>(a: F, b: F, prec: usize, cfg: &F::Cfg) -> Series2<F> {
+    let w = w_series(a, b, prec + 1, cfg);
+    // (w(z2) - w(z1)) / (z2 - z1) = Σ Aₙ Σ_{i+j=n-1} z1^i z2^j
+    let mut lambda = Series2::zero(prec, cfg);
+    let mut w1 = Series2::zero(prec, cfg);
+    for (i, row) in lambda.c.iter_mut().enumerate() {
+        for (j, c) in row.iter_mut().enumerate() {
+            *c = w[i + j + 1];
+        }
+        w1.c[i][0] = w[i];
+    }
+    let z1 = Series2::monomial(1, 0, F::one(cfg), prec, cfg);
+    let z2 = Series2::monomial(0, 1, F::one(cfg), prec, cfg);
+    let nu = w1.sub(&lambda.mul(&z1, cfg), cfg);
+    let lambda2 = lambda.mul(&lambda, cfg);
+    let lambda3 = lambda2.mul(&lambda, cfg);
+    let num = lambda
+        .scale(F::add(a, a, cfg), cfg)
+        .add(&lambda2.scale(F::mul(F::three(cfg), b, cfg), cfg), cfg)
+        .mul(&nu, cfg);
+    let den = Series2::monomial(0, 0, F::one(cfg), prec, cfg)
+        .add(&lambda2.scale(a, cfg), cfg)
+        .add(&lambda3.scale(b, cfg), cfg);
+    z1.add(&z2, cfg).add(&num.mul(&den.inv_unit(cfg), cfg), cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        algebra::Ring,
+        mod_field::{ModField, ModFieldCfg},
+    };
+
+    use super::{group_law, w_series};
+
+    #[test]
+    fn w_expansion() {
+        // w = z³ + a z⁷ + b z⁹ + 2a² z¹¹ + ...
+        let c = ModFieldCfg {
+            rem: (1u64 << 61) - 1,
+        };
+        let f = |v| ModField::new(v, &c);
+        let w = w_series(f(2), f(3), 12, &c);
+        let expected = [0, 0, 0, 1, 0, 0, 0, 2, 0, 3, 0, 8];
+        assert_eq!(w, expected.map(f));
+    }
+
+    #[test]
+    fn group_law_axioms() {
+        let c = ModFieldCfg {
+            rem: (1u64 << 61) - 1,
+        };
+        let f = |v| ModField::new(v, &c);
+        let (a, b) = (f(2), f(3));
+        let law = group_law(a, b, 10, &c);
+        for i in 0..10 {
+            for j in 0..10 - i {
+                assert_eq!(law.coeff(i, j), law.coeff(j, i));
+            }
+            let identity = if i == 1 { f(1) } else { f(0) };
+            assert_eq!(law.coeff(i, 0), identity);
+        }
+        assert!(law.on_antidiagonal(&c).iter().all(|&x| x == f(0)));
+        // z1 + z2 - 2a z1 z2 (z1 + z2)(z1² + z1 z2 + z2²) + ...
+        let minus = |k| Ring::neg(f(k), &c);
+        assert_eq!(law.coeff(4, 1), minus(4));
+        assert_eq!(law.coeff(3, 2), minus(8));
+        // the precision only cuts, it doesn't change coefficients
+        let longer = group_law(a, b, 12, &c);
+        for i in 0..10 {
+            for j in 0..10 - i {
+                assert_eq!(law.coeff(i, j), longer.coeff(i, j));
+            }
+        }
+    }
+}
@@ -0,0 +1,379 @@
+//! Identity files: a key pair together with the id of its curve, when it was
+//! made and an optional comment, as one [armored](crate::armor) block, so a
+//! key pair is one file instead of two base64 strings and a note on which
+//! curve they're for. Inside the armor:
+//!
+//! ```text
+//! version (1) | curve id length (1) | curve id | created, Unix seconds (8, LE)
+//!     [| not before (8, LE) | not after (8, LE)]
+//!     | private key (I::LEN) | public key (Point::LEN) | comment (UTF-8, rest)
+//! ```
+//!
+//! The validity period is only there from version 2 on, with 0 for an open
+//! end; identities without one are still written as version 1.
+
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::Rng;
+
+use crate::{
+    algebra::Field,
+    armor::{armor, dearmor, IDENTITY},
+    base_traits::{FromRandom, Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    error::Error,
+    mod_field::ModFieldCfg,
+    points_group::{Point, PointCfg, ValidationPolicy},
+    schnorr::{self, Signature},
+};
+
+const VERSION: u8 = 1;
+/// [`VERSION`] with the validity period.
+const VERSION_VALIDITY: u8 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Identity<I, P> {
+    pub private: PrivateKey<I>,
+    pub public: PublicKey<P>,
+    /// The name the application knows the curve by, e.g. `secp256k1`.
+    pub curve: String,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    /// When the keys become valid, in the same seconds; from creation on if
+    /// `None`.
+    pub not_before: Option<u64>,
+    /// When they expire; never if `None`.
+    pub not_after: Option<u64>,
+    pub comment: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> Error {
+    Error::Io(format!("{}: {}", path.display(), e))
+}
+
+impl<I: Natural + RW + FromRandom<()>, F: Field + RW> Identity<I, Point<F>> {
+    /// A fresh key pair on `curve`, created now.
+    pub fn generate(
+        curve: &str,
+        comment: Option<&str>,
+        rng: &mut impl Rng,
+        cfg: &PointCfg<F>,
+        n: &ModFieldCfg<I>,
+    ) -> Self {
+        let private = PrivateKey::random(rng, n);
+        Self {
+            private,
+            public: private.public_key(cfg),
+            curve: curve.to_string(),
+            created: now(),
+            not_before: None,
+            not_after: None,
+            comment: comment.map(str::to_string),
+        }
+    }
+
+    /// The identity to replace this one: a fresh key pair on the same curve
+    /// with the same comment, and a validity period as far from its creation
+    /// as this one's.
+    pub fn rotate(&self, rng: &mut impl Rng, cfg: &PointCfg<F>, n: &ModFieldCfg<I>) -> Self {
+        let new = Self::generate(&self.curve, self.comment.as_deref(), rng, cfg, n);
+        let shift = |t: u64| new.created.saturating_add(t.saturating_sub(self.created));
+        Self {
+            not_before: self.not_before.map(shift),
+            not_after: self.not_after.map(shift),
+            ..new
+        }
+    }
+}
+
+impl<I, P> Identity<I, P> {
+    /// [`Error::NotYetValid`] or [`Error::Expired`] unless `time` is within
+    /// the validity period, both ends included.
+    pub fn validity_at(&self, time: u64) -> Result<(), Error> {
+        if let Some(from) = self.not_before.filter(|&from| time < from) {
+            return Err(Error::NotYetValid { from });
+        }
+        if let Some(at) = self.not_after.filter(|&at| time > at) {
+            return Err(Error::Expired { at });
+        }
+        Ok(())
+    }
+
+    /// [`Self::validity_at`] now.
+    pub fn check_validity(&self) -> Result<(), Error> {
+        self.validity_at(now())
+    }
+}
+
+impl<I: Natural + RW, F: Field + RW> Identity<I, Point<F>> {
+    pub fn to_armored(&self) -> String {
+        assert!(self.curve.len() <= u8::MAX as usize, "curve id too long");
+        let validity = self.not_before.is_some() || self.not_after.is_some();
+        let version = if validity { VERSION_VALIDITY } else { VERSION };
+        let mut data = vec![version, self.curve.len() as u8];
+        data.extend(self.curve.as_bytes());
+        data.extend(self.created.to_le_bytes());
+        if validity {
+            data.extend(self.not_before.unwrap_or(0).to_le_bytes());
+            data.extend(self.not_after.unwrap_or(0).to_le_bytes());
+        }
+        data.extend(self.private.to_vec());
+        data.extend(self.public.point().to_vec());
+        data.extend(self.comment.as_deref().unwrap_or_default().as_bytes());
+        armor(IDENTITY, &data)
+    }
+
+    /// Parses an identity for `curve`, rejecting one for another curve and
+    /// one whose public key isn't the private key's.
+    pub fn from_armored(
+        text: &str,
+        curve: &str,
+        cfg: &PointCfg<F>,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Self, Error> {
+        let (label, data) = dearmor(text)?;
+        if label != IDENTITY {
+            return Err(Error::Armor);
+        }
+        let (&version, rest) = data.split_first().ok_or(Error::Identity)?;
+        let (&id_len, rest) = rest.split_first().ok_or(Error::Identity)?;
+        let times = match version {
+            VERSION => 1,
+            VERSION_VALIDITY => 3,
+            _ => return Err(Error::Identity),
+        };
+        if rest.len() < id_len as usize + times * 8 + I::LEN + Point::<F>::LEN {
+            return Err(Error::Identity);
+        }
+        let (id, rest) = rest.split_at(id_len as usize);
+        let id = std::str::from_utf8(id).map_err(|_| Error::Identity)?;
+        if id != curve {
+            return Err(Error::WrongCurve {
+                expected: curve.to_string(),
+                found: id.to_string(),
+            });
+        }
+        let (times, rest) = rest.split_at(times * 8);
+        let mut times = times
+            .chunks(8)
+            .map(|t| u64::from_le_bytes(t.try_into().unwrap()));
+        let created = times.next().unwrap();
+        let mut bound = || times.next().filter(|&t| t != 0);
+        let (not_before, not_after) = (bound(), bound());
+        let (private, rest) = rest.split_at(I::LEN);
+        let (public, comment) = rest.split_at(Point::<F>::LEN);
+        let private = PrivateKey::from_bytes_checked(private, n)?;
+        let public = Point::from_bytes_validated(public, ValidationPolicy::<I>::OnCurve, cfg)?;
+        let public = PublicKey::from_point(public);
+        if public != private.public_key(cfg) {
+            return Err(Error::Identity);
+        }
+        let comment = std::str::from_utf8(comment).map_err(|_| Error::Identity)?;
+        Ok(Self {
+            private,
+            public,
+            curve: id.to_string(),
+            created,
+            not_before,
+            not_after,
+            comment: (!comment.is_empty()).then(|| comment.to_string()),
+        })
+    }
+
+    /// [`schnorr::verify`] with the public key, which has to be
+    /// [valid](Self::check_validity) now; verify with `self.public` directly
+    /// to accept expired keys.
+    pub fn verify(
+        &self,
+        msg: &[u8],
+        sig: Signature<Point<F>, I>,
+        cfg: &PointCfg<F>,
+        n: &ModFieldCfg<I>,
+    ) -> Result<bool, Error> {
+        self.check_validity()?;
+        Ok(schnorr::verify(self.public, msg, sig, cfg, n))
+    }
+
+    /// Writes the identity to `path`, readable by the owner only on Unix.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path).map_err(|e| io_error(path, e))?;
+        std::io::Write::write_all(&mut file, self.to_armored().as_bytes())
+            .map_err(|e| io_error(path, e))
+    }
+
+    /// [`Self::from_armored`] of the file at `path`.
+    pub fn load(
+        path: &Path,
+        curve: &str,
+        cfg: &PointCfg<F>,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Self, Error> {
+        let text = fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+        Self::from_armored(&text, curve, cfg, n)
+    }
+}
+
+/// Replaces the identity at `path` by its [rotation](Identity::rotate) and
+/// keeps the old one next to it with `.old` appended, so what was encrypted
+/// to it can still be decrypted. Returns the new identity.
+pub fn rotate_file<I, F>(
+    path: &Path,
+    curve: &str,
+    rng: &mut impl Rng,
+    cfg: &PointCfg<F>,
+    n: &ModFieldCfg<I>,
+) -> Result<Identity<I, Point<F>>, Error>
+where
+    I: Natural + RW + FromRandom<()>,
+    F: Field + RW,
+{
+    let old = Identity::load(path, curve, cfg, n)?;
+    let new = old.rotate(rng, cfg, n);
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".old");
+    old.save(Path::new(&backup))?;
+    new.save(path)?;
+    Ok(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        armor::{armor, dearmor, PUBLIC_KEY},
+        curves::{toy, toy_order},
+        ecc::PrivateKey,
+        error::Error,
+        schnorr,
+        signer::Signer,
+    };
+
+    use super::{rotate_file, Identity};
+
+    #[test]
+    fn round_trip() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([5u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        for comment in [None, Some("alice@example.com, work laptop")] {
+            let id = Identity::<u128, _>::generate("toy", comment, &mut rng, &cfg, &n);
+            let armored = id.to_armored();
+            assert!(armored.starts_with("-----BEGIN ECC IDENTITY-----"));
+            assert_eq!(Identity::from_armored(&armored, "toy", &cfg, &n), Ok(id));
+        }
+    }
+
+    #[test]
+    fn validity() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([5u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let plain = Identity::<u128, _>::generate("toy", None, &mut rng, &cfg, &n);
+        // without a period the file stays version 1
+        let (_, data) = dearmor(&plain.to_armored()).unwrap();
+        assert_eq!(data[0], 1);
+        assert_eq!(plain.check_validity(), Ok(()));
+
+        let id = Identity {
+            created: 1000,
+            not_before: Some(2000),
+            not_after: Some(3000),
+            ..plain.clone()
+        };
+        let (_, data) = dearmor(&id.to_armored()).unwrap();
+        assert_eq!(data[0], 2);
+        assert_eq!(
+            Identity::from_armored(&id.to_armored(), "toy", &cfg, &n),
+            Ok(id.clone())
+        );
+        let open_start = Identity {
+            not_before: None,
+            ..id.clone()
+        };
+        assert_eq!(
+            Identity::from_armored(&open_start.to_armored(), "toy", &cfg, &n),
+            Ok(open_start)
+        );
+
+        assert_eq!(id.validity_at(1999), Err(Error::NotYetValid { from: 2000 }));
+        assert_eq!(id.validity_at(2000), Ok(()));
+        assert_eq!(id.validity_at(3000), Ok(()));
+        assert_eq!(id.validity_at(3001), Err(Error::Expired { at: 3000 }));
+
+        // a rotated key lives as long, counted from its own creation
+        let rotated = id.rotate(&mut rng, &cfg, &n);
+        assert_eq!(rotated.not_before, Some(rotated.created + 1000));
+        assert_eq!(rotated.not_after, Some(rotated.created + 2000));
+
+        let msg = b"signed while valid";
+        let sig = id.private.sign(msg, &cfg, &n).unwrap();
+        assert_eq!(
+            id.verify(msg, sig, &cfg, &n),
+            Err(Error::Expired { at: 3000 })
+        );
+        assert!(schnorr::verify(id.public, msg, sig, &cfg, &n));
+        let current = Identity {
+            not_after: Some(u64::MAX),
+            ..id
+        };
+        assert_eq!(current.verify(msg, sig, &cfg, &n), Ok(true));
+    }
+
+    #[test]
+    fn rejects() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([5u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let id = Identity::<u128, _>::generate("toy", None, &mut rng, &cfg, &n);
+        let parse = |text: &str| Identity::from_armored(text, "toy", &cfg, &n);
+        assert_eq!(
+            Identity::from_armored(&id.to_armored(), "secp256k1", &cfg, &n),
+            Err(Error::WrongCurve {
+                expected: "secp256k1".to_string(),
+                found: "toy".to_string()
+            })
+        );
+        let other = Identity {
+            public: PrivateKey::random(&mut rng, &n).public_key(&cfg),
+            ..id.clone()
+        };
+        assert_eq!(parse(&other.to_armored()), Err(Error::Identity));
+        assert_eq!(parse(&armor(PUBLIC_KEY, b"")), Err(Error::Armor));
+        assert_eq!(
+            parse(&armor(super::IDENTITY, &[1, 3])),
+            Err(Error::Identity)
+        );
+    }
+
+    #[test]
+    fn files() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([5u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let dir = std::env::temp_dir().join(format!("ecc-identity-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("id.asc");
+        let id = Identity::<u128, _>::generate("toy", Some("test"), &mut rng, &cfg, &n);
+        id.save(&path).unwrap();
+        assert_eq!(Identity::load(&path, "toy", &cfg, &n), Ok(id.clone()));
+
+        let new = rotate_file(&path, "toy", &mut rng, &cfg, &n).unwrap();
+        assert_ne!(new.private, id.private);
+        assert_eq!(new.comment.as_deref(), Some("test"));
+        assert_eq!(Identity::load(&path, "toy", &cfg, &n), Ok(new));
+        let old = Identity::load(&dir.join("id.asc.old"), "toy", &cfg, &n);
+        assert_eq!(old, Ok(id));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Cross-checks against the RustCrypto `k256`/`p256` crates on random inputs.
+//!
+//! Scalar multiplications over `U256` take on the order of a minute without
+//! optimizations, so those tests are ignored by default; run them with
+//! `cargo test --release -- --ignored`.
+
+use k256::elliptic_curve::{
+    ff::PrimeField,
+    sec1::{FromEncodedPoint, ToEncodedPoint},
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use crate::{
+    algebra::CommutativeOp,
+    curves::{p256, secp256k1},
+    mod_field::ModField,
+    points_group::{Point, PointCfg},
+    uint::U256,
+};
+
+const ITERATIONS: usize = 8;
+
+fn to_ours(cfg: &PointCfg<ModField<U256>>, x: &[u8], y: &[u8]) -> Point<ModField<U256>> {
+    Point::new(
+        ModField::new(U256::from_big_endian(x), &cfg.cf),
+        ModField::new(U256::from_big_endian(y), &cfg.cf),
+        cfg,
+    )
+}
+
+/// Generates the k256 and p256 tests from one body, since the two crates share
+/// their API through `elliptic_curve`.
+macro_rules! interop_tests {
+    ($krate:ident, $cfg:expr, $sec1:ident, $mul:ident, $ecdh:ident) => {
+        #[test]
+        fn $sec1() {
+            let cfg = $cfg;
+            let mut rng = ChaCha20Rng::from_seed([3; 32]);
+            for _ in 0..ITERATIONS {
+                let theirs = $krate::SecretKey::random(&mut rng).public_key();
+                let uncompressed = theirs.to_encoded_point(false);
+                let ours = to_ours(&cfg, uncompressed.x().unwrap(), uncompressed.y().unwrap());
+                let compressed = theirs.to_encoded_point(true);
+                assert_eq!(ours.to_sec1_compressed(), compressed.as_bytes());
+                assert_eq!(
+                    Point::from_sec1_compressed(compressed.as_bytes(), &cfg),
+                    Some(ours)
+                );
+                let back = $krate::EncodedPoint::from_bytes(ours.to_sec1_compressed()).unwrap();
+                assert_eq!(
+                    $krate::PublicKey::from_encoded_point(&back).unwrap(),
+                    theirs
+                );
+            }
+        }
+
+        #[test]
+        #[ignore = "slow without optimizations"]
+        fn $mul() {
+            let cfg = $cfg;
+            let mut rng = ChaCha20Rng::from_seed([4; 32]);
+            for _ in 0..ITERATIONS {
+                let k = $krate::NonZeroScalar::random(&mut rng);
+                let theirs = ($krate::ProjectivePoint::GENERATOR * *k)
+                    .to_affine()
+                    .to_encoded_point(false);
+                let ours = Point::exp(cfg.g, U256::from_big_endian(&k.to_repr()), &cfg);
+                assert_eq!(
+                    ours,
+                    to_ours(&cfg, theirs.x().unwrap(), theirs.y().unwrap())
+                );
+            }
+        }
+
+        #[test]
+        #[ignore = "slow without optimizations"]
+        fn $ecdh() {
+            let cfg = $cfg;
+            let mut rng = ChaCha20Rng::from_seed([5; 32]);
+            for _ in 0..ITERATIONS {
+                let alice = $krate::SecretKey::random(&mut rng);
+                let bob = $krate::SecretKey::random(&mut rng).public_key();
+                let theirs =
+                    $krate::ecdh::diffie_hellman(alice.to_nonzero_scalar(), bob.as_affine());
+                let bob = bob.to_encoded_point(false);
+                let ours = Point::exp(
+                    to_ours(&cfg, bob.x().unwrap(), bob.y().unwrap()),
+                    U256::from_big_endian(&alice.to_bytes()),
+                    &cfg,
+                );
+                assert_eq!(
+                    ours.x().nat().to_big_endian(),
+                    &theirs.raw_secret_bytes()[..]
+                );
+            }
+        }
+    };
+}
+
+interop_tests!(k256, secp256k1(), k256_sec1, k256_scalar_mul, k256_ecdh);
+interop_tests!(p256, p256(), p256_sec1, p256_scalar_mul, p256_ecdh);
@@ -0,0 +1,135 @@
+//! Known-answer test vectors: key pairs, scalar multiples of G and encryptions
+//! with explicit nonces, serialized as JSON so other implementations (and later
+//! versions of this crate) can be checked against them.
+
+use std::fmt::Write;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use crate::{
+    algebra::{CommutativeOp, Field},
+    base_traits::{FromRandom, Natural, RW},
+    ecc::gen_keys,
+    encoding_utils::{text_to_points, DEFAULT_KAPPA},
+    error::Error,
+    mod_field::ModField,
+    points_group::{Point, PointCfg},
+};
+
+const MESSAGES: [&str; 3] = ["", "Hello, world", "A very long sentence actually, yeah"];
+
+/// Big-endian hex, the way curve parameters are usually printed.
+pub(crate) fn hex<T: RW>(v: T) -> String {
+    let mut buf = vec![];
+    v.to_bytes(&mut buf);
+    buf.iter().rev().fold(String::new(), |mut s, b| {
+        write!(s, "{:02x}", b).unwrap();
+        s
+    })
+}
+
+fn point_json<F: RW + Field>(p: Point<F>) -> String {
+    format!(r#"{{"x": "{}", "y": "{}"}}"#, hex(p.x()), hex(p.y()))
+}
+
+/// Emits `count` vectors for the curve as a JSON object. The vectors are a pure
+/// function of `seed`; scalars are drawn from `IP`, i.e. have its width.
+pub fn curve_vectors<I: Natural + RW, IP: Natural + RW + FromRandom<()>>(
+    name: &str,
+    cfg: &PointCfg<ModField<I>>,
+    count: usize,
+    seed: [u8; 32],
+) -> Result<String, Error> {
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    let mut vectors = vec![];
+    for i in 0..count {
+        let (pr, pb) = gen_keys::<_, IP, Point<ModField<I>>>(&mut rng, cfg);
+        let k = IP::random(&mut rng, &());
+        let msg = MESSAGES[i % MESSAGES.len()];
+        let mut nonces = vec![];
+        let mut chunks = vec![];
+        for m in text_to_points(msg, DEFAULT_KAPPA, cfg)? {
+            let t = IP::random(&mut rng, &());
+            let (c1, c2) = pb.encrypt_with_nonce(m, t, cfg);
+            nonces.push(format!(r#""{}""#, hex(t)));
+            chunks.push(format!("[{}, {}]", point_json(c1), point_json(c2)));
+        }
+        vectors.push(format!(
+            concat!(
+                r#"{{"private": "{}", "public": {}, "#,
+                r#""scalar": "{}", "product": {}, "#,
+                r#""message": "{}", "nonces": [{}], "ciphertext": [{}]}}"#,
+            ),
+            hex(pr),
+            point_json(pb.point()),
+            hex(k),
+            point_json(Point::exp(cfg.g, k, cfg)),
+            msg,
+            nonces.join(", "),
+            chunks.join(", "),
+        ));
+    }
+    Ok(format!(
+        concat!(
+            r#"{{"curve": "{}", "p": "{}", "a": "{}", "b": "{}", "g": {}, "kappa": {}, "#,
+            r#""vectors": [{}]}}"#,
+        ),
+        name,
+        hex(cfg.cf.rem),
+        hex(cfg.a),
+        hex(cfg.b),
+        point_json(cfg.g),
+        DEFAULT_KAPPA,
+        vectors.join(", "),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mod_field::{ModField, ModFieldCfg},
+        points_group::{Point, PointCfg},
+    };
+
+    use super::{curve_vectors, hex};
+
+    fn config() -> PointCfg<ModField<u64>> {
+        let cfg_field = ModFieldCfg {
+            rem: 0x0014_4C3B_27FFu64,
+        };
+        PointCfg {
+            g: Point::new_unsafe(
+                ModField::new(2500, &cfg_field),
+                ModField::new(125001, &cfg_field),
+            ),
+            a: ModField::new(100, &cfg_field),
+            b: ModField::new(1, &cfg_field),
+            cf: cfg_field,
+        }
+    }
+
+    #[test]
+    fn hex_is_big_endian() {
+        assert_eq!(hex(0x0102u64), "0000000000000102");
+        assert_eq!(hex(0xABu8), "ab");
+    }
+
+    #[test]
+    fn reproducible() {
+        let cfg = config();
+        let vectors = curve_vectors::<_, u128>("toy", &cfg, 3, [5; 32]).unwrap();
+        assert_eq!(
+            vectors,
+            curve_vectors::<_, u128>("toy", &cfg, 3, [5; 32]).unwrap()
+        );
+        assert_ne!(
+            vectors,
+            curve_vectors::<_, u128>("toy", &cfg, 3, [6; 32]).unwrap()
+        );
+        assert!(vectors.starts_with(
+            r#"{"curve": "toy", "p": "000000144c3b27ff", "a": "0000000000000064", "b": "0000000000000001", "g": {"x": "00000000000009c4", "y": "000000000001e849"}"#
+        ));
+        assert_eq!(vectors.matches(r#""private""#).count(), 3);
+    }
+}
@@ -0,0 +1,220 @@
+//! Key encapsulation, and a combiner for hybrid key exchange. [`CurveContext`]
+//! is a KEM over its curve: the encapsulation is an ephemeral `E = t·G`,
+//! and the secret is hashed from `E`, the recipient's key and `t·Pub`.
+//! [`Hybrid`] runs two KEMs side by side, typically that one and a
+//! post-quantum KEM from another crate behind the [`Kem`] trait, and derives
+//! one secret from both, which stays secret as long as either KEM holds.
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    context::CurveContext,
+    ecc::{PrivateKey, PublicKey},
+    error::Error,
+    points_group::{Point, ValidationPolicy},
+};
+
+const EC_LABEL: &[u8] = b"elliptic-curves/kem/v1";
+const HYBRID_LABEL: &[u8] = b"elliptic-curves/hybrid-kem/v1";
+
+/// A key encapsulation mechanism: a fresh secret for the holder of a public
+/// key, and the encapsulation from which only they can recover it.
+pub trait Kem {
+    type PublicKey;
+    type PrivateKey;
+
+    /// The encapsulation and the shared secret.
+    fn encapsulate(
+        &self,
+        key: &Self::PublicKey,
+        rng: &mut impl Rng,
+    ) -> Result<(Vec<u8>, Vec<u8>), Error>;
+
+    fn decapsulate(&self, key: &Self::PrivateKey, encapsulation: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+fn ec_secret<F: Field + RW + Parity>(
+    ephemeral: Point<F>,
+    recipient: Point<F>,
+    shared: Point<F>,
+) -> Vec<u8> {
+    Sha256::new()
+        .chain_update(EC_LABEL)
+        .chain_update(ephemeral.to_sec1_compressed())
+        .chain_update(recipient.to_sec1_compressed())
+        .chain_update(shared.to_sec1_compressed())
+        .finalize()
+        .to_vec()
+}
+
+impl<F, I> Kem for CurveContext<F, I>
+where
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+{
+    type PublicKey = PublicKey<Point<F>>;
+    type PrivateKey = PrivateKey<I>;
+
+    /// The encapsulation is `E` SEC1 compressed.
+    fn encapsulate(
+        &self,
+        key: &PublicKey<Point<F>>,
+        rng: &mut impl Rng,
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let t = PrivateKey::random(rng, self.order()).scalar();
+        let ephemeral = self.mul_g(t);
+        let shared = key.point().mul_ct(t, self.cfg());
+        let secret = ec_secret(ephemeral, key.point(), shared);
+        Ok((ephemeral.to_sec1_compressed(), secret))
+    }
+
+    /// Fails with [`Error::Point`] or [`Error::Subgroup`] for an `E` that
+    /// isn't in the generator's subgroup.
+    fn decapsulate(&self, key: &PrivateKey<I>, encapsulation: &[u8]) -> Result<Vec<u8>, Error> {
+        let policy = ValidationPolicy::OnCurveAndSubgroup(self.order().rem);
+        let ephemeral = Point::from_sec1_compressed_validated(encapsulation, policy, self.cfg())?;
+        let shared = ephemeral.mul_ct(key.scalar(), self.cfg());
+        let recipient = self.public_key(*key).point();
+        Ok(ec_secret(ephemeral, recipient, shared))
+    }
+}
+
+/// Two KEMs as one: keys are pairs, the encapsulation is the first one's
+/// length as a little-endian `u32` followed by both, and the secret is
+/// SHA-256 over a label, both secrets and both encapsulations, each but the
+/// last prefixed with its length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hybrid<A, B>(pub A, pub B);
+
+fn combine(secrets: [&[u8]; 2], encapsulations: [&[u8]; 2]) -> Vec<u8> {
+    let mut hasher = Sha256::new().chain_update(HYBRID_LABEL);
+    let [a, b, c, d] = [secrets[0], secrets[1], encapsulations[0], encapsulations[1]];
+    for part in [a, b, c] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.update(d);
+    hasher.finalize().to_vec()
+}
+
+impl<A: Kem, B: Kem> Kem for Hybrid<A, B> {
+    type PublicKey = (A::PublicKey, B::PublicKey);
+    type PrivateKey = (A::PrivateKey, B::PrivateKey);
+
+    fn encapsulate(
+        &self,
+        (a, b): &Self::PublicKey,
+        rng: &mut impl Rng,
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let (enc_a, secret_a) = self.0.encapsulate(a, rng)?;
+        let (enc_b, secret_b) = self.1.encapsulate(b, rng)?;
+        let secret = combine([&secret_a, &secret_b], [&enc_a, &enc_b]);
+        let len = u32::try_from(enc_a.len()).map_err(|_| Error::TooLarge {
+            limit: u32::MAX as usize,
+        })?;
+        Ok(([&len.to_le_bytes()[..], &enc_a, &enc_b].concat(), secret))
+    }
+
+    fn decapsulate(
+        &self,
+        (a, b): &Self::PrivateKey,
+        encapsulation: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let short = Error::Length {
+            expected: 4,
+            actual: encapsulation.len(),
+        };
+        let (len, rest) = encapsulation
+            .split_first_chunk::<4>()
+            .ok_or(short.clone())?;
+        let (enc_a, enc_b) = rest
+            .split_at_checked(u32::from_le_bytes(*len) as usize)
+            .ok_or(short)?;
+        let secret_a = self.0.decapsulate(a, enc_a)?;
+        let secret_b = self.1.decapsulate(b, enc_b)?;
+        Ok(combine([&secret_a, &secret_b], [enc_a, enc_b]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use crate::{
+        context::CurveContext,
+        curves::{toy, toy_order},
+        error::Error,
+    };
+
+    use super::{Hybrid, Kem};
+
+    /// Stands in for a post-quantum KEM: "encapsulates" by XOR with the key.
+    struct Xor;
+
+    impl Kem for Xor {
+        type PublicKey = [u8; 8];
+        type PrivateKey = [u8; 8];
+
+        fn encapsulate(
+            &self,
+            key: &[u8; 8],
+            rng: &mut impl Rng,
+        ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+            let secret: [u8; 8] = rng.gen();
+            let enc = secret.iter().zip(key).map(|(s, k)| s ^ k).collect();
+            Ok((enc, secret.to_vec()))
+        }
+
+        fn decapsulate(&self, key: &[u8; 8], encapsulation: &[u8]) -> Result<Vec<u8>, Error> {
+            if encapsulation.len() != 8 {
+                return Err(Error::Length {
+                    expected: 8,
+                    actual: encapsulation.len(),
+                });
+            }
+            Ok(encapsulation.iter().zip(key).map(|(e, k)| e ^ k).collect())
+        }
+    }
+
+    #[test]
+    fn curve_kem() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([5u8; 32]);
+        let ctx = CurveContext::new(toy(), toy_order());
+        let (pr, pb) = ctx.gen_keys(&mut rng);
+        let (enc, secret) = ctx.encapsulate(&pb, &mut rng).unwrap();
+        assert_eq!(ctx.decapsulate(&pr, &enc), Ok(secret.clone()));
+        let (other, _) = ctx.gen_keys(&mut rng);
+        assert_ne!(ctx.decapsulate(&other, &enc), Ok(secret));
+        assert_eq!(ctx.decapsulate(&pr, &enc[1..]), Err(Error::Point));
+    }
+
+    #[test]
+    fn hybrid() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([5u8; 32]);
+        let kem = Hybrid(CurveContext::new(toy(), toy_order()), Xor);
+        let (pr, pb) = kem.0.gen_keys(&mut rng);
+        let xor_key = rng.gen();
+        let (enc, secret) = kem.encapsulate(&(pb, xor_key), &mut rng).unwrap();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(kem.decapsulate(&(pr, xor_key), &enc), Ok(secret.clone()));
+
+        // either half alone doesn't give the secret
+        let (other, _) = kem.0.gen_keys(&mut rng);
+        assert_ne!(kem.decapsulate(&(other, xor_key), &enc), Ok(secret.clone()));
+        assert_ne!(kem.decapsulate(&(pr, [0; 8]), &enc), Ok(secret.clone()));
+        // nor does moving bytes between the halves
+        let mut moved = enc.clone();
+        moved[0] += 1;
+        assert!(kem.decapsulate(&(pr, xor_key), &moved).is_err());
+        assert_eq!(
+            kem.decapsulate(&(pr, xor_key), &enc[..2]),
+            Err(Error::Length {
+                expected: 4,
+                actual: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! A private key with its public key and observers of its use. Every
+//! signature, decryption and key agreement is announced to the observers
+//! before it happens, which may refuse it, and after it succeeded. They only
+//! see what the operation is, the public keys involved and the size of the
+//! input, never the private key or the plaintext: enough to count uses, rate
+//! limit or allow a key for some operations only, the way an HSM would.
+
+use crate::{
+    algebra::{self, AbelianGroup, CommutativeMonoid, ConditionalSwap, Field, InitialPoint},
+    base_traits::{Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    encoding_utils::decrypt_file,
+    error::Error,
+    mod_field::ModFieldCfg,
+    points_group::{Point, PointCfg},
+    schnorr::{Signature, SigningSession},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Sign,
+    Decrypt,
+    /// Diffie-Hellman with a peer's public key.
+    Derive,
+}
+
+/// What an observer learns about one use of the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyUsage<P> {
+    pub operation: Operation,
+    pub key: PublicKey<P>,
+    /// The other side of a [`Operation::Derive`].
+    pub peer: Option<PublicKey<P>>,
+    /// Bytes signed or decrypted.
+    pub len: usize,
+}
+
+pub trait KeyUsageObserver<P>: Send + Sync {
+    /// Called before the operation; an error cancels it with
+    /// [`Error::Denied`].
+    fn before(&self, _usage: &KeyUsage<P>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called once the operation succeeded.
+    fn after(&self, _usage: &KeyUsage<P>) {}
+}
+
+pub struct KeyPair<I, P> {
+    private: PrivateKey<I>,
+    public: PublicKey<P>,
+    observers: Vec<Box<dyn KeyUsageObserver<P>>>,
+}
+
+impl<I: Natural, P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap> KeyPair<I, P>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    pub fn new(private: PrivateKey<I>, cfg: &P::Cfg) -> Self {
+        Self {
+            private,
+            public: private.public_key(cfg),
+            observers: vec![],
+        }
+    }
+}
+
+impl<I: Copy, P: Copy> KeyPair<I, P> {
+    pub fn public_key(&self) -> PublicKey<P> {
+        self.public
+    }
+
+    /// Adds an observer, called after the ones registered before.
+    pub fn observe(&mut self, observer: impl KeyUsageObserver<P> + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Runs `op` if every observer allows it, and tells them if it succeeded.
+    fn guarded<T>(
+        &self,
+        operation: Operation,
+        peer: Option<PublicKey<P>>,
+        len: usize,
+        op: impl FnOnce(PrivateKey<I>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let usage = KeyUsage {
+            operation,
+            key: self.public,
+            peer,
+            len,
+        };
+        for o in &self.observers {
+            o.before(&usage).map_err(Error::Denied)?;
+        }
+        let res = op(self.private)?;
+        for o in &self.observers {
+            o.after(&usage);
+        }
+        Ok(res)
+    }
+}
+
+impl<I: Natural + RW, P: AbelianGroup<algebra::ops::Add> + ConditionalSwap + RW> KeyPair<I, P>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    /// A Schnorr signature, see [`SigningSession`].
+    pub fn sign(
+        &self,
+        msg: &[u8],
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Signature<P, I>, Error> {
+        self.guarded(Operation::Sign, None, msg.len(), |key| {
+            Ok(SigningSession::new(key, msg)
+                .commit(cfg, n)
+                .finalize(cfg, n))
+        })
+    }
+
+    /// One ElGamal pair, see [`PrivateKey::decrypt`].
+    pub fn decrypt(&self, pair: (P, P), cfg: &P::Cfg) -> Result<P, Error> {
+        self.guarded(Operation::Decrypt, None, 2 * P::LEN, |key| {
+            Ok(key.decrypt(pair, cfg))
+        })
+    }
+
+    /// The Diffie-Hellman point `x · peer`.
+    pub fn derive(&self, peer: PublicKey<P>, cfg: &P::Cfg) -> Result<P, Error> {
+        self.guarded(Operation::Derive, Some(peer), 0, |key| {
+            Ok(peer.point().exp_ct(key.scalar(), cfg))
+        })
+    }
+}
+
+impl<I: Natural + RW, F: Field + RW> KeyPair<I, Point<F>>
+where
+    [(); F::LEN]:,
+{
+    /// See [`decrypt_file`].
+    pub fn decrypt_file(&self, file: &[u8], cfg: &PointCfg<F>) -> Result<Vec<u8>, Error> {
+        self.guarded(Operation::Decrypt, None, file.len(), |key| {
+            decrypt_file(key, file, cfg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::CommutativeOp,
+        curves::{toy, toy_order},
+        ecc::PrivateKey,
+        encoding_utils::encrypt_file,
+        error::Error,
+        mod_field::ModField,
+        points_group::Point,
+        schnorr::verify,
+    };
+
+    use super::{KeyPair, KeyUsage, KeyUsageObserver, Operation};
+
+    type P = Point<ModField<u64>>;
+
+    #[derive(Default)]
+    struct Log(Mutex<Vec<(bool, Operation, usize)>>);
+
+    impl KeyUsageObserver<P> for Arc<Log> {
+        fn before(&self, usage: &KeyUsage<P>) -> Result<(), String> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((false, usage.operation, usage.len));
+            Ok(())
+        }
+
+        fn after(&self, usage: &KeyUsage<P>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((true, usage.operation, usage.len));
+        }
+    }
+
+    /// Allows `limit` signatures and nothing else.
+    struct SignOnly(AtomicUsize);
+
+    impl KeyUsageObserver<P> for SignOnly {
+        fn before(&self, usage: &KeyUsage<P>) -> Result<(), String> {
+            match usage.operation {
+                Operation::Sign if self.0.load(Ordering::Relaxed) > 0 => {
+                    self.0.fetch_sub(1, Ordering::Relaxed);
+                    Ok(())
+                }
+                Operation::Sign => Err("signature quota used up".to_string()),
+                _ => Err(format!("{:?} not allowed", usage.operation)),
+            }
+        }
+    }
+
+    #[test]
+    fn observed() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([6u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let mut pair = KeyPair::<_, P>::new(PrivateKey::random(&mut rng, &n), &cfg);
+        let log = Arc::new(Log::default());
+        pair.observe(log.clone());
+
+        let sig = pair.sign(b"hello", &cfg, &n).unwrap();
+        assert!(verify(pair.public_key(), b"hello", sig, &cfg, &n));
+        let file = encrypt_file::<_, u128>(pair.public_key(), b"secret", &mut rng, &cfg).unwrap();
+        assert_eq!(pair.decrypt_file(&file, &cfg).unwrap(), b"secret");
+        let peer = PrivateKey::random(&mut rng, &n);
+        let shared = pair.derive(peer.public_key(&cfg), &cfg).unwrap();
+        assert_eq!(
+            shared,
+            P::exp(pair.public_key().point(), peer.scalar(), &cfg)
+        );
+        let log = log.0.lock().unwrap();
+        assert_eq!(
+            *log,
+            [
+                (false, Operation::Sign, 5),
+                (true, Operation::Sign, 5),
+                (false, Operation::Decrypt, file.len()),
+                (true, Operation::Decrypt, file.len()),
+                (false, Operation::Derive, 0),
+                (true, Operation::Derive, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn denied() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([6u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let mut pair = KeyPair::<_, P>::new(PrivateKey::random(&mut rng, &n), &cfg);
+        pair.observe(SignOnly(AtomicUsize::new(1)));
+        assert!(pair.sign(b"once", &cfg, &n).is_ok());
+        assert_eq!(
+            pair.sign(b"twice", &cfg, &n),
+            Err(Error::Denied("signature quota used up".to_string()))
+        );
+        let file = encrypt_file::<_, u128>(pair.public_key(), b"x", &mut rng, &cfg).unwrap();
+        assert_eq!(
+            pair.decrypt_file(&file, &cfg),
+            Err(Error::Denied("Decrypt not allowed".to_string()))
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Versioned key pairs for rotation. Envelopes are `<key id>.<ciphertext>`, so
+//! messages encrypted to a retired-but-kept key still decrypt after a new key
+//! took over.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    ecc::{gen_keys, PrivateKey, PublicKey},
+    encoding_utils::{decode_message_and_decrypt, encrypt_message_and_encode, Compression},
+    error::Error,
+    points_group::{Point, PointCfg},
+};
+
+pub type KeyId = u32;
+
+/// Key pairs by version; the newest one encrypts, all of them decrypt.
+#[derive(Clone, Debug)]
+pub struct KeyRing<I, P> {
+    // ascending ids
+    keys: Vec<(KeyId, PrivateKey<I>, PublicKey<P>)>,
+}
+
+impl<I, P> Default for KeyRing<I, P> {
+    fn default() -> Self {
+        Self { keys: vec![] }
+    }
+}
+
+impl<I: Copy, P: Copy> KeyRing<I, P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key pair under `id`, replacing one with the same id.
+    pub fn insert(&mut self, id: KeyId, pri: PrivateKey<I>, pub_: PublicKey<P>) {
+        match self.keys.binary_search_by_key(&id, |k| k.0) {
+            Ok(i) => self.keys[i] = (id, pri, pub_),
+            Err(i) => self.keys.insert(i, (id, pri, pub_)),
+        }
+    }
+
+    /// The id and public key new messages are encrypted to.
+    pub fn current(&self) -> Option<(KeyId, PublicKey<P>)> {
+        self.keys.last().map(|&(id, _, pub_)| (id, pub_))
+    }
+
+    pub fn private_key(&self, id: KeyId) -> Option<PrivateKey<I>> {
+        self.find(id).map(|k| k.1)
+    }
+
+    pub fn public_key(&self, id: KeyId) -> Option<PublicKey<P>> {
+        self.find(id).map(|k| k.2)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = KeyId> + '_ {
+        self.keys.iter().map(|k| k.0)
+    }
+
+    /// Forgets a key; its messages can't be decrypted any more.
+    pub fn retire(&mut self, id: KeyId) -> bool {
+        let len = self.keys.len();
+        self.keys.retain(|k| k.0 != id);
+        self.keys.len() != len
+    }
+
+    fn find(&self, id: KeyId) -> Option<&(KeyId, PrivateKey<I>, PublicKey<P>)> {
+        self.keys
+            .binary_search_by_key(&id, |k| k.0)
+            .ok()
+            .map(|i| &self.keys[i])
+    }
+}
+
+impl<I, F> KeyRing<I, Point<F>>
+where
+    I: FromRandom<()> + Natural + RW,
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+{
+    /// Generates a new key pair with the next id and makes it current.
+    pub fn rotate(&mut self, rng: &mut impl Rng, cfg: &PointCfg<F>) -> KeyId {
+        let id = self.keys.last().map_or(1, |k| k.0 + 1);
+        let (pri, pub_) = gen_keys(rng, cfg);
+        self.keys.push((id, pri, pub_));
+        id
+    }
+
+    /// Encrypts to the current key and tags the result with its id.
+    pub fn encrypt(
+        &self,
+        msg: &str,
+        compression: Compression,
+        rng: &mut impl Rng,
+        cfg: &PointCfg<F>,
+    ) -> Result<String, Error> {
+        let (id, key) = self.current().ok_or(Error::EmptyKeyRing)?;
+        let ciphertext = encrypt_message_and_encode::<F, I>(key, msg, compression, rng, cfg)?;
+        Ok(format!("{}.{}", id, ciphertext))
+    }
+
+    /// Decrypts an envelope with the key named in it.
+    pub fn decrypt(&self, envelope: &str, cfg: &PointCfg<F>) -> Result<String, Error>
+    where
+        [(); F::LEN]:,
+    {
+        let (id, ciphertext) = envelope.split_once('.').ok_or(Error::Envelope)?;
+        let id = id.parse::<KeyId>().map_err(|_| Error::Envelope)?;
+        let key = self.private_key(id).ok_or(Error::UnknownKey(id))?;
+        decode_message_and_decrypt(key, ciphertext, cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        curves::toy, encoding_utils::Compression, error::Error, mod_field::ModField,
+        points_group::Point,
+    };
+
+    use super::KeyRing;
+
+    type Ring = KeyRing<u128, Point<ModField<u64>>>;
+
+    #[test]
+    fn rotation() {
+        let cfg = toy();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let mut ring = Ring::new();
+        assert_eq!(
+            ring.encrypt("hi", Compression::None, &mut gen, &cfg),
+            Err(Error::EmptyKeyRing)
+        );
+        assert_eq!(ring.rotate(&mut gen, &cfg), 1);
+        let old = ring
+            .encrypt("old", Compression::None, &mut gen, &cfg)
+            .unwrap();
+        assert!(old.starts_with("1."));
+        assert_eq!(ring.rotate(&mut gen, &cfg), 2);
+        let new = ring
+            .encrypt("new", Compression::None, &mut gen, &cfg)
+            .unwrap();
+        assert!(new.starts_with("2."));
+        assert_eq!(ring.decrypt(&old, &cfg), Ok("old".to_string()));
+        assert_eq!(ring.decrypt(&new, &cfg), Ok("new".to_string()));
+
+        assert!(ring.retire(1));
+        assert!(!ring.retire(1));
+        assert_eq!(ring.decrypt(&old, &cfg), Err(Error::UnknownKey(1)));
+        assert_eq!(ring.decrypt(&new, &cfg), Ok("new".to_string()));
+        assert_eq!(ring.ids().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn insert_and_malformed() {
+        let cfg = toy();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let mut other = Ring::new();
+        other.rotate(&mut gen, &cfg);
+        let (id, pub_) = other.current().unwrap();
+
+        let mut ring = Ring::new();
+        ring.insert(7, other.private_key(id).unwrap(), pub_);
+        ring.insert(3, other.private_key(id).unwrap(), pub_);
+        assert_eq!(ring.current(), Some((7, pub_)));
+        assert_eq!(ring.ids().collect::<Vec<_>>(), [3, 7]);
+
+        let envelope = ring
+            .encrypt("x", Compression::None, &mut gen, &cfg)
+            .unwrap();
+        assert_eq!(ring.decrypt(&envelope, &cfg), Ok("x".to_string()));
+        assert_eq!(ring.decrypt("no id", &cfg), Err(Error::Envelope));
+        assert_eq!(ring.decrypt("x.AAAA", &cfg), Err(Error::Envelope));
+        assert_eq!(ring.decrypt("7.not base64!", &cfg), Err(Error::Base64));
+    }
+}
@@ -0,0 +1,70 @@
+#![feature(iter_array_chunks)]
+#![feature(generic_const_exprs)]
+#![allow(incomplete_features)]
+#![feature(cursor_remaining)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+// the derives name the crate from outside, which has to work inside as well
+extern crate self as crypto_test;
+
+pub mod age;
+pub mod algebra;
+pub mod armor;
+pub mod base_traits;
+pub mod batch;
+pub mod bitcoin;
+pub mod bls12_381;
+pub mod bulk;
+pub mod cbor;
+pub mod certificate;
+pub mod commitment;
+pub mod context;
+pub mod cose;
+pub mod csidh;
+pub mod ct_codec;
+pub mod curve_file;
+pub mod curves;
+pub mod cycle;
+pub mod der;
+pub mod dkg;
+pub mod ecc;
+pub mod ecdsa;
+pub mod ecm;
+pub mod encoding_utils;
+pub mod entropy;
+pub mod error;
+pub mod extension;
+pub mod formal_group;
+pub mod identity;
+#[cfg(test)]
+mod interop;
+pub mod kat;
+pub mod kem;
+pub mod key_pair;
+pub mod keyring;
+pub mod mac;
+pub mod mod_field;
+pub mod models;
+pub mod noise;
+pub mod openpgp;
+pub mod pairing;
+pub mod points_group;
+pub mod projective;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod recoding;
+pub mod rsa;
+pub mod schnorr;
+pub mod sealed;
+pub mod secret_scalar;
+pub mod security;
+pub mod signer;
+pub mod simple;
+pub mod soft_token;
+#[cfg(feature = "timing-tests")]
+pub mod timing;
+pub mod uint;
+pub mod x509;
+pub mod x_only;
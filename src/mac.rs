@@ -0,0 +1,91 @@
+//! HMAC-SHA256 ([RFC 2104](https://www.rfc-editor.org/rfc/rfc2104)), for the
+//! places that key a tag or derive keys from a shared secret.
+
+use sha2::{Digest, Sha256};
+
+/// SHA-256's block size, which the key is padded or hashed to.
+const BLOCK_LEN: usize = 64;
+
+/// Bytes of an HMAC-SHA256 output.
+pub const MAC_LEN: usize = 32;
+
+/// HMAC-SHA256 of the concatenation of `parts` under `key`. Keys longer than a
+/// block are hashed first, as the RFC says.
+pub fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; MAC_LEN] {
+    let hashed;
+    let key = if key.len() > BLOCK_LEN {
+        hashed = Sha256::digest(key);
+        &hashed[..]
+    } else {
+        key
+    };
+    let pad = |byte: u8| {
+        let mut block = [byte; BLOCK_LEN];
+        block.iter_mut().zip(key).for_each(|(b, k)| *b ^= k);
+        block
+    };
+    let mut inner = Sha256::new().chain_update(pad(0x36));
+    for part in parts {
+        inner.update(part);
+    }
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner.finalize())
+        .finalize()
+        .into()
+}
+
+/// HKDF ([RFC 5869](https://www.rfc-editor.org/rfc/rfc5869)) over
+/// [`hmac`], with the first two blocks of output: a `salt`-keyed extract of
+/// `ikm`, expanded with `info`.
+pub fn hkdf(salt: &[u8], ikm: &[u8], info: &[u8]) -> ([u8; MAC_LEN], [u8; MAC_LEN]) {
+    let prk = hmac(salt, &[ikm]);
+    let first = hmac(&prk, &[info, &[1]]);
+    let second = hmac(&prk, &[&first, info, &[2]]);
+    (first, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hkdf, hmac};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn rfc_4231() {
+        let mac = hmac(b"Jefe", &[b"what do ya ", b"want for nothing?"]);
+        assert_eq!(
+            hex(&mac),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // the key padded with zeros is the same key
+        let mut padded = [0; 32];
+        padded[..4].copy_from_slice(b"Jefe");
+        assert_eq!(hmac(&padded, &[b"what do ya want for nothing?"]), mac);
+
+        let long_key = [0xaa; 131];
+        let mac = hmac(
+            &long_key,
+            &[b"Test Using Larger Than Block-Size Key - Hash Key First"],
+        );
+        assert_eq!(
+            hex(&mac),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn rfc_5869() {
+        let salt = (0..=0x0c).collect::<Vec<u8>>();
+        let info = (0xf0..=0xf9).collect::<Vec<u8>>();
+        let (first, second) = hkdf(&salt, &[0x0b; 22], &info);
+        assert_eq!(
+            hex(&first),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf"
+        );
+        // the test's output is 42 bytes long
+        assert_eq!(hex(&second[..10]), "34007208d5b887185865");
+    }
+}
@@ -1,106 +1,838 @@
-#![feature(iter_array_chunks)]
 #![feature(generic_const_exprs)]
 #![allow(incomplete_features)]
-#![feature(cursor_remaining)]
-use base_traits::{FromRandom, Natural, RW};
-use clap::{Arg, Command};
-use ecc::{gen_keys, PrivateKey, PublicKey};
-use encoding_utils::{decode_message_and_decrypt, encrypt_message_and_encode};
-use mod_field::{ModField, ModFieldCfg};
-use points_group::{Point, PointCfg};
-use primitive_types::U256;
+use clap::{value_parser, Arg, ArgAction, Command};
+#[cfg(feature = "qr")]
+use crypto_test::qr;
+use crypto_test::{
+    armor,
+    base_traits::{FromRandom, Natural, RW},
+    bitcoin::Network,
+    ct_codec,
+    curve_file::Curve,
+    curves::{secp256k1, secp256k1_order, toy},
+    ecc::{self, gen_keys, Ciphertext, PrivateKey, PublicKey},
+    encoding_utils::{decrypt_file, decrypt_message, encrypt_file, encrypt_message},
+    entropy::SystemEntropy,
+    error::Error,
+    identity::{self, Identity},
+    kat,
+    mod_field::ModField,
+    points_group::{Point, PointCfg, ValidationPolicy},
+    security::curve_security_report,
+    uint::U256,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
 use rand::Rng;
 
-mod algebra;
-mod base_traits;
-mod ecc;
-mod encoding_utils;
-mod mod_field;
-mod points_group;
+mod config;
 
 type DatatypeScalar = U256;
 type DatatypeShort = U256;
 
-// https://en.bitcoin.it/wiki/Secp256k1
-fn secp256k1() -> PointCfg<ModField<DatatypeShort>> {
-    let cfg_field = ModFieldCfg {
-        rem: U256::from_big_endian(&[
-            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
-            0xFF, 0xFF, 0xFC, 0x2F,
-        ]),
-    };
-    let gx = U256::from_big_endian(&[
-        0x79, 0xBE, 0x66, 0x7E, 0xF9, 0xDC, 0xBB, 0xAC, 0x55, 0xA0, 0x62, 0x95, 0xCE, 0x87, 0x0B,
-        0x07, 0x02, 0x9B, 0xFC, 0xDB, 0x2D, 0xCE, 0x28, 0xD9, 0x59, 0xF2, 0x81, 0x5B, 0x16, 0xF8,
-        0x17, 0x98,
-    ]);
-    let gy = U256::from_big_endian(&[
-        0x48, 0x3A, 0xDA, 0x77, 0x26, 0xA3, 0xC4, 0x65, 0x5D, 0xA4, 0xFB, 0xFC, 0x0E, 0x11, 0x08,
-        0xA8, 0xFD, 0x17, 0xB4, 0x48, 0xA6, 0x85, 0x54, 0x19, 0x9C, 0x47, 0xD0, 0x8F, 0xFB, 0x10,
-        0xD4, 0xB8,
-    ]);
-    let cfg_group = PointCfg {
-        g: Point::new_unsafe(ModField::new(gx, &cfg_field), ModField::new(gy, &cfg_field)),
-        a: ModField::new(U256::from(0), &cfg_field),
-        b: ModField::new(U256::from(7), &cfg_field),
-        cf: cfg_field,
-    };
+/// Curve id written into identity files.
+const CURVE_ID: &str = "secp256k1";
 
-    assert_eq!(size_of::<DatatypeScalar>(), size_of::<U256>());
-    assert_eq!(size_of::<DatatypeShort>(), size_of::<U256>());
+/// Seed of the published vectors; changing it changes every vector.
+const KAT_SEED: [u8; 32] = [0; 32];
 
-    cfg_group
-}
+type CliPrivateKey = PrivateKey<DatatypeScalar>;
+type CliPublicKey = PublicKey<Point<ModField<DatatypeShort>>>;
+type CliCiphertext = Ciphertext<Point<ModField<DatatypeShort>>>;
+type CliIdentity = Identity<DatatypeScalar, Point<ModField<DatatypeShort>>>;
 
 fn main() {
     let cfg_group = secp256k1();
 
-    let matches = Command::new("xxx")
-        .subcommand(Command::new("genkey").about("Generate a pair of keys"))
+    let cmd = Command::new("xxx")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print results as a JSON object"),
+        )
+        .subcommand(
+            Command::new("genkey")
+                .about("Generate a pair of keys")
+                .arg(armor_arg()),
+        )
         .subcommand(
             Command::new("encrypt")
                 .about("Encrypt a message")
-                .arg(Arg::new("pubkey").required(true).help("base64 public key"))
-                .arg(Arg::new("msg").required(true).help("Message to encrypt")),
+                .arg(Arg::new("pubkey").allow_hyphen_values(true).help(
+                    "base64 or ASCII-armored public key; the config's public_key if left out",
+                ))
+                .arg(Arg::new("msg").help("Message to encrypt"))
+                .arg(armor_arg())
+                .arg(recursive_arg("Encrypt every file under this directory"))
+                .arg(out_dir_arg()),
         )
         .subcommand(
             Command::new("decrypt")
                 .about("Decrypt a message")
-                .arg(Arg::new("prikey").required(true).help("base64 private key"))
-                .arg(Arg::new("msg").required(true).help("Message to decrypt")),
+                .arg(Arg::new("prikey").allow_hyphen_values(true).help(
+                    "base64 or ASCII-armored private key; the config's private_key if left out",
+                ))
+                .arg(
+                    Arg::new("msg")
+                        .allow_hyphen_values(true)
+                        .help("Message to decrypt, base64 or ASCII-armored"),
+                )
+                .arg(recursive_arg("Decrypt every file under this directory"))
+                .arg(out_dir_arg())
+                .arg(
+                    Arg::new("identity")
+                        .long("identity")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Identity file to take the private key from"),
+                )
+                .arg(
+                    Arg::new("allow-expired")
+                        .long("allow-expired")
+                        .action(ArgAction::SetTrue)
+                        .requires("identity")
+                        .help("Use the identity even outside its validity period"),
+                ),
         )
-        .get_matches();
+        .subcommand(
+            Command::new("identity")
+                .about("Manage identity files, a key pair with its curve and a comment")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("new")
+                        .about("Generate an identity")
+                        .arg(identity_file_arg())
+                        .arg(
+                            Arg::new("comment")
+                                .long("comment")
+                                .help("Free text stored with the keys"),
+                        )
+                        .arg(
+                            Arg::new("not-before")
+                                .long("not-before")
+                                .value_name("UNIX_SECONDS")
+                                .value_parser(value_parser!(u64))
+                                .help("When the keys become valid"),
+                        )
+                        .arg(
+                            Arg::new("not-after")
+                                .long("not-after")
+                                .value_name("UNIX_SECONDS")
+                                .value_parser(value_parser!(u64))
+                                .help("When the keys expire"),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .action(ArgAction::SetTrue)
+                                .help("Overwrite an existing file"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("rotate")
+                        .about("Replace the key pair, keeping the old file with .old appended")
+                        .arg(identity_file_arg()),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the public key and details")
+                        .arg(identity_file_arg()),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Manage the config file")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Write a commented default config")
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .action(ArgAction::SetTrue)
+                                .help("Overwrite an existing config"),
+                        ),
+                )
+                .subcommand(Command::new("path").about("Print where the config is read from")),
+        )
+        .subcommand(
+            Command::new("wif")
+                .about("Export a private key in Bitcoin's WIF")
+                .arg(prikey_arg())
+                .arg(testnet_arg()),
+        )
+        .subcommand(
+            Command::new("from-wif")
+                .about("Import a private key from Bitcoin's WIF")
+                .arg(Arg::new("wif").required(true).help("WIF private key")),
+        )
+        .subcommand(
+            Command::new("address")
+                .about("Derive P2PKH and P2WPKH addresses of a public key")
+                .arg(pubkey_arg())
+                .arg(testnet_arg()),
+        )
+        .subcommand(
+            Command::new("vectors")
+                .about("Print known-answer test vectors as JSON")
+                .arg(
+                    Arg::new("curve")
+                        .long("curve")
+                        .value_parser(["toy", "secp256k1", "all"])
+                        .help("Defaults to the config's curve, else all"),
+                )
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .value_parser(value_parser!(usize))
+                        .default_value("4")
+                        .help("Vectors per curve"),
+                ),
+        )
+        .subcommand(
+            Command::new("curve-info")
+                .about("Estimate the security of a curve")
+                .arg(
+                    Arg::new("file")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("TOML or JSON curve document with an order; secp256k1 if left out"),
+                ),
+        )
+        .subcommand(
+            Command::new("self-test")
+                .about("Check that a curve is set up correctly")
+                .arg(
+                    Arg::new("file")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("TOML or JSON curve document; secp256k1 if left out"),
+                ),
+        );
+    #[cfg(feature = "qr")]
+    let cmd = cmd.subcommand(
+        Command::new("qr")
+            .about("Render a key or ciphertext as a QR code")
+            .arg(
+                Arg::new("data")
+                    .required(true)
+                    .help("base64 key or ciphertext"),
+            )
+            .arg(
+                Arg::new("format")
+                    .long("format")
+                    .value_parser(["png", "svg", "term"])
+                    .default_value("term"),
+            )
+            .arg(
+                Arg::new("output")
+                    .long("output")
+                    .help("File to write to instead of stdout"),
+            ),
+    );
+    let matches = cmd.get_matches();
+    let config = config::load().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1)
+    });
+    let armor = |args: &clap::ArgMatches| {
+        args.get_flag("armor") || config.format == Some(config::Format::Armor)
+    };
 
-    let mut rng = rand::thread_rng();
+    let json = matches.get_flag("json");
+    let mut rng = or_exit(SystemEntropy::new());
 
     match matches.subcommand() {
-        Some(("genkey", _)) => {
+        Some(("genkey", args)) => {
             let (pr, pb) = cli_genkeys::<DatatypeScalar, DatatypeShort>(&mut rng, &cfg_group);
-            println!("PRIVATE: {}", pr);
-            println!("PUBLIC: {}", pb);
+            let (pr, pb) = if armor(args) {
+                (
+                    armored(&pr, armor::PRIVATE_KEY),
+                    armored(&pb, armor::PUBLIC_KEY),
+                )
+            } else {
+                (pr, pb)
+            };
+            if json {
+                println!("{}", json_object(&[("private", &pr), ("public", &pb)]));
+            } else if armor(args) {
+                print!("{}{}", pr, pb);
+            } else {
+                println!("PRIVATE: {}", pr);
+                println!("PUBLIC: {}", pb);
+            }
+        }
+        Some(("encrypt", args)) if args.contains_id("recursive") => {
+            let key = key_or_config(args, "pubkey", config.public_key.as_deref());
+            let key = or_exit(parse_pubkey(&key));
+            let (src, dst) = recursive_dirs(args);
+            let count = process_tree(
+                src,
+                dst,
+                |path| {
+                    let mut name = path.as_os_str().to_owned();
+                    name.push(".ecc");
+                    Some(name.into())
+                },
+                |data| encrypt_file::<_, DatatypeScalar>(key, data, &mut { rng }, &cfg_group),
+            );
+            print_tree_result(json, "encrypted", count, dst);
+        }
+        Some(("decrypt", args)) if args.contains_id("recursive") => {
+            let key = key_or_config(args, "prikey", config.private_key.as_deref());
+            let key = or_exit(parse_prikey(&key));
+            let (src, dst) = recursive_dirs(args);
+            let count = process_tree(
+                src,
+                dst,
+                |path| (path.extension()? == "ecc").then(|| path.with_extension("")),
+                |data| decrypt_file(key, data, &cfg_group),
+            );
+            print_tree_result(json, "decrypted", count, dst);
         }
         Some(("encrypt", args)) => {
-            let enc = cli_encrypt(
+            let (key, msg) = key_and_msg(args, "pubkey", config.public_key.as_deref());
+            let enc = or_exit(cli_encrypt(
                 &mut rng,
-                args.get_one::<String>("pubkey").unwrap(),
-                args.get_one::<String>("msg").unwrap(),
+                or_exit(parse_pubkey(&key)),
+                &msg,
                 &cfg_group,
-            );
-            println!("{}", enc);
+            ));
+            let enc = if armor(args) {
+                armored(&enc, armor::MESSAGE)
+            } else {
+                enc
+            };
+            if json {
+                println!("{}", json_object(&[("ciphertext", &enc)]));
+            } else if armor(args) {
+                print!("{}", enc);
+            } else {
+                println!("{}", enc);
+            }
         }
         Some(("decrypt", args)) => {
-            let dec = cli_decrypt::<DatatypeScalar, DatatypeShort>(
-                args.get_one::<String>("prikey").unwrap(),
-                args.get_one::<String>("msg").unwrap(),
-                &cfg_group,
+            let (key, msg) = match args.get_one::<PathBuf>("identity") {
+                // the key's position holds the message then
+                Some(path) => match args.get_one::<String>("prikey") {
+                    Some(msg) => {
+                        let id = load_identity(path);
+                        if !args.get_flag("allow-expired") {
+                            or_exit(id.check_validity());
+                        }
+                        (id.private, msg.clone())
+                    }
+                    None => {
+                        eprintln!("error: expected <msg>");
+                        std::process::exit(1)
+                    }
+                },
+                None => {
+                    let (key, msg) = key_and_msg(args, "prikey", config.private_key.as_deref());
+                    (or_exit(parse_prikey(&key)), msg)
+                }
+            };
+            // the key multiplies these points, so off-curve ones are refused
+            let msg = or_exit(
+                base64_or_armor(&msg, armor::MESSAGE)
+                    .and_then(|m| ct_codec::base64_decode(&m))
+                    .and_then(|m| {
+                        CliCiphertext::from_raw(&m, ValidationPolicy::<u8>::OnCurve, &cfg_group)
+                    }),
+            );
+            let dec = match cli_decrypt(key, &msg, &cfg_group) {
+                // not text, but what was sent
+                Err(Error::Utf8(bytes)) if !json => {
+                    std::io::Write::write_all(&mut std::io::stdout(), &bytes).unwrap();
+                    return;
+                }
+                dec => or_exit(dec),
+            };
+            if json {
+                println!("{}", json_object(&[("message", &dec)]));
+            } else {
+                println!("{}", dec);
+            }
+        }
+        Some(("identity", args)) => {
+            let (action, args) = args.subcommand().unwrap();
+            let path = args.get_one::<PathBuf>("file").unwrap();
+            let id = match action {
+                "new" => {
+                    if path.exists() && !args.get_flag("force") {
+                        eprintln!(
+                            "error: {} exists, pass --force to overwrite",
+                            path.display()
+                        );
+                        std::process::exit(1)
+                    }
+                    let comment = args.get_one::<String>("comment").map(String::as_str);
+                    let id = CliIdentity {
+                        not_before: args.get_one::<u64>("not-before").copied(),
+                        not_after: args.get_one::<u64>("not-after").copied(),
+                        ..CliIdentity::generate(
+                            CURVE_ID,
+                            comment,
+                            &mut rng,
+                            &cfg_group,
+                            &secp256k1_order(),
+                        )
+                    };
+                    or_exit(id.save(path));
+                    id
+                }
+                "rotate" => or_exit(identity::rotate_file(
+                    path,
+                    CURVE_ID,
+                    &mut rng,
+                    &cfg_group,
+                    &secp256k1_order(),
+                )),
+                _ => load_identity(path),
+            };
+            let (public, created) = (id.public.to_string(), id.created.to_string());
+            let time = |t: Option<u64>| t.map(|t| t.to_string()).unwrap_or_default();
+            let (not_before, not_after) = (time(id.not_before), time(id.not_after));
+            let status = match id.check_validity() {
+                Ok(()) => "valid".to_string(),
+                Err(e) => e.to_string(),
+            };
+            let comment = id.comment.unwrap_or_default();
+            if json {
+                let fields = [
+                    ("public", public.as_str()),
+                    ("curve", &id.curve),
+                    ("created", &created),
+                    ("not_before", &not_before),
+                    ("not_after", &not_after),
+                    ("status", &status),
+                    ("comment", &comment),
+                ];
+                println!("{}", json_object(&fields));
+            } else {
+                println!("PUBLIC: {}", public);
+                println!("CURVE: {}", id.curve);
+                println!("CREATED: {}", created);
+                if !not_before.is_empty() {
+                    println!("NOT BEFORE: {}", not_before);
+                }
+                if !not_after.is_empty() {
+                    println!("NOT AFTER: {}", not_after);
+                }
+                println!("STATUS: {}", status);
+                if !comment.is_empty() {
+                    println!("COMMENT: {}", comment);
+                }
+            }
+        }
+        Some(("config", args)) => match args.subcommand() {
+            Some(("init", args)) => match config::init(args.get_flag("force")) {
+                Ok(path) if json => {
+                    println!("{}", json_object(&[("path", &path.to_string_lossy())]))
+                }
+                Ok(path) => println!("wrote {}", path.display()),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1)
+                }
+            },
+            Some(("path", _)) => match config::path() {
+                Some(path) if json => {
+                    println!("{}", json_object(&[("path", &path.to_string_lossy())]))
+                }
+                None if json => println!(r#"{{"path": null}}"#),
+                Some(path) => println!("{}", path.display()),
+                None => println!("no config location, set ECC_CONFIG or HOME"),
+            },
+            _ => unreachable!(),
+        },
+        Some(("wif", args)) => {
+            let pr = *args.get_one::<CliPrivateKey>("prikey").unwrap();
+            let wif = pr.wif(network(args));
+            if json {
+                println!("{}", json_object(&[("wif", &wif)]));
+            } else {
+                println!("{}", wif);
+            }
+        }
+        Some(("from-wif", args)) => {
+            let (pr, net) = or_exit(
+                CliPrivateKey::from_wif(args.get_one::<String>("wif").unwrap())
+                    .ok_or("invalid WIF"),
             );
-            println!("{}", dec);
+            if json {
+                let net = format!("{:?}", net).to_lowercase();
+                println!(
+                    "{}",
+                    json_object(&[("private", &pr.to_string()), ("network", &net)])
+                );
+            } else {
+                println!("PRIVATE: {}", pr);
+                println!("NETWORK: {:?}", net);
+            }
+        }
+        Some(("address", args)) => {
+            let pb = *args.get_one::<CliPublicKey>("pubkey").unwrap();
+            let p2pkh = pb.p2pkh_address(network(args));
+            let p2wpkh = pb.p2wpkh_address(network(args));
+            if json {
+                println!("{}", json_object(&[("p2pkh", &p2pkh), ("p2wpkh", &p2wpkh)]));
+            } else {
+                println!("P2PKH: {}", p2pkh);
+                println!("P2WPKH: {}", p2wpkh);
+            }
+        }
+        Some(("vectors", args)) => {
+            let curve = args
+                .get_one::<String>("curve")
+                .or(config.curve.as_ref())
+                .map_or("all", |c| c.as_str());
+            let count = *args.get_one::<usize>("count").unwrap();
+            let mut curves = vec![];
+            if curve != "secp256k1" {
+                curves.push(or_exit(kat::curve_vectors::<_, u128>(
+                    "toy",
+                    &toy(),
+                    count,
+                    KAT_SEED,
+                )));
+            }
+            if curve != "toy" {
+                curves.push(or_exit(kat::curve_vectors::<_, DatatypeScalar>(
+                    "secp256k1",
+                    &cfg_group,
+                    count,
+                    KAT_SEED,
+                )));
+            }
+            println!("[{}]", curves.join(",\n"));
+        }
+        Some(("curve-info", args)) => {
+            let curve = match args.get_one::<PathBuf>("file") {
+                Some(path) => Curve::<DatatypeShort>::load(path),
+                None => Ok(Curve {
+                    cfg: secp256k1(),
+                    order: Some(secp256k1_order().rem),
+                    cofactor: Some(U256::from(1u64)),
+                }),
+            };
+            let report = curve
+                .and_then(|c| curve_security_report(&c, &mut rng))
+                .unwrap_or_else(|e| {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1)
+                });
+            if json {
+                let opt = |v: Option<String>| v.unwrap_or_else(|| "null".to_string());
+                let warnings = report
+                    .warnings
+                    .iter()
+                    .map(|w| json_string(w))
+                    .collect::<Vec<_>>();
+                println!(
+                    r#"{{"rho_bits": {:.1}, "embedding_degree": {}, "anomalous": {}, "twist_rho_bits": {}, "warnings": [{}]}}"#,
+                    report.rho_bits,
+                    opt(report.embedding_degree.map(|k| k.to_string())),
+                    report.anomalous,
+                    opt(report.twist_rho_bits.map(|b| format!("{:.1}", b))),
+                    warnings.join(", ")
+                );
+            } else {
+                print!("{}", report);
+            }
+        }
+        Some(("self-test", args)) => {
+            let curve = match args.get_one::<PathBuf>("file") {
+                Some(path) => or_exit(Curve::<DatatypeShort>::load(path)),
+                None => Curve {
+                    cfg: secp256k1(),
+                    order: Some(secp256k1_order().rem),
+                    cofactor: Some(U256::from(1u64)),
+                },
+            };
+            let result = ecc::self_test::<_, DatatypeScalar, _>(&curve.cfg, curve.order, &mut rng);
+            match (result, json) {
+                (Ok(()), true) => println!(r#"{{"ok": true}}"#),
+                (Ok(()), false) => println!("OK"),
+                (Err(e), true) => {
+                    println!(
+                        r#"{{"ok": false, "error": {}}}"#,
+                        json_string(&e.to_string())
+                    );
+                    std::process::exit(1)
+                }
+                (Err(e), false) => {
+                    eprintln!("error: {}", e);
+                    std::process::exit(1)
+                }
+            }
+        }
+        #[cfg(feature = "qr")]
+        Some(("qr", args)) => {
+            let format = match args.get_one::<String>("format").unwrap().as_str() {
+                "png" => qr::QrFormat::Png,
+                "svg" => qr::QrFormat::Svg,
+                _ => qr::QrFormat::Terminal,
+            };
+            let rendered = qr::render(args.get_one::<String>("data").unwrap(), format)
+                .expect("data doesn't fit into a QR code");
+            match args.get_one::<String>("output") {
+                Some(path) => std::fs::write(path, rendered).unwrap(),
+                None => std::io::Write::write_all(&mut std::io::stdout(), &rendered).unwrap(),
+            }
         }
         _ => panic!(),
     }
 }
 
+fn or_exit<T, E: std::fmt::Display>(res: Result<T, E>) -> T {
+    res.unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1)
+    })
+}
+
+/// Pasted input may be ASCII-armored instead of plain base64.
+fn base64_or_armor(s: &str, label: &str) -> Result<String, Error> {
+    if !s.trim_start().starts_with("-----BEGIN ") {
+        return Ok(s.to_string());
+    }
+    match armor::dearmor(s)? {
+        (found, data) if found == label => Ok(ct_codec::base64_encode(&data)),
+        _ => Err(Error::Armor),
+    }
+}
+
+/// `{"key": "value", ...}` for `--json`.
+fn json_object(fields: &[(&str, &str)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(k, v)| format!("{}: {}", json_string(k), json_string(v)))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn json_string(s: &str) -> String {
+    let mut res = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn armored(base64: &str, label: &str) -> String {
+    armor::armor(label, &ct_codec::base64_decode(base64).unwrap())
+}
+
+fn parse_pubkey(s: &str) -> Result<CliPublicKey, Error> {
+    base64_or_armor(s.trim(), armor::PUBLIC_KEY)?.parse()
+}
+
+fn load_identity(path: &Path) -> CliIdentity {
+    or_exit(Identity::load(
+        path,
+        CURVE_ID,
+        &secp256k1(),
+        &secp256k1_order(),
+    ))
+}
+
+fn parse_prikey(s: &str) -> Result<CliPrivateKey, Error> {
+    CliPrivateKey::from_base64_checked(
+        &base64_or_armor(s.trim(), armor::PRIVATE_KEY)?,
+        &secp256k1_order(),
+    )
+}
+
+/// `<key> <msg>`, or just `<msg>` with the key read from the configured file.
+fn key_and_msg(args: &clap::ArgMatches, key: &str, key_file: Option<&Path>) -> (String, String) {
+    let first = args.get_one::<String>(key).cloned();
+    match (first, args.get_one::<String>("msg").cloned()) {
+        (Some(key), Some(msg)) => (key, msg),
+        (Some(msg), None) => (read_key_file(key, key_file), msg),
+        _ => {
+            eprintln!(
+                "error: expected <{}> <msg>, or a key file in the config",
+                key
+            );
+            std::process::exit(1)
+        }
+    }
+}
+
+/// The key given on the command line, else the configured key file.
+fn key_or_config(args: &clap::ArgMatches, key: &str, key_file: Option<&Path>) -> String {
+    match args.get_one::<String>(key) {
+        Some(key) => key.clone(),
+        None => read_key_file(key, key_file),
+    }
+}
+
+fn read_key_file(key: &str, key_file: Option<&Path>) -> String {
+    let Some(path) = key_file else {
+        eprintln!("error: no <{}> given and no key file in the config", key);
+        std::process::exit(1)
+    };
+    std::fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("error: {}: {}", path.display(), e);
+        std::process::exit(1)
+    })
+}
+
+fn recursive_dirs(args: &clap::ArgMatches) -> (&Path, &Path) {
+    let src = args.get_one::<PathBuf>("recursive").unwrap();
+    let dst = args.get_one::<PathBuf>("out-dir").unwrap();
+    (src, dst)
+}
+
+/// Paths of all files under `dir`, relative to `root`.
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, files)?;
+        } else {
+            files.push(path.strip_prefix(root).unwrap().to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Runs `f` on every file under `src` whose name `rename` maps to a new one,
+/// and writes the result to that name under `dst`, mirroring the tree. Files
+/// are spread over a thread per core; failures are reported and skipped, and
+/// make the process exit with an error at the end. Returns the files written.
+fn process_tree(
+    src: &Path,
+    dst: &Path,
+    rename: impl Fn(&Path) -> Option<PathBuf> + Sync,
+    f: impl Fn(&[u8]) -> Result<Vec<u8>, Error> + Sync,
+) -> usize {
+    let mut files = vec![];
+    if let Err(e) = walk(src, src, &mut files) {
+        eprintln!("error: {}: {}", src.display(), e);
+        std::process::exit(1)
+    }
+    let files = files
+        .iter()
+        .filter_map(|path| Some((path, rename(path)?)))
+        .collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let process = |from: &Path, to: &Path| -> Result<(), String> {
+        let data = std::fs::read(src.join(from)).map_err(|e| e.to_string())?;
+        let out = f(&data).map_err(|e| e.to_string())?;
+        let to = dst.join(to);
+        std::fs::create_dir_all(to.parent().unwrap()).map_err(|e| e.to_string())?;
+        std::fs::write(to, out).map_err(|e| e.to_string())
+    };
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    std::thread::scope(|s| {
+        for _ in 0..threads.min(files.len()) {
+            s.spawn(|| {
+                while let Some((from, to)) = files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(e) = process(from, to) {
+                        eprintln!("error: {}: {}", src.join(from).display(), e);
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    if failed.into_inner() {
+        std::process::exit(1)
+    }
+    files.len()
+}
+
+fn print_tree_result(json: bool, action: &str, count: usize, dst: &Path) {
+    if json {
+        let count = count.to_string();
+        println!(
+            "{}",
+            json_object(&[("files", &count), ("out_dir", &dst.to_string_lossy())])
+        );
+    } else {
+        println!("{} {} files into {}", action, count, dst.display());
+    }
+}
+
+fn pubkey_arg() -> Arg {
+    Arg::new("pubkey")
+        .required(true)
+        .allow_hyphen_values(true)
+        .value_parser(parse_pubkey)
+        .help("base64 or ASCII-armored public key")
+}
+
+fn prikey_arg() -> Arg {
+    Arg::new("prikey")
+        .required(true)
+        .allow_hyphen_values(true)
+        .value_parser(parse_prikey)
+        .help("base64 or ASCII-armored private key")
+}
+
+fn identity_file_arg() -> Arg {
+    Arg::new("file")
+        .required(true)
+        .value_parser(value_parser!(PathBuf))
+        .help("Identity file")
+}
+
+fn armor_arg() -> Arg {
+    Arg::new("armor")
+        .long("armor")
+        .action(ArgAction::SetTrue)
+        .help("Print ASCII armor instead of bare base64")
+}
+
+fn recursive_arg(help: &'static str) -> Arg {
+    Arg::new("recursive")
+        .long("recursive")
+        .value_name("DIR")
+        .value_parser(value_parser!(PathBuf))
+        .requires("out-dir")
+        .conflicts_with("msg")
+        .help(help)
+}
+
+fn out_dir_arg() -> Arg {
+    Arg::new("out-dir")
+        .long("out-dir")
+        .value_name("DIR")
+        .value_parser(value_parser!(PathBuf))
+        .requires("recursive")
+        .help("Where --recursive mirrors the tree to")
+}
+
+fn testnet_arg() -> Arg {
+    Arg::new("testnet")
+        .long("testnet")
+        .action(ArgAction::SetTrue)
+        .help("Use testnet prefixes")
+}
+
+fn network(args: &clap::ArgMatches) -> Network {
+    if args.get_flag("testnet") {
+        Network::Testnet
+    } else {
+        Network::Mainnet
+    }
+}
+
 fn cli_genkeys<IP: Natural + FromRandom<()> + RW, I: Natural + RW>(
     rng: &mut impl Rng,
     cfg: &PointCfg<ModField<I>>,
@@ -111,41 +843,38 @@ fn cli_genkeys<IP: Natural + FromRandom<()> + RW, I: Natural + RW>(
 
 fn cli_encrypt<I: Natural + RW + FromRandom<()>>(
     rng: &mut impl Rng,
-    pubkey: &str,
+    pubkey: PublicKey<Point<ModField<I>>>,
     msg: &str,
     cfg: &PointCfg<ModField<I>>,
-) -> String
+) -> Result<String, Error>
 where
-    [(); ModField::<I>::LEN - 1]:,
     [(); Point::<ModField<I>>::LEN]:,
 {
-    let pb = PublicKey::from_base64(pubkey);
-    encrypt_message_and_encode::<ModField<I>, I>(pb, msg, rng, cfg)
+    encrypt_message::<ModField<I>, I>(pubkey, msg, rng, cfg).map(|c| c.to_string())
 }
 
-fn cli_decrypt<IP: Natural + FromRandom<()> + RW, I: Natural + RW + FromRandom<()>>(
-    prikey: &str,
-    msg_base64: &str,
+fn cli_decrypt<IP: Natural + RW, I: Natural + RW>(
+    prikey: PrivateKey<IP>,
+    msg: &Ciphertext<Point<ModField<I>>>,
     cfg: &PointCfg<ModField<I>>,
-) -> String
+) -> Result<String, Error>
 where
-    [(); Point::<ModField<I>>::LEN]:,
     [(); ModField::<I>::LEN]:,
 {
-    let pr = PrivateKey::<IP>::from_base64(prikey);
-    decode_message_and_decrypt::<IP, ModField<I>>(pr, msg_base64, cfg)
+    decrypt_message(prikey, msg, cfg)
 }
 
 #[cfg(test)]
 mod tests {
     use rand::SeedableRng;
 
-    use crate::{
-        cli_decrypt, cli_encrypt, cli_genkeys,
+    use crypto_test::{
         mod_field::{ModField, ModFieldCfg},
         points_group::{Point, PointCfg},
     };
 
+    use crate::{cli_decrypt, cli_encrypt, cli_genkeys, json_object};
+
     #[test]
     fn full() {
         let cfg_field = ModFieldCfg {
@@ -167,9 +896,18 @@ mod tests {
         let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
         for _ in 0..100 {
             let (pr, pb) = cli_genkeys::<u128, u64>(&mut gen, &cfg_group);
-            let enc = cli_encrypt(&mut gen, &pb, text, &cfg_group);
-            let dec = cli_decrypt::<u128, u64>(&pr, &enc, &cfg_group);
-            assert_eq!(dec, text);
+            let enc = cli_encrypt(&mut gen, pb.parse().unwrap(), text, &cfg_group).unwrap();
+            let dec =
+                cli_decrypt::<u128, u64>(pr.parse().unwrap(), &enc.parse().unwrap(), &cfg_group);
+            assert_eq!(dec.as_deref(), Ok(text));
         }
     }
+
+    #[test]
+    fn json_escaping() {
+        assert_eq!(
+            json_object(&[("message", "say \"hi\"\\\n\u{1}ü"), ("k", "")]),
+            r#"{"message": "say \"hi\"\\\n\u0001ü", "k": ""}"#
+        );
+    }
 }
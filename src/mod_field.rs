@@ -4,13 +4,14 @@ use rand::Rng;
 
 use crate::{
     algebra::{
-        self, AbelianGroup, CommutativeMonoid, CommutativeOp, Configurable, DiscreteRoot, Field,
-        Identity, Inverse, InverseNonZero,
+        self, AbelianGroup, CommutativeMonoid, CommutativeOp, ConditionalSwap, Configurable,
+        DiscreteRoot, Field, Identity, Inverse, InverseNonZero, Ring,
     },
-    base_traits::{Capacitor, FromRandom, Natural, RW},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    error::Error,
 };
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ModFieldCfg<I> {
     pub rem: I,
 }
@@ -24,7 +25,7 @@ impl<I: Natural> Configurable for ModField<I> {
     type Cfg = ModFieldCfg<I>;
 }
 
-fn gcd<S>(a: S, b: S) -> S
+pub fn gcd<S>(a: S, b: S) -> S
 where
     S: Natural,
 {
@@ -44,28 +45,38 @@ where
     }
 }
 
+/// Elements are reduced, so one at or above the modulus comes from a bigger
+/// field; debug builds refuse to mix them.
+fn debug_check<I: Natural>(a: ModField<I>, b: ModField<I>, c: &ModFieldCfg<I>) {
+    debug_assert!(
+        a.val < c.rem && b.val < c.rem,
+        "element not below the modulus, is it from another field?"
+    );
+}
+
+/// Addition and negation run the same instructions whatever the operands,
+/// as long as `I`'s carries and selection do: the sum is reduced by always
+/// subtracting the modulus and keeping whichever result is right.
 impl<I: Natural> CommutativeOp<algebra::ops::Add> for ModField<I> {
     fn op(a: Self, b: Self, c: &ModFieldCfg<I>) -> Self {
-        let max = <I as Natural>::max();
-
-        // a + b > MAX
-        // a > MAX - b
-        //
-        // r1 = MAX % r
-        if a.val > max - b.val {
-            let r1 = max % c.rem;
-            let r2 = (b.val - (max - a.val)) % c.rem;
-            Self::new(r1 + r2, c)
-        } else {
-            Self::new(a.val + b.val, c)
+        debug_check(a, b, c);
+        // a + b < 2·rem, so one subtraction is enough; it's needed if the sum
+        // wrapped past MAX or is still at least rem
+        let (sum, carry) = a.val.overflowing_add(b.val);
+        let (reduced, borrow) = sum.overflowing_sub(c.rem);
+        Self {
+            val: I::ct_select(reduced, sum, carry | !borrow),
         }
     }
 }
 
 impl<I: Natural> Inverse<algebra::ops::Add> for ModField<I> {
     fn inv(self, cfg: &ModFieldCfg<I>) -> Self {
+        // 0 - self, adding rem back if it borrowed; -0 is 0, not `rem`
+        let (diff, borrow) = I::zero().overflowing_sub(self.val);
+        let (wrapped, _) = diff.overflowing_add(cfg.rem);
         Self {
-            val: cfg.rem - self.val,
+            val: I::ct_select(wrapped, diff, borrow),
         }
     }
 }
@@ -78,7 +89,10 @@ impl<I: Natural> Identity<algebra::ops::Add> for ModField<I> {
 
 impl<I: Natural> CommutativeOp<algebra::ops::Mul> for ModField<I> {
     fn op(a: Self, b: Self, c: &ModFieldCfg<I>) -> Self {
-        CommutativeMonoid::<algebra::ops::Add>::exp(a, b.val, c)
+        debug_check(a, b, c);
+        Self {
+            val: a.val.mul_mod(b.val, c.rem),
+        }
     }
 }
 impl<I: Natural> Identity<algebra::ops::Mul> for ModField<I> {
@@ -93,32 +107,90 @@ impl<I: Natural> AbelianGroup<algebra::ops::Add> for ModField<I> {}
 
 impl<I: Natural> InverseNonZero<algebra::ops::Mul> for ModField<I> {
     fn inv(self, c: &ModFieldCfg<I>) -> Option<Self> {
-        if gcd(c.rem, self.nat()) != I::one() {
-            return None;
+        self.inv_or_factor(c).ok()
+    }
+}
+
+/// Any modulus gives a ring; only a prime one gives a field, which the type
+/// can't check, so [`Field`] is the caller's promise.
+impl<I: Natural> Ring for ModField<I> {
+    fn sum_of_products(pairs: &[(Self, Self)], c: &ModFieldCfg<I>) -> Self {
+        Self {
+            val: I::sum_of_products_mod(pairs.iter().map(|&(a, b)| (a.val, b.val)), c.rem),
         }
-        // Little Fermat's theorem
-        Some(CommutativeMonoid::<algebra::ops::Mul>::exp(
-            self,
-            c.rem - I::two(),
-            c,
-        ))
+    }
+}
+impl<I: Natural> Field for ModField<I> {
+    fn is_canonical(self, c: &ModFieldCfg<I>) -> bool {
+        self.val < c.rem
     }
 }
 
-impl<I: Natural> Field for ModField<I> {}
+impl<I: Natural> ConditionalSwap for ModField<I> {
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+        let (x, y) = (a.val, b.val);
+        a.val = I::ct_select(y, x, swap);
+        b.val = I::ct_select(x, y, swap);
+    }
+}
 
+/// Square roots modulo an odd prime: one exponentiation for `p = 3 mod 4`,
+/// Tonelli–Shanks otherwise. Of the two roots, either may come out.
 impl<I: Natural> DiscreteRoot<algebra::ops::Mul> for ModField<I> {
     fn sqrt(self, c: &ModFieldCfg<I>) -> Option<Self> {
-        if self.pow((c.rem - I::one()) / I::two(), c) != Self::one(c) {
+        let one = Self::one(c);
+        if self == Self::zero(c) {
+            return Some(self);
+        }
+        // Euler's criterion
+        if self.pow((c.rem - I::one()) / I::two(), c) != one {
             return None;
         }
         let three = I::two() + I::one();
         let four = I::two() + I::two();
         if c.rem % four == three {
-            Some(self.pow((c.rem + I::one()) / four, c))
-        } else {
-            todo!();
+            return Some(self.pow((c.rem + I::one()) / four, c));
+        }
+
+        // p - 1 = q·2^s with q odd
+        let (mut q, mut s) = (c.rem - I::one(), 0u32);
+        while q % I::two() == I::zero() {
+            q = q / I::two();
+            s += 1;
+        }
+        // half of the candidates are non-residues, so this ends quickly;
+        // the bound is only for a modulus that isn't prime after all
+        let minus_one = Self::neg(one, c);
+        let mut z = Self::two(c);
+        while z.pow((c.rem - I::one()) / I::two(), c) != minus_one {
+            z = Self::add(z, one, c);
+            if z == Self::zero(c) {
+                return None;
+            }
         }
+
+        // invariants: r² = self·t, t of order 2^(m-1) at most, c of order 2^m
+        let mut m = s;
+        let mut gen = z.pow(q, c);
+        let mut t = self.pow(q, c);
+        let mut r = self.pow((q + I::one()) / I::two(), c);
+        while t != one {
+            let mut i = 0;
+            let mut t_pow = t;
+            while t_pow != one {
+                t_pow = t_pow.sqr(c);
+                i += 1;
+                if i == m {
+                    return None;
+                }
+            }
+            let b = (0..m - i - 1).fold(gen, |b, _| b.sqr(c));
+            m = i;
+            gen = b.sqr(c);
+            t = Self::mul(t, gen, c);
+            r = Self::mul(r, b, c);
+        }
+        Some(r)
     }
 }
 
@@ -129,11 +201,67 @@ impl<I: Natural> ModField<I> {
     pub fn nat(self) -> I {
         self.val
     }
+
+    /// The inverse for any modulus, prime or not, by the extended Euclidean
+    /// algorithm. If there's none, the error is `gcd(self, rem)`: a proper
+    /// factor of the modulus unless `self` is zero.
+    pub fn inv_or_factor(self, c: &ModFieldCfg<I>) -> Result<Self, I> {
+        let (mut r0, mut r1) = (c.rem, self.val);
+        let (mut t0, mut t1) = (Self::zero(c), Self::one(c));
+        while r1 != I::zero() {
+            let q = r0 / r1;
+            (r0, r1) = (r1, r0 - q * r1);
+            (t0, t1) = (t1, Self::sub(t0, Self::mul(Self::new(q, c), t1, c), c));
+        }
+        if r0 == I::one() {
+            Ok(t0)
+        } else {
+            Err(r0)
+        }
+    }
+}
+
+/// Coordinates live mod `p`, scalars mod the group order `n`, and the two
+/// are usually close but not equal, so moving a value between them can wrap
+/// or not fit. Both are [`ModField`]s over the same integer type.
+impl<I: Natural> ModField<I> {
+    /// A field element, usually an x coordinate, as a scalar mod `n`, like
+    /// ECDSA's `r = x mod n`. Values in `[n, p)` wrap around, so this isn't
+    /// injective when `p > n`.
+    pub fn to_scalar(self, n: &ModFieldCfg<I>) -> Self {
+        Self::new(self.val, n)
+    }
+
+    /// [`ModField::to_scalar`], but `None` if the result is zero, which
+    /// ECDSA has to reject and retry with another nonce.
+    pub fn to_scalar_nonzero(self, n: &ModFieldCfg<I>) -> Option<Self> {
+        let s = self.to_scalar(n);
+        (s.val != I::zero()).then_some(s)
+    }
+
+    /// A scalar as a field element without reduction, `None` if it isn't
+    /// below `p`. For ECDSA key recovery this is the first candidate `x = r`;
+    /// the others are `r + j·n`.
+    pub fn from_scalar(s: Self, c: &ModFieldCfg<I>) -> Option<Self> {
+        (s.val < c.rem).then_some(Self { val: s.val })
+    }
 }
 
+/// Uniform: draws above the largest multiple of the modulus are retried
+/// rather than reduced, which would favour small values. That only matters
+/// when the modulus isn't far below the integer's range, as for 256-bit
+/// curves in a `U256`, where it's still rare enough not to cost anything.
 impl<I: Natural + FromRandom<()>> FromRandom<ModFieldCfg<I>> for ModField<I> {
     fn random(r: &mut impl Rng, cfg: &ModFieldCfg<I>) -> Self {
-        Self::new(I::random(r, &()), cfg)
+        let max = <I as Natural>::max();
+        // 2^bits mod rem, the number of values past the last full multiple
+        let excess = (max % cfg.rem + I::one()) % cfg.rem;
+        loop {
+            let v = I::random(r, &());
+            if v <= max - excess {
+                return Self::new(v, cfg);
+            }
+        }
     }
 }
 
@@ -174,16 +302,37 @@ impl<I: Natural + RW> RW for ModField<I> {
     const LEN: usize = I::LEN;
 }
 
+impl<I: Natural + RW> ModField<I> {
+    /// Decodes exactly [`RW::LEN`] bytes, rejecting values not below the
+    /// modulus. [`RW::from_bytes`] has no modulus to compare against and
+    /// keeps them, so `v` and `v + p` would both decode to the same element.
+    pub fn from_bytes_canonical(bytes: &[u8], c: &ModFieldCfg<I>) -> Result<Self, Error> {
+        if bytes.len() != Self::LEN {
+            return Err(Error::Length {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
+        }
+        let v = Self::from_bytes(&mut &bytes[..]);
+        v.is_canonical(c).then_some(v).ok_or(Error::NonCanonical)
+    }
+}
+
+impl<I: Natural> Parity for ModField<I> {
+    fn is_odd(self) -> bool {
+        self.val % I::two() == I::one()
+    }
+}
+
 impl<I: Natural> Capacitor for ModField<I> {
     fn capacity(cfg: &Self::Cfg) -> usize {
         let mut rem = cfg.rem;
-        let mut v256 = I::one();
-        for _ in 0..8 {
-            v256 = v256 * I::two();
-        }
         let mut c = 0;
         loop {
-            rem = rem / v256;
+            // halving byte-wise, since 256 itself may not fit into I
+            for _ in 0..8 {
+                rem = rem / I::two();
+            }
             if rem != I::zero() {
                 c += 1;
             } else {
@@ -198,9 +347,12 @@ mod tests {
     use rand::SeedableRng;
 
     use crate::{
-        algebra::Field,
-        base_traits::FromRandom,
+        algebra::{laws, DiscreteRoot, Field, Ring},
+        base_traits::{FromRandom, Natural},
+        curves::{p256, secp256k1, toy_order},
+        error::Error,
         mod_field::{gcd, ModField},
+        uint::U256,
     };
 
     use super::ModFieldCfg;
@@ -215,6 +367,130 @@ mod tests {
         F::new(a, &cfg())
     }
 
+    #[test]
+    fn laws() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let c = cfg();
+        laws::field(|| F::random(&mut rng, &c), &c, 50);
+        // the scalars of the toy curve
+        let n = toy_order();
+        laws::field(|| ModField::random(&mut rng, &n), &n, 50);
+        let c = secp256k1().cf;
+        laws::field(|| ModField::random(&mut rng, &c), &c, 20);
+    }
+
+    #[test]
+    fn u256_field() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for c in [secp256k1().cf, p256().cf] {
+            let (zero, one) = (ModField::zero(&c), ModField::one(&c));
+            let minus_one = ModField::new(c.rem - U256::one(), &c);
+
+            // sums past 2^256 take the overflow branch
+            assert_eq!(
+                ModField::add(minus_one, minus_one, &c),
+                ModField::two(&c).neg(&c)
+            );
+            assert_eq!(ModField::add(minus_one, one, &c), zero);
+            assert_eq!(ModField::sub(zero, one, &c), minus_one);
+            assert_eq!(ModField::mul(minus_one, minus_one, &c), one);
+
+            for _ in 0..8 {
+                let a = ModField::random_nonzero(&mut rng, &c);
+                let b = ModField::random(&mut rng, &c);
+                assert!(a.is_canonical(&c) && b.is_canonical(&c));
+                assert_eq!(ModField::sub(ModField::add(a, b, &c), b, &c), a);
+
+                let inv = a.reciprocal(&c).unwrap();
+                assert_eq!(ModField::mul(a, inv, &c), one);
+                // Fermat
+                assert_eq!(a.pow(c.rem - U256::two(), &c), inv);
+
+                let square = a.sqr(&c);
+                let root = square.sqrt(&c).unwrap();
+                assert!(root == a || root == ModField::neg(a, &c));
+            }
+            assert_eq!(zero.reciprocal(&c), None);
+            // -1 is a non-residue for p = 3 mod 4
+            assert_eq!(minus_one.sqrt(&c), None);
+        }
+    }
+
+    #[test]
+    fn sqrt_exhaustive() {
+        // p = 1 mod 4 takes Tonelli–Shanks; 257 = 2^8 + 1 is its longest run
+        for rem in [3u64, 5, 13, 17, 19, 41, 97, 257, 1009] {
+            let c = ModFieldCfg { rem };
+            let squares: Vec<u64> = (0..rem).map(|a| a * a % rem).collect();
+            for a in 0..rem {
+                match F::new(a, &c).sqrt(&c) {
+                    Some(root) => assert_eq!(root.sqr(&c), F::new(a, &c), "{a} mod {rem}"),
+                    None => assert!(!squares.contains(&a), "{a} mod {rem}"),
+                }
+            }
+        }
+        let c = ModFieldCfg { rem: 17u64 };
+        let root = F::new(2, &c).sqrt(&c).unwrap().nat();
+        assert!(root == 6 || root == 11);
+        assert_eq!(F::new(3, &c).sqrt(&c), None);
+    }
+
+    #[test]
+    fn sqrt_two_adic() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        // 2^64 - 2^32 + 1, with p - 1 divisible by 2^32
+        let c = ModFieldCfg {
+            rem: 0xffff_ffff_0000_0001u64,
+        };
+        // 7 generates the multiplicative group, so it's a non-residue
+        assert_eq!(F::new(7, &c).sqrt(&c), None);
+        for _ in 0..20 {
+            let a = F::random(&mut rng, &c);
+            let root = a.sqr(&c).sqrt(&c).unwrap();
+            assert!(root == a || root == a.neg(&c));
+        }
+        // the Pasta base fields are 1 mod 2^32 as well
+        let c = crate::curves::pallas_base();
+        let a = ModField::random_nonzero(&mut rng, &c);
+        let root = a.sqr(&c).sqrt(&c).unwrap();
+        assert!(root == a || root == a.neg(&c));
+    }
+
+    #[test]
+    fn canonical_decoding() {
+        let c = cfg();
+        assert_eq!(F::from_bytes_canonical(&18u64.to_le_bytes(), &c), Ok(f(18)));
+        assert_eq!(F::from_bytes_canonical(&0u64.to_le_bytes(), &c), Ok(f(0)));
+        for v in [19u64, 20, 38, u64::MAX] {
+            assert_eq!(
+                F::from_bytes_canonical(&v.to_le_bytes(), &c),
+                Err(Error::NonCanonical)
+            );
+        }
+        assert_eq!(
+            F::from_bytes_canonical(&[0; 7], &c),
+            Err(Error::Length {
+                expected: 8,
+                actual: 7
+            })
+        );
+    }
+
+    #[test]
+    fn scalar_conversions() {
+        let (p, n) = (cfg(), ModFieldCfg { rem: 17u64 });
+        assert_eq!(f(18).to_scalar(&n), F::new(1, &n));
+        assert_eq!(f(5).to_scalar(&n), F::new(5, &n));
+        assert_eq!(f(17).to_scalar_nonzero(&n), None);
+        assert_eq!(F::from_scalar(F::new(16, &n), &p), Some(f(16)));
+
+        // n > p, a scalar may not fit
+        let (p, n) = (n, cfg());
+        assert_eq!(F::from_scalar(F::new(18, &n), &p), None);
+        assert_eq!(F::from_scalar(F::new(3, &n), &p), Some(F::new(3, &p)));
+        assert_eq!(F::new(16, &p).to_scalar_nonzero(&n), Some(F::new(16, &n)));
+    }
+
     #[test]
     fn simple() {
         assert_eq!(f(27), f(8));
@@ -226,6 +502,22 @@ mod tests {
     }
 
     type H = ModField<u8>;
+    #[test]
+    fn add_sub_exhaustive() {
+        for rem in [2u8, 79, 128, 129, 251, 255] {
+            let cfg = ModFieldCfg { rem };
+            for a in 0..rem {
+                for b in 0..rem {
+                    let (ha, hb) = (H::new(a, &cfg), H::new(b, &cfg));
+                    let sum = ((a as u16 + b as u16) % rem as u16) as u8;
+                    assert_eq!(H::add(ha, hb, &cfg).nat(), sum);
+                    let diff = ((a as u16 + rem as u16 - b as u16) % rem as u16) as u8;
+                    assert_eq!(H::sub(ha, hb, &cfg).nat(), diff);
+                }
+            }
+        }
+    }
+
     #[test]
     fn add_overflow1() {
         let cfg = ModFieldCfg { rem: 79 };
@@ -299,6 +591,7 @@ mod tests {
     #[test]
     fn neg() {
         assert_eq!(f(11).neg(&cfg()), f(8));
+        assert_eq!(f(0).neg(&cfg()), f(0));
     }
 
     #[test]
@@ -321,6 +614,21 @@ mod tests {
         assert_eq!(gcd(123u64, 66), 3);
     }
 
+    #[test]
+    fn composite_modulus() {
+        let c = ModFieldCfg { rem: 35u64 };
+        let f = |v| ModField::new(v, &c);
+        assert_eq!(f(2).reciprocal(&c), Some(f(18)));
+        for v in 1..35 {
+            match f(v).inv_or_factor(&c) {
+                Ok(inv) => assert_eq!(Ring::mul(f(v), inv, &c), f(1)),
+                Err(d) => assert_eq!(d, gcd(v, 35)),
+            }
+        }
+        assert_eq!(f(10).reciprocal(&c), None);
+        assert_eq!(f(0).inv_or_factor(&c).map(ModField::nat), Err(35));
+    }
+
     #[test]
     fn div_circ() {
         let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
@@ -0,0 +1,275 @@
+//! The same curve in different models: short Weierstrass `y² = x³ + a·x + b`
+//! (what [`Point`] implements), Montgomery `B·v² = u³ + A·u² + u` and twisted
+//! Edwards `a·x² + y² = 1 + d·x²·y²`. Every Montgomery curve has a
+//! Weierstrass form and a twisted Edwards form and back; a Weierstrass curve
+//! only has a Montgomery form if it has a point of order 2.
+//!
+//! Points are plain `(u, v)` or `(x, y)` pairs outside of Weierstrass form.
+//! The maps aren't defined at a few exceptional points, where they give
+//! `None`.
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field},
+    points_group::{Point, PointCfg},
+};
+
+/// `B·v² = u³ + A·u² + u`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Montgomery<F: Field> {
+    pub a: F,
+    pub b: F,
+    pub cf: F::Cfg,
+}
+
+/// `a·x² + y² = 1 + d·x²·y²`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TwistedEdwards<F: Field> {
+    pub a: F,
+    pub d: F,
+    pub cf: F::Cfg,
+}
+
+impl<F: Field> Montgomery<F> {
+    pub fn contains(&self, (u, v): (F, F)) -> bool {
+        let cf = &self.cf;
+        let rhs = F::mul(
+            u,
+            F::sop(&[(u, u), (self.a, u), (F::one(cf), F::one(cf))], cf),
+            cf,
+        );
+        F::mul(self.b, v.sqr(cf), cf) == rhs
+    }
+
+    /// `3·B`, the denominator of both the shift and the scale to Weierstrass.
+    fn three_b(&self) -> F {
+        F::mul(F::three(&self.cf), self.b, &self.cf)
+    }
+
+    /// `(x, y) = (u / B + A / 3B, v / B)`.
+    pub fn point_to_weierstrass(&self, (u, v): (F, F)) -> Point<F> {
+        let cf = &self.cf;
+        let x = F::div(
+            F::sop(&[(F::three(cf), u), (self.a, F::one(cf))], cf),
+            self.three_b(),
+            cf,
+        );
+        Point::new_unsafe(x, F::div(v, self.b, cf))
+    }
+
+    /// `(u, v) = (B·x - A / 3, B·y)`.
+    pub fn point_from_weierstrass(&self, p: Point<F>) -> (F, F) {
+        let cf = &self.cf;
+        let u = F::sub(
+            F::mul(self.b, p.x(), cf),
+            F::div(self.a, F::three(cf), cf),
+            cf,
+        );
+        (u, F::mul(self.b, p.y(), cf))
+    }
+
+    /// `a = (3 - A²) / 3B²`, `b = (2A³ - 9A) / 27B³`, with the Weierstrass
+    /// image of the Montgomery point `g` as the generator.
+    pub fn to_weierstrass(&self, g: (F, F)) -> PointCfg<F>
+    where
+        F::Cfg: Clone,
+    {
+        let cf = &self.cf;
+        let three_b = self.three_b();
+        let a = F::div(
+            F::sub(F::three(cf), self.a.sqr(cf), cf),
+            F::mul(three_b, self.b, cf),
+            cf,
+        );
+        let nine = F::mul(F::three(cf), F::three(cf), cf);
+        let b = F::div(
+            F::sub(
+                F::mul(F::two(cf), self.a.cube(cf), cf),
+                F::mul(nine, self.a, cf),
+                cf,
+            ),
+            three_b.cube(cf),
+            cf,
+        );
+        let c = PointCfg {
+            g: self.point_to_weierstrass(g),
+            a,
+            b,
+            cf: cf.clone(),
+        };
+        Point::new(c.g.x(), c.g.y(), &c);
+        c
+    }
+
+    /// `a = (A + 2) / B`, `d = (A - 2) / B`.
+    pub fn to_twisted_edwards(&self) -> TwistedEdwards<F>
+    where
+        F::Cfg: Clone,
+    {
+        let cf = &self.cf;
+        let two = F::two(cf);
+        TwistedEdwards {
+            a: F::div(F::add(self.a, two, cf), self.b, cf),
+            d: F::div(F::sub(self.a, two, cf), self.b, cf),
+            cf: cf.clone(),
+        }
+    }
+
+    /// `(x, y) = (u / v, (u - 1) / (u + 1))`. `None` for the points with
+    /// `v = 0` or `u = -1`; the point at infinity isn't a pair to begin with.
+    pub fn point_to_twisted_edwards(&self, (u, v): (F, F)) -> Option<(F, F)> {
+        let cf = &self.cf;
+        let one = F::one(cf);
+        let x = F::mul(u, v.reciprocal(cf)?, cf);
+        let y = F::mul(F::sub(u, one, cf), F::add(u, one, cf).reciprocal(cf)?, cf);
+        Some((x, y))
+    }
+}
+
+impl<F: Field + DiscreteRoot<algebra::ops::Mul>> Montgomery<F> {
+    /// The Montgomery form of a Weierstrass curve with a point `(α, 0)` of
+    /// order 2 such that `3α² + a` is a square `s²`: `A = 3α / s`,
+    /// `B = 1 / s`. `None` if it isn't; `B` is only defined up to the choice
+    /// of the root.
+    pub fn from_weierstrass(c: &PointCfg<F>, alpha: F) -> Option<Self>
+    where
+        F::Cfg: Clone,
+    {
+        let cf = &c.cf;
+        let zero = F::zero(cf);
+        let rhs = F::sop(
+            &[(alpha.sqr(cf), alpha), (c.a, alpha), (c.b, F::one(cf))],
+            cf,
+        );
+        if rhs != zero {
+            return None;
+        }
+        let s = F::sop(&[(F::three(cf), alpha.sqr(cf)), (c.a, F::one(cf))], cf).sqrt(cf)?;
+        let b = s.reciprocal(cf)?;
+        Some(Self {
+            a: F::mul(F::mul(F::three(cf), alpha, cf), b, cf),
+            b,
+            cf: cf.clone(),
+        })
+    }
+}
+
+impl<F: Field> TwistedEdwards<F> {
+    pub fn contains(&self, (x, y): (F, F)) -> bool {
+        let cf = &self.cf;
+        let (xx, yy) = (x.sqr(cf), y.sqr(cf));
+        let lhs = F::sop(&[(self.a, xx), (yy, F::one(cf))], cf);
+        F::add(F::one(cf), F::mul(self.d, F::mul(xx, yy, cf), cf), cf) == lhs
+    }
+
+    /// `A = 2(a + d) / (a - d)`, `B = 4 / (a - d)`.
+    pub fn to_montgomery(&self) -> Montgomery<F>
+    where
+        F::Cfg: Clone,
+    {
+        let cf = &self.cf;
+        let diff = F::sub(self.a, self.d, cf);
+        Montgomery {
+            a: F::div(F::mul(F::two(cf), F::add(self.a, self.d, cf), cf), diff, cf),
+            b: F::div(F::four(cf), diff, cf),
+            cf: cf.clone(),
+        }
+    }
+
+    /// `(u, v) = ((1 + y) / (1 - y), u / x)`. `None` for the points with
+    /// `x = 0`: the neutral element and the point of order 2.
+    pub fn point_to_montgomery(&self, (x, y): (F, F)) -> Option<(F, F)> {
+        let cf = &self.cf;
+        let one = F::one(cf);
+        let u = F::mul(F::add(one, y, cf), F::sub(one, y, cf).reciprocal(cf)?, cf);
+        Some((u, F::mul(u, x.reciprocal(cf)?, cf)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeOp, Ring},
+        curves::{curve25519, ed25519},
+        mod_field::{ModField, ModFieldCfg},
+        points_group::Point,
+        uint::U256,
+    };
+
+    use super::Montgomery;
+
+    fn hex(s: &str) -> ModField<U256> {
+        ModField::new(U256::from_be_hex(s).unwrap(), &curve25519().cf)
+    }
+
+    #[test]
+    fn curve25519_ed25519() {
+        let (m, e) = (curve25519(), ed25519());
+        let cf = &m.cf;
+        let u_base = (
+            ModField::new(U256::from(9u64), cf),
+            hex("20ae19a1b8a086b4e01edd2c7748d14c923d4d7e6d7c61b229e9c5a27eced3d9"),
+        );
+        let ed_base = (
+            hex("216936d3cd6e53fec0a4e231fdd6dc5c692cc7609525a7b2c9562d608f25d51a"),
+            hex("6666666666666666666666666666666666666666666666666666666666666658"),
+        );
+        assert!(m.contains(u_base));
+        assert!(e.contains(ed_base));
+
+        // Ed25519's Montgomery form is Curve25519 with B = -486664, a twist
+        // by -486664 that leaves u alone, so the base points share their u
+        let from_ed = e.to_montgomery();
+        assert_eq!(from_ed.a, m.a);
+        assert_eq!(from_ed.b, ModField::new(U256::from(486664u64), cf).neg(cf));
+        let (u, v) = e.point_to_montgomery(ed_base).unwrap();
+        assert_eq!(u, u_base.0);
+        assert!(from_ed.contains((u, v)));
+        assert_eq!(from_ed.to_twisted_edwards(), e);
+        assert_eq!(from_ed.point_to_twisted_edwards((u, v)), Some(ed_base));
+
+        // Curve25519 itself to Edwards, with a = 486664, and back
+        let te = m.to_twisted_edwards();
+        let p = m.point_to_twisted_edwards(u_base).unwrap();
+        assert!(te.contains(p));
+        assert_eq!(te.to_montgomery(), m);
+        assert_eq!(te.point_to_montgomery(p), Some(u_base));
+        assert_eq!(
+            te.point_to_montgomery((ModField::zero(cf), ModField::one(cf))),
+            None
+        );
+
+        // and to Weierstrass, where the group law agrees with the Edwards one
+        // through the maps
+        let w = m.to_weierstrass(u_base);
+        assert_eq!(m.point_from_weierstrass(w.g), u_base);
+        let g3 = Point::exp(w.g, 3u64, &w);
+        let back = m.point_from_weierstrass(g3);
+        assert!(m.contains(back));
+        assert!(te.contains(m.point_to_twisted_edwards(back).unwrap()));
+    }
+
+    #[test]
+    fn weierstrass_round_trip() {
+        let cf = ModFieldCfg { rem: 1019u64 };
+        let f = |v| ModField::new(v, &cf);
+        let m = Montgomery {
+            a: f(6),
+            b: f(1),
+            cf,
+        };
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let w = m.to_weierstrass((f(0), f(0)));
+        let p = Point::random(&mut rng, &w);
+        let q = m.point_from_weierstrass(p);
+        assert!(m.contains(q));
+        assert_eq!(m.point_to_weierstrass(q), p);
+        // (0, 0) is the point of order 2 the way back goes through
+        let alpha = w.g.x();
+        let back = Montgomery::from_weierstrass(&w, alpha).unwrap();
+        assert_eq!(back.a.sqr(&cf), m.a.sqr(&cf));
+        assert!(back.contains(back.point_from_weierstrass(p)));
+        assert_eq!(Montgomery::from_weierstrass(&w, f(1)), None);
+    }
+}
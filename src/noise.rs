@@ -0,0 +1,757 @@
+//! A minimal instance of the [Noise protocol framework](https://noiseprotocol.org/noise.html):
+//! the DH is a [`CurveContext`]'s curve with SEC1 compressed public keys,
+//! the hash SHA-256, and the cipher `ChaChaHmac`, ChaCha20 with an
+//! HMAC-SHA256 tag truncated to 16 bytes, since the crate has no Poly1305.
+//! The first 32 bytes of the keystream key the MAC and encryption starts at
+//! the second block, as in ChaCha20-Poly1305. With a non-standard cipher the
+//! protocol names, e.g. `Noise_XX_secp256k1_ChaChaHmac_SHA256`, are this
+//! crate's own and only talk to themselves.
+//!
+//! Only the handshake patterns in [`Pattern`] are supported, without PSKs or
+//! rekeying.
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{self, DiscreteRoot, Field},
+    base_traits::{Capacitor, FromRandom, Natural, Parity, RW},
+    context::CurveContext,
+    ecc::{PrivateKey, PublicKey},
+    error::Error,
+    mac::{self, hmac},
+    points_group::{Point, ValidationPolicy},
+};
+
+const HASH_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+/// Noise messages, handshake or transport, are at most this long.
+pub const MAX_MESSAGE: usize = 65535;
+
+/// Noise's `HKDF` with two outputs, RFC 5869's without `info`; none of the
+/// supported patterns needs a third.
+fn hkdf(chaining_key: &[u8; HASH_LEN], input: &[u8]) -> ([u8; HASH_LEN], [u8; HASH_LEN]) {
+    mac::hkdf(chaining_key, input, &[])
+}
+
+/// The keystream with `nonce` as the ChaCha20 stream, and the MAC key from
+/// its first block.
+fn keystream(key: &[u8; 32], nonce: u64) -> (ChaCha20Rng, [u8; HASH_LEN]) {
+    let mut stream = ChaCha20Rng::from_seed(*key);
+    stream.set_stream(nonce);
+    let mut mac_key = [0; HASH_LEN];
+    stream.fill_bytes(&mut mac_key);
+    stream.set_word_pos(16);
+    (stream, mac_key)
+}
+
+fn tag(mac_key: &[u8; HASH_LEN], ad: &[u8], ciphertext: &[u8]) -> [u8; TAG_LEN] {
+    let lens = [
+        (ad.len() as u64).to_le_bytes(),
+        (ciphertext.len() as u64).to_le_bytes(),
+    ];
+    let mac = hmac(mac_key, &[ad, ciphertext, &lens[0], &lens[1]]);
+    mac[..TAG_LEN].try_into().unwrap()
+}
+
+/// A key and the nonce of its next message.
+#[derive(Clone)]
+pub struct CipherState {
+    key: Option<[u8; 32]>,
+    nonce: u64,
+}
+
+impl CipherState {
+    fn new(key: Option<[u8; 32]>) -> Self {
+        Self { key, nonce: 0 }
+    }
+
+    fn next_nonce(&mut self) -> Result<u64, Error> {
+        // 2^64 - 1 is reserved
+        if self.nonce == u64::MAX {
+            return Err(Error::Handshake);
+        }
+        self.nonce += 1;
+        Ok(self.nonce - 1)
+    }
+
+    /// The plaintext as is until there's a key.
+    pub fn encrypt_with_ad(&mut self, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let Some(key) = self.key else {
+            return Ok(plaintext.to_vec());
+        };
+        let (mut stream, mac_key) = keystream(&key, self.next_nonce()?);
+        let mut ciphertext = vec![0; plaintext.len()];
+        stream.fill_bytes(&mut ciphertext);
+        ciphertext
+            .iter_mut()
+            .zip(plaintext)
+            .for_each(|(c, p)| *c ^= p);
+        let tag = tag(&mac_key, ad, &ciphertext);
+        ciphertext.extend(tag);
+        Ok(ciphertext)
+    }
+
+    /// [`Error::Tag`] if the ciphertext or `ad` were modified; the nonce only
+    /// advances on success.
+    pub fn decrypt_with_ad(&mut self, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let plaintext = self.decrypt_at(self.nonce, ad, ciphertext)?;
+        if self.key.is_some() {
+            self.nonce += 1;
+        }
+        Ok(plaintext)
+    }
+
+    /// [`Self::decrypt_with_ad`] at the given nonce, leaving the next one as
+    /// it is.
+    fn decrypt_at(&self, nonce: u64, ad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let Some(key) = self.key else {
+            return Ok(ciphertext.to_vec());
+        };
+        let (body, received) = ciphertext
+            .split_at_checked(ciphertext.len().wrapping_sub(TAG_LEN))
+            .ok_or(Error::Length {
+                expected: TAG_LEN,
+                actual: ciphertext.len(),
+            })?;
+        if nonce == u64::MAX {
+            return Err(Error::Handshake);
+        }
+        let (mut stream, mac_key) = keystream(&key, nonce);
+        let expected = tag(&mac_key, ad, body);
+        if expected
+            .iter()
+            .zip(received)
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            != 0
+        {
+            return Err(Error::Tag);
+        }
+        let mut plaintext = vec![0; body.len()];
+        stream.fill_bytes(&mut plaintext);
+        plaintext.iter_mut().zip(body).for_each(|(p, c)| *p ^= c);
+        Ok(plaintext)
+    }
+}
+
+struct SymmetricState {
+    cipher: CipherState,
+    chaining_key: [u8; HASH_LEN],
+    hash: [u8; HASH_LEN],
+}
+
+impl SymmetricState {
+    fn new(protocol_name: &str) -> Self {
+        let name = protocol_name.as_bytes();
+        let mut hash = [0; HASH_LEN];
+        if name.len() <= HASH_LEN {
+            hash[..name.len()].copy_from_slice(name);
+        } else {
+            hash = Sha256::digest(name).into();
+        }
+        Self {
+            cipher: CipherState::new(None),
+            chaining_key: hash,
+            hash,
+        }
+    }
+
+    fn mix_key(&mut self, input: &[u8]) {
+        let (chaining_key, key) = hkdf(&self.chaining_key, input);
+        self.chaining_key = chaining_key;
+        self.cipher = CipherState::new(Some(key));
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        self.hash = Sha256::new()
+            .chain_update(self.hash)
+            .chain_update(data)
+            .finalize()
+            .into();
+    }
+
+    fn encrypt_and_hash(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let ciphertext = self.cipher.encrypt_with_ad(&self.hash, plaintext)?;
+        self.mix_hash(&ciphertext);
+        Ok(ciphertext)
+    }
+
+    fn decrypt_and_hash(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        let plaintext = self.cipher.decrypt_with_ad(&self.hash, ciphertext)?;
+        self.mix_hash(ciphertext);
+        Ok(plaintext)
+    }
+
+    fn split(&self) -> (CipherState, CipherState) {
+        let (first, second) = hkdf(&self.chaining_key, &[]);
+        (
+            CipherState::new(Some(first)),
+            CipherState::new(Some(second)),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    E,
+    S,
+    EE,
+    ES,
+    SE,
+    SS,
+}
+
+/// The supported handshake patterns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// No static keys: confidentiality against passive attackers only.
+    NN,
+    /// The initiator knows the responder's static key beforehand.
+    NK,
+    /// Both sides know the other's static key beforehand.
+    KK,
+    /// Both static keys are sent during the handshake, encrypted.
+    XX,
+}
+
+impl Pattern {
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::NN => "NN",
+            Pattern::NK => "NK",
+            Pattern::KK => "KK",
+            Pattern::XX => "XX",
+        }
+    }
+
+    /// The pre-messages of the initiator and the responder.
+    fn pre_messages(self) -> (&'static [Token], &'static [Token]) {
+        match self {
+            Pattern::NN | Pattern::XX => (&[], &[]),
+            Pattern::NK => (&[], &[Token::S]),
+            Pattern::KK => (&[Token::S], &[Token::S]),
+        }
+    }
+
+    /// The messages, alternating from the initiator.
+    fn messages(self) -> &'static [&'static [Token]] {
+        use Token::*;
+        match self {
+            Pattern::NN => &[&[E], &[E, EE]],
+            Pattern::NK => &[&[E, ES], &[E, EE]],
+            Pattern::KK => &[&[E, ES, SS], &[E, EE, SE]],
+            Pattern::XX => &[&[E], &[E, EE, S, ES], &[S, SE]],
+        }
+    }
+}
+
+type KeyPair<F, I> = (PrivateKey<I>, PublicKey<Point<F>>);
+
+/// One side of a handshake: calls to [`Self::write_message`] and
+/// [`Self::read_message`] alternate as the pattern says, starting with the
+/// initiator's write, and [`Self::into_transport`] takes over once
+/// [`Self::is_finished`].
+pub struct HandshakeState<'c, F: Field, I> {
+    ctx: &'c CurveContext<F, I>,
+    symmetric: SymmetricState,
+    pattern: Pattern,
+    initiator: bool,
+    message: usize,
+    s: Option<KeyPair<F, I>>,
+    e: Option<KeyPair<F, I>>,
+    rs: Option<PublicKey<Point<F>>>,
+    re: Option<PublicKey<Point<F>>>,
+}
+
+impl<'c, F, I> HandshakeState<'c, F, I>
+where
+    F: Field + RW + DiscreteRoot<algebra::ops::Mul> + Capacitor + Parity,
+    I: FromRandom<()> + Natural,
+{
+    /// `curve` names the DH in the protocol name. `s` is this side's static
+    /// key and `rs` the other side's, if the pattern needs them beforehand;
+    /// [`Error::Handshake`] if it's missing.
+    pub fn new(
+        ctx: &'c CurveContext<F, I>,
+        curve: &str,
+        pattern: Pattern,
+        initiator: bool,
+        prologue: &[u8],
+        s: Option<PrivateKey<I>>,
+        rs: Option<PublicKey<Point<F>>>,
+    ) -> Result<Self, Error> {
+        let name = format!("Noise_{}_{}_ChaChaHmac_SHA256", pattern.name(), curve);
+        let mut state = Self {
+            ctx,
+            symmetric: SymmetricState::new(&name),
+            pattern,
+            initiator,
+            message: 0,
+            s: s.map(|s| (s, ctx.public_key(s))),
+            e: None,
+            rs,
+            re: None,
+        };
+        state.symmetric.mix_hash(prologue);
+        let (from_initiator, from_responder) = pattern.pre_messages();
+        for (tokens, own) in [(from_initiator, initiator), (from_responder, !initiator)] {
+            for &token in tokens {
+                debug_assert_eq!(token, Token::S);
+                let key = match own {
+                    true => state.s.map(|(_, public)| public),
+                    false => state.rs,
+                };
+                let key = key.ok_or(Error::Handshake)?;
+                state.symmetric.mix_hash(&key.point().to_sec1_compressed());
+            }
+        }
+        Ok(state)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.message == self.pattern.messages().len()
+    }
+
+    /// The other side's static key, once known.
+    pub fn remote_static(&self) -> Option<PublicKey<Point<F>>> {
+        self.rs
+    }
+
+    fn my_turn(&self) -> bool {
+        !self.is_finished() && self.message.is_multiple_of(2) == self.initiator
+    }
+
+    fn dh(
+        &self,
+        private: Option<KeyPair<F, I>>,
+        public: Option<PublicKey<Point<F>>>,
+    ) -> Result<Vec<u8>, Error> {
+        let ((private, _), public) = private.zip(public).ok_or(Error::Handshake)?;
+        let shared = public.point().mul_ct(private.scalar(), self.ctx.cfg());
+        Ok(shared.to_sec1_compressed())
+    }
+
+    /// The DH of a token, in the initiator's terms.
+    fn mix_dh(&mut self, token: Token) -> Result<(), Error> {
+        let (local, remote) = match (token, self.initiator) {
+            (Token::EE, _) => (self.e, self.re),
+            (Token::SS, _) => (self.s, self.rs),
+            (Token::ES, true) | (Token::SE, false) => (self.e, self.rs),
+            (Token::ES, false) | (Token::SE, true) => (self.s, self.re),
+            (Token::E | Token::S, _) => unreachable!(),
+        };
+        let shared = self.dh(local, remote)?;
+        self.symmetric.mix_key(&shared);
+        Ok(())
+    }
+
+    fn parse_key(&self, bytes: &[u8]) -> Result<PublicKey<Point<F>>, Error> {
+        let policy = ValidationPolicy::OnCurveAndSubgroup(self.ctx.order().rem);
+        Point::from_sec1_compressed_validated(bytes, policy, self.ctx.cfg())
+            .map(PublicKey::from_point)
+    }
+
+    pub fn write_message(&mut self, payload: &[u8], rng: &mut impl Rng) -> Result<Vec<u8>, Error> {
+        if !self.my_turn() {
+            return Err(Error::Handshake);
+        }
+        let mut message = vec![];
+        for &token in self.pattern.messages()[self.message] {
+            match token {
+                Token::E => {
+                    let e = self.ctx.gen_keys(rng);
+                    let public = e.1.point().to_sec1_compressed();
+                    self.symmetric.mix_hash(&public);
+                    message.extend(public);
+                    self.e = Some(e);
+                }
+                Token::S => {
+                    let (_, public) = self.s.ok_or(Error::Handshake)?;
+                    let public = public.point().to_sec1_compressed();
+                    message.extend(self.symmetric.encrypt_and_hash(&public)?);
+                }
+                _ => self.mix_dh(token)?,
+            }
+        }
+        message.extend(self.symmetric.encrypt_and_hash(payload)?);
+        if message.len() > MAX_MESSAGE {
+            return Err(Error::TooLarge { limit: MAX_MESSAGE });
+        }
+        self.message += 1;
+        Ok(message)
+    }
+
+    /// The payload of the other side's message.
+    pub fn read_message(&mut self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        if self.is_finished() || self.my_turn() {
+            return Err(Error::Handshake);
+        }
+        if message.len() > MAX_MESSAGE {
+            return Err(Error::TooLarge { limit: MAX_MESSAGE });
+        }
+        let key_len = F::LEN + 1;
+        let mut rest = message;
+        let mut take = |len: usize| {
+            let (taken, tail) = rest.split_at_checked(len).ok_or(Error::Length {
+                expected: len,
+                actual: rest.len(),
+            })?;
+            rest = tail;
+            Ok::<_, Error>(taken)
+        };
+        for &token in self.pattern.messages()[self.message] {
+            match token {
+                Token::E => {
+                    let public = take(key_len)?;
+                    self.re = Some(self.parse_key(public)?);
+                    self.symmetric.mix_hash(public);
+                }
+                Token::S => {
+                    let tag = self.symmetric.cipher.key.map_or(0, |_| TAG_LEN);
+                    let public = self.symmetric.decrypt_and_hash(take(key_len + tag)?)?;
+                    self.rs = Some(self.parse_key(&public)?);
+                }
+                _ => self.mix_dh(token)?,
+            }
+        }
+        let payload = self.symmetric.decrypt_and_hash(rest)?;
+        self.message += 1;
+        Ok(payload)
+    }
+
+    /// The cipher states for the rest of the session; [`Error::Handshake`]
+    /// before the handshake is finished.
+    pub fn into_transport(self) -> Result<Transport, Error> {
+        if !self.is_finished() {
+            return Err(Error::Handshake);
+        }
+        let (initiator, responder) = self.symmetric.split();
+        let (send, receive) = match self.initiator {
+            true => (initiator, responder),
+            false => (responder, initiator),
+        };
+        Ok(Transport {
+            send,
+            receive,
+            hash: self.symmetric.hash,
+            window: ReplayWindow::new(DEFAULT_WINDOW),
+        })
+    }
+}
+
+/// An established session.
+#[derive(Clone)]
+pub struct Transport {
+    send: CipherState,
+    receive: CipherState,
+    hash: [u8; HASH_LEN],
+    window: ReplayWindow,
+}
+
+/// Sequence numbers [`Transport::decrypt_frame`] accepts behind the highest
+/// one, unless configured otherwise.
+pub const DEFAULT_WINDOW: u32 = 64;
+
+/// The sequence numbers seen within `size` of the highest one, as a bitmap
+/// with the highest in bit 0. Anything older is taken for a replay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayWindow {
+    size: u32,
+    highest: Option<u64>,
+    seen: u128,
+}
+
+impl ReplayWindow {
+    /// Panics unless `1 <= size <= 128`.
+    pub fn new(size: u32) -> Self {
+        assert!(
+            (1..=u128::BITS).contains(&size),
+            "window of {} frames",
+            size
+        );
+        Self {
+            size,
+            highest: None,
+            seen: 0,
+        }
+    }
+
+    /// Whether `sequence` is new and not too old.
+    pub fn check(&self, sequence: u64) -> bool {
+        match self.highest {
+            Some(highest) if sequence <= highest => {
+                let age = highest - sequence;
+                age < self.size as u64 && self.seen >> age & 1 == 0
+            }
+            _ => true,
+        }
+    }
+
+    /// Records `sequence` as seen; only call it after [`Self::check`] and
+    /// once the frame is authenticated.
+    pub fn accept(&mut self, sequence: u64) {
+        match self.highest {
+            Some(highest) if sequence <= highest => self.seen |= 1 << (highest - sequence),
+            highest => {
+                let shift = highest.map_or(u64::MAX, |h| sequence - h);
+                self.seen = self.seen.checked_shl(shift.min(128) as u32).unwrap_or(0) | 1;
+                self.highest = Some(sequence);
+            }
+        }
+    }
+}
+
+impl Transport {
+    /// Replaces the replay window of [`Self::decrypt_frame`] with an empty
+    /// one of `size` frames.
+    pub fn with_replay_window(mut self, size: u32) -> Self {
+        self.window = ReplayWindow::new(size);
+        self
+    }
+
+    pub fn encrypt(&mut self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        if payload.len() + TAG_LEN > MAX_MESSAGE {
+            return Err(Error::TooLarge { limit: MAX_MESSAGE });
+        }
+        self.send.encrypt_with_ad(&[], payload)
+    }
+
+    /// Messages have to arrive in order: the nonces are implicit.
+    pub fn decrypt(&mut self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        self.receive.decrypt_with_ad(&[], message)
+    }
+
+    /// [`Self::encrypt`] behind the nonce as a big-endian `u64` sequence
+    /// number, for transports that may lose or reorder messages.
+    pub fn encrypt_frame(&mut self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        let sequence = self.send.nonce;
+        Ok([&sequence.to_be_bytes()[..], &self.encrypt(payload)?].concat())
+    }
+
+    /// Inverse of [`Self::encrypt_frame`], in any order within the replay
+    /// window: [`Error::Replay`] for a frame seen before or too far behind
+    /// the newest. A session uses either frames or [`Self::decrypt`], not both.
+    pub fn decrypt_frame(&mut self, frame: &[u8]) -> Result<Vec<u8>, Error> {
+        let (sequence, message) = frame.split_first_chunk::<8>().ok_or(Error::Length {
+            expected: 8,
+            actual: frame.len(),
+        })?;
+        let sequence = u64::from_be_bytes(*sequence);
+        if !self.window.check(sequence) {
+            return Err(Error::Replay(sequence));
+        }
+        let payload = self.receive.decrypt_at(sequence, &[], message)?;
+        self.window.accept(sequence);
+        Ok(payload)
+    }
+
+    /// The handshake hash, equal on both sides, for channel binding.
+    pub fn handshake_hash(&self) -> [u8; HASH_LEN] {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        context::CurveContext,
+        curves::{toy, toy_order},
+        error::Error,
+        mod_field::ModField,
+    };
+
+    use super::{HandshakeState, Pattern, ReplayWindow, Transport};
+
+    type Ctx = CurveContext<ModField<u64>, u128>;
+
+    /// Runs the whole handshake with a payload on every message.
+    fn handshake<'c>(
+        mut initiator: HandshakeState<'c, ModField<u64>, u128>,
+        mut responder: HandshakeState<'c, ModField<u64>, u128>,
+        rng: &mut impl rand::Rng,
+    ) -> Result<(Transport, Transport), Error> {
+        let sides = [&mut initiator, &mut responder];
+        let mut i = 0;
+        while !sides[0].is_finished() {
+            let payload = format!("message {}", i);
+            let message = sides[i % 2].write_message(payload.as_bytes(), rng)?;
+            assert_eq!(sides[1 - i % 2].read_message(&message)?, payload.as_bytes());
+            i += 1;
+        }
+        assert!(responder.is_finished());
+        Ok((initiator.into_transport()?, responder.into_transport()?))
+    }
+
+    fn talk(a: &mut Transport, b: &mut Transport) {
+        assert_eq!(a.handshake_hash(), b.handshake_hash());
+        for text in [&b"hello"[..], b"", b"bye"] {
+            let message = a.encrypt(text).unwrap();
+            assert_eq!(message.len(), text.len() + 16);
+            assert!(text.is_empty() || message[..text.len()] != *text);
+            assert_eq!(b.decrypt(&message).unwrap(), text);
+            let reply = b.encrypt(text).unwrap();
+            assert_eq!(a.decrypt(&reply).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([9u8; 32]);
+        let ctx: Ctx = CurveContext::new(toy(), toy_order());
+        let (alice, alice_pub) = ctx.gen_keys(&mut rng);
+        let (bob, bob_pub) = ctx.gen_keys(&mut rng);
+        let new = |pattern, initiator, s, rs| {
+            HandshakeState::new(&ctx, "toy", pattern, initiator, b"chat v1", s, rs).unwrap()
+        };
+
+        let (mut a, mut b) = handshake(
+            new(Pattern::NN, true, None, None),
+            new(Pattern::NN, false, None, None),
+            &mut rng,
+        )
+        .unwrap();
+        talk(&mut a, &mut b);
+
+        let (mut a, mut b) = handshake(
+            new(Pattern::NK, true, None, Some(bob_pub)),
+            new(Pattern::NK, false, Some(bob), None),
+            &mut rng,
+        )
+        .unwrap();
+        talk(&mut a, &mut b);
+
+        let (mut a, mut b) = handshake(
+            new(Pattern::KK, true, Some(alice), Some(bob_pub)),
+            new(Pattern::KK, false, Some(bob), Some(alice_pub)),
+            &mut rng,
+        )
+        .unwrap();
+        talk(&mut a, &mut b);
+
+        let (initiator, responder) = (
+            new(Pattern::XX, true, Some(alice), None),
+            new(Pattern::XX, false, Some(bob), None),
+        );
+        let (mut a, mut b) = handshake(initiator, responder, &mut rng).unwrap();
+        talk(&mut b, &mut a);
+
+        // the static keys are learned along the way
+        let mut initiator = new(Pattern::XX, true, Some(alice), None);
+        let mut responder = new(Pattern::XX, false, Some(bob), None);
+        let m1 = initiator.write_message(b"", &mut rng).unwrap();
+        responder.read_message(&m1).unwrap();
+        let m2 = responder.write_message(b"", &mut rng).unwrap();
+        initiator.read_message(&m2).unwrap();
+        assert_eq!(initiator.remote_static(), Some(bob_pub));
+        let m3 = initiator.write_message(b"", &mut rng).unwrap();
+        responder.read_message(&m3).unwrap();
+        assert_eq!(responder.remote_static(), Some(alice_pub));
+    }
+
+    #[test]
+    fn replay_window() {
+        let mut window = ReplayWindow::new(4);
+        for sequence in [5, 3, 6, 4] {
+            assert!(window.check(sequence), "{}", sequence);
+            window.accept(sequence);
+        }
+        // seen, or 4 or more behind 6
+        for sequence in [6, 5, 4, 3, 2, 1] {
+            assert!(!window.check(sequence), "{}", sequence);
+        }
+        assert!(window.check(7));
+        window.accept(1000);
+        assert!(!window.check(996));
+        assert!(window.check(997));
+
+        let mut window = ReplayWindow::new(128);
+        window.accept(0);
+        window.accept(127);
+        assert!(!window.check(0));
+        assert!(window.check(1));
+    }
+
+    #[test]
+    fn frames() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([9u8; 32]);
+        let ctx: Ctx = CurveContext::new(toy(), toy_order());
+        let new = |initiator| {
+            HandshakeState::new(&ctx, "toy", Pattern::NN, initiator, b"", None, None).unwrap()
+        };
+        let (mut a, b) = handshake(new(true), new(false), &mut rng).unwrap();
+        let mut b = b.with_replay_window(3);
+        let frames: Vec<_> = (0..6u8).map(|i| a.encrypt_frame(&[i]).unwrap()).collect();
+
+        // lost and reordered within the window
+        assert_eq!(b.decrypt_frame(&frames[1]), Ok(vec![1]));
+        assert_eq!(b.decrypt_frame(&frames[0]), Ok(vec![0]));
+        assert_eq!(b.decrypt_frame(&frames[4]), Ok(vec![4]));
+        assert_eq!(b.decrypt_frame(&frames[1]), Err(Error::Replay(1)));
+        assert_eq!(b.decrypt_frame(&frames[4]), Err(Error::Replay(4)));
+        assert_eq!(b.decrypt_frame(&frames[2]), Ok(vec![2]));
+
+        // a forged sequence number neither decrypts nor moves the window
+        let mut forged = frames[5].clone();
+        forged[7] = 200;
+        assert_eq!(b.decrypt_frame(&forged), Err(Error::Tag));
+        assert_eq!(b.decrypt_frame(&frames[3]), Ok(vec![3]));
+        assert_eq!(b.decrypt_frame(&frames[5]), Ok(vec![5]));
+    }
+
+    #[test]
+    fn failures() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([9u8; 32]);
+        let ctx: Ctx = CurveContext::new(toy(), toy_order());
+        let (bob, _) = ctx.gen_keys(&mut rng);
+        let (_, eve_pub) = ctx.gen_keys(&mut rng);
+        let new = |pattern, initiator, prologue: &[u8], s, rs| {
+            HandshakeState::new(&ctx, "toy", pattern, initiator, prologue, s, rs)
+        };
+
+        // NK needs the responder's key up front, and to the right one
+        assert!(matches!(
+            new(Pattern::NK, true, b"", None, None),
+            Err(Error::Handshake)
+        ));
+        let wrong = handshake(
+            new(Pattern::NK, true, b"", None, Some(eve_pub)).unwrap(),
+            new(Pattern::NK, false, b"", Some(bob), None).unwrap(),
+            &mut rng,
+        );
+        assert_eq!(wrong.err(), Some(Error::Tag));
+
+        // a different prologue fails at the first encrypted payload
+        let different = handshake(
+            new(Pattern::NN, true, b"v1", None, None).unwrap(),
+            new(Pattern::NN, false, b"v2", None, None).unwrap(),
+            &mut rng,
+        );
+        assert_eq!(different.err(), Some(Error::Tag));
+
+        let mut initiator = new(Pattern::NN, true, b"", None, None).unwrap();
+        assert_eq!(initiator.read_message(&[]), Err(Error::Handshake));
+        initiator.write_message(b"", &mut rng).unwrap();
+        assert_eq!(
+            initiator.write_message(b"", &mut rng),
+            Err(Error::Handshake)
+        );
+        assert!(initiator.into_transport().is_err());
+
+        let (mut a, mut b) = handshake(
+            new(Pattern::NN, true, b"", None, None).unwrap(),
+            new(Pattern::NN, false, b"", None, None).unwrap(),
+            &mut rng,
+        )
+        .unwrap();
+        let mut message = a.encrypt(b"pay 10").unwrap();
+        message[0] ^= 1;
+        assert_eq!(b.decrypt(&message), Err(Error::Tag));
+        // out of order, i.e. a skipped nonce
+        a.encrypt(b"first").unwrap();
+        let second = a.encrypt(b"second").unwrap();
+        assert_eq!(b.decrypt(&second), Err(Error::Tag));
+    }
+}
@@ -0,0 +1,545 @@
+//! A minimal slice of OpenPGP (RFC 4880): v4 public key packets for ECDSA
+//! keys on the curves of [`NamedCurve`] (RFC 6637), and v4 signature packets
+//! over SHA-256, enough to sign files for `gpg --verify` and check
+//! `gpg --detach-sign` output. Packets are read in either header format and
+//! written in the new one, without partial lengths.
+//!
+//! ```text
+//! public key:  version (4) | created (4) | algorithm | OID length | curve OID
+//!              | MPI of the SEC1 point
+//! signature:   version (4) | type | algorithm | hash algorithm
+//!              | hashed subpackets (2-byte length) | unhashed subpackets
+//!              | first 2 digest bytes | MPI r | MPI s
+//! ```
+//!
+//! EdDSA signatures parse the same way, but there's no Ed25519 here to check
+//! them with, so they never verify.
+
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    base_traits::{Endianness, RW},
+    ecc::PrivateKey,
+    ecdsa,
+    uint::U256,
+    x509::{NamedCurve, SubjectPublicKey},
+};
+
+pub const SIGNATURE_LABEL: &str = "PGP SIGNATURE";
+pub const PUBLIC_KEY_LABEL: &str = "PGP PUBLIC KEY BLOCK";
+
+const TAG_SIGNATURE: u8 = 2;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_USER_ID: u8 = 13;
+
+pub const ECDSA: u8 = 19;
+pub const EDDSA: u8 = 22;
+const SHA256: u8 = 8;
+
+/// Signature types.
+pub const BINARY_DOCUMENT: u8 = 0x00;
+pub const POSITIVE_CERTIFICATION: u8 = 0x13;
+
+const SUB_CREATED: u8 = 2;
+const SUB_ISSUER: u8 = 16;
+const SUB_KEY_FLAGS: u8 = 27;
+const SUB_ISSUER_FINGERPRINT: u8 = 33;
+
+/// Key flags: may certify and sign.
+const CERTIFY_SIGN: u8 = 0x03;
+
+/// A packet with a new-format header.
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let len = body.len();
+    let mut res = vec![0xC0 | tag];
+    match len {
+        0..192 => res.push(len as u8),
+        192..8384 => res.extend([((len - 192) >> 8) as u8 + 192, (len - 192) as u8]),
+        _ => {
+            res.push(0xFF);
+            res.extend((len as u32).to_be_bytes());
+        }
+    }
+    res.extend(body);
+    res
+}
+
+/// The tag and body of the packet at the start of `input`, and what's after.
+fn read_packet(input: &[u8]) -> Result<(u8, &[u8], &[u8]), String> {
+    let truncated = || "truncated packet".to_string();
+    let (&ctb, rest) = input.split_first().ok_or_else(truncated)?;
+    if ctb & 0x80 == 0 {
+        return Err("not an OpenPGP packet".to_string());
+    }
+    let (tag, len, rest) = if ctb & 0x40 != 0 {
+        let (len, rest) = match rest {
+            [a @ 0..192, rest @ ..] => (*a as usize, rest),
+            [a @ 192..224, b, rest @ ..] => (((*a as usize - 192) << 8) + *b as usize + 192, rest),
+            [0xFF, a, b, c, d, rest @ ..] => (u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest),
+            [224..=254, ..] => return Err("partial lengths aren't supported".to_string()),
+            _ => return Err(truncated()),
+        };
+        (ctb & 0x3F, len, rest)
+    } else {
+        let width = match ctb & 0x03 {
+            0 => 1,
+            1 => 2,
+            2 => 4,
+            _ => return Err("indeterminate lengths aren't supported".to_string()),
+        };
+        if rest.len() < width {
+            return Err(truncated());
+        }
+        let (len, rest) = rest.split_at(width);
+        let len = len.iter().fold(0, |acc, &b| acc << 8 | b as usize);
+        ((ctb >> 2) & 0x0F, len, rest)
+    };
+    if rest.len() < len {
+        return Err(truncated());
+    }
+    let (body, rest) = rest.split_at(len);
+    Ok((tag, body, rest))
+}
+
+/// A multiprecision integer: its length in bits, then big-endian bytes.
+fn mpi(bytes: &[u8]) -> Vec<u8> {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let bytes = &bytes[zeros..];
+    let bits = bytes
+        .first()
+        .map_or(0, |&b| 8 * bytes.len() - b.leading_zeros() as usize);
+    [(bits as u16).to_be_bytes().to_vec(), bytes.to_vec()].concat()
+}
+
+fn read_mpi(input: &[u8]) -> Result<(&[u8], &[u8]), String> {
+    let [a, b, rest @ ..] = input else {
+        return Err("truncated MPI".to_string());
+    };
+    let len = (u16::from_be_bytes([*a, *b]) as usize).div_ceil(8);
+    if rest.len() < len {
+        return Err("truncated MPI".to_string());
+    }
+    Ok(rest.split_at(len))
+}
+
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    assert!(data.len() < 191);
+    [&[data.len() as u8 + 1, kind], data].concat()
+}
+
+/// The data of the first subpacket of `kind` in `subpackets`; the critical
+/// bit is ignored.
+fn find_subpacket(mut subpackets: &[u8], kind: u8) -> Option<&[u8]> {
+    while !subpackets.is_empty() {
+        let (len, rest) = match subpackets {
+            [a @ 0..192, rest @ ..] => (*a as usize, rest),
+            [a @ 192..=254, b, rest @ ..] => (((*a as usize - 192) << 8) + *b as usize + 192, rest),
+            [0xFF, a, b, c, d, rest @ ..] => (u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest),
+            _ => return None,
+        };
+        if len == 0 || rest.len() < len {
+            return None;
+        }
+        let (sub, rest) = rest.split_at(len);
+        if sub[0] & 0x7F == kind {
+            return Some(&sub[1..]);
+        }
+        subpackets = rest;
+    }
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKeyPacket {
+    /// Unix seconds, part of the fingerprint.
+    pub created: u32,
+    pub public: SubjectPublicKey,
+}
+
+impl PublicKeyPacket {
+    fn body(&self) -> Vec<u8> {
+        let oid = self.public.curve.oid();
+        let mut res = vec![4];
+        res.extend(self.created.to_be_bytes());
+        res.push(ECDSA);
+        res.push(oid.len() as u8);
+        res.extend(oid);
+        res.extend(mpi(&self.public.to_sec1()));
+        res
+    }
+
+    /// What signatures over the key hash first.
+    fn hash_input(&self) -> Vec<u8> {
+        let body = self.body();
+        [vec![0x99], (body.len() as u16).to_be_bytes().to_vec(), body].concat()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        packet(TAG_PUBLIC_KEY, &self.body())
+    }
+
+    /// The first packet of `input`, which makes this read the primary key of
+    /// a `gpg --export` too.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, String> {
+        let (tag, body, _) = read_packet(input)?;
+        if tag != TAG_PUBLIC_KEY {
+            return Err(format!("expected a public key packet, found tag {}", tag));
+        }
+        let [4, t0, t1, t2, t3, algorithm, oid_len, rest @ ..] = body else {
+            return Err("only v4 keys are supported".to_string());
+        };
+        if *algorithm != ECDSA {
+            return Err(format!("unsupported public key algorithm {}", algorithm));
+        }
+        let oid_len = *oid_len as usize;
+        if rest.len() < oid_len {
+            return Err("truncated curve OID".to_string());
+        }
+        let (oid, rest) = rest.split_at(oid_len);
+        let curve = NamedCurve::from_oid(oid).ok_or("unsupported curve")?;
+        let (point, _) = read_mpi(rest)?;
+        Ok(Self {
+            created: u32::from_be_bytes([*t0, *t1, *t2, *t3]),
+            public: SubjectPublicKey::from_sec1(curve, point)?,
+        })
+    }
+
+    /// SHA-1 of the key, as RFC 4880 has it for v4 keys.
+    pub fn fingerprint(&self) -> [u8; 20] {
+        Sha1::digest(self.hash_input()).into()
+    }
+
+    /// The last 8 bytes of the fingerprint.
+    pub fn key_id(&self) -> [u8; 8] {
+        self.fingerprint()[12..].try_into().unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignaturePacket {
+    pub sig_type: u8,
+    pub algorithm: u8,
+    pub hash_algorithm: u8,
+    /// Raw subpackets; only the hashed ones are signed.
+    pub hashed: Vec<u8>,
+    pub unhashed: Vec<u8>,
+    /// The first two bytes of the digest, a quick check before verifying.
+    pub digest_prefix: [u8; 2],
+    /// Big-endian.
+    pub r: Vec<u8>,
+    pub s: Vec<u8>,
+}
+
+impl SignaturePacket {
+    /// The signed part of the packet.
+    fn hashed_part(&self) -> Vec<u8> {
+        let mut res = vec![4, self.sig_type, self.algorithm, self.hash_algorithm];
+        res.extend((self.hashed.len() as u16).to_be_bytes());
+        res.extend(&self.hashed);
+        res
+    }
+
+    /// SHA-256 of what's signed, then the hashed part and its trailer.
+    fn digest(&self, signed: &[u8]) -> [u8; 32] {
+        let hashed = self.hashed_part();
+        Sha256::new()
+            .chain_update(signed)
+            .chain_update(&hashed)
+            .chain_update([4, 0xFF])
+            .chain_update((hashed.len() as u32).to_be_bytes())
+            .finalize()
+            .into()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = self.hashed_part();
+        body.extend((self.unhashed.len() as u16).to_be_bytes());
+        body.extend(&self.unhashed);
+        body.extend(self.digest_prefix);
+        body.extend(mpi(&self.r));
+        body.extend(mpi(&self.s));
+        packet(TAG_SIGNATURE, &body)
+    }
+
+    /// The first packet of `input`, which has to be a signature.
+    pub fn from_bytes(input: &[u8]) -> Result<Self, String> {
+        let (tag, body, _) = read_packet(input)?;
+        if tag != TAG_SIGNATURE {
+            return Err(format!("expected a signature packet, found tag {}", tag));
+        }
+        let [4, sig_type, algorithm, hash_algorithm, rest @ ..] = body else {
+            return Err("only v4 signatures are supported".to_string());
+        };
+        let subpackets = |input: &[u8]| -> Result<(Vec<u8>, usize), String> {
+            let [a, b, rest @ ..] = input else {
+                return Err("truncated signature".to_string());
+            };
+            let len = u16::from_be_bytes([*a, *b]) as usize;
+            match rest.get(..len) {
+                Some(sub) => Ok((sub.to_vec(), 2 + len)),
+                None => Err("truncated subpackets".to_string()),
+            }
+        };
+        let (hashed, read) = subpackets(rest)?;
+        let rest = &rest[read..];
+        let (unhashed, read) = subpackets(rest)?;
+        let [p0, p1, rest @ ..] = &rest[read..] else {
+            return Err("truncated signature".to_string());
+        };
+        let (r, rest) = read_mpi(rest)?;
+        let (s, _) = read_mpi(rest)?;
+        Ok(Self {
+            sig_type: *sig_type,
+            algorithm: *algorithm,
+            hash_algorithm: *hash_algorithm,
+            hashed,
+            unhashed,
+            digest_prefix: [*p0, *p1],
+            r: r.to_vec(),
+            s: s.to_vec(),
+        })
+    }
+
+    /// Unix seconds, from the hashed subpackets only.
+    pub fn created(&self) -> Option<u32> {
+        let time = find_subpacket(&self.hashed, SUB_CREATED)?;
+        Some(u32::from_be_bytes(time.try_into().ok()?))
+    }
+
+    /// The key ID of the signer, from either the issuer or the issuer
+    /// fingerprint subpacket. Neither is signed necessarily, so this only
+    /// says which key to try.
+    pub fn issuer(&self) -> Option<[u8; 8]> {
+        for subpackets in [&self.hashed, &self.unhashed] {
+            if let Some(id) = find_subpacket(subpackets, SUB_ISSUER) {
+                return id.try_into().ok();
+            }
+            if let Some([4, fingerprint @ ..]) = find_subpacket(subpackets, SUB_ISSUER_FINGERPRINT)
+            {
+                return fingerprint.get(12..)?.try_into().ok();
+            }
+        }
+        None
+    }
+
+    fn verify_digest(&self, key: &PublicKeyPacket, signed: &[u8]) -> bool {
+        if self.algorithm != ECDSA || self.hash_algorithm != SHA256 {
+            return false;
+        }
+        let digest = self.digest(signed);
+        if digest[..2] != self.digest_prefix {
+            return false;
+        }
+        let natural = |bytes: &[u8]| {
+            (bytes.len() <= U256::LEN).then(|| {
+                let be = [vec![0; U256::LEN - bytes.len()], bytes.to_vec()].concat();
+                U256::from_bytes_endian(&mut &be[..], Endianness::Big)
+            })
+        };
+        let (Some(r), Some(s)) = (natural(&self.r), natural(&self.s)) else {
+            return false;
+        };
+        let curve = key.public.curve;
+        ecdsa::verify_digest(
+            key.public.key,
+            &digest,
+            (r, s),
+            &curve.cfg(),
+            &curve.order(),
+        )
+    }
+
+    /// Whether this is `key`'s signature of `data` as a binary document, as
+    /// `gpg --detach-sign` makes them.
+    pub fn verify(&self, key: &PublicKeyPacket, data: &[u8]) -> bool {
+        self.sig_type == BINARY_DOCUMENT && self.verify_digest(key, data)
+    }
+
+    /// Whether this is `key`'s self-signature over `user_id`.
+    pub fn verify_certification(&self, key: &PublicKeyPacket, user_id: &str) -> bool {
+        (0x10..=0x13).contains(&self.sig_type)
+            && self.verify_digest(key, &certification_input(key, user_id))
+    }
+}
+
+fn certification_input(key: &PublicKeyPacket, user_id: &str) -> Vec<u8> {
+    let mut res = key.hash_input();
+    res.push(0xB4);
+    res.extend((user_id.len() as u32).to_be_bytes());
+    res.extend(user_id.as_bytes());
+    res
+}
+
+fn sign(
+    secret: PrivateKey<U256>,
+    key: &PublicKeyPacket,
+    sig_type: u8,
+    mut hashed: Vec<u8>,
+    signed: &[u8],
+    created: u32,
+    rng: &mut impl Rng,
+) -> SignaturePacket {
+    let fingerprint = [&[4], &key.fingerprint()[..]].concat();
+    hashed.extend(subpacket(SUB_ISSUER_FINGERPRINT, &fingerprint));
+    hashed.extend(subpacket(SUB_CREATED, &created.to_be_bytes()));
+    let mut sig = SignaturePacket {
+        sig_type,
+        algorithm: ECDSA,
+        hash_algorithm: SHA256,
+        hashed,
+        unhashed: subpacket(SUB_ISSUER, &key.key_id()),
+        digest_prefix: [0; 2],
+        r: vec![],
+        s: vec![],
+    };
+    let digest = sig.digest(signed);
+    let curve = key.public.curve;
+    let (r, s) = ecdsa::sign_digest(secret, &digest, rng, &curve.cfg(), &curve.order());
+    sig.digest_prefix = [digest[0], digest[1]];
+    sig.r = r.to_vec_endian(Endianness::Big);
+    sig.s = s.to_vec_endian(Endianness::Big);
+    sig
+}
+
+/// A detached signature of `data` made at `created`, for
+/// `gpg --verify sig data` once the key is imported.
+pub fn sign_detached(
+    secret: PrivateKey<U256>,
+    key: &PublicKeyPacket,
+    data: &[u8],
+    created: u32,
+    rng: &mut impl Rng,
+) -> SignaturePacket {
+    sign(secret, key, BINARY_DOCUMENT, vec![], data, created, rng)
+}
+
+/// The key, a user ID and the self-signature binding them, which is the
+/// least `gpg --import` takes.
+pub fn export_public_key(
+    secret: PrivateKey<U256>,
+    key: &PublicKeyPacket,
+    user_id: &str,
+    rng: &mut impl Rng,
+) -> Vec<u8> {
+    let flags = subpacket(SUB_KEY_FLAGS, &[CERTIFY_SIGN]);
+    let signed = certification_input(key, user_id);
+    let sig = sign(
+        secret,
+        key,
+        POSITIVE_CERTIFICATION,
+        flags,
+        &signed,
+        key.created,
+        rng,
+    );
+    [
+        key.to_bytes(),
+        packet(TAG_USER_ID, user_id.as_bytes()),
+        sig.to_bytes(),
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        armor,
+        ct_codec::{hex_decode, hex_encode},
+        ecc::PrivateKey,
+        x509::{NamedCurve, SubjectPublicKey},
+    };
+
+    use super::{
+        export_public_key, read_packet, sign_detached, PublicKeyPacket, SignaturePacket,
+        SIGNATURE_LABEL,
+    };
+
+    const MESSAGE: &[u8] = b"hello, detached\n";
+
+    // gpg --quick-gen-key 'toy <toy@example.org>' nistp256 sign; gpg --export
+    const KEY: &str = concat!(
+        "9852046ad1f13213082a8648ce3d030107020304c4621cb3745bdcdde19ceeb6d510157a",
+        "725aa4dec41ef102b5ad6e5171a8f15c1ecb7002e272ca059fdd773b189d2f195ae94bf7",
+        "f7464c992bb0fa963a60bd35b415746f79203c746f79406578616d706c652e6f72673e88",
+        "900413130800381621048822fc4967f08b77c0d88adf9590243e9a7030f405026ad1f132",
+        "021b03050b0908070206150a09080b020416020301021e01021780000a09109590243e9a",
+        "7030f43a280100f3d0df8069d3508d73d538f288f67afe4568633d031bbe3838b699cf1d",
+        "823c410100e9da47d2fcbe7bb25049fbf94143b16925d79031676333e5b21a75b99cdcdc",
+        "51",
+    );
+
+    // gpg --digest-algo SHA256 --detach-sign, of MESSAGE
+    const SIGNATURE: &str = concat!(
+        "887504001308001d1621048822fc4967f08b77c0d88adf9590243e9a7030f405026ad1f1",
+        "32000a09109590243e9a7030f4d70600fe3f65d27d1088ec0b45ca871cbc52ff704bee54",
+        "252d8c7cabd9e776d93e86e9330100c92141a618f384c730e1f3ee771d2bfba838f0d4e8",
+        "f9348e0ab63764afc6101c",
+    );
+
+    #[test]
+    fn reads_gpg_packets() {
+        let export = hex_decode(KEY).unwrap();
+        let key = PublicKeyPacket::from_bytes(&export).unwrap();
+        assert_eq!(key.public.curve, NamedCurve::P256);
+        assert_eq!(
+            hex_encode(&key.fingerprint()),
+            "8822fc4967f08b77c0d88adf9590243e9a7030f4"
+        );
+        assert_eq!(key.to_bytes()[1..], export[1..2 + 82]);
+
+        let bytes = hex_decode(SIGNATURE).unwrap();
+        let sig = SignaturePacket::from_bytes(&bytes).unwrap();
+        assert_eq!(sig.issuer(), Some(key.key_id()));
+        assert_eq!(sig.created(), Some(key.created));
+        assert_eq!(sig.r.len(), 32);
+        // written back with a new-format header
+        assert_eq!(sig.to_bytes()[1..], bytes[1..]);
+        assert_eq!(read_packet(&sig.to_bytes()).unwrap().1, &bytes[2..]);
+        assert!(SignaturePacket::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(SignaturePacket::from_bytes(&export).is_err());
+    }
+
+    #[test]
+    #[ignore = "slow without optimizations"]
+    fn interop() {
+        let export = hex_decode(KEY).unwrap();
+        let key = PublicKeyPacket::from_bytes(&export).unwrap();
+        let sig = SignaturePacket::from_bytes(&hex_decode(SIGNATURE).unwrap()).unwrap();
+        assert!(sig.verify(&key, MESSAGE));
+        assert!(!sig.verify(&key, b"hello, attached\n"));
+        let (_, _, rest) = read_packet(&export).unwrap();
+        let (_, _, rest) = read_packet(rest).unwrap();
+        let self_sig = SignaturePacket::from_bytes(rest).unwrap();
+        assert!(self_sig.verify_certification(&key, "toy <toy@example.org>"));
+        assert!(!self_sig.verify_certification(&key, "toy <toy@example.com>"));
+
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([4u8; 32]);
+        let curve = NamedCurve::Secp256k1;
+        let secret = PrivateKey::random(&mut rng, &curve.order());
+        let ours = PublicKeyPacket {
+            created: 1_700_000_000,
+            public: SubjectPublicKey {
+                curve,
+                key: secret.public_key(&curve.cfg()),
+            },
+        };
+        let sig = sign_detached(secret, &ours, MESSAGE, 1_700_000_001, &mut rng);
+        let armored = armor::armor(SIGNATURE_LABEL, &sig.to_bytes());
+        let (_, bytes) = armor::dearmor(&armored).unwrap();
+        let sig = SignaturePacket::from_bytes(&bytes).unwrap();
+        assert_eq!(sig.created(), Some(1_700_000_001));
+        assert!(sig.verify(&ours, MESSAGE));
+        assert!(!sig.verify(&key, MESSAGE));
+
+        let export = export_public_key(secret, &ours, "ours", &mut rng);
+        assert_eq!(PublicKeyPacket::from_bytes(&export), Ok(ours));
+        let (_, _, rest) = read_packet(&export).unwrap();
+        let (_, _, rest) = read_packet(rest).unwrap();
+        let self_sig = SignaturePacket::from_bytes(rest).unwrap();
+        assert!(self_sig.verify_certification(&ours, "ours"));
+    }
+}
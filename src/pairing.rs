@@ -0,0 +1,239 @@
+//! Miller's algorithm with the Weil and reduced Tate pairings it gives, and
+//! the MOV reduction built on them, for toy curves of embedding degree one:
+//! `r | p - 1`, so the pairing values live in `F_p*` and no extension field is
+//! needed. The Weil pairing further needs all of `E[r]` over `F_p`.
+//!
+//! Functions are evaluated at points shifted by a random `S` to stay clear of
+//! their zeros and poles, retrying when that fails.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{CommutativeOp, Field, Inverse, Ring},
+    base_traits::{FromRandom, Natural},
+    mod_field::ModField,
+    points_group::{Point, PointCfg},
+};
+
+type Pt<I> = Point<ModField<I>>;
+
+/// The line through `a` and `b` over the vertical line through `a + b`,
+/// evaluated at `s`, and `a + b`. `None` if `s` is a zero or a pole.
+fn line<F: Field>(a: Point<F>, b: Point<F>, s: Point<F>, c: &PointCfg<F>) -> Option<(F, Point<F>)> {
+    let cf = &c.cf;
+    let zero = F::zero(cf);
+    let sum = Point::op(a, b, c);
+    if sum.is_infinity() {
+        let v = F::sub(s.x(), a.x(), cf);
+        return (v != zero).then_some((v, sum));
+    }
+    let (n, d) = Point::slope(a, b, c);
+    let l = F::div(n, d, cf);
+    let num = F::sub(
+        F::sub(s.y(), a.y(), cf),
+        F::mul(l, F::sub(s.x(), a.x(), cf), cf),
+        cf,
+    );
+    let den = F::sub(s.x(), sum.x(), cf);
+    (num != zero && den != zero).then(|| (F::div(num, den, cf), sum))
+}
+
+/// `f(s)` for the Miller function `f` with divisor
+/// `k·(p) - (k·p) - (k - 1)·(O)`, along with `k·p`. `None` if `s` is a zero
+/// or a pole, or if a multiple of `p` short of `k·p` is the point at
+/// infinity.
+pub fn miller_loop<F: Field, I: Natural>(
+    p: Point<F>,
+    k: I,
+    s: Point<F>,
+    c: &PointCfg<F>,
+) -> Option<(F, Point<F>)> {
+    let mut bits = vec![];
+    let mut k = k;
+    while k != I::zero() {
+        bits.push(k % I::two() == I::one());
+        k = k / I::two();
+    }
+    let finite = |t: Point<F>| (!t.is_infinity()).then_some(t);
+    let (mut t, mut f) = (p, F::one(&c.cf));
+    for &bit in bits.iter().rev().skip(1) {
+        let (g, doubled) = finite(t).and_then(|t| line(t, t, s, c))?;
+        f = F::mul(f.sqr(&c.cf), g, &c.cf);
+        t = doubled;
+        if bit {
+            let (g, sum) = finite(t).and_then(|t| line(t, p, s, c))?;
+            f = F::mul(f, g, &c.cf);
+            t = sum;
+        }
+    }
+    Some((f, t))
+}
+
+/// `f(s)` for the Miller function `f` of `p`, with divisor `r·(p) - r·(O)`,
+/// `p` of order `r`. `None` if `s` is a zero or a pole.
+pub fn miller<F: Field, I: Natural>(p: Point<F>, r: I, s: Point<F>, c: &PointCfg<F>) -> Option<F> {
+    let (f, t) = miller_loop(p, r, s, c)?;
+    assert!(t.is_infinity(), "the point's order isn't r");
+    Some(f)
+}
+
+/// `f(a) / f(b)` for the Miller function of `p`. `None` at the point at
+/// infinity, where `f` has its pole.
+fn miller_ratio<I: Natural>(
+    p: Pt<I>,
+    r: I,
+    a: Pt<I>,
+    b: Pt<I>,
+    c: &PointCfg<ModField<I>>,
+) -> Option<ModField<I>> {
+    if a.is_infinity() || b.is_infinity() {
+        return None;
+    }
+    let (fa, fb) = (miller(p, r, a, c)?, miller(p, r, b, c)?);
+    Some(ModField::div(fa, fb, &c.cf))
+}
+
+/// The Weil pairing `e_r(p, q)` of two points of order `r`.
+pub fn weil<I, R>(p: Pt<I>, q: Pt<I>, r: I, c: &PointCfg<ModField<I>>, rng: &mut R) -> ModField<I>
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    loop {
+        let s = Point::random(rng, c);
+        let minus_s = Inverse::inv(s, c);
+        let fp = miller_ratio(p, r, Point::op(q, s, c), s, c);
+        let fq = miller_ratio(q, r, Point::op(p, minus_s, c), minus_s, c);
+        if let (Some(fp), Some(fq)) = (fp, fq) {
+            return ModField::div(fp, fq, &c.cf);
+        }
+    }
+}
+
+/// The reduced Tate pairing `f_p(q)^((p - 1) / r)`, `p` of order `r`.
+pub fn tate<I, R>(p: Pt<I>, q: Pt<I>, r: I, c: &PointCfg<ModField<I>>, rng: &mut R) -> ModField<I>
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    let exponent = (c.cf.rem - I::one()) / r;
+    loop {
+        let s = Point::random(rng, c);
+        if let Some(f) = miller_ratio(p, r, Point::op(q, s, c), s, c) {
+            return f.pow(exponent, &c.cf);
+        }
+    }
+}
+
+/// The MOV attack: `k` with `q = k·p` for `p` of order `r`, by moving the
+/// discrete logarithm into `F_p*` with the Tate pairing. That one's solved by
+/// brute force here, so only for toy sizes. `None` if the pairing is
+/// degenerate on `p`.
+pub fn mov_discrete_log<I, R>(
+    p: Pt<I>,
+    q: Pt<I>,
+    r: I,
+    c: &PointCfg<ModField<I>>,
+    rng: &mut R,
+) -> Option<I>
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    let (g, h) = (tate(p, p, r, c, rng), tate(p, q, r, c, rng));
+    let one = ModField::one(&c.cf);
+    if g == one {
+        return None;
+    }
+    let (mut k, mut acc) = (I::zero(), one);
+    while acc != h {
+        acc = ModField::mul(acc, g, &c.cf);
+        k = k + I::one();
+    }
+    Some(k)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeOp, Inverse, Ring},
+        mod_field::{ModField, ModFieldCfg},
+        points_group::{Point, PointCfg},
+    };
+
+    use super::{mov_discrete_log, tate, weil};
+
+    fn curve(p: u64, a: u64, b: u64) -> PointCfg<ModField<u64>> {
+        let cf = ModFieldCfg { rem: p };
+        let (a, b) = (ModField::new(a, &cf), ModField::new(b, &cf));
+        let mut c = PointCfg {
+            g: Point::new_unsafe(a, b),
+            a,
+            b,
+            cf,
+        };
+        c.g = Point::random(&mut rand_chacha::ChaCha8Rng::from_seed([3u8; 32]), &c);
+        c
+    }
+
+    #[test]
+    fn weil_bilinear() {
+        // y² = x³ + 4 over F_547 has 3 · 13² points, all of E[13] among them
+        let c = curve(547, 0, 4);
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let torsion = |rng: &mut rand_chacha::ChaCha8Rng| loop {
+            if let Some(p) = Point::random(rng, &c).clear_cofactor(3u64, &c) {
+                return p;
+            }
+        };
+        let (p, mut q) = (torsion(&mut rng), torsion(&mut rng));
+        while weil(p, q, 13, &c, &mut rng) == ModField::one(&c.cf) {
+            q = torsion(&mut rng);
+        }
+        let e = weil(p, q, 13, &c, &mut rng);
+        assert_eq!(e.pow(13u64, &c.cf), ModField::one(&c.cf));
+        assert_eq!(weil(p, p, 13, &c, &mut rng), ModField::one(&c.cf));
+        assert_eq!(
+            ModField::mul(e, weil(q, p, 13, &c, &mut rng), &c.cf),
+            ModField::one(&c.cf)
+        );
+        for (i, j) in [(2u64, 3u64), (5, 7), (12, 1)] {
+            let (pi, qj) = (Point::exp(p, i, &c), Point::exp(q, j, &c));
+            assert_eq!(weil(pi, qj, 13, &c, &mut rng), e.pow(i * j, &c.cf));
+        }
+        let sum = Point::op(p, q, &c);
+        assert_eq!(
+            weil(sum, q, 13, &c, &mut rng),
+            weil(p, q, 13, &c, &mut rng),
+            "e(p + q, q) = e(p, q) e(q, q)"
+        );
+        let minus = Inverse::inv(p, &c);
+        assert_eq!(
+            ModField::mul(weil(minus, q, 13, &c, &mut rng), e, &c.cf),
+            ModField::one(&c.cf)
+        );
+    }
+
+    #[test]
+    fn mov() {
+        // y² = x³ + x + 3 over F_24407 has 2 · 12203 points, and 12203 | p - 1
+        let c = curve(24407, 1, 3);
+        let r = 12203u64;
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let p = loop {
+            if let Some(p) = Point::random(&mut rng, &c).clear_cofactor(2u64, &c) {
+                break p;
+            }
+        };
+        let t = tate(p, p, r, &c, &mut rng);
+        assert_ne!(t, ModField::one(&c.cf));
+        assert_eq!(t.pow(r, &c.cf), ModField::one(&c.cf));
+        for k in [1u64, 2, 1000, 12202] {
+            let q = Point::exp(p, k, &c);
+            assert_eq!(tate(p, q, r, &c, &mut rng), t.pow(k, &c.cf));
+            assert_eq!(mov_discrete_log(p, q, r, &c, &mut rng), Some(k));
+        }
+    }
+}
@@ -1,16 +1,55 @@
-use std::io::{Read, Write};
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+    str::FromStr,
+};
 
 use rand::Rng;
 
 use crate::{
-    algebra::{self, CommutativeOp, Configurable, DiscreteRoot, Field, InitialPoint, Inverse},
-    base_traits::{FromRandom, RW},
+    algebra::{
+        self, AbelianGroup, CommutativeMonoid, CommutativeOp, ConditionalSwap, Configurable,
+        DiscreteRoot, Field, Identity, InitialPoint, Inverse, Ring,
+    },
+    base_traits::{Endianness, FromRandom, Natural, Parity, RW},
+    batch::batch_inverse,
+    error::Error,
+    mod_field::ModField,
+    projective::ProjectivePoint,
 };
 
+/// An affine point or the point at infinity, which has zero coordinates and
+/// the flag set so that it equals only itself.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Point<F> {
     x: F,
     y: F,
+    infinity: bool,
+}
+
+/// SEC1's encoding of the point at infinity.
+pub const SEC1_INFINITY: u8 = 0x00;
+
+/// Every byte of the point at infinity in the [`RW`] form.
+const INFINITY_BYTE: u8 = 0xff;
+
+/// The affine form, the one that is serialized. Sums are cheaper to
+/// accumulate in [`ProjectivePoint`](crate::projective::ProjectivePoint).
+pub type AffinePoint<F> = Point<F>;
+
+/// How much to check of a point from outside before using it. Skipping the
+/// checks is only sound for points that can't be attacker-chosen; an
+/// off-curve point lands the arithmetic on another, possibly weak curve
+/// (invalid curve attack), and one outside the subgroup leaks the private
+/// scalar modulo the small orders (small subgroup attack). The subgroup check
+/// costs a scalar multiplication and is redundant with a cofactor of 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationPolicy<I> {
+    None,
+    /// On the curve, with canonical coordinates.
+    OnCurve,
+    /// On the curve and in the subgroup of the given prime order.
+    OnCurveAndSubgroup(I),
 }
 
 pub struct PointCfg<F: Field> {
@@ -26,54 +65,266 @@ impl<F: Field> Configurable for Point<F> {
 
 impl<F: Field> Point<F> {
     pub fn new_unsafe(x: F, y: F) -> Self {
-        Self { x, y }
+        Self {
+            x,
+            y,
+            infinity: false,
+        }
     }
 
     pub fn new(x: F, y: F, cp: &<Self as Configurable>::Cfg) -> Self {
-        let lhs = y.sqr(&cp.cf);
-        let rhs = F::add(
-            F::add(x.cube(&cp.cf), F::mul(cp.a, x, &cp.cf), &cp.cf),
-            cp.b,
-            &cp.cf,
-        );
-        assert!(lhs == rhs);
-        Self { x, y }
+        let p = Self::new_unsafe(x, y);
+        assert!(p.is_on(cp));
+        p
+    }
+
+    /// The identity of the group, also [`Identity::identity`].
+    pub fn infinity(cp: &<Self as Configurable>::Cfg) -> Self {
+        Self {
+            x: F::zero(&cp.cf),
+            y: F::zero(&cp.cf),
+            infinity: true,
+        }
+    }
+
+    pub fn is_infinity(self) -> bool {
+        self.infinity
+    }
+
+    /// The point itself if it passes the checks of `policy`. Every policy
+    /// but `None` rejects the point at infinity, which is no one's key.
+    pub fn validate<I: Natural>(
+        self,
+        policy: ValidationPolicy<I>,
+        cp: &<Self as Configurable>::Cfg,
+    ) -> Result<Self, Error> {
+        let on_curve = || self.is_on(cp);
+        match policy {
+            ValidationPolicy::None => Ok(self),
+            _ if !(self.x.is_canonical(&cp.cf) && self.y.is_canonical(&cp.cf)) => {
+                Err(Error::NonCanonical)
+            }
+            ValidationPolicy::OnCurve | ValidationPolicy::OnCurveAndSubgroup(_)
+                if self.infinity || !on_curve() =>
+            {
+                Err(Error::Point)
+            }
+            ValidationPolicy::OnCurveAndSubgroup(n) if !self.in_subgroup(n, cp) => {
+                Err(Error::Subgroup)
+            }
+            _ => Ok(self),
+        }
+    }
+
+    pub(crate) fn is_on(self, cp: &<Self as Configurable>::Cfg) -> bool {
+        self.infinity || self.y.sqr(&cp.cf) == Self::curve_rhs(self.x, cp)
+    }
+
+    /// Whether `a + b` is the point at infinity: `b = -a`, which includes
+    /// doubling a point with `y = 0`, the one case of a vertical tangent.
+    fn sums_to_infinity(a: Self, b: Self, cp: &<Self as Configurable>::Cfg) -> bool {
+        !a.infinity && !b.infinity && a.x == b.x && (a.y != b.y || a.y == F::zero(&cp.cf))
+    }
+
+    /// `x³ + a·x + b`, as one sum of products.
+    fn curve_rhs(x: F, cp: &<Self as Configurable>::Cfg) -> F {
+        let one = F::one(&cp.cf);
+        F::sop(&[(x.sqr(&cp.cf), x), (cp.a, x), (cp.b, one)], &cp.cf)
     }
 }
 
-impl<F: Field> CommutativeOp<algebra::ops::Add> for Point<F> {
-    fn op(a: Self, b: Self, c: &Self::Cfg) -> Self {
-        let Point { x: x1, y: y1 } = a;
-        let Point { x: x2, y: y2 } = b;
+impl<F: Field> Point<F> {
+    /// Numerator and denominator of the slope of the line through `a` and `b`
+    /// (the tangent if they coincide). Neither may be infinity, and the line
+    /// can't be vertical.
+    pub(crate) fn slope(a: Self, b: Self, c: &PointCfg<F>) -> (F, F) {
+        let Point { x: x1, y: y1, .. } = a;
+        let Point { x: x2, y: y2, .. } = b;
         assert!(!(x1 == x2 && y1 != y2));
-        let l = if a != b {
-            F::div(F::sub(y2, y1, &c.cf), F::sub(x2, x1, &c.cf), &c.cf)
+        if a != b {
+            (F::sub(y2, y1, &c.cf), F::sub(x2, x1, &c.cf))
         } else {
             // (3x^2 + a) / (2y)
-            F::div(
-                F::add(F::mul(F::three(&c.cf), x1.sqr(&c.cf), &c.cf), c.a, &c.cf),
-                F::mul(F::two(&c.cf), y1, &c.cf),
-                &c.cf,
+            let one = F::one(&c.cf);
+            (
+                F::sop(&[(F::three(&c.cf), x1.sqr(&c.cf)), (c.a, one)], &c.cf),
+                F::add(y1, y1, &c.cf),
             )
+        }
+    }
+
+    /// `a + b` given the slope `l` of the line through them.
+    fn add_with_slope(a: Self, b: Self, l: F, c: &PointCfg<F>) -> Self {
+        let Point { x: x1, y: y1, .. } = a;
+        let minus_one = F::neg(F::one(&c.cf), &c.cf);
+        // x3 = l² - x1 - x2, y3 = l·(x1 - x3) - y1
+        let x3 = F::sop(&[(l, l), (F::add(x1, b.x, &c.cf), minus_one)], &c.cf);
+        let y3 = F::sop(&[(l, F::sub(x1, x3, &c.cf)), (y1, minus_one)], &c.cf);
+        Point::new(x3, y3, c)
+    }
+
+    /// Adds many independent pairs with a single field inversion, shared by all
+    /// slopes via Montgomery's trick. Pairs with infinity in them or as their
+    /// sum need no inversion and are added on their own.
+    pub fn add_batch_affine(pairs: &[(Self, Self)], c: &PointCfg<F>) -> Vec<Self> {
+        let special =
+            |a: Self, b: Self| a.infinity || b.infinity || Self::sums_to_infinity(a, b, c);
+        let (nums, dens): (Vec<_>, Vec<_>) = pairs
+            .iter()
+            .map(|&(a, b)| match special(a, b) {
+                true => (F::zero(&c.cf), F::one(&c.cf)),
+                false => Self::slope(a, b, c),
+            })
+            .unzip();
+        let invs = batch_inverse(&dens, &c.cf).expect("no denominator is zero");
+        pairs
+            .iter()
+            .zip(nums.into_iter().zip(invs))
+            .map(|(&(a, b), (n, inv))| match special(a, b) {
+                true => CommutativeOp::op(a, b, c),
+                false => Self::add_with_slope(a, b, F::mul(n, inv, &c.cf), c),
+            })
+            .collect()
+    }
+}
+
+impl<F: Field> Point<F> {
+    /// Maps the point into the prime-order subgroup by multiplying with the
+    /// cofactor `h`. `None` if the point lies in a small subgroup, which is
+    /// exactly what a small-subgroup confinement attack would send.
+    pub fn clear_cofactor<I: Natural>(self, h: I, c: &PointCfg<F>) -> Option<Self> {
+        let p = CommutativeMonoid::exp(self, h, c);
+        (!p.infinity).then_some(p)
+    }
+
+    /// Whether the point lies in the subgroup of prime order `n`.
+    pub fn in_subgroup<I: Natural>(self, n: I, c: &PointCfg<F>) -> bool {
+        CommutativeMonoid::exp(self, n, c).infinity
+    }
+}
+
+/// Arithmetic over `Z/nZ` with composite `n`. That's a ring, not a field, so
+/// a slope's denominator may be nonzero yet not invertible; instead of a
+/// point the operation then yields `gcd(denominator, n)`, which is how
+/// Lenstra's ECM finds factors.
+impl<I: Natural> Point<ModField<I>> {
+    /// `a + b` with `None` standing for the point at infinity.
+    pub fn try_add(
+        a: Option<Self>,
+        b: Option<Self>,
+        c: &PointCfg<ModField<I>>,
+    ) -> Result<Option<Self>, I> {
+        let finite = |p: Option<Self>| p.filter(|p| !p.infinity);
+        let (a, b) = match (finite(a), finite(b)) {
+            (None, p) | (p, None) => return Ok(p),
+            (Some(a), Some(b)) => (a, b),
         };
-        let x3 = F::sub(l.sqr(&c.cf), F::add(x1, x2, &c.cf), &c.cf);
-        let y3 = F::neg(
-            F::add(F::mul(l, F::sub(x3, x1, &c.cf), &c.cf), y1, &c.cf),
-            &c.cf,
+        if a.x == b.x && ModField::add(a.y, b.y, &c.cf) == ModField::zero(&c.cf) {
+            return Ok(None);
+        }
+        if a.x == b.x && a.y != b.y {
+            // unlike over a field, y1² = y2² doesn't mean y1 = ±y2: the sum
+            // is a zero divisor
+            return match ModField::add(a.y, b.y, &c.cf).inv_or_factor(&c.cf) {
+                Err(d) => Err(d),
+                Ok(_) => unreachable!("y1 + y2 is invertible, but (y1 + y2)(y1 - y2) = 0"),
+            };
+        }
+        let (n, d) = Self::slope(a, b, c);
+        let l = ModField::mul(n, d.inv_or_factor(&c.cf)?, &c.cf);
+        Ok(Some(Self::add_with_slope(a, b, l, c)))
+    }
+
+    /// `k · self` by double-and-add, stopping at the first failed inversion.
+    pub fn try_mul(self, k: I, c: &PointCfg<ModField<I>>) -> Result<Option<Self>, I> {
+        let (mut acc, mut base, mut k) = (None, Some(self), k);
+        while k != I::zero() {
+            if k % I::two() == I::one() {
+                acc = Self::try_add(acc, base, c)?;
+            }
+            k = k / I::two();
+            if k != I::zero() {
+                base = Self::try_add(base, base, c)?;
+            }
+        }
+        Ok(acc)
+    }
+}
+
+/// Points don't know their curve, so one from another curve would be added
+/// as if it were on this one. Debug builds check both operands instead, which
+/// catches mixed-up configs before they silently give garbage.
+impl<F: Field> CommutativeOp<algebra::ops::Add> for Point<F> {
+    fn op(a: Self, b: Self, c: &Self::Cfg) -> Self {
+        debug_assert!(
+            a.is_on(c) && b.is_on(c),
+            "adding a point that isn't on this curve, is it from another config?"
         );
-        Point::new(x3, y3, c)
+        if a.infinity {
+            return b;
+        }
+        if b.infinity {
+            return a;
+        }
+        if Self::sums_to_infinity(a, b, c) {
+            return Self::infinity(c);
+        }
+        let (n, d) = Self::slope(a, b, c);
+        Self::add_with_slope(a, b, F::div(n, d, &c.cf), c)
+    }
+}
+
+impl<F: Field> Identity<algebra::ops::Add> for Point<F> {
+    fn identity(c: &Self::Cfg) -> Self {
+        Self::infinity(c)
     }
 }
 
 impl<F: Field> Inverse<algebra::ops::Add> for Point<F> {
     fn inv(self, c: &Self::Cfg) -> Self {
         Self {
-            x: self.x,
             y: F::neg(self.y, &c.cf),
+            ..self
         }
     }
 }
 
+impl<F: Field> CommutativeMonoid<algebra::ops::Add> for Point<F> {}
+impl<F: Field> AbelianGroup<algebra::ops::Add> for Point<F> {}
+
+impl<F: ConditionalSwap> ConditionalSwap for Point<F> {
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+        F::conditional_swap(&mut a.x, &mut b.x, swap);
+        F::conditional_swap(&mut a.y, &mut b.y, swap);
+        let flip = (a.infinity ^ b.infinity) & std::hint::black_box(swap);
+        a.infinity ^= flip;
+        b.infinity ^= flip;
+    }
+}
+
+/// `k · p` for a secret `k` by the Montgomery ladder,
+/// [`CommutativeMonoid::exp_ct`], over [`ProjectivePoint`]: the affine law
+/// special-cases the identity and doubling, which the ladder runs into for
+/// the leading zero bits of `k`, while the complete projective formulas
+/// don't. The result is converted to affine once; the point at infinity for
+/// a multiple of the order.
+pub fn scalar_mul_ladder<F: Field, I: Natural>(p: Point<F>, k: I, c: &PointCfg<F>) -> Point<F> {
+    ProjectivePoint::from_affine(p, c)
+        .exp_ct(k, c)
+        .to_affine(c)
+        .unwrap_or(Point::infinity(c))
+}
+
+impl<F: Field> Point<F> {
+    /// `k · self` for a secret `k`, by [`scalar_mul_ladder`].
+    /// [`CommutativeOp::exp`] is faster, but its recursion follows the bits
+    /// of `k`.
+    pub fn mul_ct<I: Natural>(self, k: I, c: &PointCfg<F>) -> Self {
+        scalar_mul_ladder(self, k, c)
+    }
+}
+
+/// The point at infinity has no coordinates; it reads as zeros here.
 impl<F> Point<F> {
     pub fn x(self) -> F {
         self.x
@@ -84,43 +335,213 @@ impl<F> Point<F> {
     }
 }
 
-impl<F: Field + DiscreteRoot<algebra::ops::Mul>> Point<F> {
-    pub fn from_x(x: F, cp: &<Self as Configurable>::Cfg) -> Option<Self> {
-        let y2 = F::add(
-            F::add(x.cube(&cp.cf), F::mul(cp.a, x, &cp.cf), &cp.cf),
-            cp.b,
-            &cp.cf,
-        );
-        y2.sqrt(&cp.cf).map(|yres| Self::new(x, yres, cp))
+impl<F: Parity> Point<F> {
+    pub fn y_is_odd(self) -> bool {
+        self.y.is_odd()
+    }
+}
+
+impl<F: Field + DiscreteRoot<algebra::ops::Mul> + Parity> Point<F> {
+    /// Both points with the given x: the one with even y first, then the one with odd y.
+    /// If y is zero, the two coincide.
+    pub fn from_x(x: F, cp: &<Self as Configurable>::Cfg) -> Option<[Self; 2]> {
+        let y = Self::curve_rhs(x, cp).sqrt(&cp.cf)?;
+        let p = Self::new(x, y, cp);
+        let q = Self::new(x, F::neg(y, &cp.cf), cp);
+        Some(if p.y_is_odd() { [q, p] } else { [p, q] })
+    }
+
+    /// The point with the given x whose y has the requested parity.
+    pub fn from_x_with_parity(x: F, odd: bool, cp: &<Self as Configurable>::Cfg) -> Option<Self> {
+        Self::from_x(x, cp)?
+            .into_iter()
+            .find(|p| p.y_is_odd() == odd)
     }
 }
 
-impl<F: Field + DiscreteRoot<algebra::ops::Mul>> Point<F>
+impl<F: Field + RW + Parity> Point<F> {
+    /// SEC1 compressed form: `0x02 | y_is_odd` followed by big-endian x, or
+    /// the single byte `0x00` for the point at infinity.
+    pub fn to_sec1_compressed(self) -> Vec<u8> {
+        if self.infinity {
+            return vec![SEC1_INFINITY];
+        }
+        let mut res = vec![0x02 | self.y_is_odd() as u8];
+        self.x.to_bytes_endian(Endianness::Big, &mut res);
+        res
+    }
+}
+
+impl<F: Field + RW + Parity + DiscreteRoot<algebra::ops::Mul>> Point<F> {
+    /// The inverse of [`Point::to_sec1_compressed`], the point at infinity
+    /// included, so a public key also has to be checked for it; a
+    /// [`ValidationPolicy`] other than `None` rejects it.
+    pub fn from_sec1_compressed(bytes: &[u8], cp: &<Self as Configurable>::Cfg) -> Option<Self> {
+        if bytes == [SEC1_INFINITY] {
+            return Some(Self::infinity(cp));
+        }
+        let (&tag, x) = bytes.split_first()?;
+        if !(tag == 0x02 || tag == 0x03) || x.len() != F::LEN {
+            return None;
+        }
+        let x = F::from_bytes_endian(&mut &x[..], Endianness::Big);
+        if !x.is_canonical(&cp.cf) {
+            return None;
+        }
+        Self::from_x_with_parity(x, tag == 0x03, cp)
+    }
+
+    /// Like `from_sec1_compressed`, but rejects the point at infinity and
+    /// points outside the subgroup of prime order `n`. Needed on curves with
+    /// a cofactor.
+    pub fn from_sec1_compressed_in_subgroup<I: Natural>(
+        bytes: &[u8],
+        n: I,
+        cp: &<Self as Configurable>::Cfg,
+    ) -> Option<Self> {
+        Self::from_sec1_compressed(bytes, cp).filter(|p| !p.infinity && p.in_subgroup(n, cp))
+    }
+
+    /// [`Point::from_sec1_compressed`] with the checks of `policy`. A
+    /// decompressed point is always on the curve, so only the subgroup check
+    /// adds anything.
+    pub fn from_sec1_compressed_validated<I: Natural>(
+        bytes: &[u8],
+        policy: ValidationPolicy<I>,
+        cp: &<Self as Configurable>::Cfg,
+    ) -> Result<Self, Error> {
+        Self::from_sec1_compressed(bytes, cp)
+            .ok_or(Error::Point)?
+            .validate(policy, cp)
+    }
+}
+
+impl<F: Field + DiscreteRoot<algebra::ops::Mul> + Parity> Point<F>
 where
     F: FromRandom<F::Cfg>,
 {
     pub fn random<R: Rng>(r: &mut R, cfg: &<Self as Configurable>::Cfg) -> Self {
         loop {
             let x = F::random(r, &cfg.cf);
-            if let Some(p) = Self::from_x(x, cfg) {
+            if let Some(p) = Self::from_x_with_parity(x, r.gen(), cfg) {
                 return p;
             }
         }
     }
 }
 
+impl<F: Field + DiscreteRoot<algebra::ops::Mul> + Parity> PointCfg<F>
+where
+    F: FromRandom<F::Cfg>,
+{
+    /// A generator of the subgroup of prime order `n`, the largest prime in
+    /// the factorization `factors` of the group order as `(prime, exponent)`
+    /// pairs, found by clearing the cofactor of random points. `g` is
+    /// ignored. `None` if `n` divides the order more than once, so that the
+    /// subgroup may not be cyclic, or if the points contradict the
+    /// factorization.
+    pub fn find_generator<R: Rng, I: Natural>(
+        &self,
+        r: &mut R,
+        factors: &[(I, u32)],
+    ) -> Option<Point<F>> {
+        let (i, &(n, e)) = factors.iter().enumerate().max_by_key(|(_, (p, _))| *p)?;
+        if e != 1 {
+            return None;
+        }
+        let mut h = I::one();
+        for (j, &(p, e)) in factors.iter().enumerate() {
+            if j != i {
+                h = h * (0..e).fold(I::one(), |acc, _| acc * p);
+            }
+        }
+        // a point survives clearing with probability 1 - 1/n, so running out
+        // of tries means the cofactor is wrong rather than bad luck
+        for _ in 0..64 {
+            match Point::random(r, self).clear_cofactor(h, self) {
+                Some(g) if g.in_subgroup(n, self) => return Some(g),
+                Some(_) => return None,
+                None => continue,
+            }
+        }
+        None
+    }
+}
+
+/// Coordinates, `x` then `y`. The point at infinity has none and is written
+/// as [`RW::LEN`] bytes of `0xff`, which no canonical coordinates can be:
+/// all ones is a composite `2ⁿ - 1`, so never below a prime modulus that
+/// fits. It still fails validation, as any point at infinity from outside.
 impl<F: RW + Field> RW for Point<F> {
     fn to_bytes(self, w: &mut impl Write) -> usize {
-        self.x.to_bytes(w) + self.y.to_bytes(w)
+        self.to_bytes_endian(Endianness::Little, w)
     }
 
     fn from_bytes(r: &mut impl Read) -> Self {
-        Self::new_unsafe(F::from_bytes(r), F::from_bytes(r))
+        Self::from_bytes_endian(r, Endianness::Little)
+    }
+
+    fn to_bytes_endian(self, e: Endianness, w: &mut impl Write) -> usize {
+        if self.infinity {
+            w.write_all(&vec![INFINITY_BYTE; Self::LEN]).unwrap();
+            return Self::LEN;
+        }
+        self.x.to_bytes_endian(e, w) + self.y.to_bytes_endian(e, w)
+    }
+
+    fn from_bytes_endian(r: &mut impl Read, e: Endianness) -> Self {
+        let mut buf = vec![0; Self::LEN];
+        r.read_exact(&mut buf).unwrap();
+        if buf.iter().all(|&b| b == INFINITY_BYTE) {
+            // the zero coordinates of `Point::infinity`, without a config
+            let zero = F::from_bytes(&mut &vec![0; F::LEN][..]);
+            return Self {
+                x: zero,
+                y: zero,
+                infinity: true,
+            };
+        }
+        let mut r = buf.as_slice();
+        Self::new_unsafe(
+            F::from_bytes_endian(&mut r, e),
+            F::from_bytes_endian(&mut r, e),
+        )
     }
 
     const LEN: usize = F::LEN * 2;
 }
 
+impl<F: RW + Field> Point<F> {
+    /// Decodes the [`RW`] form, `x` then `y`, with the checks of `policy`.
+    pub fn from_bytes_validated<I: Natural>(
+        bytes: &[u8],
+        policy: ValidationPolicy<I>,
+        cp: &<Self as Configurable>::Cfg,
+    ) -> Result<Self, Error> {
+        if bytes.len() != Self::LEN {
+            return Err(Error::Length {
+                expected: Self::LEN,
+                actual: bytes.len(),
+            });
+        }
+        Self::from_bytes(&mut &bytes[..]).validate(policy, cp)
+    }
+}
+
+impl<F: RW + Field> Display for Point<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_base64())
+    }
+}
+
+impl<F: RW + Field> FromStr for Point<F> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::try_from_base64(s)
+    }
+}
+
 impl<F: Field> InitialPoint<Point<F>> for PointCfg<F> {
     fn g(&self) -> Point<F> {
         self.g
@@ -129,12 +550,17 @@ impl<F: Field> InitialPoint<Point<F>> for PointCfg<F> {
 
 #[cfg(test)]
 mod tests {
+    use rand::{Rng, SeedableRng};
+
     use crate::{
-        algebra::{self, CommutativeOp},
+        algebra::{self, CommutativeMonoid, CommutativeOp, ConditionalSwap, Inverse, Ring},
+        base_traits::{Endianness, RW},
+        curves::toy_order,
+        error::Error,
         mod_field::{ModField, ModFieldCfg},
     };
 
-    use super::{Point, PointCfg};
+    use super::{Point, PointCfg, ValidationPolicy};
 
     fn cfg() -> PointCfg<ModField<u64>> {
         let cfg_field = ModFieldCfg {
@@ -153,6 +579,101 @@ mod tests {
         cfg_group
     }
 
+    #[test]
+    fn laws() {
+        let cfg = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        algebra::laws::abelian_group::<algebra::ops::Add, _>(
+            || match gen.gen_range(0..4) {
+                0 => Point::infinity(&cfg),
+                _ => Point::random(&mut gen, &cfg),
+            },
+            &cfg,
+            50,
+        );
+    }
+
+    #[test]
+    fn infinity() {
+        let cfg = cfg();
+        let o = Point::infinity(&cfg);
+        let g = cfg.g;
+        let minus_g = Inverse::inv(g, &cfg);
+        assert_eq!(Point::op(g, minus_g, &cfg), o);
+        assert_eq!(Point::op(o, g, &cfg), g);
+        assert_eq!(Point::op(g, o, &cfg), g);
+        assert_eq!(Point::op(o, o, &cfg), o);
+        assert_eq!(Inverse::inv(o, &cfg), o);
+        assert!(o.is_infinity() && !g.is_infinity());
+
+        let n = toy_order().rem;
+        assert_eq!(CommutativeOp::exp(g, n, &cfg), o);
+        assert_eq!(CommutativeOp::exp(g, n + 1, &cfg), g);
+        assert_eq!(CommutativeMonoid::exp(g, 0u8, &cfg), o);
+        assert_eq!(
+            Point::add_batch_affine(&[(g, minus_g), (o, g), (g, g)], &cfg),
+            vec![o, g, Point::op(g, g, &cfg)]
+        );
+
+        // no key, and nothing to decode as one
+        assert_eq!(o.to_sec1_compressed(), vec![0]);
+        assert_eq!(
+            o.validate(ValidationPolicy::<u8>::OnCurve, &cfg),
+            Err(Error::Point)
+        );
+        assert_eq!(Point::from_sec1_compressed(&[0], &cfg), Some(o));
+    }
+
+    #[test]
+    fn mul_ct() {
+        let cfg = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([3u8; 32]);
+        let (g, n) = (cfg.g, toy_order().rem);
+        for _ in 0..20 {
+            let k = gen.gen_range(1..n);
+            assert_eq!(g.mul_ct(k, &cfg), CommutativeOp::exp(g, k, &cfg));
+        }
+        let o = Point::infinity(&cfg);
+        assert_eq!(g.mul_ct(0u128, &cfg), o);
+        assert_eq!(g.mul_ct(1u8, &cfg), g);
+        assert_eq!(g.mul_ct(n, &cfg), o);
+        assert_eq!(g.mul_ct(n + 1, &cfg), g);
+        assert_eq!(
+            g.mul_ct(u128::MAX, &cfg),
+            CommutativeOp::exp(g, u128::MAX % n, &cfg)
+        );
+        assert_eq!(o.mul_ct(5u8, &cfg), o);
+
+        let (mut a, mut b) = (g, o);
+        Point::conditional_swap(&mut a, &mut b, false);
+        assert_eq!((a, b), (g, o));
+        Point::conditional_swap(&mut a, &mut b, true);
+        assert_eq!((a, b), (o, g));
+
+        // any group with the swap, such as the integers mod p under addition
+        let c = ModFieldCfg { rem: 1019u64 };
+        let x = ModField::new(7, &c);
+        assert_eq!(
+            CommutativeMonoid::<algebra::ops::Add>::exp_ct(&x, 300u64, &c),
+            ModField::new(2100 % 1019, &c)
+        );
+    }
+
+    #[test]
+    fn add_batch_affine() {
+        let cfg = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let mut pairs = (0..16)
+            .map(|_| (Point::random(&mut gen, &cfg), Point::random(&mut gen, &cfg)))
+            .collect::<Vec<_>>();
+        pairs.push((cfg.g, cfg.g));
+        let sums = Point::add_batch_affine(&pairs, &cfg);
+        for (&(a, b), sum) in pairs.iter().zip(sums) {
+            assert_eq!(sum, Point::op(a, b, &cfg));
+        }
+        assert_eq!(Point::add_batch_affine(&[], &cfg), vec![]);
+    }
+
     #[test]
     fn g_exists() {
         let cfg = cfg();
@@ -186,4 +707,209 @@ mod tests {
             p(3851261364, 66206903692)
         );
     }
+
+    #[test]
+    fn from_x_both_roots() {
+        let cfg = cfg();
+        let [even, odd] = Point::from_x(cfg.g.x(), &cfg).unwrap();
+        assert!(!even.y_is_odd());
+        assert!(odd.y_is_odd());
+        assert_eq!(odd, Inverse::inv(even, &cfg));
+        assert!(even == cfg.g || odd == cfg.g);
+        assert_eq!(
+            Point::from_x_with_parity(cfg.g.x(), cfg.g.y_is_odd(), &cfg),
+            Some(cfg.g)
+        );
+    }
+
+    #[test]
+    fn sec1_compressed() {
+        let cfg = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for _ in 0..20 {
+            let p = Point::random(&mut gen, &cfg);
+            let bytes = p.to_sec1_compressed();
+            assert_eq!(bytes.len(), 9);
+            assert_eq!(bytes[0], 0x02 | p.y_is_odd() as u8);
+            assert_eq!(Point::from_sec1_compressed(&bytes, &cfg), Some(p));
+
+            // x + p has the same parity check to pass, but isn't canonical
+            let mut shifted = bytes.clone();
+            shifted[1..].copy_from_slice(&(p.x().nat() + cfg.cf.rem).to_be_bytes());
+            assert_eq!(Point::from_sec1_compressed(&shifted, &cfg), None);
+        }
+        assert_eq!(Point::from_sec1_compressed(&[0x04; 9], &cfg), None);
+        assert_eq!(Point::from_sec1_compressed(&[0x02; 8], &cfg), None);
+    }
+
+    #[test]
+    fn cofactor() {
+        // y^2 = x^3 + x + 1 over F_23 has 28 = 4 · 7 points
+        let cf = ModFieldCfg { rem: 23u64 };
+        let cfg = PointCfg {
+            g: Point::new_unsafe(ModField::new(3, &cf), ModField::new(10, &cf)),
+            a: ModField::new(1, &cf),
+            b: ModField::new(1, &cf),
+            cf,
+        };
+        let mut points = (0..23)
+            .filter_map(|x| Point::from_x(ModField::new(x, &cfg.cf), &cfg))
+            .flatten()
+            .collect::<Vec<_>>();
+        // the one point of order 2 has y = 0 and comes twice, as its own negation
+        points.dedup();
+        let two_torsion = Point::new(ModField::new(4, &cfg.cf), ModField::zero(&cfg.cf), &cfg);
+        assert!(points.contains(&two_torsion));
+        assert_eq!(points.len(), 27);
+        assert!(!two_torsion.in_subgroup(7u8, &cfg));
+        assert!(two_torsion.in_subgroup(2u8, &cfg));
+        let subgroup = points
+            .iter()
+            .filter(|p| p.in_subgroup(7u8, &cfg))
+            .collect::<Vec<_>>();
+        assert_eq!(subgroup.len(), 6);
+        let mut cleared = 0;
+        for &p in &points {
+            match p.clear_cofactor(4u8, &cfg) {
+                Some(q) => {
+                    assert!(q.in_subgroup(7u8, &cfg));
+                    cleared += 1;
+                }
+                None => assert!(!p.in_subgroup(7u8, &cfg)),
+            }
+            let bytes = p.to_sec1_compressed();
+            assert_eq!(
+                Point::from_sec1_compressed_in_subgroup(&bytes, 7u8, &cfg),
+                subgroup.contains(&&p).then_some(p)
+            );
+        }
+        // the 3 points of order 2 or 4 fall into infinity
+        assert_eq!(cleared, 24);
+    }
+
+    #[test]
+    fn infinity_encoding() {
+        let cfg = cfg();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let p = Point::random(&mut gen, &cfg);
+        for q in [p, Point::infinity(&cfg)] {
+            let sec1 = q.to_sec1_compressed();
+            assert_eq!(Point::from_sec1_compressed(&sec1, &cfg), Some(q));
+        }
+        assert_eq!(Point::from_sec1_compressed(&[], &cfg), None);
+        assert_eq!(Point::from_sec1_compressed(&[0; 9], &cfg), None);
+        assert_eq!(
+            Point::from_sec1_compressed_in_subgroup(&[0], 7u8, &cfg),
+            None
+        );
+
+        for e in [Endianness::Little, Endianness::Big] {
+            let mut bytes = vec![];
+            Point::infinity(&cfg).to_bytes_endian(e, &mut bytes);
+            assert_eq!(bytes, vec![0xff; 16]);
+            assert_eq!(
+                Point::from_bytes_endian(&mut &bytes[..], e),
+                Point::infinity(&cfg)
+            );
+        }
+        // not to be confused with (0, 0), on the curve where b = 0
+        let zeros = Point::<ModField<u64>>::from_bytes(&mut &[0; 16][..]);
+        assert!(!zeros.is_infinity());
+        assert_eq!(
+            Point::from_bytes_validated(
+                &Point::infinity(&cfg).to_vec(),
+                ValidationPolicy::<u8>::OnCurve,
+                &cfg
+            ),
+            Err(Error::Point)
+        );
+    }
+
+    #[test]
+    fn validation_policy() {
+        let cf = ModFieldCfg { rem: 23u64 };
+        let cfg = PointCfg {
+            g: Point::new_unsafe(ModField::new(17, &cf), ModField::new(3, &cf)),
+            a: ModField::new(1, &cf),
+            b: ModField::new(1, &cf),
+            cf,
+        };
+        let subgroup = ValidationPolicy::OnCurveAndSubgroup(7u8);
+        let two_torsion = Point::new_unsafe(ModField::new(4, &cf), ModField::zero(&cf));
+        let off_curve = Point::new_unsafe(ModField::new(4, &cf), ModField::one(&cf));
+        assert_eq!(cfg.g.validate(subgroup, &cfg), Ok(cfg.g));
+        assert_eq!(two_torsion.validate(subgroup, &cfg), Err(Error::Subgroup));
+        assert_eq!(
+            two_torsion.validate(ValidationPolicy::<u8>::OnCurve, &cfg),
+            Ok(two_torsion)
+        );
+        assert_eq!(off_curve.validate(subgroup, &cfg), Err(Error::Point));
+        assert_eq!(
+            off_curve.validate(ValidationPolicy::<u8>::None, &cfg),
+            Ok(off_curve)
+        );
+
+        // x = 4 + 23, the same point if it were reduced
+        let mut shifted = 27u64.to_le_bytes().to_vec();
+        shifted.extend(0u64.to_le_bytes());
+        assert_eq!(
+            Point::from_bytes_validated(&shifted, ValidationPolicy::<u8>::OnCurve, &cfg),
+            Err(Error::NonCanonical)
+        );
+
+        let bytes = off_curve.to_vec();
+        assert_eq!(
+            Point::from_bytes_validated(&bytes, ValidationPolicy::<u8>::OnCurve, &cfg),
+            Err(Error::Point)
+        );
+        assert_eq!(
+            Point::from_bytes_validated(&bytes[1..], ValidationPolicy::<u8>::None, &cfg),
+            Err(Error::Length {
+                expected: 16,
+                actual: 15
+            })
+        );
+        let point = Point::random(&mut rand_chacha::ChaCha8Rng::from_seed([1u8; 32]), &cfg);
+        assert_eq!(
+            Point::from_sec1_compressed_validated(&point.to_sec1_compressed(), subgroup, &cfg)
+                .is_ok(),
+            point.in_subgroup(7u8, &cfg)
+        );
+    }
+
+    #[test]
+    fn find_generator() {
+        let cf = ModFieldCfg { rem: 23u64 };
+        let cfg = PointCfg {
+            g: Point::new_unsafe(ModField::new(3, &cf), ModField::new(10, &cf)),
+            a: ModField::new(1, &cf),
+            b: ModField::new(1, &cf),
+            cf,
+        };
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for _ in 0..10 {
+            let g = cfg.find_generator(&mut gen, &[(2u8, 2), (7, 1)]).unwrap();
+            assert!(g.in_subgroup(7u8, &cfg));
+            assert!(g.clear_cofactor(1u8, &cfg).is_some());
+        }
+        // 28 isn't 20, and 7 isn't the order of anything in a group of 20
+        assert_eq!(cfg.find_generator(&mut gen, &[(2u8, 2), (5, 1)]), None);
+        assert_eq!(cfg.find_generator(&mut gen, &[(7u8, 2)]), None);
+        assert_eq!(cfg.find_generator::<_, u8>(&mut gen, &[]), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "another config")]
+    fn mixed_configs() {
+        let cf = ModFieldCfg { rem: 23u64 };
+        let other = PointCfg {
+            g: Point::new_unsafe(ModField::new(3, &cf), ModField::new(10, &cf)),
+            a: ModField::new(1, &cf),
+            b: ModField::new(1, &cf),
+            cf,
+        };
+        let cfg = cfg();
+        Point::op(cfg.g, other.g, &cfg);
+    }
 }
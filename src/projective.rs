@@ -0,0 +1,263 @@
+//! Points in homogeneous projective coordinates `(X : Y : Z)`, `x = X / Z`,
+//! `y = Y / Z`, for accumulating sums without an inversion per addition.
+//! The addition is the complete formula of Renes, Costello and Batina
+//! ("Complete addition formulas for prime order elliptic curves", algorithm
+//! 1): no special cases for doubling, inverses or the point at infinity
+//! `(0 : 1 : 0)`, so unlike [`Point`] this is a proper group.
+//!
+//! The coordinates aren't unique, so there's deliberately no [`RW`]: a
+//! projective point has to go through [`ProjectivePoint::to_affine`] before
+//! it's serialized, like in the RustCrypto crates. SEC1 is the exception, as
+//! it has a form for the point at infinity.
+//!
+//! [`RW`]: crate::base_traits::RW
+
+use crate::{
+    algebra::{
+        self, AbelianGroup, CommutativeMonoid, CommutativeOp, ConditionalSwap, Configurable,
+        DiscreteRoot, Field, Identity, InitialPoint, Inverse,
+    },
+    base_traits::{Parity, RW},
+    batch::batch_inverse,
+    points_group::{AffinePoint, Point, PointCfg},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectivePoint<F> {
+    x: F,
+    y: F,
+    z: F,
+}
+
+impl<F: Field> Configurable for ProjectivePoint<F> {
+    type Cfg = PointCfg<F>;
+}
+
+impl<F: Field> ProjectivePoint<F> {
+    pub fn from_affine(p: AffinePoint<F>, c: &PointCfg<F>) -> Self {
+        if p.is_infinity() {
+            return Self::identity(c);
+        }
+        Self {
+            x: p.x(),
+            y: p.y(),
+            z: F::one(&c.cf),
+        }
+    }
+
+    pub fn is_identity(self, c: &PointCfg<F>) -> bool {
+        self.z == F::zero(&c.cf)
+    }
+
+    /// `None` for the point at infinity, which has no affine form.
+    pub fn to_affine(self, c: &PointCfg<F>) -> Option<AffinePoint<F>> {
+        let z = self.z.reciprocal(&c.cf)?;
+        Some(Point::new_unsafe(
+            F::mul(self.x, z, &c.cf),
+            F::mul(self.y, z, &c.cf),
+        ))
+    }
+
+    /// [`ProjectivePoint::to_affine`] for many points with one inversion.
+    /// `None` if any of them is the point at infinity.
+    pub fn to_affine_batch(points: &[Self], c: &PointCfg<F>) -> Option<Vec<AffinePoint<F>>> {
+        let zs = points.iter().map(|p| p.z).collect::<Vec<_>>();
+        let invs = batch_inverse(&zs, &c.cf)?;
+        Some(
+            points
+                .iter()
+                .zip(invs)
+                .map(|(p, z)| Point::new_unsafe(F::mul(p.x, z, &c.cf), F::mul(p.y, z, &c.cf)))
+                .collect(),
+        )
+    }
+
+    /// Whether both stand for the same point, `X₁·Z₂ = X₂·Z₁` and
+    /// `Y₁·Z₂ = Y₂·Z₁`. `==` can't be used since scaling all coordinates
+    /// gives another representation of the same point.
+    pub fn same_point(a: Self, b: Self, c: &PointCfg<F>) -> bool {
+        let cf = &c.cf;
+        F::mul(a.x, b.z, cf) == F::mul(b.x, a.z, cf) && F::mul(a.y, b.z, cf) == F::mul(b.y, a.z, cf)
+    }
+}
+
+impl<F: Field + RW + Parity> ProjectivePoint<F> {
+    /// SEC1 compressed form, `0x00` for the point at infinity.
+    pub fn to_sec1_compressed(self, c: &PointCfg<F>) -> Vec<u8> {
+        self.to_affine(c)
+            .unwrap_or(Point::infinity(c))
+            .to_sec1_compressed()
+    }
+}
+
+impl<F: Field + RW + Parity + DiscreteRoot<algebra::ops::Mul>> ProjectivePoint<F> {
+    pub fn from_sec1_compressed(bytes: &[u8], c: &PointCfg<F>) -> Option<Self> {
+        Point::from_sec1_compressed(bytes, c).map(|p| Self::from_affine(p, c))
+    }
+}
+
+impl<F: Field> CommutativeOp<algebra::ops::Add> for ProjectivePoint<F> {
+    fn op(p: Self, q: Self, c: &PointCfg<F>) -> Self {
+        let cf = &c.cf;
+        let (add, sub, mul) = (
+            |a, b| F::add(a, b, cf),
+            |a, b| F::sub(a, b, cf),
+            |a, b| F::mul(a, b, cf),
+        );
+        let b3 = mul(F::three(cf), c.b);
+        let t0 = mul(p.x, q.x);
+        let t1 = mul(p.y, q.y);
+        let t2 = mul(p.z, q.z);
+        let t3 = sub(mul(add(p.x, p.y), add(q.x, q.y)), add(t0, t1));
+        let t4 = sub(mul(add(p.x, p.z), add(q.x, q.z)), add(t0, t2));
+        let t5 = sub(mul(add(p.y, p.z), add(q.y, q.z)), add(t1, t2));
+        let z3 = add(mul(c.a, t4), mul(b3, t2));
+        let x3 = sub(t1, z3);
+        let z3 = add(t1, z3);
+        let y3 = mul(x3, z3);
+        let t1 = add(add(t0, t0), t0);
+        let t2 = mul(c.a, t2);
+        let t4 = add(mul(b3, t4), mul(c.a, sub(t0, t2)));
+        let t1 = add(t1, t2);
+        Self {
+            x: sub(mul(t3, x3), mul(t5, t4)),
+            y: add(y3, mul(t1, t4)),
+            z: add(mul(t5, z3), mul(t3, t1)),
+        }
+    }
+}
+
+impl<F: Field> Identity<algebra::ops::Add> for ProjectivePoint<F> {
+    fn identity(c: &PointCfg<F>) -> Self {
+        Self {
+            x: F::zero(&c.cf),
+            y: F::one(&c.cf),
+            z: F::zero(&c.cf),
+        }
+    }
+}
+
+impl<F: Field> Inverse<algebra::ops::Add> for ProjectivePoint<F> {
+    fn inv(self, c: &PointCfg<F>) -> Self {
+        Self {
+            y: F::neg(self.y, &c.cf),
+            ..self
+        }
+    }
+}
+
+impl<F: Field> CommutativeMonoid<algebra::ops::Add> for ProjectivePoint<F> {}
+impl<F: Field> AbelianGroup<algebra::ops::Add> for ProjectivePoint<F> {}
+
+impl<F: ConditionalSwap> ConditionalSwap for ProjectivePoint<F> {
+    fn conditional_swap(a: &mut Self, b: &mut Self, swap: bool) {
+        F::conditional_swap(&mut a.x, &mut b.x, swap);
+        F::conditional_swap(&mut a.y, &mut b.y, swap);
+        F::conditional_swap(&mut a.z, &mut b.z, swap);
+    }
+}
+
+impl<F: Field> InitialPoint<ProjectivePoint<F>> for PointCfg<F> {
+    fn g(&self) -> ProjectivePoint<F> {
+        ProjectivePoint::from_affine(self.g, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeMonoid, CommutativeOp, Identity, Inverse},
+        curves::{secp256k1, toy, toy_order},
+        ecc::gen_keys,
+        points_group::Point,
+    };
+
+    use super::ProjectivePoint;
+
+    #[test]
+    fn matches_affine() {
+        let c = toy();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for _ in 0..20 {
+            let (a, b) = (Point::random(&mut rng, &c), Point::random(&mut rng, &c));
+            let (pa, pb) = (
+                ProjectivePoint::from_affine(a, &c),
+                ProjectivePoint::from_affine(b, &c),
+            );
+            let sum = ProjectivePoint::op(pa, pb, &c).to_affine(&c);
+            assert_eq!(sum, Some(Point::op(a, b, &c)));
+            let double = ProjectivePoint::op(pa, pa, &c).to_affine(&c);
+            assert_eq!(double, Some(Point::op(a, a, &c)));
+            let k = 0xDEAD_BEEFu64;
+            assert_eq!(
+                CommutativeMonoid::exp(pa, k, &c).to_affine(&c),
+                Some(CommutativeOp::exp(a, k, &c))
+            );
+        }
+    }
+
+    #[test]
+    fn identity() {
+        let c = toy();
+        let g = ProjectivePoint::from_affine(c.g, &c);
+        let o = ProjectivePoint::identity(&c);
+        assert!(ProjectivePoint::same_point(
+            ProjectivePoint::op(g, o, &c),
+            g,
+            &c
+        ));
+        assert!(ProjectivePoint::op(o, o, &c).is_identity(&c));
+        assert!(ProjectivePoint::op(g, g.inv(&c), &c).is_identity(&c));
+        assert!(CommutativeMonoid::exp(g, toy_order().rem, &c).is_identity(&c));
+        assert_eq!(o.to_affine(&c), None);
+        assert!(CommutativeMonoid::exp(g, 0u8, &c).is_identity(&c));
+
+        // a protocol step that lands on infinity can still be encoded
+        let zero = CommutativeMonoid::exp(g, toy_order().rem, &c);
+        assert_eq!(zero.to_sec1_compressed(&c), vec![0]);
+        let decoded = ProjectivePoint::from_sec1_compressed(&[0], &c).unwrap();
+        assert!(decoded.is_identity(&c));
+        let decoded = ProjectivePoint::from_sec1_compressed(&g.to_sec1_compressed(&c), &c);
+        assert!(ProjectivePoint::same_point(decoded.unwrap(), g, &c));
+    }
+
+    #[test]
+    fn exp_variants() {
+        let c = toy();
+        let g = ProjectivePoint::from_affine(c.g, &c);
+        for k in [0u64, 1, 2, 3, 0xDEAD_BEEF, u64::MAX] {
+            let expected = CommutativeMonoid::exp(g, k, &c);
+            assert!(ProjectivePoint::same_point(g.exp_ct(k, &c), expected, &c));
+            if k != 0 {
+                assert!(ProjectivePoint::same_point(
+                    g.exp_vartime(k, &c),
+                    expected,
+                    &c
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn batch_normalization() {
+        let c = secp256k1();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let points = (0..8)
+            .map(|_| {
+                gen_keys::<_, u64, ProjectivePoint<_>>(&mut rng, &c)
+                    .1
+                    .point()
+            })
+            .collect::<Vec<_>>();
+        let affine = ProjectivePoint::to_affine_batch(&points, &c).unwrap();
+        for (p, a) in points.iter().zip(affine) {
+            assert_eq!(p.to_affine(&c), Some(a));
+            Point::new(a.x(), a.y(), &c);
+        }
+        let mut with_identity = points.clone();
+        with_identity.push(ProjectivePoint::identity(&c));
+        assert_eq!(ProjectivePoint::to_affine_batch(&with_identity, &c), None);
+    }
+}
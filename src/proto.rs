@@ -0,0 +1,227 @@
+//! Messages of `proto/ecc.proto`, laid out the way prost-build emits them,
+//! plus conversions from and to the crate's types.
+
+use crate::{
+    algebra::Field,
+    base_traits::{Natural, RW},
+    ecc,
+    points_group::{self, PointCfg, ValidationPolicy},
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Point {
+    #[prost(bytes = "vec", tag = "1")]
+    pub x: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub y: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PublicKey {
+    #[prost(message, optional, tag = "1")]
+    pub point: Option<Point>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ElGamalPair {
+    #[prost(message, optional, tag = "1")]
+    pub c1: Option<Point>,
+    #[prost(message, optional, tag = "2")]
+    pub c2: Option<Point>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Ciphertext {
+    #[prost(message, repeated, tag = "1")]
+    pub chunks: Vec<ElGamalPair>,
+}
+
+/// A message which is missing a field, has a coordinate of the wrong width
+/// or a point that fails validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MalformedMessage;
+
+fn coord_to_bytes<F: RW>(f: F) -> Vec<u8> {
+    let mut buf = vec![];
+    f.to_bytes(&mut buf);
+    buf
+}
+
+fn coord_from_bytes<F: RW>(bytes: &[u8]) -> Result<F, MalformedMessage> {
+    if bytes.len() != F::LEN {
+        return Err(MalformedMessage);
+    }
+    Ok(F::from_bytes(&mut &bytes[..]))
+}
+
+impl<F: RW + Copy> From<points_group::Point<F>> for Point {
+    fn from(p: points_group::Point<F>) -> Self {
+        Self {
+            x: coord_to_bytes(p.x()),
+            y: coord_to_bytes(p.y()),
+        }
+    }
+}
+
+impl Point {
+    /// The point, if it passes the checks of `policy`.
+    pub fn into_point<F: Field + RW, I: Natural>(
+        self,
+        policy: ValidationPolicy<I>,
+        cfg: &PointCfg<F>,
+    ) -> Result<points_group::Point<F>, MalformedMessage> {
+        let p =
+            points_group::Point::new_unsafe(coord_from_bytes(&self.x)?, coord_from_bytes(&self.y)?);
+        p.validate(policy, cfg).map_err(|_| MalformedMessage)
+    }
+}
+
+fn required<F: Field + RW, I: Natural>(
+    p: Option<Point>,
+    policy: ValidationPolicy<I>,
+    cfg: &PointCfg<F>,
+) -> Result<points_group::Point<F>, MalformedMessage> {
+    p.ok_or(MalformedMessage)?.into_point(policy, cfg)
+}
+
+impl<F: RW + Copy> From<ecc::PublicKey<points_group::Point<F>>> for PublicKey {
+    fn from(key: ecc::PublicKey<points_group::Point<F>>) -> Self {
+        Self {
+            point: Some(key.point().into()),
+        }
+    }
+}
+
+impl PublicKey {
+    /// The key, if its point passes the checks of `policy`.
+    pub fn into_key<F: Field + RW, I: Natural>(
+        self,
+        policy: ValidationPolicy<I>,
+        cfg: &PointCfg<F>,
+    ) -> Result<ecc::PublicKey<points_group::Point<F>>, MalformedMessage> {
+        Ok(ecc::PublicKey::from_point(required(
+            self.point, policy, cfg,
+        )?))
+    }
+}
+
+impl<F: RW + Copy> From<ecc::Ciphertext<points_group::Point<F>>> for Ciphertext {
+    fn from(ct: ecc::Ciphertext<points_group::Point<F>>) -> Self {
+        Self {
+            chunks: ct
+                .chunks
+                .into_iter()
+                .map(|(c1, c2)| ElGamalPair {
+                    c1: Some(c1.into()),
+                    c2: Some(c2.into()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Ciphertext {
+    /// The ciphertext, if all of its points pass the checks of `policy`.
+    /// The second point of a pair carries the message point, which needn't
+    /// be in the subgroup, so [`ValidationPolicy::OnCurve`] is the one for
+    /// message points that may lie outside it.
+    pub fn into_ciphertext<F: Field + RW, I: Natural>(
+        self,
+        policy: ValidationPolicy<I>,
+        cfg: &PointCfg<F>,
+    ) -> Result<ecc::Ciphertext<points_group::Point<F>>, MalformedMessage> {
+        Ok(ecc::Ciphertext {
+            chunks: self
+                .chunks
+                .into_iter()
+                .map(|pair| {
+                    Ok((
+                        required(pair.c1, policy, cfg)?,
+                        required(pair.c2, policy, cfg)?,
+                    ))
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use rand::SeedableRng;
+
+    use crate::{
+        curves::toy_order,
+        ecc::gen_keys,
+        encoding_utils::encrypt_message,
+        mod_field::{ModField, ModFieldCfg},
+        points_group::{
+            self, PointCfg,
+            ValidationPolicy::{self, OnCurve, OnCurveAndSubgroup},
+        },
+    };
+
+    use super::{Ciphertext, MalformedMessage, Point, PublicKey};
+
+    type P = points_group::Point<ModField<u64>>;
+
+    const ON_CURVE: ValidationPolicy<u128> = OnCurve;
+
+    fn config() -> PointCfg<ModField<u64>> {
+        let cfg_field = ModFieldCfg {
+            rem: 0x0014_4C3B_27FFu64,
+        };
+        PointCfg {
+            g: points_group::Point::new_unsafe(
+                ModField::new(2500, &cfg_field),
+                ModField::new(125001, &cfg_field),
+            ),
+            a: ModField::new(100, &cfg_field),
+            b: ModField::new(1, &cfg_field),
+            cf: cfg_field,
+        }
+    }
+
+    #[test]
+    fn public_key_wire_format() {
+        let cfg = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (_, pb) = gen_keys::<_, u128, P>(&mut gen, &cfg);
+        let bytes = PublicKey::from(pb).encode_to_vec();
+        let decoded = PublicKey::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.clone().into_key(ON_CURVE, &cfg), Ok(pb));
+        // off the curve
+        let mut moved = decoded;
+        moved.point.as_mut().unwrap().y[0] ^= 1;
+        assert_eq!(moved.into_key(ON_CURVE, &cfg), Err(MalformedMessage));
+    }
+
+    #[test]
+    fn ciphertext_wire_format() {
+        let cfg = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (_, pb) = gen_keys::<_, u128, P>(&mut gen, &cfg);
+        let ct = encrypt_message::<_, u64>(pb, "Hello, world", &mut gen, &cfg).unwrap();
+        let bytes = Ciphertext::from(ct.clone()).encode_to_vec();
+        let decoded = Ciphertext::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.into_ciphertext(ON_CURVE, &cfg), Ok(ct));
+    }
+
+    #[test]
+    fn malformed() {
+        let short = Point {
+            x: vec![0; 7],
+            y: vec![0; 8],
+        };
+        let cfg = config();
+        assert_eq!(short.into_point(ON_CURVE, &cfg), Err(MalformedMessage));
+        assert_eq!(
+            PublicKey { point: None }.into_key(ON_CURVE, &cfg),
+            Err(MalformedMessage)
+        );
+        assert_eq!(
+            Point::from(cfg.g).into_point(OnCurveAndSubgroup(toy_order().rem), &cfg),
+            Ok(cfg.g)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use qrcode::{
+    render::{svg, unicode},
+    types::QrError,
+    Color, QrCode,
+};
+
+/// Modules of quiet zone around the symbol, as the spec requires.
+const QUIET_ZONE: usize = 4;
+/// PNG pixels per module.
+const PNG_SCALE: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QrFormat {
+    Png,
+    Svg,
+    Terminal,
+}
+
+/// Renders a (base64) key or ciphertext as a QR code. Fails when the data
+/// doesn't fit into the largest QR version.
+pub fn render(data: &str, format: QrFormat) -> Result<Vec<u8>, QrError> {
+    let code = QrCode::new(data.as_bytes())?;
+    Ok(match format {
+        QrFormat::Png => png(&code),
+        QrFormat::Svg => code
+            .render::<svg::Color>()
+            .min_dimensions(256, 256)
+            .build()
+            .into_bytes(),
+        QrFormat::Terminal => code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build()
+            .into_bytes(),
+    })
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in bytes {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn png_chunk(kind: &[u8; 4], data: &[u8], out: &mut Vec<u8>) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// 8-bit grayscale PNG; the zlib stream uses stored (uncompressed) blocks,
+/// which is plenty for a few hundred kilobytes of pixels.
+fn png(code: &QrCode) -> Vec<u8> {
+    let modules = code.width();
+    let colors = code.to_colors();
+    let side = (modules + 2 * QUIET_ZONE) * PNG_SCALE;
+
+    let mut raw = Vec::with_capacity((side + 1) * side);
+    for py in 0..side {
+        raw.push(0); // filter: none
+        for px in 0..side {
+            let (mx, my) = (px / PNG_SCALE, py / PNG_SCALE);
+            let dark = (QUIET_ZONE..QUIET_ZONE + modules).contains(&mx)
+                && (QUIET_ZONE..QUIET_ZONE + modules).contains(&my)
+                && colors[(my - QUIET_ZONE) * modules + mx - QUIET_ZONE] == Color::Dark;
+            raw.push(if dark { 0x00 } else { 0xFF });
+        }
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = raw.chunks(u16::MAX as usize).collect::<Vec<_>>();
+    for (i, block) in blocks.iter().enumerate() {
+        zlib.push((i + 1 == blocks.len()) as u8);
+        zlib.extend((block.len() as u16).to_le_bytes());
+        zlib.extend((!(block.len() as u16)).to_le_bytes());
+        zlib.extend(*block);
+    }
+    zlib.extend(adler32(&raw).to_be_bytes());
+
+    let mut ihdr = vec![];
+    ihdr.extend((side as u32).to_be_bytes());
+    ihdr.extend((side as u32).to_be_bytes());
+    ihdr.extend([8, 0, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(b"IHDR", &ihdr, &mut out);
+    png_chunk(b"IDAT", &zlib, &mut out);
+    png_chunk(b"IEND", &[], &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, render, QrFormat};
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+
+    #[test]
+    fn formats() {
+        let key = "7JozpsAYSyryZwZh80xSdvllfgpNnZ7XX5I7WFimgA8uRjgWbyWEEAIMRWRSE1/FtoP0sCDv6MyYuWrB7+074Q==";
+        let png = render(key, QrFormat::Png).unwrap();
+        assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+        assert_eq!(png[png.len() - 8..png.len() - 4], *b"IEND");
+        let svg = String::from_utf8(render(key, QrFormat::Svg).unwrap()).unwrap();
+        assert!(svg.contains("<svg"));
+        let term = String::from_utf8(render(key, QrFormat::Terminal).unwrap()).unwrap();
+        assert!(term.lines().count() > 10);
+    }
+
+    #[test]
+    fn too_long() {
+        assert!(render(&"A".repeat(5000), QrFormat::Svg).is_err());
+    }
+}
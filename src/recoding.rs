@@ -0,0 +1,138 @@
+//! Scalar recodings into signed digits, least significant first. Signed digits
+//! let a multiplication subtract points (negation is free on a curve), so fewer
+//! digits are nonzero and tables only need the odd or non-negative half.
+//!
+//! The scalar is consumed through `Natural::low_bits`/`drop_bits` and never
+//! goes negative: a pending `+1` is carried separately, so even `I::max()`
+//! recodes without overflow.
+
+use crate::base_traits::Natural;
+
+/// Non-adjacent form: digits in {-1, 0, 1}, no two adjacent ones nonzero.
+pub fn naf<I: Natural>(k: I) -> Vec<i8> {
+    wnaf(k, 2)
+}
+
+/// Width-`w` NAF: every nonzero digit is odd with |d| < 2^(w-1), and any `w`
+/// consecutive digits contain at most one nonzero. `2 ≤ w ≤ 8`.
+pub fn wnaf<I: Natural>(k: I, w: u32) -> Vec<i8> {
+    assert!((2..=8).contains(&w), "wNAF width {} out of 2..=8", w);
+    let (mut k, mut carry) = (k, 0);
+    let mut digits = vec![];
+    while k != I::zero() || carry != 0 {
+        // low w bits of k + carry, which is at most 2^w
+        let u = k.low_bits(w) as i32 + carry;
+        if u % 2 == 1 {
+            let d = if u >= 1 << (w - 1) { u - (1 << w) } else { u };
+            digits.push(d as i8);
+            digits.extend((1..w).map(|_| 0));
+            // (k + carry - d) / 2^w, with (u - d) / 2^w ∈ {0, 1}
+            carry = (u - d) >> w;
+            k = k.drop_bits(w);
+        } else {
+            digits.push(0);
+            carry = (k.low_bits(1) as i32 + carry) / 2;
+            k = k.drop_bits(1);
+        }
+    }
+    trim(digits)
+}
+
+/// Fixed-window signed digits: `k = Σ d_i · 2^(w·i)` with
+/// `-2^(w-1) ≤ d_i < 2^(w-1)`. `2 ≤ w ≤ 8`.
+pub fn signed_window<I: Natural>(k: I, w: u32) -> Vec<i8> {
+    assert!((2..=8).contains(&w), "window width {} out of 2..=8", w);
+    let (mut k, mut carry) = (k, 0);
+    let mut digits = vec![];
+    while k != I::zero() || carry != 0 {
+        let u = k.low_bits(w) as i32 + carry;
+        let d = if u >= 1 << (w - 1) { u - (1 << w) } else { u };
+        digits.push(d as i8);
+        carry = (u - d) >> w;
+        k = k.drop_bits(w);
+    }
+    trim(digits)
+}
+
+fn trim(mut digits: Vec<i8>) -> Vec<i8> {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{base_traits::FromRandom, uint::U256};
+
+    use super::{naf, signed_window, wnaf};
+
+    /// Σ d_i · 2^(step·i), fine for scalars below 2^100.
+    fn value(digits: &[i8], step: u32) -> i128 {
+        digits
+            .iter()
+            .rev()
+            .fold(0, |acc, &d| (acc << step) + d as i128)
+    }
+
+    fn scalars() -> Vec<u64> {
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let mut ks = vec![0, 1, 2, 3, 7, 255, 256, u64::MAX];
+        ks.extend((0..200).map(|_| u64::random(&mut gen, &())));
+        ks
+    }
+
+    #[test]
+    fn naf_is_non_adjacent() {
+        for k in scalars() {
+            let digits = naf(k);
+            assert_eq!(value(&digits, 1), k as i128);
+            assert!(digits.windows(2).all(|p| p[0] == 0 || p[1] == 0));
+        }
+        assert_eq!(naf(7u64), [-1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn wnaf_digits() {
+        for w in 2..=8 {
+            for k in scalars() {
+                let digits = wnaf(k, w);
+                assert_eq!(value(&digits, 1), k as i128, "k = {}, w = {}", k, w);
+                for (i, &d) in digits.iter().enumerate() {
+                    if d != 0 {
+                        assert!(d % 2 != 0 && (d as i32).abs() < 1 << (w - 1));
+                        let next = &digits[i + 1..digits.len().min(i + w as usize)];
+                        assert!(next.iter().all(|&d| d == 0));
+                    }
+                }
+                assert_ne!(digits.last(), Some(&0));
+            }
+        }
+    }
+
+    #[test]
+    fn signed_window_digits() {
+        for w in 2..=8 {
+            for k in scalars() {
+                let digits = signed_window(k, w);
+                assert_eq!(value(&digits, w), k as i128, "k = {}, w = {}", k, w);
+                let half = 1i32 << (w - 1);
+                assert!(digits.iter().all(|&d| (-half..half).contains(&(d as i32))));
+            }
+        }
+    }
+
+    #[test]
+    fn no_overflow_at_max() {
+        // the top digit needs one bit more than the type has
+        assert_eq!(naf(u128::MAX), naf(U256::from(u128::MAX)));
+        assert_eq!(wnaf(u8::MAX, 5).len(), 9);
+        let digits = signed_window(U256::MAX, 4);
+        assert_eq!(digits.len(), 65);
+        assert_eq!(digits[0], -1);
+        assert!(digits[1..64].iter().all(|&d| d == 0));
+        assert_eq!(digits[64], 1);
+    }
+}
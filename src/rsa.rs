@@ -0,0 +1,172 @@
+//! Textbook RSA over [`ModField`] as a ring, to compare key sizes and speed
+//! with the curve schemes. There's no padding, so it's deterministic and
+//! malleable: for teaching only.
+
+use rand::Rng;
+
+use crate::{
+    algebra::Ring,
+    base_traits::{FromRandom, Natural},
+    mod_field::{ModField, ModFieldCfg},
+};
+
+/// Miller–Rabin rounds; each lets a composite through with probability at
+/// most 1/4.
+const ROUNDS: usize = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey<I> {
+    pub n: I,
+    pub e: I,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PrivateKey<I> {
+    n: I,
+    d: I,
+}
+
+/// `2^k`.
+fn pow2<I: Natural>(k: u32) -> I {
+    (0..k).fold(I::one(), |acc, _| acc * I::two())
+}
+
+/// Miller–Rabin with `rounds` random bases.
+pub fn is_probable_prime<I, R>(n: I, rounds: usize, rng: &mut R) -> bool
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    let three = I::two() + I::one();
+    if n < I::two() {
+        return false;
+    }
+    if n <= three {
+        return true;
+    }
+    if n % I::two() == I::zero() {
+        return false;
+    }
+    let (mut d, mut s) = (n - I::one(), 0);
+    while d % I::two() == I::zero() {
+        d = d / I::two();
+        s += 1;
+    }
+    let c = ModFieldCfg { rem: n };
+    let minus_one = ModField::new(n - I::one(), &c);
+    'witness: for _ in 0..rounds {
+        let a = ModField::new(I::two() + I::random(rng, &()) % (n - three), &c);
+        let mut x = a.pow(d, &c);
+        if x == ModField::one(&c) || x == minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.sqr(&c);
+            if x == minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// A random prime of exactly `bits` bits.
+fn random_prime<I, R>(bits: u32, rng: &mut R) -> I
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    let top = pow2::<I>(bits - 1);
+    loop {
+        let candidate = (top + I::random(rng, &()) % top) / I::two() * I::two() + I::one();
+        if is_probable_prime(candidate, ROUNDS, rng) {
+            return candidate;
+        }
+    }
+}
+
+/// A key pair with `n` the product of two `bits`-bit primes and `e = 65537`.
+/// `I` must hold `2 · bits` bits.
+pub fn gen_keys<I, R>(r: &mut R, bits: u32) -> (PrivateKey<I>, PublicKey<I>)
+where
+    I: Natural + FromRandom<()>,
+    R: Rng,
+{
+    assert!(bits >= 2, "primes need at least two bits");
+    let top = pow2::<I>(bits - 1);
+    assert!(
+        top <= <I as Natural>::max() / top / I::two() / I::two(),
+        "the modulus doesn't fit"
+    );
+    let e = pow2::<I>(16) + I::one();
+    loop {
+        let (p, q) = (random_prime::<I, R>(bits, r), random_prime(bits, r));
+        if p == q {
+            continue;
+        }
+        let phi = ModFieldCfg {
+            rem: (p - I::one()) * (q - I::one()),
+        };
+        // e must be a unit modulo φ(n)
+        if let Ok(d) = ModField::new(e, &phi).inv_or_factor(&phi) {
+            let n = p * q;
+            return (PrivateKey { n, d: d.nat() }, PublicKey { n, e });
+        }
+    }
+}
+
+impl<I: Natural> PublicKey<I> {
+    pub fn encrypt(self, msg: I) -> I {
+        assert!(msg < self.n, "the message must be below the modulus");
+        let c = ModFieldCfg { rem: self.n };
+        ModField::new(msg, &c).pow(self.e, &c).nat()
+    }
+}
+
+impl<I: Natural> PrivateKey<I> {
+    pub fn decrypt(self, ciphertext: I) -> I {
+        let c = ModFieldCfg { rem: self.n };
+        ModField::new(ciphertext, &c).pow(self.d, &c).nat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use crate::uint::U256;
+
+    use super::{gen_keys, is_probable_prime};
+
+    #[test]
+    fn primality() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let primes = (0u64..200)
+            .filter(|&n| is_probable_prime(n, 20, &mut rng))
+            .collect::<Vec<_>>();
+        assert_eq!(primes.len(), 46);
+        assert_eq!(primes[..6], [2, 3, 5, 7, 11, 13]);
+        // the toy curve's field, and Carmichael numbers fooling Fermat's test
+        assert!(is_probable_prime(0x0014_4C3B_27FFu64, 20, &mut rng));
+        for n in [561u64, 1105, 1729, 2465, 10897308871] {
+            assert!(!is_probable_prime(n, 20, &mut rng), "{}", n);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let (pr, pb) = gen_keys::<u128, _>(&mut rng, 32);
+        assert!(pb.n >= 1 << 62);
+        for _ in 0..10 {
+            let msg = rng.gen::<u128>() % pb.n;
+            let c = pb.encrypt(msg);
+            assert_ne!(c, msg);
+            assert_eq!(pr.decrypt(c), msg);
+        }
+        let (pr, pb) = gen_keys::<U256, _>(&mut rng, 64);
+        let msg = U256::from(0xC0FFEEu64);
+        assert_eq!(pr.decrypt(pb.encrypt(msg)), msg);
+    }
+}
@@ -0,0 +1,533 @@
+//! Schnorr signatures over any curve whose group order `n` is known. Scalars
+//! are `ModField<I>` modulo `n`.
+//!
+//! Signing is a session: [`SigningSession::new`] binds the key to one message,
+//! [`SigningSession::commit`] derives the nonce from both and publishes
+//! `R = k·G`, [`Committed::finalize`] answers with `s = k + e·x`. The nonce
+//! never leaves the session and each step consumes the previous one, so a
+//! nonce can't be reused for a second message (which would reveal `x`).
+//!
+//! Signatures by different keys on different messages can be checked
+//! together, [`verify_batch`], or half-aggregated, [`aggregate`]: every `R`
+//! is kept but the `s` values fold into one, which saves a scalar per
+//! signature. Both only take keys whose owner proved possession of the
+//! private key, [`verify_possession`], which rules out rogue keys made up
+//! from other people's.
+
+use rand::Rng;
+
+use crate::{
+    algebra::{self, CommutativeMonoid, CommutativeOp, ConditionalSwap, InitialPoint, Ring},
+    base_traits::{Endianness, FromRandom, Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    error::Error,
+    mod_field::{ModField, ModFieldCfg},
+    secret_scalar::{ScalarModulus, SecretScalar},
+};
+
+const NONCE_TAG: &[u8] = b"schnorr/nonce";
+const CHALLENGE_TAG: &[u8] = b"schnorr/challenge";
+const AGGREGATE_TAG: &[u8] = b"schnorr/aggregate";
+const POSSESSION_TAG: &[u8] = b"schnorr/possession";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature<P, I> {
+    pub r: P,
+    pub s: I,
+}
+
+impl<P: RW, I: RW> RW for Signature<P, I> {
+    fn to_bytes(self, w: &mut impl std::io::Write) -> usize {
+        self.r.to_bytes(w) + self.s.to_bytes(w)
+    }
+
+    fn from_bytes(r: &mut impl std::io::Read) -> Self {
+        Self {
+            r: P::from_bytes(r),
+            s: I::from_bytes(r),
+        }
+    }
+
+    fn to_bytes_endian(self, e: Endianness, w: &mut impl std::io::Write) -> usize {
+        self.r.to_bytes_endian(e, w) + self.s.to_bytes_endian(e, w)
+    }
+
+    fn from_bytes_endian(r: &mut impl std::io::Read, e: Endianness) -> Self {
+        Self {
+            r: P::from_bytes_endian(r, e),
+            s: I::from_bytes_endian(r, e),
+        }
+    }
+
+    const LEN: usize = P::LEN + I::LEN;
+}
+
+/// A key bound to the one message it is about to sign.
+pub struct SigningSession<'m, I> {
+    key: PrivateKey<I>,
+    msg: &'m [u8],
+}
+
+/// A session whose nonce commitment `R` is fixed; only
+/// [`Committed::finalize`] is left.
+pub struct Committed<'m, I: Natural, P> {
+    key: PrivateKey<I>,
+    msg: &'m [u8],
+    k: ModField<I>,
+    r: P,
+}
+
+impl<'m, I: Natural + RW> SigningSession<'m, I> {
+    pub fn new(key: PrivateKey<I>, msg: &'m [u8]) -> Self {
+        Self { key, msg }
+    }
+
+    /// Derives the nonce deterministically from the key and the message, so
+    /// equal messages get equal nonces and different ones unrelated nonces.
+    pub fn commit<P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap>(
+        self,
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Committed<'m, I, P>
+    where
+        P::Cfg: InitialPoint<P>,
+    {
+        let x = self.key.scalar().to_vec();
+        let mut counter = 0u8;
+        let k = loop {
+            let k = hash_to_scalar(NONCE_TAG, &[&x, self.msg, &[counter]], n);
+            if k != ModField::zero(n) {
+                break k;
+            }
+            counter += 1;
+        };
+        Committed {
+            key: self.key,
+            msg: self.msg,
+            k,
+            r: cfg.g().exp_ct(k.nat(), cfg),
+        }
+    }
+}
+
+impl<I: Natural + RW, P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + RW>
+    Committed<'_, I, P>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    pub fn commitment(&self) -> P {
+        self.r
+    }
+
+    pub fn finalize(self, cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Signature<P, I> {
+        let public = cfg.g().exp_ct(self.key.scalar(), cfg);
+        respond(self.key, self.msg, self.k, self.r, public, n)
+    }
+}
+
+/// Signs with a caller-chosen nonce `k`, for test vectors only: signing two
+/// different messages with the same `k` reveals the private key.
+pub fn danger_sign_with_nonce<
+    I: Natural + RW,
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + RW,
+>(
+    key: PrivateKey<I>,
+    msg: &[u8],
+    k: I,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> Signature<P, I>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    let r = cfg.g().exp_ct(k, cfg);
+    let public = cfg.g().exp_ct(key.scalar(), cfg);
+    respond(key, msg, ModField::new(k, n), r, public, n)
+}
+
+/// `s·G == R + e·P`
+pub fn verify<I: Natural + RW, P: CommutativeOp<algebra::ops::Add> + RW + Eq>(
+    key: PublicKey<P>,
+    msg: &[u8],
+    sig: Signature<P, I>,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> bool
+where
+    P::Cfg: InitialPoint<P>,
+{
+    let e = challenge(sig.r, key.point(), msg, n);
+    if sig.s >= n.rem || sig.s == I::zero() || e == ModField::zero(n) {
+        return false;
+    }
+    let lhs = P::exp(cfg.g(), sig.s, cfg);
+    let rhs = P::op(sig.r, P::exp(key.point(), e.nat(), cfg), cfg);
+    lhs == rhs
+}
+
+/// A key's signature on itself and a context string naming where it's
+/// used, so a proof for one application doesn't carry over to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofOfPossession<P, I>(pub Signature<P, I>);
+
+/// A public key whose proof of possession verified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProvenKey<P>(PublicKey<P>);
+
+impl<P> ProvenKey<P> {
+    pub fn key(self) -> PublicKey<P> {
+        self.0
+    }
+}
+
+fn possession_message<P: RW>(key: P, context: &[u8]) -> Vec<u8> {
+    let mut msg = POSSESSION_TAG.to_vec();
+    msg.extend((context.len() as u64).to_be_bytes());
+    msg.extend(context);
+    msg.extend(key.to_vec());
+    msg
+}
+
+pub fn prove_possession<
+    I: Natural + RW,
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + RW,
+>(
+    key: PrivateKey<I>,
+    context: &[u8],
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> ProofOfPossession<P, I>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    let msg = possession_message(cfg.g().exp_ct(key.scalar(), cfg), context);
+    let sig = SigningSession::new(key, &msg)
+        .commit(cfg, n)
+        .finalize(cfg, n);
+    ProofOfPossession(sig)
+}
+
+/// [`Error::Signature`] unless `proof` is `key`'s for `context`.
+pub fn verify_possession<I: Natural + RW, P: CommutativeOp<algebra::ops::Add> + RW + Eq>(
+    key: PublicKey<P>,
+    proof: ProofOfPossession<P, I>,
+    context: &[u8],
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> Result<ProvenKey<P>, Error>
+where
+    P::Cfg: InitialPoint<P>,
+{
+    let msg = possession_message(key.point(), context);
+    if !verify(key, &msg, proof.0, cfg, n) {
+        return Err(Error::Signature);
+    }
+    Ok(ProvenKey(key))
+}
+
+/// A key, a message and the key's signature on it.
+pub type Signed<'m, P, I> = (ProvenKey<P>, &'m [u8], Signature<P, I>);
+
+/// Half-aggregated signatures: the `R` of each, in order, and
+/// `s = Σ zᵢ·sᵢ`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggregateSignature<P, I> {
+    pub rs: Vec<P>,
+    pub s: I,
+}
+
+/// Folds the `s` values with weights `zᵢ` hashed from all keys, messages
+/// and `R`s, so a signature can't be moved to another position or set
+/// (Chalkias et al., "Non-interactive half-aggregation of EdDSA"). The
+/// signatures aren't checked; a bad one makes the aggregate fail.
+pub fn aggregate<I: Natural + RW, P: RW + Copy>(
+    signed: &[Signed<P, I>],
+    n: &ModFieldCfg<I>,
+) -> AggregateSignature<P, I> {
+    let rs = signed.iter().map(|(_, _, sig)| sig.r).collect::<Vec<_>>();
+    let items = signed
+        .iter()
+        .map(|&(key, msg, _)| (key, msg))
+        .collect::<Vec<_>>();
+    let s = aggregate_weights(&items, &rs, n)
+        .into_iter()
+        .zip(signed)
+        .fold(ModField::zero(n), |acc, (z, (_, _, sig))| {
+            ModField::add(acc, ModField::mul(z, ModField::new(sig.s, n), n), n)
+        });
+    AggregateSignature { rs, s: s.nat() }
+}
+
+/// Whether `sig` aggregates a valid signature for each of `items`, in order.
+pub fn verify_aggregate<I: Natural + RW, P: CommutativeOp<algebra::ops::Add> + RW + Eq>(
+    items: &[(ProvenKey<P>, &[u8])],
+    sig: &AggregateSignature<P, I>,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> bool
+where
+    P::Cfg: InitialPoint<P>,
+{
+    if items.is_empty() || items.len() != sig.rs.len() {
+        return false;
+    }
+    let weights = aggregate_weights(items, &sig.rs, n);
+    verify_weighted(items, &sig.rs, sig.s, &weights, cfg, n)
+}
+
+/// [`verify`] for all of `signed` at once, with random weights so that
+/// errors can't cancel out. Without a multi-scalar multiplication that's no
+/// faster than one by one, but it's the equation aggregation rests on.
+pub fn verify_batch<I, P>(
+    signed: &[Signed<P, I>],
+    rng: &mut impl Rng,
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> bool
+where
+    I: Natural + RW + FromRandom<()>,
+    P: CommutativeOp<algebra::ops::Add> + RW + Eq,
+    P::Cfg: InitialPoint<P>,
+{
+    if signed.is_empty() || signed.iter().any(|(_, _, sig)| sig.s >= n.rem) {
+        return false;
+    }
+    let items = signed
+        .iter()
+        .map(|&(key, msg, _)| (key, msg))
+        .collect::<Vec<_>>();
+    let rs = signed.iter().map(|(_, _, sig)| sig.r).collect::<Vec<_>>();
+    let weights = (0..signed.len())
+        .map(|_| ModField::random_nonzero(rng, n))
+        .collect::<Vec<_>>();
+    let s = weights
+        .iter()
+        .zip(signed)
+        .fold(ModField::zero(n), |acc, (&a, (_, _, sig))| {
+            ModField::add(acc, ModField::mul(a, ModField::new(sig.s, n), n), n)
+        });
+    verify_weighted(&items, &rs, s.nat(), &weights, cfg, n)
+}
+
+/// `zᵢ = H(R₁ || P₁ || m₁ || … || Rₙ || Pₙ || mₙ || i) mod n`
+fn aggregate_weights<I: Natural + RW, P: RW + Copy>(
+    items: &[(ProvenKey<P>, &[u8])],
+    rs: &[P],
+    n: &ModFieldCfg<I>,
+) -> Vec<ModField<I>> {
+    let mut transcript = vec![];
+    for (&(key, msg), r) in items.iter().zip(rs) {
+        transcript.extend(r.to_vec());
+        transcript.extend(key.0.point().to_vec());
+        transcript.extend((msg.len() as u64).to_be_bytes());
+        transcript.extend(msg);
+    }
+    (0..items.len() as u64)
+        .map(|i| hash_to_scalar(AGGREGATE_TAG, &[&transcript, &i.to_be_bytes()], n))
+        .collect()
+}
+
+/// `s·G == Σ wᵢ·(Rᵢ + eᵢ·Pᵢ)`
+fn verify_weighted<I: Natural + RW, P: CommutativeOp<algebra::ops::Add> + RW + Eq>(
+    items: &[(ProvenKey<P>, &[u8])],
+    rs: &[P],
+    s: I,
+    weights: &[ModField<I>],
+    cfg: &P::Cfg,
+    n: &ModFieldCfg<I>,
+) -> bool
+where
+    P::Cfg: InitialPoint<P>,
+{
+    if s >= n.rem || s == I::zero() {
+        return false;
+    }
+    let mut terms = vec![];
+    for ((&(key, msg), &r), &w) in items.iter().zip(rs).zip(weights) {
+        let key = key.0.point();
+        let e = challenge(r, key, msg, n);
+        if e == ModField::zero(n) {
+            return false;
+        }
+        terms.push((r, w));
+        terms.push((key, ModField::mul(w, e, n)));
+    }
+    // exp can't take 0, and a zero weight drops its term anyway
+    let rhs = terms
+        .into_iter()
+        .filter(|&(_, w)| w != ModField::zero(n))
+        .map(|(p, w)| P::exp(p, w.nat(), cfg))
+        .reduce(|a, b| P::op(a, b, cfg));
+    rhs == Some(P::exp(cfg.g(), s, cfg))
+}
+
+fn respond<I: Natural + RW, P: RW + Copy>(
+    key: PrivateKey<I>,
+    msg: &[u8],
+    k: ModField<I>,
+    r: P,
+    public: P,
+    n: &ModFieldCfg<I>,
+) -> Signature<P, I> {
+    let e = challenge(r, public, msg, n);
+    // x and k are secret: s is computed in constant time
+    let m = ScalarModulus::new(n);
+    let secret = |v: I| SecretScalar::from_natural(v, &m);
+    let ex = secret(e.nat()).mul(&secret(key.scalar()), &m);
+    let s = secret(k.nat()).add(&ex, &m);
+    Signature {
+        r,
+        s: s.to_natural(),
+    }
+}
+
+/// `e = H(R || P || msg) mod n`
+fn challenge<I: Natural + RW, P: RW + Copy>(
+    r: P,
+    public: P,
+    msg: &[u8],
+    n: &ModFieldCfg<I>,
+) -> ModField<I> {
+    hash_to_scalar(CHALLENGE_TAG, &[&r.to_vec(), &public.to_vec(), msg], n)
+}
+
+/// [`SecretScalar::from_hash`] of the length-prefixed parts.
+fn hash_to_scalar<I: Natural + RW>(tag: &[u8], parts: &[&[u8]], n: &ModFieldCfg<I>) -> ModField<I> {
+    let mut msg = vec![];
+    for part in parts {
+        msg.extend((part.len() as u64).to_be_bytes());
+        msg.extend(*part);
+    }
+    let k = SecretScalar::from_hash(tag, &msg, &ScalarModulus::new(n));
+    ModField::new(k.to_natural(), n)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeOp, Inverse},
+        curves::{toy, toy_order},
+        ecc::{gen_keys, PrivateKey, PublicKey},
+        error::Error,
+        mod_field::ModField,
+        points_group::Point,
+    };
+
+    use super::{
+        aggregate, danger_sign_with_nonce, prove_possession, verify, verify_aggregate,
+        verify_batch, verify_possession, Signature, SigningSession,
+    };
+
+    type P = Point<ModField<u64>>;
+
+    fn keys() -> (PrivateKey<u128>, PublicKey<P>) {
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        gen_keys(&mut gen, &toy())
+    }
+
+    fn sign(key: PrivateKey<u128>, msg: &[u8]) -> Signature<P, u128> {
+        SigningSession::new(key, msg)
+            .commit(&toy(), &toy_order())
+            .finalize(&toy(), &toy_order())
+    }
+
+    #[test]
+    fn sign_verify() {
+        let (pri, pub_) = keys();
+        let (cfg, n) = (toy(), toy_order());
+        for msg in [&b""[..], b"hello", &[0xFF; 100]] {
+            let sig = sign(pri, msg);
+            assert!(verify(pub_, msg, sig, &cfg, &n));
+            assert!(!verify(pub_, b"other", sig, &cfg, &n));
+            let forged = Signature {
+                s: (sig.s + 1) % n.rem,
+                ..sig
+            };
+            assert!(!verify(pub_, msg, forged, &cfg, &n));
+        }
+        let (_, other) = gen_keys::<_, u128, P>(&mut rand::thread_rng(), &cfg);
+        assert!(!verify(other, b"hello", sign(pri, b"hello"), &cfg, &n));
+    }
+
+    #[test]
+    fn deterministic_nonces() {
+        let (pri, _) = keys();
+        let (cfg, n) = (toy(), toy_order());
+        let committed = SigningSession::new(pri, b"a").commit::<P>(&cfg, &n);
+        let r = committed.commitment();
+        assert_eq!(committed.finalize(&cfg, &n).r, r);
+        assert_eq!(sign(pri, b"a"), sign(pri, b"a"));
+        assert_ne!(sign(pri, b"a").r, sign(pri, b"b").r);
+    }
+
+    #[test]
+    fn explicit_nonce() {
+        let (pri, pub_) = keys();
+        let (cfg, n) = (toy(), toy_order());
+        let sig = danger_sign_with_nonce::<_, P>(pri, b"vector", 12345, &cfg, &n);
+        assert!(verify(pub_, b"vector", sig, &cfg, &n));
+        let again = danger_sign_with_nonce::<_, P>(pri, b"other", 12345, &cfg, &n);
+        assert_eq!(sig.r, again.r);
+    }
+
+    #[test]
+    fn proof_of_possession() {
+        let (cfg, n) = (toy(), toy_order());
+        let (pri, pub_) = keys();
+        let proof = prove_possession::<_, P>(pri, b"app", &cfg, &n);
+        let proven = verify_possession(pub_, proof, b"app", &cfg, &n).unwrap();
+        assert_eq!(proven.key(), pub_);
+        assert_eq!(
+            verify_possession(pub_, proof, b"other app", &cfg, &n),
+            Err(Error::Signature)
+        );
+
+        // a rogue key P' = A - P, for which the attacker knows no private
+        // key, can't borrow the proof of A
+        let (attacker, a) = gen_keys::<_, u128, P>(&mut rand::thread_rng(), &cfg);
+        let rogue = PublicKey::from_point(P::op(a.point(), pub_.point().inv(&cfg), &cfg));
+        let borrowed = prove_possession::<_, P>(attacker, b"app", &cfg, &n);
+        assert!(verify_possession(rogue, borrowed, b"app", &cfg, &n).is_err());
+    }
+
+    #[test]
+    fn aggregation() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let msgs = [&b"first"[..], b"second", b"third"];
+        let signed = msgs
+            .iter()
+            .map(|&msg| {
+                let (pri, pub_) = gen_keys::<_, u128, P>(&mut rng, &cfg);
+                let proof = prove_possession(pri, b"test", &cfg, &n);
+                let key = verify_possession(pub_, proof, b"test", &cfg, &n).unwrap();
+                (key, msg, sign(pri, msg))
+            })
+            .collect::<Vec<_>>();
+        let items = signed
+            .iter()
+            .map(|&(key, msg, _)| (key, msg))
+            .collect::<Vec<_>>();
+        assert!(verify_batch(&signed, &mut rng, &cfg, &n));
+
+        let agg = aggregate(&signed, &n);
+        assert_eq!(agg.rs.len(), 3);
+        assert!(verify_aggregate(&items, &agg, &cfg, &n));
+        assert!(!verify_aggregate(&items[..2], &agg, &cfg, &n));
+        let mut swapped = items.clone();
+        swapped.swap(0, 1);
+        assert!(!verify_aggregate(&swapped, &agg, &cfg, &n));
+        let mut altered = items.clone();
+        altered[2].1 = b"fourth";
+        assert!(!verify_aggregate(&altered, &agg, &cfg, &n));
+
+        // one bad signature spoils both
+        let mut bad = signed.clone();
+        bad[1].2.s = (bad[1].2.s + 1) % n.rem;
+        assert!(!verify_batch(&bad, &mut rng, &cfg, &n));
+        assert!(!verify_aggregate(&items, &aggregate(&bad, &n), &cfg, &n));
+        assert!(!verify_batch::<u128, P>(&[], &mut rng, &cfg, &n));
+    }
+}
@@ -0,0 +1,159 @@
+//! Private keys kept encrypted in memory. A key is sealed under a random key
+//! of the process, made on first use and never written anywhere, and only
+//! unsealed for the duration of one operation, so a heap dump or a stray
+//! `Debug` print shows ciphertext instead of long-lived scalars. It is no
+//! protection against code running inside the process, which can read the
+//! process key as well, and copies of the unsealed [`PrivateKey`] made by the
+//! operation itself may linger on the stack.
+
+use std::{marker::PhantomData, sync::OnceLock};
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{self, AbelianGroup, CommutativeMonoid, ConditionalSwap, Configurable, InitialPoint},
+    base_traits::{Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    entropy::SystemEntropy,
+    error::Error,
+    mod_field::ModFieldCfg,
+    schnorr::Signature,
+    signer::{Decrypter, Signer},
+};
+
+const TAG: &[u8] = b"sealed-private-key/v1";
+
+static PROCESS_KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+fn process_key() -> Result<&'static [u8; 32], Error> {
+    if let Some(key) = PROCESS_KEY.get() {
+        return Ok(key);
+    }
+    let mut key = [0; 32];
+    SystemEntropy::new()?.fill_bytes(&mut key);
+    // whoever came first wins, the other key is dropped unused
+    Ok(PROCESS_KEY.get_or_init(|| key))
+}
+
+/// XORs `data` with the keystream of this key and nonce.
+fn apply_keystream(key: &[u8; 32], nonce: &[u8; 16], data: &mut [u8]) {
+    let seed = Sha256::new()
+        .chain_update(TAG)
+        .chain_update(key)
+        .chain_update(nonce)
+        .finalize();
+    let mut stream = vec![0; data.len()];
+    ChaCha20Rng::from_seed(seed.into()).fill_bytes(&mut stream);
+    for (d, s) in data.iter_mut().zip(&stream) {
+        *d ^= s;
+    }
+    wipe(&mut stream);
+}
+
+fn wipe(buf: &mut [u8]) {
+    buf.fill(0);
+    std::hint::black_box(buf);
+}
+
+#[derive(Clone)]
+pub struct SealedPrivateKey<I> {
+    nonce: [u8; 16],
+    sealed: Vec<u8>,
+    scalar: PhantomData<I>,
+}
+
+impl<I> std::fmt::Debug for SealedPrivateKey<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SealedPrivateKey(..)")
+    }
+}
+
+impl<I: RW + Copy> SealedPrivateKey<I> {
+    /// Fails only if the process key has yet to be made and the system's
+    /// entropy source fails.
+    pub fn seal(key: PrivateKey<I>) -> Result<Self, Error> {
+        let process_key = process_key()?;
+        let mut nonce = [0; 16];
+        SystemEntropy::new()?.fill_bytes(&mut nonce);
+        let mut sealed = key.to_vec();
+        apply_keystream(process_key, &nonce, &mut sealed);
+        Ok(Self {
+            nonce,
+            sealed,
+            scalar: PhantomData,
+        })
+    }
+
+    /// Runs `f` with the unsealed key, which it shouldn't keep.
+    pub fn with_key<T>(&self, f: impl FnOnce(PrivateKey<I>) -> T) -> T {
+        // there is a sealed key, so the process key exists
+        let process_key = PROCESS_KEY.get().unwrap();
+        let mut bytes = self.sealed.clone();
+        apply_keystream(process_key, &self.nonce, &mut bytes);
+        let key = PrivateKey::from_bytes(&mut &bytes[..]);
+        wipe(&mut bytes);
+        f(key)
+    }
+}
+
+impl<I, P> Signer<P, I> for SealedPrivateKey<I>
+where
+    I: Natural + RW,
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    fn public_key(&self, cfg: &<P as Configurable>::Cfg) -> PublicKey<P> {
+        self.with_key(|key| key.public_key(cfg))
+    }
+
+    fn sign(&self, msg: &[u8], cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Result<Signature<P, I>, Error> {
+        self.with_key(|key| Signer::<P, I>::sign(&key, msg, cfg, n))
+    }
+}
+
+impl<I: Natural + RW, P: AbelianGroup<algebra::ops::Add> + ConditionalSwap> Decrypter<P>
+    for SealedPrivateKey<I>
+{
+    fn diffie_hellman(&self, point: P, cfg: &P::Cfg) -> Result<P, Error> {
+        self.with_key(|key| Decrypter::diffie_hellman(&key, point, cfg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        base_traits::RW,
+        curves::{toy, toy_order},
+        ecc::PrivateKey,
+        encoding_utils::{decrypt_file, encrypt_file},
+        mod_field::ModField,
+        points_group::Point,
+        schnorr::verify,
+        signer::Signer,
+    };
+
+    use super::SealedPrivateKey;
+
+    type P = Point<ModField<u64>>;
+
+    #[test]
+    fn sealed() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([8u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let pr = PrivateKey::random(&mut rng, &n);
+        let sealed = SealedPrivateKey::seal(pr).unwrap();
+        assert_ne!(sealed.sealed, pr.to_vec());
+        assert_ne!(sealed.sealed, SealedPrivateKey::seal(pr).unwrap().sealed);
+        assert_eq!(format!("{:?}", sealed), "SealedPrivateKey(..)");
+        assert_eq!(sealed.with_key(|key| key), pr);
+
+        let sig = Signer::<P, _>::sign(&sealed, b"msg", &cfg, &n).unwrap();
+        assert!(verify(pr.public_key(&cfg), b"msg", sig, &cfg, &n));
+        let file = encrypt_file::<_, u128>(pr.public_key(&cfg), b"data", &mut rng, &cfg).unwrap();
+        assert_eq!(decrypt_file(&sealed, &file, &cfg).unwrap(), b"data");
+    }
+}
@@ -0,0 +1,339 @@
+//! Arithmetic modulo the group order for secret scalars (private keys, nonces)
+//! in time independent of their values. `Natural` and `ModField` branch on
+//! comparisons and divide, which is fine for public values but leaks secret
+//! ones; here every operation runs the same limb operations whatever the
+//! inputs, choosing results with masks instead of branches. Only the size of
+//! the modulus and of byte inputs affects timing.
+
+use std::{fmt, hint::black_box};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    base_traits::{Natural, RW},
+    mod_field::ModFieldCfg,
+};
+
+/// The modulus `n` as little-endian limbs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScalarModulus {
+    n: Vec<u64>,
+}
+
+impl ScalarModulus {
+    pub fn new<I: Natural + RW>(n: &ModFieldCfg<I>) -> Self {
+        assert!(n.rem > I::one(), "modulus must be above 1");
+        Self {
+            n: limbs(&n.rem.to_vec()),
+        }
+    }
+
+    /// Bit length of `n`.
+    pub fn bits(&self) -> usize {
+        let top = self.n.iter().rposition(|&l| l != 0).unwrap();
+        64 * top + (64 - self.n[top].leading_zeros() as usize)
+    }
+}
+
+/// `expand_message_xmd` of RFC 9380, section 5.3.1, with SHA-256.
+fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    assert!(dst.len() <= 255 && len <= 255 * 32 && len <= u16::MAX as usize);
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let b0 = Sha256::new()
+        .chain_update([0; 64])
+        .chain_update(msg)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut b = Sha256::new()
+        .chain_update(b0)
+        .chain_update([1])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut res = b.to_vec();
+    for i in 2..=len.div_ceil(32) as u8 {
+        let mixed = b0.iter().zip(b).map(|(x, y)| x ^ y).collect::<Vec<_>>();
+        b = Sha256::new()
+            .chain_update(mixed)
+            .chain_update([i])
+            .chain_update(&dst_prime)
+            .finalize();
+        res.extend(b);
+    }
+    res.truncate(len);
+    res
+}
+
+/// A value in `[0, n)`. There's no `PartialEq`, use [`SecretScalar::ct_eq`].
+#[derive(Clone)]
+pub struct SecretScalar {
+    limbs: Vec<u64>,
+}
+
+impl fmt::Debug for SecretScalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretScalar(..)")
+    }
+}
+
+fn limbs(bytes: &[u8]) -> Vec<u64> {
+    bytes
+        .chunks(8)
+        .map(|c| {
+            let mut limb = [0; 8];
+            limb[..c.len()].copy_from_slice(c);
+            u64::from_le_bytes(limb)
+        })
+        .collect()
+}
+
+/// All ones if `bit` is 1, else zero.
+fn mask(bit: u64) -> u64 {
+    black_box(0u64.wrapping_sub(bit))
+}
+
+/// `a` where `mask` is all ones, else `b`.
+fn select(mask: u64, a: &[u64], b: &mut [u64]) {
+    for (x, &y) in b.iter_mut().zip(a) {
+        *x = (y & mask) | (*x & !mask);
+    }
+}
+
+/// `a -= b`, returning the borrow.
+fn sub_in_place(a: &mut [u64], b: &[u64]) -> u64 {
+    let mut borrow = 0;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (d, b1) = x.overflowing_sub(y);
+        let (d, b2) = d.overflowing_sub(borrow);
+        *x = d;
+        borrow = (b1 | b2) as u64;
+    }
+    borrow
+}
+
+/// `a += b`, returning the carry.
+fn add_in_place(a: &mut [u64], b: &[u64]) -> u64 {
+    let mut carry = 0;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let (s, c1) = x.overflowing_add(y);
+        let (s, c2) = s.overflowing_add(carry);
+        *x = s;
+        carry = (c1 | c2) as u64;
+    }
+    carry
+}
+
+/// Reduces `r + carry·2^(64·len)`, known to be below `2n`, to below `n`.
+fn reduce_once(r: &mut [u64], carry: u64, n: &[u64]) {
+    let mut d = r.to_vec();
+    let borrow = sub_in_place(&mut d, n);
+    // r ≥ n if it overflowed or the subtraction didn't
+    select(mask(carry | (borrow ^ 1)), &d, r);
+}
+
+/// `r = 2r + bit mod n`.
+fn shift_in(r: &mut [u64], bit: u64, n: &[u64]) {
+    let mut carry = bit;
+    for x in r.iter_mut() {
+        let top = *x >> 63;
+        *x = *x << 1 | carry;
+        carry = top;
+    }
+    reduce_once(r, carry, n);
+}
+
+impl SecretScalar {
+    pub fn zero(m: &ScalarModulus) -> Self {
+        Self {
+            limbs: vec![0; m.n.len()],
+        }
+    }
+
+    /// Little-endian bytes of any length, reduced mod `n`.
+    pub fn from_le_bytes(bytes: &[u8], m: &ScalarModulus) -> Self {
+        let mut res = Self::zero(m);
+        for &b in bytes.iter().rev() {
+            for i in (0..8).rev() {
+                shift_in(&mut res.limbs, (b >> i & 1) as u64, &m.n);
+            }
+        }
+        res
+    }
+
+    /// Hashes `msg` to a scalar as RFC 9380's `hash_to_field` does: the message
+    /// is expanded to `bits(n) + 128` bits before the reduction, which leaves a
+    /// bias of at most 2^-128 towards small values. `dst` separates the uses.
+    pub fn from_hash(dst: &[u8], msg: &[u8], m: &ScalarModulus) -> Self {
+        let mut bytes = expand_message_xmd(msg, dst, (m.bits() + 128).div_ceil(8));
+        // big-endian, as in the RFC
+        bytes.reverse();
+        Self::from_le_bytes(&bytes, m)
+    }
+
+    pub fn from_natural<I: RW>(k: I, m: &ScalarModulus) -> Self {
+        Self::from_le_bytes(&k.to_vec(), m)
+    }
+
+    /// The value as `I`, which has to be wide enough for `n`.
+    pub fn to_natural<I: RW>(&self) -> I {
+        let mut bytes = self
+            .limbs
+            .iter()
+            .flat_map(|l| l.to_le_bytes())
+            .collect::<Vec<_>>();
+        bytes.resize(I::LEN.max(bytes.len()), 0);
+        I::from_bytes(&mut &bytes[..])
+    }
+
+    pub fn add(&self, other: &Self, m: &ScalarModulus) -> Self {
+        let mut res = self.clone();
+        let carry = add_in_place(&mut res.limbs, &other.limbs);
+        reduce_once(&mut res.limbs, carry, &m.n);
+        res
+    }
+
+    pub fn sub(&self, other: &Self, m: &ScalarModulus) -> Self {
+        let mut res = self.clone();
+        let borrow = sub_in_place(&mut res.limbs, &other.limbs);
+        let n = m.n.iter().map(|&l| l & mask(borrow)).collect::<Vec<_>>();
+        add_in_place(&mut res.limbs, &n);
+        res
+    }
+
+    pub fn neg(&self, m: &ScalarModulus) -> Self {
+        Self::zero(m).sub(self, m)
+    }
+
+    /// Schoolbook product, then reduced a bit at a time.
+    pub fn mul(&self, other: &Self, m: &ScalarModulus) -> Self {
+        let len = self.limbs.len();
+        let mut product = vec![0u64; 2 * len];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let t = a as u128 * b as u128 + product[i + j] as u128 + carry;
+                product[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            product[i + len] = carry as u64;
+        }
+        let mut res = Self::zero(m);
+        for limb in product.iter().rev() {
+            for i in (0..64).rev() {
+                shift_in(&mut res.limbs, limb >> i & 1, &m.n);
+            }
+        }
+        res
+    }
+
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        let diff = self
+            .limbs
+            .iter()
+            .zip(&other.limbs)
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        black_box(diff) == 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.ct_eq(&Self {
+            limbs: vec![0; self.limbs.len()],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use crate::{
+        algebra::Ring,
+        curves::{secp256k1_order, toy_order},
+        mod_field::{ModField, ModFieldCfg},
+        uint::{Uint, U256},
+    };
+
+    use super::{expand_message_xmd, ScalarModulus, SecretScalar};
+
+    #[test]
+    fn matches_mod_field() {
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        // secp256k1's order has the top bit set, the toy one is small
+        let orders = [
+            secp256k1_order(),
+            ModFieldCfg {
+                rem: U256::from(toy_order().rem),
+            },
+        ];
+        for n in orders {
+            let m = ScalarModulus::new(&n);
+            for _ in 0..20 {
+                let a = Uint(gen.gen()) % n.rem;
+                let b = if gen.gen() {
+                    Uint(gen.gen()) % n.rem
+                } else {
+                    a
+                };
+                let (fa, fb) = (ModField::new(a, &n), ModField::new(b, &n));
+                let sa = SecretScalar::from_natural(a, &m);
+                let sb = SecretScalar::from_natural(b, &m);
+                let check = |s: SecretScalar, f: ModField<U256>| {
+                    assert_eq!(s.to_natural::<U256>(), f.nat());
+                };
+                check(sa.add(&sb, &m), Ring::add(fa, fb, &n));
+                check(sa.sub(&sb, &m), Ring::sub(fa, fb, &n));
+                check(sa.mul(&sb, &m), Ring::mul(fa, fb, &n));
+                check(sa.neg(&m), Ring::sub(ModField::zero(&n), fa, &n));
+                assert_eq!(sa.ct_eq(&sb), a == b);
+                assert!(sa.sub(&sa, &m).is_zero());
+            }
+        }
+    }
+
+    #[test]
+    fn reduction() {
+        let m = ScalarModulus::new(&ModFieldCfg { rem: 1000u64 });
+        let s = SecretScalar::from_le_bytes(&123456789u64.to_le_bytes(), &m);
+        assert_eq!(s.to_natural::<u64>(), 789);
+        let s = SecretScalar::from_le_bytes(&[0xFF; 40], &m);
+        assert_eq!(s.to_natural::<u64>(), 575);
+        assert_eq!(
+            SecretScalar::from_natural(1000u64, &m).to_natural::<u64>(),
+            0
+        );
+        assert_eq!(format!("{:?}", s), "SecretScalar(..)");
+    }
+
+    #[test]
+    fn expand_message() {
+        // RFC 9380, appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        let hex = |b: Vec<u8>| b.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!(
+            hex(expand_message_xmd(b"", dst, 0x20)),
+            "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+        );
+        assert_eq!(
+            hex(expand_message_xmd(b"abc", dst, 0x20)),
+            "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+        );
+        // the length is hashed in, so longer outputs don't extend shorter ones
+        let long = expand_message_xmd(b"abc", dst, 0x80);
+        assert_eq!(long.len(), 0x80);
+        assert_ne!(long[..0x20], expand_message_xmd(b"abc", dst, 0x20)[..]);
+    }
+
+    #[test]
+    fn hash_to_scalar() {
+        let n = secp256k1_order();
+        let m = ScalarModulus::new(&n);
+        assert_eq!(m.bits(), 256);
+        assert_eq!(ScalarModulus::new(&ModFieldCfg { rem: 1000u64 }).bits(), 10);
+        let a = SecretScalar::from_hash(b"test", b"msg", &m);
+        assert!(a.ct_eq(&SecretScalar::from_hash(b"test", b"msg", &m)));
+        assert!(!a.ct_eq(&SecretScalar::from_hash(b"test2", b"msg", &m)));
+        assert!(!a.ct_eq(&SecretScalar::from_hash(b"test", b"msg2", &m)));
+        assert!(a.to_natural::<U256>() < n.rem);
+    }
+}
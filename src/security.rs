@@ -0,0 +1,249 @@
+//! A rough security estimate for a custom curve from its group order: the
+//! cost of Pollard's rho, which only depends on the largest prime factor of
+//! the order, and the known shortcuts around it. These are the checks of
+//! SafeCurves that need no more than the order; passing them doesn't make a
+//! curve safe, failing one makes it broken.
+//!
+//! - a small embedding degree `k` moves the discrete logarithm into
+//!   `F_{p^k}`, where index calculus is subexponential (MOV, Frey–Rück);
+//! - an anomalous curve, one with `p` points, has a linear-time logarithm
+//!   (Smart);
+//! - a weak quadratic twist is attackable through x-only arithmetic, which
+//!   can't tell a point from a point on the twist.
+
+use std::fmt::Display;
+
+use rand::Rng;
+
+use crate::{
+    base_traits::{FromRandom, Natural, RW},
+    curve_file::Curve,
+    rsa::is_probable_prime,
+};
+
+/// Below this many bits of rho cost a curve gets a warning, as for NIST's
+/// 112-bit security floor.
+pub const MIN_BITS: f64 = 112.0;
+
+/// Embedding degrees up to this are reported; beyond it the extension field
+/// is already too big for index calculus to help.
+const MAX_EMBEDDING: u32 = 20;
+
+/// Small factors are divided out up to this bound before the rest is taken as
+/// the largest prime factor, which overestimates the cost if it's composite.
+const TRIAL_BOUND: u32 = 1 << 12;
+
+const ROUNDS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecurityReport {
+    /// `log₂` of the `√(πn/4)` group operations rho needs for the largest
+    /// prime factor `n` of the order.
+    pub rho_bits: f64,
+    /// The smallest `k ≤ 20` with `n | p^k - 1`.
+    pub embedding_degree: Option<u32>,
+    pub anomalous: bool,
+    /// `rho_bits` of the quadratic twist, `None` if its order doesn't fit.
+    pub twist_rho_bits: Option<f64>,
+    pub warnings: Vec<String>,
+}
+
+fn log2<I: Natural>(n: I) -> f64 {
+    let (mut v, mut bits) = (n, 0u32);
+    while v != I::zero() {
+        v = v / I::two();
+        bits += 1;
+    }
+    let shift = bits.saturating_sub(32);
+    shift as f64 + (n.drop_bits(shift).low_bits(32) as f64).log2()
+}
+
+fn rho_bits<I: Natural>(n: I) -> f64 {
+    (log2(n) + (std::f64::consts::PI / 4.0).log2()) / 2.0
+}
+
+/// The largest prime factor of `n`, or a composite upper bound of it whose
+/// factors are all above [`TRIAL_BOUND`].
+fn largest_factor<I: Natural>(n: I) -> I {
+    let (mut n, mut largest) = (n, I::one());
+    let mut d = I::two();
+    for _ in 2..TRIAL_BOUND {
+        if d > n / d {
+            break;
+        }
+        while n % d == I::zero() {
+            (n, largest) = (n / d, d);
+        }
+        d = d + I::one();
+    }
+    if n > largest {
+        n
+    } else {
+        largest
+    }
+}
+
+/// The order of the twist, `2(p + 1) - N`, if it fits into `I`.
+fn twist_order<I: Natural>(p: I, order: I) -> Option<I> {
+    let p1 = p + I::one();
+    if order <= p1 {
+        let t = p1 - order;
+        (t <= <I as Natural>::max() - p1).then(|| p1 + t)
+    } else {
+        Some(p1 - (order - p1))
+    }
+}
+
+/// Analyzes a curve with a known generator order `n`. The group order is
+/// `n` times the cofactor, or `n` alone if no cofactor is given.
+pub fn curve_security_report<I, R>(curve: &Curve<I>, rng: &mut R) -> Result<SecurityReport, String>
+where
+    I: Natural + RW + FromRandom<()>,
+    R: Rng,
+{
+    let n = curve.order.ok_or("the order of the generator is unknown")?;
+    let p = curve.cfg.cf.rem;
+    let h = curve.cofactor.unwrap_or(I::one());
+    if h != I::zero() && h > <I as Natural>::max() / n {
+        return Err("the group order doesn't fit".to_string());
+    }
+    let order = n * h;
+    let mut warnings = vec![];
+
+    let largest = if is_probable_prime(n, ROUNDS, rng) {
+        n
+    } else {
+        warnings.push("the order of the generator isn't prime".to_string());
+        largest_factor(n)
+    };
+    let rho = rho_bits(largest);
+    if rho < MIN_BITS {
+        warnings.push(format!(
+            "rho takes about 2^{:.1} operations, below 2^{}",
+            rho, MIN_BITS
+        ));
+    }
+
+    let q = p % n;
+    let (mut pk, mut embedding_degree) = (q, None);
+    for k in 1..=MAX_EMBEDDING {
+        if pk == I::one() {
+            embedding_degree = Some(k);
+            break;
+        }
+        pk = pk.mul_mod(q, n);
+    }
+    if let Some(k) = embedding_degree {
+        warnings.push(format!(
+            "embedding degree {}: the MOV attack maps logarithms into F_p^{}",
+            k, k
+        ));
+    }
+
+    let anomalous = order == p || n == p;
+    if anomalous {
+        warnings.push("anomalous: Smart's attack takes logarithms in linear time".to_string());
+    }
+
+    let twist_rho_bits = twist_order(p, order).map(|t| rho_bits(largest_factor(t)));
+    match twist_rho_bits {
+        Some(bits) if bits < MIN_BITS => warnings.push(format!(
+            "the twist only needs about 2^{:.1} operations, x-only users must validate points",
+            bits
+        )),
+        Some(_) => {}
+        None => warnings.push("the order of the twist doesn't fit, not checked".to_string()),
+    }
+
+    Ok(SecurityReport {
+        rho_bits: rho,
+        embedding_degree,
+        anomalous,
+        twist_rho_bits,
+        warnings,
+    })
+}
+
+impl Display for SecurityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "RHO: 2^{:.1}", self.rho_bits)?;
+        match self.embedding_degree {
+            Some(k) => writeln!(f, "EMBEDDING DEGREE: {}", k)?,
+            None => writeln!(f, "EMBEDDING DEGREE: > {}", MAX_EMBEDDING)?,
+        }
+        writeln!(f, "ANOMALOUS: {}", self.anomalous)?;
+        match self.twist_rho_bits {
+            Some(bits) => writeln!(f, "TWIST RHO: 2^{:.1}", bits)?,
+            None => writeln!(f, "TWIST RHO: unknown")?,
+        }
+        for w in &self.warnings {
+            writeln!(f, "WARNING: {}", w)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        curve_file::Curve,
+        curves::{secp256k1, secp256k1_order},
+        uint::U256,
+    };
+
+    use super::{curve_security_report, largest_factor, twist_order};
+
+    fn small(a: u64, b: u64, g: (u64, u64), order: u64, cofactor: u64) -> Curve<u64> {
+        Curve::new(23, a, b, g, Some(order), Some(cofactor)).unwrap()
+    }
+
+    #[test]
+    fn small_curves() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        // 28 = 4 · 7 points, and 23³ = 1 mod 7; the twist has 20
+        let c = small(1, 1, (17, 3), 7, 4);
+        let report = curve_security_report(&c, &mut rng).unwrap();
+        assert_eq!(report.embedding_degree, Some(3));
+        assert!(!report.anomalous);
+        assert_eq!(twist_order(23u64, 28), Some(20));
+        assert_eq!(report.warnings.len(), 3);
+
+        // 23 points over F_23
+        let c = small(5, 3, (0, 7), 23, 1);
+        let report = curve_security_report(&c, &mut rng).unwrap();
+        assert!(report.anomalous);
+        assert_eq!(report.embedding_degree, None);
+
+        let unknown = Curve {
+            order: None,
+            ..small(1, 1, (17, 3), 7, 4)
+        };
+        assert!(curve_security_report(&unknown, &mut rng).is_err());
+    }
+
+    #[test]
+    fn secp256k1_report() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let c = Curve {
+            cfg: secp256k1(),
+            order: Some(secp256k1_order().rem),
+            cofactor: Some(U256::from(1u64)),
+        };
+        let report = curve_security_report(&c, &mut rng).unwrap();
+        assert!((report.rho_bits - 127.8).abs() < 0.1, "{}", report.rho_bits);
+        assert_eq!(report.embedding_degree, None);
+        assert!(!report.anomalous);
+        // 2^256 doesn't fit into U256 and neither does the twist's order
+        assert_eq!(report.twist_rho_bits, None);
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[test]
+    fn factors() {
+        assert_eq!(largest_factor(28u64), 7);
+        assert_eq!(largest_factor(1024u64), 2);
+        assert_eq!(largest_factor(4099u64 * 4111 * 3), 4099 * 4111);
+    }
+}
@@ -0,0 +1,188 @@
+//! Who holds the private scalar is up to the implementation: [`PrivateKey`]
+//! holds it in memory, [`KeyPair`] asks its observers first, and an HSM, a
+//! remote service or a threshold of share holders can implement the same
+//! traits. Protocol code that takes a [`Signer`] or [`Decrypter`], like
+//! [`decrypt_file`](crate::encoding_utils::decrypt_file), works with any.
+
+use crate::{
+    algebra::{self, AbelianGroup, CommutativeMonoid, ConditionalSwap, Configurable, InitialPoint},
+    base_traits::{Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    error::Error,
+    key_pair::KeyPair,
+    mod_field::ModFieldCfg,
+    schnorr::{Signature, SigningSession},
+};
+
+/// Makes Schnorr signatures.
+pub trait Signer<P: Configurable, I> {
+    fn public_key(&self, cfg: &P::Cfg) -> PublicKey<P>;
+
+    fn sign(&self, msg: &[u8], cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Result<Signature<P, I>, Error>;
+}
+
+/// Decrypts ElGamal pairs.
+pub trait Decrypter<P: AbelianGroup<algebra::ops::Add> + ConditionalSwap> {
+    /// `x · point` for the private scalar `x`, all that decryption needs of
+    /// it. A threshold implementation sums the shares' products.
+    fn diffie_hellman(&self, point: P, cfg: &P::Cfg) -> Result<P, Error>;
+
+    /// `C2 - x · C1`
+    fn decrypt(&self, (c1, c2): (P, P), cfg: &P::Cfg) -> Result<P, Error> {
+        let mask = self.diffie_hellman(c1, cfg)?;
+        Ok(P::op(c2, P::inv(mask, cfg), cfg))
+    }
+}
+
+impl<I, P> Signer<P, I> for PrivateKey<I>
+where
+    I: Natural + RW,
+    P: CommutativeMonoid<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    fn public_key(&self, cfg: &P::Cfg) -> PublicKey<P> {
+        PrivateKey::public_key(*self, cfg)
+    }
+
+    fn sign(&self, msg: &[u8], cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Result<Signature<P, I>, Error> {
+        Ok(SigningSession::new(*self, msg)
+            .commit(cfg, n)
+            .finalize(cfg, n))
+    }
+}
+
+impl<I: Natural + RW, P: AbelianGroup<algebra::ops::Add> + ConditionalSwap> Decrypter<P>
+    for PrivateKey<I>
+{
+    fn diffie_hellman(&self, point: P, cfg: &P::Cfg) -> Result<P, Error> {
+        Ok(point.exp_ct(self.scalar(), cfg))
+    }
+}
+
+impl<I, P> Signer<P, I> for KeyPair<I, P>
+where
+    I: Natural + RW,
+    P: AbelianGroup<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    fn public_key(&self, _cfg: &P::Cfg) -> PublicKey<P> {
+        KeyPair::public_key(self)
+    }
+
+    fn sign(&self, msg: &[u8], cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Result<Signature<P, I>, Error> {
+        KeyPair::sign(self, msg, cfg, n)
+    }
+}
+
+/// Observed per pair, not per message.
+impl<I, P> Decrypter<P> for KeyPair<I, P>
+where
+    I: Natural + RW,
+    P: AbelianGroup<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    fn diffie_hellman(&self, point: P, cfg: &P::Cfg) -> Result<P, Error> {
+        self.derive(PublicKey::from_point(point), cfg)
+    }
+
+    fn decrypt(&self, pair: (P, P), cfg: &P::Cfg) -> Result<P, Error> {
+        KeyPair::decrypt(self, pair, cfg)
+    }
+}
+
+impl<P: Configurable, I, T: Signer<P, I> + ?Sized> Signer<P, I> for &T {
+    fn public_key(&self, cfg: &P::Cfg) -> PublicKey<P> {
+        (**self).public_key(cfg)
+    }
+
+    fn sign(&self, msg: &[u8], cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Result<Signature<P, I>, Error> {
+        (**self).sign(msg, cfg, n)
+    }
+}
+
+impl<P, T> Decrypter<P> for &T
+where
+    P: AbelianGroup<algebra::ops::Add> + ConditionalSwap,
+    T: Decrypter<P> + ?Sized,
+{
+    fn diffie_hellman(&self, point: P, cfg: &P::Cfg) -> Result<P, Error> {
+        (**self).diffie_hellman(point, cfg)
+    }
+
+    fn decrypt(&self, pair: (P, P), cfg: &P::Cfg) -> Result<P, Error> {
+        (**self).decrypt(pair, cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::{CommutativeOp, Ring},
+        curves::{toy, toy_order},
+        ecc::{PrivateKey, PublicKey},
+        encoding_utils::{decrypt_file, encrypt_file},
+        error::Error,
+        key_pair::KeyPair,
+        mod_field::{ModField, ModFieldCfg},
+        points_group::{Point, PointCfg},
+        schnorr::verify,
+    };
+
+    use super::{Decrypter, Signer};
+
+    type P = Point<ModField<u64>>;
+
+    /// Two additive shares of the key, never put together.
+    struct Shares(PrivateKey<u128>, PrivateKey<u128>);
+
+    impl Decrypter<P> for Shares {
+        fn diffie_hellman(&self, point: P, cfg: &PointCfg<ModField<u64>>) -> Result<P, Error> {
+            let part = |share: &PrivateKey<u128>| share.diffie_hellman(point, cfg);
+            Ok(P::op(part(&self.0)?, part(&self.1)?, cfg))
+        }
+    }
+
+    /// Protocol code, unaware of where the key is.
+    fn sign_and_check(
+        signer: &impl Signer<P, u128>,
+        cfg: &PointCfg<ModField<u64>>,
+        n: &ModFieldCfg<u128>,
+    ) {
+        let sig = signer.sign(b"message", cfg, n).unwrap();
+        assert!(verify(signer.public_key(cfg), b"message", sig, cfg, n));
+    }
+
+    #[test]
+    fn backends() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([7u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let pr = PrivateKey::random(&mut rng, &n);
+        sign_and_check(&pr, &cfg, &n);
+        sign_and_check(&KeyPair::new(pr, &cfg), &cfg, &n);
+
+        let (x1, x2) = (
+            PrivateKey::random(&mut rng, &n),
+            PrivateKey::random(&mut rng, &n),
+        );
+        let x = ModField::add(
+            ModField::new(x1.scalar(), &n),
+            ModField::new(x2.scalar(), &n),
+            &n,
+        );
+        let key: PublicKey<P> = PrivateKey::from_scalar(x.nat(), &n)
+            .unwrap()
+            .public_key(&cfg);
+        let file = encrypt_file::<_, u128>(key, b"for both", &mut rng, &cfg).unwrap();
+        assert_eq!(
+            decrypt_file(Shares(x1, x2), &file, &cfg).unwrap(),
+            b"for both"
+        );
+        let pair = KeyPair::<_, P>::new(pr, &cfg);
+        let file = encrypt_file::<_, u128>(pair.public_key(), b"hi", &mut rng, &cfg).unwrap();
+        assert_eq!(decrypt_file(pr, &file, &cfg).unwrap(), b"hi");
+        assert_eq!(decrypt_file(&pair, &file, &cfg).unwrap(), b"hi");
+        assert_eq!(pair.decrypt_file(&file, &cfg).unwrap(), b"hi");
+    }
+}
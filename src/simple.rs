@@ -0,0 +1,57 @@
+//! The zero-config way in: secp256k1, 256-bit scalars and the system's
+//! entropy, so there's no config to pass around. Everything here is a thin
+//! wrapper around the generic API, which is there for anything else.
+
+use std::sync::OnceLock;
+
+use crate::{
+    context::CurveContext,
+    curves::{secp256k1, secp256k1_order},
+    ecc,
+    entropy::SystemEntropy,
+    error::Error,
+    mod_field::ModField,
+    points_group::Point,
+    uint::U256,
+};
+
+pub type PrivateKey = ecc::PrivateKey<U256>;
+pub type PublicKey = ecc::PublicKey<Point<ModField<U256>>>;
+
+fn context() -> &'static CurveContext<ModField<U256>, U256> {
+    static CONTEXT: OnceLock<CurveContext<ModField<U256>, U256>> = OnceLock::new();
+    CONTEXT.get_or_init(|| CurveContext::new(secp256k1(), secp256k1_order()))
+}
+
+/// A fresh key pair. Fails only if the system's entropy source does.
+pub fn gen_keys() -> Result<(PrivateKey, PublicKey), Error> {
+    Ok(context().gen_keys(&mut SystemEntropy::new()?))
+}
+
+/// Encrypts any bytes to `key`, in the format of
+/// [`encrypt_file`](crate::encoding_utils::encrypt_file).
+pub fn encrypt(key: &PublicKey, data: &[u8]) -> Result<Vec<u8>, Error> {
+    context().encrypt_file(*key, data, &mut SystemEntropy::new()?)
+}
+
+/// The inverse of [`encrypt`].
+pub fn decrypt(key: &PrivateKey, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    context().decrypt_file(*key, ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt, gen_keys};
+
+    #[test]
+    fn round_trip() {
+        let (pr, pb) = gen_keys().unwrap();
+        for data in [&b""[..], b"hello", &[0; 100]] {
+            let ciphertext = encrypt(&pb, data).unwrap();
+            assert_eq!(decrypt(&pr, &ciphertext).unwrap(), data);
+        }
+        let (other, _) = gen_keys().unwrap();
+        let ciphertext = encrypt(&pb, b"hello").unwrap();
+        assert_ne!(decrypt(&other, &ciphertext), Ok(b"hello".to_vec()));
+    }
+}
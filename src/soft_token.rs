@@ -0,0 +1,395 @@
+//! A software token with the shape of a PKCS#11 one, as the example of a
+//! [`Signer`]/[`Decrypter`] that isn't a key in memory, and to show what an
+//! HSM interface comes down to on elliptic curves. Each method names the
+//! PKCS#11 function it stands for. A token has slots, each guarded by a PIN
+//! and holding objects under numeric handles; private keys never leave the
+//! token, callers get a handle to sign and derive with. Keys are kept
+//! [sealed](SealedPrivateKey), and nothing is persisted.
+
+use std::{collections::BTreeMap, fmt};
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{self, AbelianGroup, ConditionalSwap, InitialPoint},
+    base_traits::{FromRandom, Natural, RW},
+    ecc::{PrivateKey, PublicKey},
+    error::Error,
+    mod_field::ModFieldCfg,
+    schnorr::Signature,
+    sealed::SealedPrivateKey,
+    signer::{Decrypter, Signer},
+};
+
+pub type SlotId = usize;
+pub type ObjectHandle = u32;
+
+/// The PKCS#11 return values that can come up here.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenError {
+    /// `CKR_SLOT_ID_INVALID`
+    SlotIdInvalid,
+    /// `CKR_PIN_INCORRECT`
+    PinIncorrect,
+    /// `CKR_USER_NOT_LOGGED_IN`
+    UserNotLoggedIn,
+    /// `CKR_OBJECT_HANDLE_INVALID`
+    ObjectHandleInvalid,
+    /// `CKR_KEY_TYPE_INCONSISTENT`: a public key where a private one is needed.
+    KeyTypeInconsistent,
+    /// `CKR_FUNCTION_FAILED`
+    FunctionFailed(Error),
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::SlotIdInvalid => write!(f, "no such slot"),
+            TokenError::PinIncorrect => write!(f, "wrong PIN"),
+            TokenError::UserNotLoggedIn => write!(f, "not logged in"),
+            TokenError::ObjectHandleInvalid => write!(f, "no such object"),
+            TokenError::KeyTypeInconsistent => write!(f, "not a private key"),
+            TokenError::FunctionFailed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+enum Object<I, P> {
+    Public(PublicKey<P>),
+    Private(SealedPrivateKey<I>, PublicKey<P>),
+}
+
+struct Slot<I, P> {
+    label: String,
+    pin: [u8; 32],
+    logged_in: bool,
+    objects: BTreeMap<ObjectHandle, (String, Object<I, P>)>,
+    next_handle: ObjectHandle,
+}
+
+fn pin_hash(pin: &str) -> [u8; 32] {
+    Sha256::digest(pin.as_bytes()).into()
+}
+
+pub struct SoftToken<I, P> {
+    slots: Vec<Slot<I, P>>,
+}
+
+impl<I, P> Default for SoftToken<I, P> {
+    fn default() -> Self {
+        Self { slots: vec![] }
+    }
+}
+
+impl<I: Natural + RW, P: Copy> SoftToken<I, P> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `C_InitToken`: an empty slot with this label and PIN.
+    pub fn init_slot(&mut self, label: &str, pin: &str) -> SlotId {
+        self.slots.push(Slot {
+            label: label.to_string(),
+            pin: pin_hash(pin),
+            logged_in: false,
+            objects: BTreeMap::new(),
+            next_handle: 1,
+        });
+        self.slots.len() - 1
+    }
+
+    /// `C_GetSlotList` and `C_GetTokenInfo`: the slots with their labels.
+    pub fn slots(&self) -> impl Iterator<Item = (SlotId, &str)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, &s.label[..]))
+    }
+
+    fn slot(&self, slot: SlotId) -> Result<&Slot<I, P>, TokenError> {
+        self.slots.get(slot).ok_or(TokenError::SlotIdInvalid)
+    }
+
+    fn slot_mut(&mut self, slot: SlotId) -> Result<&mut Slot<I, P>, TokenError> {
+        self.slots.get_mut(slot).ok_or(TokenError::SlotIdInvalid)
+    }
+
+    /// A slot whose private objects may be used.
+    fn user_slot_mut(&mut self, slot: SlotId) -> Result<&mut Slot<I, P>, TokenError> {
+        let slot = self.slot_mut(slot)?;
+        match slot.logged_in {
+            true => Ok(slot),
+            false => Err(TokenError::UserNotLoggedIn),
+        }
+    }
+
+    /// `C_Login` as the user.
+    pub fn login(&mut self, slot: SlotId, pin: &str) -> Result<(), TokenError> {
+        let slot = self.slot_mut(slot)?;
+        if slot.pin != pin_hash(pin) {
+            return Err(TokenError::PinIncorrect);
+        }
+        slot.logged_in = true;
+        Ok(())
+    }
+
+    /// `C_Logout`
+    pub fn logout(&mut self, slot: SlotId) -> Result<(), TokenError> {
+        self.slot_mut(slot)?.logged_in = false;
+        Ok(())
+    }
+
+    fn insert(slot: &mut Slot<I, P>, label: &str, object: Object<I, P>) -> ObjectHandle {
+        let handle = slot.next_handle;
+        slot.next_handle += 1;
+        slot.objects.insert(handle, (label.to_string(), object));
+        handle
+    }
+
+    /// `C_CreateObject` of a public key, which needs no login.
+    pub fn import_public_key(
+        &mut self,
+        slot: SlotId,
+        label: &str,
+        key: PublicKey<P>,
+    ) -> Result<ObjectHandle, TokenError> {
+        let slot = self.slot_mut(slot)?;
+        Ok(Self::insert(slot, label, Object::Public(key)))
+    }
+
+    /// `C_FindObjects` by label: public objects always, private ones only
+    /// when logged in.
+    pub fn find_objects(&self, slot: SlotId, label: &str) -> Result<Vec<ObjectHandle>, TokenError> {
+        let slot = self.slot(slot)?;
+        let visible = |o: &Object<I, P>| slot.logged_in || matches!(o, Object::Public(_));
+        Ok(slot
+            .objects
+            .iter()
+            .filter(|(_, (l, o))| l == label && visible(o))
+            .map(|(&h, _)| h)
+            .collect())
+    }
+
+    /// `C_GetAttributeValue` of `CKA_EC_POINT`, of either kind of key.
+    pub fn public_key(
+        &self,
+        slot: SlotId,
+        handle: ObjectHandle,
+    ) -> Result<PublicKey<P>, TokenError> {
+        let slot = self.slot(slot)?;
+        match slot.objects.get(&handle) {
+            Some((_, Object::Public(key))) => Ok(*key),
+            Some((_, Object::Private(_, key))) if slot.logged_in => Ok(*key),
+            _ => Err(TokenError::ObjectHandleInvalid),
+        }
+    }
+
+    /// `C_DestroyObject`
+    pub fn destroy_object(&mut self, slot: SlotId, handle: ObjectHandle) -> Result<(), TokenError> {
+        let slot = self.slot_mut(slot)?;
+        let private = matches!(slot.objects.get(&handle), Some((_, Object::Private(..))));
+        if private && !slot.logged_in {
+            return Err(TokenError::UserNotLoggedIn);
+        }
+        slot.objects
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or(TokenError::ObjectHandleInvalid)
+    }
+
+    fn private_key(
+        &self,
+        slot: SlotId,
+        handle: ObjectHandle,
+    ) -> Result<&SealedPrivateKey<I>, TokenError> {
+        let slot = self.slot(slot)?;
+        match slot.objects.get(&handle) {
+            _ if !slot.logged_in => Err(TokenError::UserNotLoggedIn),
+            Some((_, Object::Private(key, _))) => Ok(key),
+            Some((_, Object::Public(_))) => Err(TokenError::KeyTypeInconsistent),
+            None => Err(TokenError::ObjectHandleInvalid),
+        }
+    }
+
+    /// The private key behind `handle` as a [`Signer`] and [`Decrypter`],
+    /// for protocol code that takes one.
+    pub fn key(
+        &self,
+        slot: SlotId,
+        handle: ObjectHandle,
+    ) -> Result<TokenKey<'_, I, P>, TokenError> {
+        self.private_key(slot, handle)?;
+        Ok(TokenKey {
+            token: self,
+            slot,
+            handle,
+        })
+    }
+}
+
+impl<I, P> SoftToken<I, P>
+where
+    I: Natural + RW + FromRandom<()>,
+    P: AbelianGroup<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    /// `C_GenerateKeyPair` with `CKM_EC_KEY_PAIR_GEN`: handles of the public
+    /// and the private key, both under `label`.
+    pub fn generate_key_pair(
+        &mut self,
+        slot: SlotId,
+        label: &str,
+        rng: &mut impl Rng,
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<(ObjectHandle, ObjectHandle), TokenError> {
+        let slot = self.user_slot_mut(slot)?;
+        let private = PrivateKey::random(rng, n);
+        let public = private.public_key(cfg);
+        let sealed = SealedPrivateKey::seal(private).map_err(TokenError::FunctionFailed)?;
+        let public_handle = Self::insert(slot, label, Object::Public(public));
+        let private_handle = Self::insert(slot, label, Object::Private(sealed, public));
+        Ok((public_handle, private_handle))
+    }
+
+    /// `C_Sign` with a Schnorr mechanism.
+    pub fn sign(
+        &self,
+        slot: SlotId,
+        handle: ObjectHandle,
+        msg: &[u8],
+        cfg: &P::Cfg,
+        n: &ModFieldCfg<I>,
+    ) -> Result<Signature<P, I>, TokenError> {
+        Signer::<P, I>::sign(self.private_key(slot, handle)?, msg, cfg, n)
+            .map_err(TokenError::FunctionFailed)
+    }
+
+    /// `C_DeriveKey` with `CKM_ECDH1_DERIVE`, returning the shared point
+    /// rather than storing it as a new object.
+    pub fn derive(
+        &self,
+        slot: SlotId,
+        handle: ObjectHandle,
+        peer: PublicKey<P>,
+        cfg: &P::Cfg,
+    ) -> Result<P, TokenError> {
+        self.private_key(slot, handle)?
+            .diffie_hellman(peer.point(), cfg)
+            .map_err(TokenError::FunctionFailed)
+    }
+}
+
+/// A private key on a [`SoftToken`]. Fails with [`Error::Denied`] once the
+/// slot is logged out or the key destroyed, and `public_key` panics then.
+pub struct TokenKey<'t, I, P> {
+    token: &'t SoftToken<I, P>,
+    slot: SlotId,
+    handle: ObjectHandle,
+}
+
+impl<I, P> Signer<P, I> for TokenKey<'_, I, P>
+where
+    I: Natural + RW + FromRandom<()>,
+    P: AbelianGroup<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    fn public_key(&self, _cfg: &P::Cfg) -> PublicKey<P> {
+        self.token.public_key(self.slot, self.handle).unwrap()
+    }
+
+    fn sign(&self, msg: &[u8], cfg: &P::Cfg, n: &ModFieldCfg<I>) -> Result<Signature<P, I>, Error> {
+        self.token
+            .sign(self.slot, self.handle, msg, cfg, n)
+            .map_err(|e| Error::Denied(e.to_string()))
+    }
+}
+
+impl<I, P> Decrypter<P> for TokenKey<'_, I, P>
+where
+    I: Natural + RW + FromRandom<()>,
+    P: AbelianGroup<algebra::ops::Add> + ConditionalSwap + RW,
+    P::Cfg: InitialPoint<P>,
+{
+    fn diffie_hellman(&self, point: P, cfg: &P::Cfg) -> Result<P, Error> {
+        self.token
+            .derive(self.slot, self.handle, PublicKey::from_point(point), cfg)
+            .map_err(|e| Error::Denied(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::CommutativeOp,
+        curves::{toy, toy_order},
+        ecc::PrivateKey,
+        encoding_utils::{decrypt_file, encrypt_file},
+        mod_field::ModField,
+        points_group::Point,
+        schnorr::verify,
+        signer::Signer,
+    };
+
+    use super::{SoftToken, TokenError};
+
+    type P = Point<ModField<u64>>;
+
+    #[test]
+    fn session() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([9u8; 32]);
+        let (cfg, n) = (toy(), toy_order());
+        let mut token = SoftToken::<u128, P>::new();
+        let slot = token.init_slot("test", "1234");
+        assert_eq!(token.slots().collect::<Vec<_>>(), [(0, "test")]);
+        assert_eq!(
+            token.generate_key_pair(slot, "k", &mut rng, &cfg, &n),
+            Err(TokenError::UserNotLoggedIn)
+        );
+        assert_eq!(token.login(slot, "0000"), Err(TokenError::PinIncorrect));
+        token.login(slot, "1234").unwrap();
+        let (public, private) = token
+            .generate_key_pair(slot, "k", &mut rng, &cfg, &n)
+            .unwrap();
+        assert_eq!(token.find_objects(slot, "k"), Ok(vec![public, private]));
+        let pb = token.public_key(slot, public).unwrap();
+
+        let sig = token.sign(slot, private, b"msg", &cfg, &n).unwrap();
+        assert!(verify(pb, b"msg", sig, &cfg, &n));
+        assert_eq!(
+            token.sign(slot, public, b"msg", &cfg, &n),
+            Err(TokenError::KeyTypeInconsistent)
+        );
+        let peer = PrivateKey::random(&mut rng, &n);
+        let shared = token.derive(slot, private, peer.public_key(&cfg), &cfg);
+        assert_eq!(shared, Ok(P::exp(pb.point(), peer.scalar(), &cfg)));
+
+        // protocol code through the traits
+        let key = token.key(slot, private).unwrap();
+        assert_eq!(Signer::<P, _>::public_key(&key, &cfg), pb);
+        let file = encrypt_file::<_, u128>(pb, b"for the token", &mut rng, &cfg).unwrap();
+        assert_eq!(decrypt_file(&key, &file, &cfg).unwrap(), b"for the token");
+
+        token.logout(slot).unwrap();
+        assert_eq!(token.find_objects(slot, "k"), Ok(vec![public]));
+        assert_eq!(
+            token.derive(slot, private, pb, &cfg),
+            Err(TokenError::UserNotLoggedIn)
+        );
+        assert_eq!(
+            token.destroy_object(slot, private),
+            Err(TokenError::UserNotLoggedIn)
+        );
+        assert_eq!(token.destroy_object(slot, public), Ok(()));
+        assert_eq!(
+            token.public_key(slot, public),
+            Err(TokenError::ObjectHandleInvalid)
+        );
+        assert_eq!(token.login(1, "1234"), Err(TokenError::SlotIdInvalid));
+    }
+}
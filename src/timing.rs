@@ -0,0 +1,237 @@
+//! dudect-style timing leak detection (Reparaz, Balasch, Verbauwhede: "Dude, is
+//! my code constant time?"). An operation is timed on two classes of secret
+//! inputs, a fixed one and random ones, interleaved at random; Welch's t-test
+//! then tells whether the two timing distributions differ.
+
+use std::{hint::black_box, time::Instant};
+
+use rand::Rng;
+
+/// |t| above this means the timings of the classes almost surely differ.
+pub const THRESHOLD: f64 = 4.5;
+
+/// Measurements slower than this percentile are dropped as interrupts and
+/// other noise, as dudect does.
+const CROP_PERCENTILE: f64 = 0.9;
+
+#[derive(Default)]
+struct Welch {
+    n: [f64; 2],
+    mean: [f64; 2],
+    m2: [f64; 2],
+}
+
+impl Welch {
+    // Welford's online mean/variance
+    fn push(&mut self, class: usize, x: f64) {
+        self.n[class] += 1.0;
+        let delta = x - self.mean[class];
+        self.mean[class] += delta / self.n[class];
+        self.m2[class] += delta * (x - self.mean[class]);
+    }
+
+    fn t(&self) -> f64 {
+        let var = |c: usize| self.m2[c] / (self.n[c] - 1.0);
+        (self.mean[0] - self.mean[1]) / (var(0) / self.n[0] + var(1) / self.n[1]).sqrt()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Report {
+    /// Welch's t statistic of fixed vs. random class timings.
+    pub t: f64,
+    /// Measurements left after cropping.
+    pub samples: usize,
+}
+
+impl Report {
+    pub fn leaks(&self) -> bool {
+        self.t.abs() > THRESHOLD
+    }
+}
+
+/// Times `op` on `samples` inputs made by `input(rng, fixed)`, where `fixed`
+/// picks the class. Inputs are generated before timing starts.
+pub fn measure<R: Rng, T>(
+    samples: usize,
+    rng: &mut R,
+    mut input: impl FnMut(&mut R, bool) -> T,
+    mut op: impl FnMut(T),
+) -> Report {
+    let inputs = (0..samples)
+        .map(|_| {
+            let fixed = rng.gen::<bool>();
+            (fixed, input(rng, fixed))
+        })
+        .collect::<Vec<_>>();
+    let mut timings = Vec::with_capacity(samples);
+    for (fixed, x) in inputs {
+        let start = Instant::now();
+        op(black_box(x));
+        timings.push((fixed as usize, start.elapsed().as_nanos() as f64));
+    }
+    let mut sorted = timings.iter().map(|&(_, t)| t).collect::<Vec<_>>();
+    sorted.sort_by(f64::total_cmp);
+    let cutoff = sorted[((samples - 1) as f64 * CROP_PERCENTILE) as usize];
+    let mut welch = Welch::default();
+    let mut kept = 0;
+    for (class, t) in timings {
+        if t <= cutoff {
+            welch.push(class, t);
+            kept += 1;
+        }
+    }
+    Report {
+        t: welch.t(),
+        samples: kept,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hint::black_box;
+
+    use rand::{Rng, SeedableRng};
+
+    use crate::{
+        algebra::{CommutativeMonoid, CommutativeOp, Field, Ring},
+        base_traits::FromRandom,
+        mod_field::{ModField, ModFieldCfg},
+        points_group::{Point, PointCfg},
+        projective::ProjectivePoint,
+    };
+
+    use super::{measure, Welch};
+
+    const SAMPLES: usize = 20_000;
+
+    fn config() -> PointCfg<ModField<u64>> {
+        let cfg_field = ModFieldCfg {
+            rem: 0x0014_4C3B_27FFu64,
+        };
+        PointCfg {
+            g: Point::new_unsafe(
+                ModField::new(2500, &cfg_field),
+                ModField::new(125001, &cfg_field),
+            ),
+            a: ModField::new(100, &cfg_field),
+            b: ModField::new(1, &cfg_field),
+            cf: cfg_field,
+        }
+    }
+
+    #[test]
+    fn welch_statistic() {
+        let mut welch = Welch::default();
+        for x in [1.0, 2.0, 3.0, 4.0] {
+            welch.push(0, x);
+            welch.push(1, x + 2.0);
+        }
+        assert!((welch.t() + 2.0 / (5.0f64 / 6.0).sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn detects_leak() {
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let report = measure(
+            SAMPLES,
+            &mut gen,
+            |r, fixed| if fixed { 0 } else { r.gen_range(0..2000) },
+            |n: u32| {
+                for i in 0..n {
+                    black_box(i);
+                }
+            },
+        );
+        assert!(report.leaks(), "{:?}", report);
+    }
+
+    #[test]
+    #[ignore = "double-and-add branches on the scalar"]
+    fn scalar_mul() {
+        let cfg = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let report = measure(
+            SAMPLES / 10,
+            &mut gen,
+            |r, fixed| {
+                if fixed {
+                    1u128 << 127
+                } else {
+                    u128::random(r, &()) | 1 << 127
+                }
+            },
+            |k| {
+                black_box(CommutativeOp::exp(cfg.g, k, &cfg));
+            },
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+
+    #[test]
+    fn scalar_mul_ct() {
+        let cfg = config();
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let report = measure(
+            SAMPLES / 10,
+            &mut gen,
+            |r, fixed| {
+                if fixed {
+                    1u128 << 127
+                } else {
+                    u128::random(r, &()) | 1 << 127
+                }
+            },
+            |k| {
+                black_box(cfg.g.mul_ct(k, &cfg));
+            },
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+
+    /// The ladder behind `mul_ct` on its own, without the inversion back to
+    /// affine: the complete formulas have no special cases left to leak.
+    #[test]
+    fn projective_ladder() {
+        let cfg = config();
+        let g = ProjectivePoint::from_affine(cfg.g, &cfg);
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let report = measure(
+            SAMPLES / 10,
+            &mut gen,
+            |r, fixed| {
+                if fixed {
+                    1u128 << 127
+                } else {
+                    u128::random(r, &()) | 1 << 127
+                }
+            },
+            |k| {
+                black_box(g.exp_ct(k, &cfg));
+            },
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+
+    #[test]
+    #[ignore = "extended Euclid takes as many steps as the operand needs"]
+    fn field_inversion() {
+        let cfg = config().cf;
+        let mut gen = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        let report = measure(
+            SAMPLES,
+            &mut gen,
+            |r, fixed| {
+                if fixed {
+                    ModField::one(&cfg)
+                } else {
+                    ModField::random_nonzero(r, &cfg)
+                }
+            },
+            |x| {
+                black_box(x.reciprocal(&cfg));
+            },
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+}
@@ -0,0 +1,525 @@
+//! Fixed-width unsigned integers on `u64` limbs, least significant first.
+//! Arithmetic panics on overflow like the primitive types in debug builds;
+//! [`Natural::mul_mod`] goes through the double-width product instead, so
+//! modular multiplication needs no headroom.
+
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    io::{Read, Write},
+    ops::{Add, Div, Mul, Rem, Shl, Shr, Sub},
+};
+
+use rand::Rng;
+
+use crate::{
+    base_traits::{FromRandom, Natural, RW},
+    ct_codec,
+    error::Error,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uint<const LIMBS: usize>(pub [u64; LIMBS]);
+
+pub type U256 = Uint<4>;
+
+impl<const LIMBS: usize> Uint<LIMBS> {
+    pub const ZERO: Self = Self([0; LIMBS]);
+    pub const MAX: Self = Self([u64::MAX; LIMBS]);
+    pub const BITS: u32 = 64 * LIMBS as u32;
+    const BYTES: usize = 8 * LIMBS;
+
+    pub fn low_u64(self) -> u64 {
+        self.0[0]
+    }
+
+    /// Length of the binary representation, zero for zero.
+    pub fn bits(self) -> u32 {
+        match self.0.iter().rposition(|&l| l != 0) {
+            Some(i) => 64 * i as u32 + 64 - self.0[i].leading_zeros(),
+            None => 0,
+        }
+    }
+
+    fn bit(self, i: u32) -> bool {
+        self.0[i as usize / 64] >> (i % 64) & 1 == 1
+    }
+
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let mut res = Self::ZERO;
+        let mut carry = false;
+        for i in 0..LIMBS {
+            let (s, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (s, c2) = s.overflowing_add(carry as u64);
+            res.0[i] = s;
+            carry = c1 | c2;
+        }
+        (res, carry)
+    }
+
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let mut res = Self::ZERO;
+        let mut borrow = false;
+        for i in 0..LIMBS {
+            let (d, b1) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (d, b2) = d.overflowing_sub(borrow as u64);
+            res.0[i] = d;
+            borrow = b1 | b2;
+        }
+        (res, borrow)
+    }
+
+    /// The full product as `(low, high)` halves.
+    pub fn widening_mul(self, rhs: Self) -> (Self, Self) {
+        let (mut lo, mut hi) = (Self::ZERO, Self::ZERO);
+        for i in 0..LIMBS {
+            let mut carry = 0u128;
+            for j in 0..LIMBS {
+                let k = i + j;
+                let limb = if k < LIMBS {
+                    &mut lo.0[k]
+                } else {
+                    &mut hi.0[k - LIMBS]
+                };
+                let t = self.0[i] as u128 * rhs.0[j] as u128 + *limb as u128 + carry;
+                *limb = t as u64;
+                carry = t >> 64;
+            }
+            hi.0[i] = carry as u64;
+        }
+        (lo, hi)
+    }
+
+    /// `2 · self` and the bit shifted out.
+    fn double(self) -> (Self, bool) {
+        let mut res = Self::ZERO;
+        for i in 0..LIMBS {
+            res.0[i] = self.0[i] << 1 | if i > 0 { self.0[i - 1] >> 63 } else { 0 };
+        }
+        (res, self.0[LIMBS - 1] >> 63 == 1)
+    }
+
+    fn div_rem_u64(self, d: u64) -> (Self, u64) {
+        let mut q = Self::ZERO;
+        let mut r = 0u128;
+        for i in (0..LIMBS).rev() {
+            let cur = r << 64 | self.0[i] as u128;
+            q.0[i] = (cur / d as u128) as u64;
+            r = cur % d as u128;
+        }
+        (q, r as u64)
+    }
+
+    /// Schoolbook binary long division, starting at the highest bit the
+    /// quotient can have, so the common `x % m` with `x < 2m` is cheap.
+    fn div_rem(self, rhs: Self) -> (Self, Self) {
+        assert!(rhs != Self::ZERO, "division by zero");
+        if self < rhs {
+            return (Self::ZERO, self);
+        }
+        if rhs.0[1..].iter().all(|&l| l == 0) {
+            let (q, r) = self.div_rem_u64(rhs.0[0]);
+            return (q, Self::from(r));
+        }
+        let shift = self.bits() - rhs.bits();
+        let (mut q, mut r, mut d) = (Self::ZERO, self, rhs << shift as usize);
+        for i in (0..=shift).rev() {
+            if r >= d {
+                r = r.overflowing_sub(d).0;
+                q.0[i as usize / 64] |= 1 << (i % 64);
+            }
+            d = d >> 1;
+        }
+        (q, r)
+    }
+
+    /// `(hi · 2^BITS + lo) mod m` for `hi < m`, bit by bit.
+    fn reduce_wide(lo: Self, hi: Self, m: Self) -> Self {
+        if hi == Self::ZERO {
+            return lo % m;
+        }
+        let mut r = hi;
+        for i in (0..Self::BITS).rev() {
+            let (mut doubled, carry) = r.double();
+            doubled.0[0] |= lo.bit(i) as u64;
+            r = if carry || doubled >= m {
+                doubled.overflowing_sub(m).0
+            } else {
+                doubled
+            };
+        }
+        r
+    }
+
+    /// Big-endian bytes, at most [`Self::BITS`] / 8 of them.
+    pub fn from_big_endian(bytes: &[u8]) -> Self {
+        assert!(bytes.len() <= Self::BYTES, "too many bytes");
+        let mut le = bytes.to_vec();
+        le.reverse();
+        Self::from_little_endian(&le)
+    }
+
+    /// Little-endian bytes, at most [`Self::BITS`] / 8 of them.
+    pub fn from_little_endian(bytes: &[u8]) -> Self {
+        assert!(bytes.len() <= Self::BYTES, "too many bytes");
+        let mut res = Self::ZERO;
+        for (i, &b) in bytes.iter().enumerate() {
+            res.0[i / 8] |= (b as u64) << (8 * (i % 8));
+        }
+        res
+    }
+
+    pub fn to_big_endian(self) -> Vec<u8> {
+        let mut res = self.to_little_endian();
+        res.reverse();
+        res
+    }
+
+    pub fn to_little_endian(self) -> Vec<u8> {
+        self.0.iter().flat_map(|l| l.to_le_bytes()).collect()
+    }
+
+    /// Big-endian hex of even length, e.g. a curve constant.
+    pub fn from_be_hex(hex: &str) -> Result<Self, Error> {
+        let bytes = ct_codec::hex_decode(hex)?;
+        if bytes.len() > Self::BYTES {
+            return Err(Error::Length {
+                expected: Self::BYTES,
+                actual: bytes.len(),
+            });
+        }
+        Ok(Self::from_big_endian(&bytes))
+    }
+}
+
+impl<const LIMBS: usize> From<u64> for Uint<LIMBS> {
+    fn from(v: u64) -> Self {
+        let mut res = Self::ZERO;
+        res.0[0] = v;
+        res
+    }
+}
+
+impl<const LIMBS: usize> From<u128> for Uint<LIMBS> {
+    fn from(v: u128) -> Self {
+        let mut res = Self::from(v as u64);
+        if LIMBS > 1 {
+            res.0[1] = (v >> 64) as u64;
+        } else {
+            assert!(v >> 64 == 0, "arithmetic operation overflow");
+        }
+        res
+    }
+}
+
+impl<const LIMBS: usize> Ord for Uint<LIMBS> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl<const LIMBS: usize> PartialOrd for Uint<LIMBS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const LIMBS: usize> Add for Uint<LIMBS> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let (res, overflow) = self.overflowing_add(rhs);
+        assert!(!overflow, "arithmetic operation overflow");
+        res
+    }
+}
+
+impl<const LIMBS: usize> Sub for Uint<LIMBS> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        let (res, overflow) = self.overflowing_sub(rhs);
+        assert!(!overflow, "arithmetic operation overflow");
+        res
+    }
+}
+
+impl<const LIMBS: usize> Mul for Uint<LIMBS> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (lo, hi) = self.widening_mul(rhs);
+        assert!(hi == Self::ZERO, "arithmetic operation overflow");
+        lo
+    }
+}
+
+impl<const LIMBS: usize> Div for Uint<LIMBS> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        self.div_rem(rhs).0
+    }
+}
+
+impl<const LIMBS: usize> Rem for Uint<LIMBS> {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        self.div_rem(rhs).1
+    }
+}
+
+impl<const LIMBS: usize> Shl<usize> for Uint<LIMBS> {
+    type Output = Self;
+
+    fn shl(self, n: usize) -> Self {
+        let (limbs, bits) = (n / 64, n % 64);
+        let mut res = Self::ZERO;
+        for i in limbs..LIMBS {
+            res.0[i] = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                res.0[i] |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        res
+    }
+}
+
+impl<const LIMBS: usize> Shr<usize> for Uint<LIMBS> {
+    type Output = Self;
+
+    fn shr(self, n: usize) -> Self {
+        let (limbs, bits) = (n / 64, n % 64);
+        let mut res = Self::ZERO;
+        for i in 0..LIMBS.saturating_sub(limbs) {
+            res.0[i] = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < LIMBS {
+                res.0[i] |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        res
+    }
+}
+
+impl<const LIMBS: usize> Natural for Uint<LIMBS> {
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    fn one() -> Self {
+        Self::from(1u64)
+    }
+
+    fn max() -> Self {
+        Self::MAX
+    }
+
+    fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        Uint::overflowing_add(self, rhs)
+    }
+
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        Uint::overflowing_sub(self, rhs)
+    }
+
+    fn ct_select(a: Self, b: Self, choice: bool) -> Self {
+        let mask = std::hint::black_box(choice as u64).wrapping_neg();
+        Self(std::array::from_fn(|i| b.0[i] ^ ((a.0[i] ^ b.0[i]) & mask)))
+    }
+
+    fn low_bits(self, n: u32) -> u32 {
+        (self.low_u64() & ((1u64 << n) - 1)) as u32
+    }
+
+    fn drop_bits(self, n: u32) -> Self {
+        self >> n as usize
+    }
+
+    fn mul_mod(self, rhs: Self, m: Self) -> Self {
+        let (lo, hi) = self.widening_mul(rhs);
+        Self::reduce_wide(lo, hi, m)
+    }
+
+    /// Adds up the double-width products, only keeping the high half below
+    /// `m`, and reduces once.
+    fn sum_of_products_mod(pairs: impl IntoIterator<Item = (Self, Self)>, m: Self) -> Self {
+        let (mut lo, mut hi) = (Self::ZERO, Self::ZERO);
+        for (a, b) in pairs {
+            let (plo, phi) = a.widening_mul(b);
+            let (sum, carry) = lo.overflowing_add(plo);
+            lo = sum;
+            // hi, phi < m, so this is below 2m and one subtraction does
+            let (sum, c1) = hi.overflowing_add(phi);
+            let (sum, c2) = sum.overflowing_add(Self::from(carry as u64));
+            hi = if c1 || c2 || sum >= m {
+                sum.overflowing_sub(m).0
+            } else {
+                sum
+            };
+        }
+        Self::reduce_wide(lo, hi, m)
+    }
+}
+
+impl<const LIMBS: usize> RW for Uint<LIMBS> {
+    const LEN: usize = Self::BYTES;
+
+    fn to_bytes(self, w: &mut impl Write) -> usize {
+        w.write(&self.to_little_endian()).unwrap()
+    }
+
+    fn from_bytes(r: &mut impl Read) -> Self {
+        let mut res = Self::ZERO;
+        for limb in res.0.iter_mut() {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf).unwrap();
+            *limb = u64::from_le_bytes(buf);
+        }
+        res
+    }
+}
+
+impl<const LIMBS: usize> FromRandom<()> for Uint<LIMBS> {
+    fn random(rng: &mut impl Rng, _: &()) -> Self {
+        let mut res = Self::ZERO;
+        for limb in res.0.iter_mut().rev() {
+            *limb = rng.next_u64();
+        }
+        res
+    }
+}
+
+impl<const LIMBS: usize> Display for Uint<LIMBS> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut chunks = vec![];
+        let mut v = *self;
+        loop {
+            let (q, r) = v.div_rem_u64(CHUNK);
+            chunks.push(r);
+            v = q;
+            if v == Self::ZERO {
+                break;
+            }
+        }
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+        chunks.try_for_each(|c| write!(f, "{:019}", c))
+    }
+}
+
+impl<const LIMBS: usize> Debug for Uint<LIMBS> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+
+    use crate::{base_traits::Natural, error::Error};
+
+    use super::{Uint, U256};
+
+    /// Checks against `u128` arithmetic on values that fit.
+    #[test]
+    fn matches_u128() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for _ in 0..1000 {
+            let (a, b) = (rng.gen::<u64>() as u128, rng.gen::<u32>() as u128 + 1);
+            let (ua, ub) = (U256::from(a), U256::from(b));
+            assert_eq!(ua + ub, U256::from(a + b));
+            assert_eq!(ua * ub, U256::from(a * b));
+            assert_eq!(ua / ub, U256::from(a / b));
+            assert_eq!(ua % ub, U256::from(a % b));
+            assert_eq!(a.cmp(&b), ua.cmp(&ub));
+            let (wide, divisor) = ((a * b) << 20, (b << 50) + 7);
+            assert_eq!(
+                U256::from(wide) % U256::from(divisor),
+                U256::from(wide % divisor)
+            );
+            assert_eq!(
+                U256::from(wide) / U256::from(divisor),
+                U256::from(wide / divisor)
+            );
+            let m = rng.gen::<u64>() as u128 | 1;
+            assert_eq!(
+                U256::from(a % m).mul_mod(U256::from(b % m), U256::from(m)),
+                U256::from(a % m * (b % m) % m)
+            );
+        }
+    }
+
+    #[test]
+    fn mul_mod_full_width() {
+        // (p - 1)² = 1 mod p, with a product needing all 512 bits
+        let p =
+            U256::from_be_hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f")
+                .unwrap();
+        let minus_one = p - U256::one();
+        assert_eq!(minus_one.mul_mod(minus_one, p), U256::one());
+        let (lo, hi) = U256::MAX.widening_mul(U256::MAX);
+        assert_eq!((lo, hi), (U256::one(), U256::MAX - U256::one()));
+        let two = U256::from(2u64);
+        let half = p / two + U256::one();
+        assert_eq!(half.mul_mod(two, p), U256::one());
+    }
+
+    #[test]
+    fn sum_of_products() {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        let m = U256::MAX - U256::from(188u64);
+        let pairs = (0..20)
+            .map(|_| (Uint(rng.gen()) % m, Uint(rng.gen()) % m))
+            .collect::<Vec<_>>();
+        let folded = pairs
+            .iter()
+            .fold(U256::ZERO, |acc, &(a, b)| acc.add_mod(a.mul_mod(b, m), m));
+        assert_eq!(U256::sum_of_products_mod(pairs.iter().copied(), m), folded);
+        let small = pairs
+            .iter()
+            .map(|&(a, b)| (a.low_u64() >> 1, b.low_u64() >> 1));
+        let m = u64::MAX >> 1;
+        assert_eq!(
+            u64::sum_of_products_mod(small.clone(), m) as u128,
+            small.fold(0, |acc, (a, b)| (acc + a as u128 * b as u128) % m as u128)
+        );
+    }
+
+    #[test]
+    fn shifts_and_bytes() {
+        let n = U256::from(0x0123_4567_89AB_CDEFu64) << 100;
+        assert_eq!(n >> 100, U256::from(0x0123_4567_89AB_CDEFu64));
+        assert_eq!(n.bits(), 157);
+        assert_eq!(U256::MAX >> 255, U256::one());
+        assert_eq!(U256::one() << 256, U256::ZERO);
+        assert_eq!(U256::from_big_endian(&n.to_big_endian()), n);
+        assert_eq!(U256::from_little_endian(&n.to_little_endian()), n);
+        assert_eq!(U256::from_big_endian(&[1, 0]), U256::from(256u64));
+        assert_eq!(
+            Uint::<2>::from_be_hex("0100000000000000000000000000000000"),
+            Err(Error::Length {
+                expected: 16,
+                actual: 17
+            })
+        );
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(U256::ZERO.to_string(), "0");
+        assert_eq!(U256::from(u128::MAX).to_string(), u128::MAX.to_string());
+        assert_eq!(
+            U256::MAX.to_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow")]
+    fn overflow() {
+        let _ = U256::MAX + U256::one();
+    }
+}
@@ -0,0 +1,320 @@
+//! Just enough of X.509 (RFC 5280) to read certificates made by OpenSSL and
+//! friends: the signed `TBSCertificate`, the issuer and subject names as raw
+//! DER to match up chains, the subject's EC public key on a named curve this
+//! crate has, and the issuer's ECDSA signature, which [`verify_ecdsa_sha256`]
+//! checks. Validity, extensions and everything else are skipped, so this
+//! isn't a path validator, only a way to get keys and signatures out.
+//!
+//! ```text
+//! Certificate ::= SEQUENCE {
+//!     tbsCertificate     SEQUENCE {
+//!         version         [0] EXPLICIT INTEGER OPTIONAL,
+//!         serialNumber    INTEGER,
+//!         signature       AlgorithmIdentifier,
+//!         issuer          Name,
+//!         validity        SEQUENCE,
+//!         subject         Name,
+//!         subjectPublicKeyInfo SEQUENCE {
+//!             algorithm   SEQUENCE { id-ecPublicKey, namedCurve OBJECT IDENTIFIER },
+//!             subjectPublicKey BIT STRING },  -- SEC1 point
+//!         ... },
+//!     signatureAlgorithm AlgorithmIdentifier,
+//!     signatureValue     BIT STRING }  -- SEQUENCE { r INTEGER, s INTEGER }
+//! ```
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    armor,
+    base_traits::{Endianness, RW},
+    curves::{p256, p256_order, secp256k1, secp256k1_order},
+    der::{read, read_integer, BIT_STRING, INTEGER, OBJECT_IDENTIFIER, SEQUENCE},
+    ecc::PublicKey,
+    ecdsa,
+    mod_field::{ModField, ModFieldCfg},
+    points_group::{Point, PointCfg, ValidationPolicy},
+    uint::U256,
+};
+
+pub const PEM_LABEL: &str = "CERTIFICATE";
+
+/// `[0]`, constructed: the version of v2 and v3 certificates.
+const VERSION: u8 = 0xA0;
+
+/// `1.2.840.10045.2.1`, `id-ecPublicKey`.
+const EC_PUBLIC_KEY: [u8; 7] = [0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+/// `1.2.840.10045.4.3.2`, `ecdsa-with-SHA256`.
+pub const ECDSA_WITH_SHA256: [u8; 8] = [0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+
+type P = Point<ModField<U256>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NamedCurve {
+    /// `prime256v1` to OpenSSL.
+    P256,
+    Secp256k1,
+}
+
+impl NamedCurve {
+    pub(crate) fn oid(self) -> &'static [u8] {
+        match self {
+            // 1.2.840.10045.3.1.7
+            NamedCurve::P256 => &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07],
+            // 1.3.132.0.10
+            NamedCurve::Secp256k1 => &[0x2B, 0x81, 0x04, 0x00, 0x0A],
+        }
+    }
+
+    pub(crate) fn from_oid(oid: &[u8]) -> Option<Self> {
+        [NamedCurve::P256, NamedCurve::Secp256k1]
+            .into_iter()
+            .find(|c| c.oid() == oid)
+    }
+
+    pub fn cfg(self) -> PointCfg<ModField<U256>> {
+        match self {
+            NamedCurve::P256 => p256(),
+            NamedCurve::Secp256k1 => secp256k1(),
+        }
+    }
+
+    pub fn order(self) -> ModFieldCfg<U256> {
+        match self {
+            NamedCurve::P256 => p256_order(),
+            NamedCurve::Secp256k1 => secp256k1_order(),
+        }
+    }
+}
+
+/// The key of a `SubjectPublicKeyInfo`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SubjectPublicKey {
+    pub curve: NamedCurve,
+    pub key: PublicKey<P>,
+}
+
+impl SubjectPublicKey {
+    /// A DER `SubjectPublicKeyInfo`, as in certificates and the output of
+    /// `openssl ec -pubout -outform DER`. Both curves have cofactor 1, so a
+    /// point on the curve is in the group.
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let (spki, rest) = read(der, SEQUENCE)?;
+        if !rest.is_empty() {
+            return Err("trailing data after SubjectPublicKeyInfo".to_string());
+        }
+        let (algorithm, spki) = read(spki, SEQUENCE)?;
+        let (oid, algorithm) = read(algorithm, OBJECT_IDENTIFIER)?;
+        if oid != EC_PUBLIC_KEY {
+            return Err("not an EC public key".to_string());
+        }
+        let (oid, _) = read(algorithm, OBJECT_IDENTIFIER)
+            .map_err(|_| "only named curves are supported".to_string())?;
+        let curve = NamedCurve::from_oid(oid).ok_or("unsupported curve")?;
+        let (point, _) = read(spki, BIT_STRING)?;
+        Self::from_sec1(curve, bits(point)?)
+    }
+
+    /// A SEC1 point, compressed or not, on `curve`.
+    pub(crate) fn from_sec1(curve: NamedCurve, bytes: &[u8]) -> Result<Self, String> {
+        let cfg = curve.cfg();
+        let point = match bytes {
+            [0x04, xy @ ..] if xy.len() == 2 * U256::LEN => {
+                let (x, y) = xy.split_at(U256::LEN);
+                let coord = |b: &[u8]| ModField::from_bytes_endian(&mut &b[..], Endianness::Big);
+                Point::new_unsafe(coord(x), coord(y))
+                    .validate(ValidationPolicy::<U256>::OnCurve, &cfg)
+                    .map_err(|e| e.to_string())?
+            }
+            compressed => Point::from_sec1_compressed(compressed, &cfg)
+                .filter(|p| !p.is_infinity())
+                .ok_or("invalid SEC1 point".to_string())?,
+        };
+        Ok(Self {
+            curve,
+            key: PublicKey::from_point(point),
+        })
+    }
+
+    /// The uncompressed SEC1 point.
+    pub(crate) fn to_sec1(self) -> Vec<u8> {
+        let p = self.key.point();
+        let mut res = vec![0x04];
+        res.extend(p.x().to_vec_endian(Endianness::Big));
+        res.extend(p.y().to_vec_endian(Endianness::Big));
+        res
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct X509Certificate {
+    /// The DER `TBSCertificate`, which is what's signed.
+    pub tbs: Vec<u8>,
+    /// DER `Name`s, equal between a certificate's issuer and its issuer's
+    /// subject when both come from the same software.
+    pub issuer: Vec<u8>,
+    pub subject: Vec<u8>,
+    pub public: SubjectPublicKey,
+    /// The content of the algorithm's OBJECT IDENTIFIER, such as
+    /// [`ECDSA_WITH_SHA256`].
+    pub signature_algorithm: Vec<u8>,
+    /// The signature with its BIT STRING wrapping removed.
+    pub signature: Vec<u8>,
+}
+
+impl X509Certificate {
+    pub fn from_der(der: &[u8]) -> Result<Self, String> {
+        let (cert, rest) = read(der, SEQUENCE)?;
+        if !rest.is_empty() {
+            return Err("trailing data after Certificate".to_string());
+        }
+        let (tbs, cert) = element(cert, SEQUENCE)?;
+
+        let (mut fields, _) = read(tbs, SEQUENCE)?;
+        if let Ok((_, rest)) = read(fields, VERSION) {
+            fields = rest;
+        }
+        let (_serial, fields) = read(fields, INTEGER)?;
+        let (_algorithm, fields) = read(fields, SEQUENCE)?;
+        let (issuer, fields) = element(fields, SEQUENCE)?;
+        let (_validity, fields) = read(fields, SEQUENCE)?;
+        let (subject, fields) = element(fields, SEQUENCE)?;
+        let (spki, _extensions) = element(fields, SEQUENCE)?;
+        let public = SubjectPublicKey::from_der(spki)?;
+
+        let (algorithm, cert) = read(cert, SEQUENCE)?;
+        let (oid, _) = read(algorithm, OBJECT_IDENTIFIER)?;
+        let (signature, _) = read(cert, BIT_STRING)?;
+        Ok(Self {
+            tbs: tbs.to_vec(),
+            issuer: issuer.to_vec(),
+            subject: subject.to_vec(),
+            public,
+            signature_algorithm: oid.to_vec(),
+            signature: bits(signature)?.to_vec(),
+        })
+    }
+
+    /// The first `CERTIFICATE` block of a PEM file.
+    pub fn from_pem(pem: &str) -> Result<Self, String> {
+        match armor::dearmor(pem).map_err(|e| e.to_string())? {
+            (label, der) if label == PEM_LABEL => Self::from_der(&der),
+            (label, _) => Err(format!("expected {}, found {}", PEM_LABEL, label)),
+        }
+    }
+
+    /// Whether `issuer` signed this certificate. Only ECDSA with SHA-256 is
+    /// supported; other algorithms don't verify.
+    pub fn verify_signature(&self, issuer: &SubjectPublicKey) -> bool {
+        if self.signature_algorithm != ECDSA_WITH_SHA256 {
+            return false;
+        }
+        let Ok((sig, _)) = read(&self.signature, SEQUENCE) else {
+            return false;
+        };
+        let Ok((r, sig)) = read_integer::<U256>(sig) else {
+            return false;
+        };
+        let Ok((s, _)) = read_integer::<U256>(sig) else {
+            return false;
+        };
+        verify_ecdsa_sha256(issuer, &self.tbs, r, s)
+    }
+}
+
+/// The bytes of a BIT STRING's content, which has to be whole bytes.
+fn bits(content: &[u8]) -> Result<&[u8], String> {
+    match content.split_first() {
+        Some((0, bytes)) => Ok(bytes),
+        _ => Err("BIT STRING with unused bits".to_string()),
+    }
+}
+
+/// The length of the tag and length octets in front of `content`, as
+/// [`read`] accepts them.
+fn header_len(content: &[u8]) -> usize {
+    match content.len() {
+        0..=0x7F => 2,
+        0x80..0x100 => 3,
+        _ => 4,
+    }
+}
+
+/// Like [`read`], but the whole element instead of its content.
+fn element(input: &[u8], tag: u8) -> Result<(&[u8], &[u8]), String> {
+    let (content, rest) = read(input, tag)?;
+    Ok((&input[..header_len(content) + content.len()], rest))
+}
+
+/// ECDSA over SHA-256 of `msg`.
+pub fn verify_ecdsa_sha256(key: &SubjectPublicKey, msg: &[u8], r: U256, s: U256) -> bool {
+    let (cfg, n) = (key.curve.cfg(), key.curve.order());
+    let digest = Sha256::digest(msg).into();
+    ecdsa::verify_digest(key.key, &digest, (r, s), &cfg, &n)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ct_codec::hex_decode;
+
+    use super::{NamedCurve, X509Certificate, ECDSA_WITH_SHA256};
+
+    // openssl req -x509 -new -key ca.key -subj "/CN=toy root" -sha256
+    // with a prime256v1 key
+    const ROOT: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBejCCASGgAwIBAgIUBH0qvWWuS34L1rP86O72MsebtJkwCgYIKoZIzj0EAwIw
+EzERMA8GA1UEAwwIdG95IHJvb3QwHhcNMjYxMDE2MDkzNjI2WhcNMzYxMDEzMDkz
+NjI2WjATMREwDwYDVQQDDAh0b3kgcm9vdDBZMBMGByqGSM49AgEGCCqGSM49AwEH
+A0IABOFHkTjEAvSgD+8PTbleSWTRDmhN1caw9xxxP8RbQrodPBn5ZiAFktHJyy9V
+XKOhrMW3p870GFOAkOGi2d9ym56jUzBRMB0GA1UdDgQWBBTtKPOFfhByspZgkYxj
+vy4KL3+qWDAfBgNVHSMEGDAWgBTtKPOFfhByspZgkYxjvy4KL3+qWDAPBgNVHRMB
+Af8EBTADAQH/MAoGCCqGSM49BAMCA0cAMEQCIBxEIamOkoGVi5S4irMYsJnZibwS
+VMZ4uy6JGq2dtq5vAiArjEdQucl55nGLwGRBoNauo9LbMjs2m4e+5cC/SkYDqg==
+-----END CERTIFICATE-----
+";
+
+    // openssl x509 -req -CA root.pem -CAkey ca.key -sha256 -outform DER
+    // for "/CN=alice" with a secp256k1 key
+    const LEAF: &str = concat!(
+        "308201503081f7a003020102020107300a06082a8648ce3d04030230133111300f060355",
+        "04030c08746f7920726f6f74301e170d3236313031363039333632365a170d3237313031",
+        "363039333632365a3010310e300c06035504030c05616c6963653056301006072a8648ce",
+        "3d020106052b8104000a0342000483008570239977895c5643311e83caf7cfcdb61eeb5a",
+        "1574df1eb0f91989555a57024a244ddee6a84033bc738774203b53a549b4d8a7eab518d7",
+        "48643f7f5f18a3423040301d0603551d0e04160414f812bd0f33537b791b66a2dbf2b797",
+        "6a27b6becc301f0603551d23041830168014ed28f3857e1072b29660918c63bf2e0a2f7f",
+        "aa58300a06082a8648ce3d0403020348003045022100a81e9b22bc867dc8d2edaa8bc1d8",
+        "dc65cc43ad3ffa799dab077d545272dd698302207221833bf3a63ae6cbf6fcb0e2518e61",
+        "698e645623eee8c2346ee95bed199c41",
+    );
+
+    #[test]
+    fn parses_openssl_certificates() {
+        let root = X509Certificate::from_pem(ROOT).unwrap();
+        let der = hex_decode(LEAF).unwrap();
+        let leaf = X509Certificate::from_der(&der).unwrap();
+        assert_eq!(root.public.curve, NamedCurve::P256);
+        assert_eq!(leaf.public.curve, NamedCurve::Secp256k1);
+        assert_eq!(root.issuer, root.subject);
+        assert_eq!(leaf.issuer, root.subject);
+        assert_ne!(leaf.subject, root.subject);
+        assert_eq!(leaf.signature_algorithm, ECDSA_WITH_SHA256);
+        assert_eq!(&leaf.tbs[..], &der[4..4 + 3 + 0xF7]);
+
+        assert!(X509Certificate::from_der(&der[..der.len() - 1]).is_err());
+        assert!(X509Certificate::from_pem(&ROOT.replace("CERTIFICATE", "X")).is_err());
+    }
+
+    #[test]
+    #[ignore = "slow without optimizations"]
+    fn verifies_openssl_signatures() {
+        let root = X509Certificate::from_pem(ROOT).unwrap();
+        let leaf = X509Certificate::from_der(&hex_decode(LEAF).unwrap()).unwrap();
+        assert!(root.verify_signature(&root.public));
+        assert!(leaf.verify_signature(&root.public));
+        assert!(!leaf.verify_signature(&leaf.public));
+        let mut altered = leaf.clone();
+        altered.tbs[20] ^= 1;
+        assert!(!altered.verify_signature(&root.public));
+    }
+}
@@ -0,0 +1,143 @@
+//! x-only arithmetic on `y² = x³ + a·x + b` with the Montgomery ladder and
+//! the formulas of Brier and Joye ("Weierstraß Elliptic Curves and
+//! Side-Channel Attacks"). `x(k·P)` only depends on `x(P)`, so Diffie–Hellman
+//! can skip y altogether, and with it the square root of decompression.
+//!
+//! Every x of the field belongs to the curve or to its quadratic twist; the
+//! ladder can't tell which, so an x from outside has to come from a curve
+//! whose twist is secure too, or be checked by the caller.
+
+use crate::{algebra::Field, base_traits::Natural, ecc::PrivateKey, points_group::PointCfg};
+
+/// `(X : Z)` with `x = X / Z`, `Z = 0` being the point at infinity.
+#[derive(Clone, Copy)]
+struct Xz<F> {
+    x: F,
+    z: F,
+}
+
+fn xdbl<F: Field>(p: Xz<F>, c: &PointCfg<F>) -> Xz<F> {
+    let cf = &c.cf;
+    let Xz { x, z } = p;
+    let (xx, zz) = (x.sqr(cf), z.sqr(cf));
+    let eight_b = F::mul(F::add(F::four(cf), F::four(cf), cf), c.b, cf);
+    // X₂ = (X² - a·Z²)² - 8b·X·Z³, Z₂ = 4Z·(X³ + a·X·Z² + b·Z³)
+    let x2 = F::sub(
+        F::sub(xx, F::mul(c.a, zz, cf), cf).sqr(cf),
+        F::mul(eight_b, F::mul(x, F::mul(z, zz, cf), cf), cf),
+        cf,
+    );
+    let z2 = F::mul(
+        F::mul(F::four(cf), z, cf),
+        F::sop(
+            &[(xx, x), (F::mul(c.a, x, cf), zz), (F::mul(c.b, z, cf), zz)],
+            cf,
+        ),
+        cf,
+    );
+    Xz { x: x2, z: z2 }
+}
+
+/// `x(m + n)` from `x(m)`, `x(n)` and the affine `x(m - n)`.
+fn xadd<F: Field>(m: Xz<F>, n: Xz<F>, diff: F, c: &PointCfg<F>) -> Xz<F> {
+    let cf = &c.cf;
+    let (xm_zn, xn_zm) = (F::mul(m.x, n.z, cf), F::mul(n.x, m.z, cf));
+    let zz = F::mul(m.z, n.z, cf);
+    let t = F::sub(xm_zn, xn_zm, cf).sqr(cf);
+    // X = 2(Xm·Zn + Xn·Zm)(Xm·Xn + a·Zm·Zn) + 4b·(Zm·Zn)² - x(m - n)·T,
+    // Z = T with T = (Xm·Zn - Xn·Zm)²
+    let x = F::sop(
+        &[
+            (
+                F::two(cf),
+                F::mul(
+                    F::add(xm_zn, xn_zm, cf),
+                    F::sop(&[(m.x, n.x), (c.a, zz)], cf),
+                    cf,
+                ),
+            ),
+            (F::mul(F::four(cf), c.b, cf), zz.sqr(cf)),
+            (F::neg(diff, cf), t),
+        ],
+        cf,
+    );
+    Xz { x, z: t }
+}
+
+/// `x(k·P)` from `x(P)`, `None` if `k·P` is the point at infinity. Runs one
+/// doubling and one addition per bit of `k`, whatever the bit.
+pub fn ladder<F: Field, I: Natural>(x: F, k: I, c: &PointCfg<F>) -> Option<F> {
+    let mut bits = vec![];
+    let mut k = k;
+    while k != I::zero() {
+        bits.push(k % I::two() == I::one());
+        k = k / I::two();
+    }
+    let (one, zero) = (F::one(&c.cf), F::zero(&c.cf));
+    let (mut r0, mut r1) = (Xz { x: one, z: zero }, Xz { x, z: one });
+    for &bit in bits.iter().rev() {
+        let sum = xadd(r0, r1, x, c);
+        if bit {
+            (r0, r1) = (sum, xdbl(r1, c));
+        } else {
+            (r0, r1) = (xdbl(r0, c), sum);
+        }
+    }
+    (r0.z != zero).then(|| F::div(r0.x, r0.z, &c.cf))
+}
+
+impl<I: Natural> PrivateKey<I> {
+    /// The x-coordinate of `self · Q` given only that of the peer's `Q`.
+    /// `None` if the product is the point at infinity, which a valid peer
+    /// key in the prime-order subgroup never gives.
+    pub fn diffie_hellman_x<F: Field>(self, peer_x: F, c: &PointCfg<F>) -> Option<F> {
+        ladder(peer_x, self.scalar(), c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use crate::{
+        algebra::CommutativeOp,
+        curves::{toy, toy_order},
+        ecc::gen_keys,
+        points_group::Point,
+    };
+
+    use super::ladder;
+
+    #[test]
+    fn matches_full_points() {
+        let c = toy();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([1u8; 32]);
+        for k in [1u64, 2, 3, 4, 5, 17, 1 << 40, 0xDEAD_BEEF] {
+            let p = Point::random(&mut rng, &c);
+            assert_eq!(ladder(p.x(), k, &c), Some(Point::exp(p, k, &c).x()));
+        }
+        assert_eq!(ladder(c.g.x(), 0u64, &c), None);
+        assert_eq!(ladder(c.g.x(), toy_order().rem, &c), None);
+        assert_eq!(
+            ladder(c.g.x(), toy_order().rem + 1, &c),
+            Some(c.g.x()),
+            "wraps around at the order"
+        );
+    }
+
+    #[test]
+    fn diffie_hellman() {
+        let c = toy();
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed([2u8; 32]);
+        for _ in 0..10 {
+            let (alice, alice_pub) = gen_keys::<_, u64, Point<_>>(&mut rng, &c);
+            let (bob, bob_pub) = gen_keys::<_, u64, Point<_>>(&mut rng, &c);
+            let shared = alice.diffie_hellman_x(bob_pub.point().x(), &c);
+            assert_eq!(shared, bob.diffie_hellman_x(alice_pub.point().x(), &c));
+            assert_eq!(
+                shared,
+                Some(Point::exp(bob_pub.point(), alice.scalar(), &c).x())
+            );
+        }
+    }
+}